@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use tetris_rs::ai::{Bot, Controller, Difficulty};
+use tetris_rs::game::{Game, Input};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, Tetromino};
+use tetris_rs::replay::{Replay, ReplayPosition};
+use tetris_rs::timestep::LOGIC_TICK;
+
+// Fills the `rows` bottom rows with a fixed pattern leaving one hole per row,
+// so no row is full.
+fn filled_grid(rows: usize) -> Grid {
+  let mut grid = Grid::default();
+  for y in (DEFAULT_GRID_HEIGHT - rows)..DEFAULT_GRID_HEIGHT {
+    let hole = (y * 3) % DEFAULT_GRID_WIDTH;
+    for x in 0..DEFAULT_GRID_WIDTH {
+      if x != hole {
+        grid.set(x as i32, y as i32, Case::Filled(Tetromino::ALL[(x + y) % 7]));
+      }
+    }
+  }
+  return grid;
+}
+
+// Same as `filled_grid(8)` with the `full` bottom rows completed.
+fn grid_with_full_rows(full: usize) -> Grid {
+  let mut grid = filled_grid(8);
+  for y in (DEFAULT_GRID_HEIGHT - full)..DEFAULT_GRID_HEIGHT {
+    for x in 0..DEFAULT_GRID_WIDTH {
+      grid.set(x as i32, y as i32, Case::Filled(Tetromino::I));
+    }
+  }
+  return grid;
+}
+
+// A seeded game with a piece in play over the given board.
+fn game_with_grid(grid: Grid) -> Game {
+  let mut game = Game::with_seed(42);
+  game.step(Duration::from_secs(2));
+  game.set_grid(grid);
+  return game;
+}
+
+fn bench_collision(c: &mut Criterion) {
+  let piece = create_piece(Tetromino::T, DEFAULT_GRID_WIDTH);
+  for &rows in &[0, 8, 16] {
+    let grid = filled_grid(rows);
+    c.bench_function(&format!("collides/{}_rows", rows), |b| b.iter(|| {
+      (0..DEFAULT_GRID_HEIGHT as i32).filter(|&dy| grid.collides(black_box(&piece), 0, dy)).count()
+    }));
+  }
+}
+
+fn bench_clear_rows(c: &mut Criterion) {
+  for &full in &[0, 1, 4] {
+    let grid = grid_with_full_rows(full);
+    c.bench_function(&format!("clear_full_rows/{}_full", full), |b| b.iter_batched(
+      || grid.clone(),
+      |mut grid| grid.clear_full_rows(),
+      BatchSize::SmallInput,
+    ));
+  }
+}
+
+fn bench_rotation(c: &mut Criterion) {
+  for &rows in &[0, 16] {
+    let mut game = game_with_grid(filled_grid(rows));
+    c.bench_function(&format!("rotate/{}_rows", rows), |b| b.iter(|| game.apply(black_box(Input::RotateCW))));
+  }
+}
+
+// Every move recomputes the ghost, moving back and forth keeps the piece in place.
+fn bench_ghost(c: &mut Criterion) {
+  for &rows in &[0, 16] {
+    let mut game = game_with_grid(filled_grid(rows));
+    c.bench_function(&format!("ghost/{}_rows", rows), |b| b.iter(|| {
+      game.apply(Input::MoveLeft);
+      game.apply(Input::MoveRight);
+      black_box(game.ghost_y())
+    }));
+  }
+}
+
+// Going back in a replay plays it again from the start: a game of the bot,
+// ten minutes at most.
+fn bench_replay_seek(c: &mut Criterion) {
+  let mut game = Game::with_seed(42);
+  let mut bot = Bot::new(Difficulty::Hard);
+  let mut replay = Replay::start(&game);
+  while replay.ticks < 120 * 600 && !game.is_over() {
+    if let Some(input) = bot.update(&game, LOGIC_TICK) {
+      replay.record(input);
+      game.apply(input);
+    }
+    game.step(LOGIC_TICK);
+    replay.step();
+    game.drain_events();
+  }
+  replay.finish(&game);
+  let ticks = replay.ticks;
+  let mut position = ReplayPosition::new(replay);
+  c.bench_function("replay/seek_full_game", |b| b.iter(|| position.seek(black_box(ticks))));
+}
+
+criterion_group!(benches, bench_collision, bench_clear_rows, bench_rotation, bench_ghost, bench_replay_seek);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+// A bot for --bot-cmd that keeps the stack as flat as it can: for every
+// spawn it reads a BotRequest line and answers the placement leaving the
+// fewest holes, then the smallest steps between neighbour columns.
+//
+//   cargo build --example flat_bot
+//   cargo run -- --bot-cmd target/debug/examples/flat_bot
+
+use std::io::{self, BufRead, Write};
+
+use tetris_rs::external::{BotRequest, BotResponse};
+use tetris_rs::grid::Grid;
+use tetris_rs::piece::{Case, Piece};
+
+fn column_heights(grid: &Grid) -> Vec<usize> {
+  return (0..grid.width() as i32).map(|x| {
+    let top = (0..grid.height() as i32).find(|&y| grid.get(x, y) != Some(Case::Empty));
+    top.map_or(0, |y| grid.height() - y as usize)
+  }).collect();
+}
+
+// Holes, then bumpiness, then height: lower is flatter.
+fn flatness(grid: &Grid) -> (usize, usize, usize) {
+  let heights = column_heights(grid);
+  let holes = (0..grid.width()).map(|x| {
+    let top = grid.height() - heights[x];
+    (top..grid.height()).filter(|&y| grid.get(x as i32, y as i32) == Some(Case::Empty)).count()
+  }).sum();
+  let bumpiness = heights.windows(2).map(|pair| (pair[0] as i32 - pair[1] as i32).unsigned_abs() as usize).sum();
+  return (holes, bumpiness, heights.iter().sum());
+}
+
+fn choose(grid: &Grid, piece: &Piece) -> BotResponse {
+  let mut best = None;
+  let mut rotated = piece.clone();
+  for rotation in 0..4 {
+    for x in -2..grid.width() as i32 {
+      let mut candidate = rotated.clone();
+      candidate.x = x;
+      if grid.collides(&candidate, 0, 0) {
+        continue;
+      }
+      while !grid.collides(&candidate, 0, 1) {
+        candidate.y += 1;
+      }
+      let mut after = grid.clone();
+      after.put_piece(&candidate);
+      after.clear_full_rows();
+      let score = flatness(&after);
+      if best.is_none_or(|(best_score, _, _)| score < best_score) {
+        best = Some((score, rotation, x));
+      }
+    }
+    rotated = rotated.rotated();
+  }
+  let (_, rotation, x) = best.unwrap_or(((0, 0, 0), 0, piece.x));
+  return BotResponse::Placement { rotation: rotation, x: x };
+}
+
+fn main() -> io::Result<()> {
+  let stdin = io::stdin();
+  let mut stdout = io::stdout();
+  for line in stdin.lock().lines() {
+    let request: BotRequest = serde_json::from_str(&line?)?;
+    let response = choose(&request.grid, &request.current_piece);
+    serde_json::to_writer(&mut stdout, &response)?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()?;
+  }
+  return Ok(());
+}
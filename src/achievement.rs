@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::game::{Game, GameEvent};
+use crate::stats::LifetimeStats;
+
+const MAX_TIME_40_LINES: Duration = Duration::from_secs(120);
+const LIFETIME_LINES: u64 = 1000;
+
+// Unlocked once for good, kept by name in lifetime.json.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Achievement {
+  FirstTetris,
+  Level10,
+  FortyLinesInTwoMinutes,
+  TSpinDouble,
+  ThousandLines,
+}
+
+impl Achievement {
+  pub const ALL: [Achievement; 5] = [
+    Achievement::FirstTetris,
+    Achievement::Level10,
+    Achievement::FortyLinesInTwoMinutes,
+    Achievement::TSpinDouble,
+    Achievement::ThousandLines,
+  ];
+
+  // Stored in the statistics, never to change once released. Names and
+  // descriptions are under achievement.ID in resources/lang.
+  pub fn id(&self) -> &'static str {
+    return match self {
+      Achievement::FirstTetris => "first-tetris",
+      Achievement::Level10 => "level-10",
+      Achievement::FortyLinesInTwoMinutes => "40-lines-in-2-minutes",
+      Achievement::TSpinDouble => "t-spin-double",
+      Achievement::ThousandLines => "1000-lines",
+    };
+  }
+}
+
+// Follows the events of one game to tell which achievements they unlock.
+#[derive(Clone,Debug,Default)]
+pub struct AchievementTracker {
+  // A TSpin event comes right before the PieceLocked of its piece.
+  t_spin_pending: bool,
+  // The last locked piece was a T-spin, for the LinesCleared that follow it.
+  t_spin: bool,
+}
+
+impl AchievementTracker {
+  pub fn reset(&mut self) {
+    *self = AchievementTracker::default();
+  }
+
+  // Achievements met by `event`, just drained from `game`. `lifetime` holds
+  // the games before this one, locked and unlocked alike are returned.
+  pub fn check(&mut self, game: &Game, event: GameEvent, lifetime: &LifetimeStats) -> Vec<Achievement> {
+    let score = game.current_score();
+    return match event {
+      GameEvent::TSpin => {
+        self.t_spin_pending = true;
+        Vec::new()
+      },
+      GameEvent::PieceLocked => {
+        self.t_spin = self.t_spin_pending;
+        self.t_spin_pending = false;
+        Vec::new()
+      },
+      GameEvent::LinesCleared(lines) => Achievement::ALL.iter().copied().filter(|achievement| match achievement {
+        Achievement::FirstTetris => lines >= 4,
+        Achievement::FortyLinesInTwoMinutes => score.line_removed >= 40 && game.time_played() < MAX_TIME_40_LINES,
+        Achievement::TSpinDouble => self.t_spin && lines == 2,
+        Achievement::ThousandLines => lifetime.lines + score.line_removed as u64 >= LIFETIME_LINES,
+        Achievement::Level10 => false,
+      }).collect(),
+      GameEvent::LevelUp(level) if level >= 10 => vec![Achievement::Level10],
+      _ => Vec::new(),
+    };
+  }
+}
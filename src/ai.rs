@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::game::{Game, Input};
+use crate::grid::Grid;
+use crate::piece::{Case, Piece};
+
+// Weights of the board heuristic, tuned for the 10x20 board.
+const WEIGHT_HEIGHT: f64 = -0.510066;
+const WEIGHT_LINES: f64 = 0.760666;
+const WEIGHT_HOLES: f64 = -0.35663;
+const WEIGHT_BUMPINESS: f64 = -0.184483;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Difficulty {
+  Easy,
+  Normal,
+  Hard,
+}
+
+impl Difficulty {
+  pub fn from_name(name: &str) -> Option<Difficulty> {
+    return match name {
+      "easy" => Some(Difficulty::Easy),
+      "normal" => Some(Difficulty::Normal),
+      "hard" => Some(Difficulty::Hard),
+      _ => None,
+    };
+  }
+
+  // Inputs per second, the hard drop included.
+  pub fn moves_per_second(&self) -> f64 {
+    return match self {
+      Difficulty::Easy => 2.0,
+      Difficulty::Normal => 6.0,
+      Difficulty::Hard => 15.0,
+    };
+  }
+}
+
+// Where to drop the current piece: its rotation from spawn and its column.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Placement {
+  pub rotation: usize,
+  pub x: i32,
+  pub score: f64,
+}
+
+// Higher is better: low and flat stack, no holes, many lines.
+pub fn evaluate(grid: &Grid, lines: u32) -> f64 {
+  let heights: Vec<i32> = (0..grid.width() as i32).map(|x| {
+    let top = (0..grid.height() as i32).find(|&y| grid.get(x, y) != Some(Case::Empty));
+    top.map_or(0, |y| grid.height() as i32 - y)
+  }).collect();
+  let holes = (0..grid.width() as i32).map(|x| {
+    let top = grid.height() as i32 - heights[x as usize];
+    (top..grid.height() as i32).filter(|&y| grid.get(x, y) == Some(Case::Empty)).count()
+  }).sum::<usize>();
+  let height: i32 = heights.iter().sum();
+  let bumpiness: i32 = heights.windows(2).map(|pair| (pair[0] - pair[1]).abs()).sum();
+  return WEIGHT_HEIGHT * height as f64
+    + WEIGHT_LINES * lines as f64
+    + WEIGHT_HOLES * holes as f64
+    + WEIGHT_BUMPINESS * bumpiness as f64;
+}
+
+// Tries the 4 rotations at the spawn position, then every column the piece
+// can slide to from there, so at most 4 x board width drops are simulated.
+pub fn best_placement(grid: &Grid, piece: &Piece) -> Option<Placement> {
+  let mut best: Option<Placement> = None;
+  let mut rotated = piece.clone();
+  for rotation in 0..4 {
+    if rotation > 0 {
+      rotated = rotated.rotated();
+    }
+    if grid.collides(&rotated, 0, 0) {
+      continue;
+    }
+    for &direction in &[-1, 1] {
+      let mut dx = if direction < 0 { 0 } else { 1 };
+      while !grid.collides(&rotated, dx, 0) {
+        let drop = (0..).take_while(|&dy| !grid.collides(&rotated, dx, dy)).last().unwrap_or(0);
+        let mut landed = rotated.clone();
+        landed.x += dx;
+        landed.y += drop;
+        let mut after = grid.clone();
+        after.put_piece(&landed);
+        let lines = after.clear_full_rows();
+        let score = evaluate(&after, lines);
+        if best.is_none_or(|best| score > best.score) {
+          best = Some(Placement { rotation: rotation, x: landed.x, score: score });
+        }
+        dx += direction;
+      }
+    }
+  }
+  return best;
+}
+
+// Drives a board instead of the keyboard: asked for an input once per logic tick.
+pub trait Controller {
+  fn update(&mut self, game: &Game, delta: Duration) -> Option<Input>;
+}
+
+// Rotates the piece from its spawn orientation, slides it to column `x` and drops it.
+pub fn placement_inputs(piece: &Piece, rotation: usize, x: i32) -> VecDeque<Input> {
+  let mut inputs = VecDeque::new();
+  inputs.extend(std::iter::repeat_n(Input::RotateCW, rotation % 4));
+  let dx = x - piece.x;
+  let step = if dx < 0 { Input::MoveLeft } else { Input::MoveRight };
+  inputs.extend(std::iter::repeat_n(step, dx.unsigned_abs() as usize));
+  inputs.push_back(Input::HardDrop);
+  return inputs;
+}
+
+// Plays a board through the same inputs as a human, at the pace of its difficulty.
+pub struct Bot {
+  interval: Duration,
+  timer: Duration,
+  plan: VecDeque<Input>,
+  // The plan is for the piece in play.
+  planned: bool,
+}
+
+impl Bot {
+  pub fn new(difficulty: Difficulty) -> Bot {
+    return Bot {
+      interval: Duration::from_secs_f64(1.0 / difficulty.moves_per_second()),
+      timer: Duration::from_secs(0),
+      plan: VecDeque::new(),
+      planned: false,
+    };
+  }
+
+  fn plan(grid: &Grid, piece: &Piece) -> VecDeque<Input> {
+    return match best_placement(grid, piece) {
+      Some(placement) => {
+        debug!("Bot: {:?} to {:?}", piece.tetromino, placement);
+        placement_inputs(piece, placement.rotation, placement.x)
+      },
+      None => VecDeque::from(vec![Input::HardDrop]),
+    };
+  }
+}
+
+impl Controller for Bot {
+  // The next input, if it is time for one. The search runs once per piece.
+  fn update(&mut self, game: &Game, delta: Duration) -> Option<Input> {
+    let piece = match game.current_piece() {
+      Some(piece) if !game.is_over() => piece,
+      _ => {
+        self.plan.clear();
+        self.planned = false;
+        return None;
+      },
+    };
+    if !self.planned {
+      self.plan = Bot::plan(game.grid(), piece);
+      self.planned = true;
+      self.timer = Duration::from_secs(0);
+    }
+
+    self.timer += delta;
+    if self.timer < self.interval {
+      return None;
+    }
+    self.timer -= self.interval;
+    return self.plan.pop_front();
+  }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use ggez::audio;
+use ggez::audio::SoundSource;
+use ggez::filesystem;
+use ggez::{Context, GameResult};
+
+use log::{info, warn};
+use rand::seq::SliceRandom;
+
+// Every track of this folder of the resources is played in a shuffled loop
+// instead of the theme of the settings.
+const MUSIC_DIRECTORY: &str = "/music";
+const MUSIC_EXTENSIONS: [&str; 4] = ["ogg", "wav", "flac", "mp3"];
+
+// The tracks of the music folder, shuffled again after each round. A round
+// never starts with the track that ended the last one.
+struct Playlist {
+  tracks: Vec<String>,
+  index: usize,
+}
+
+impl Playlist {
+  fn new(mut tracks: Vec<String>) -> Playlist {
+    tracks.shuffle(&mut rand::thread_rng());
+    return Playlist { tracks: tracks, index: 0 };
+  }
+
+  fn current(&self) -> &str {
+    return &self.tracks[self.index];
+  }
+
+  fn advance(&mut self) {
+    self.index += 1;
+    if self.index < self.tracks.len() {
+      return;
+    }
+    let last = self.tracks[self.tracks.len() - 1].clone();
+    self.tracks.shuffle(&mut rand::thread_rng());
+    if self.tracks.len() > 1 && self.tracks[0] == last {
+      self.tracks.swap(0, 1);
+    }
+    self.index = 0;
+  }
+}
+
+fn music_tracks(ctx: &mut Context) -> Vec<String> {
+  let mut tracks: Vec<String> = match filesystem::read_dir(ctx, MUSIC_DIRECTORY) {
+    Ok(paths) => paths.filter(|path| path.extension().and_then(|extension| extension.to_str())
+      .is_some_and(|extension| MUSIC_EXTENSIONS.contains(&extension.to_lowercase().as_str())))
+      .filter_map(|path| path.to_str().map(String::from)).collect(),
+    Err(_) => Vec::new(),
+  };
+  // read_dir lists the same file from both the folder and the zip.
+  tracks.sort();
+  tracks.dedup();
+  return tracks;
+}
+
+// Wraps every sound the game plays. When the audio device is missing or a
+// resource cannot be loaded, the matching calls become no-ops and a warning
+// is printed once, so the game stays playable without sound.
+pub struct AudioSystem {
+  enabled: bool,
+  theme: Option<audio::Source>,
+  // With any music, the theme is its current track and does not repeat.
+  playlist: Option<Playlist>,
+  // Played, repeating, when there is no music or none of it loads.
+  theme_path: String,
+  // Started and not stopped since, a track that ended is then followed by
+  // the next one. The pitch of the level applies to every track.
+  theme_started: bool,
+  theme_pitch: f32,
+  // Heard now, and once the fade running is over. The fade is the change of
+  // volume a second.
+  theme_volume: f32,
+  theme_target: f32,
+  theme_fade: f32,
+  warnings: HashSet<String>,
+}
+
+impl AudioSystem {
+  pub fn new(ctx: &mut Context, enabled: bool, theme_path: &str) -> AudioSystem {
+    let mut audio = AudioSystem {
+      enabled: enabled,
+      theme: None,
+      playlist: None,
+      theme_path: theme_path.to_string(),
+      theme_started: false,
+      theme_pitch: 1.0,
+      theme_volume: 0.0,
+      theme_target: 0.0,
+      theme_fade: 0.0,
+      warnings: HashSet::new(),
+    };
+    let tracks = if enabled { music_tracks(ctx) } else { Vec::new() };
+    if !tracks.is_empty() {
+      info!("Playing {} tracks of {}", tracks.len(), MUSIC_DIRECTORY);
+      let playlist = Playlist::new(tracks);
+      audio.theme = audio.load(ctx, playlist.current());
+      audio.playlist = Some(playlist);
+    } else {
+      audio.theme = audio.load_theme(ctx);
+    }
+    return audio;
+  }
+
+  fn load_theme(&mut self, ctx: &mut Context) -> Option<audio::Source> {
+    let path = self.theme_path.clone();
+    let mut theme = self.load(ctx, &path)?;
+    theme.set_repeat(true);
+    return Some(theme);
+  }
+
+  fn warn(&mut self, what: &str, reason: &str) {
+    if self.warnings.insert(what.to_string()) {
+      warn!("No sound for {} ({})", what, reason);
+    }
+  }
+
+  fn load(&mut self, ctx: &mut Context, path: &str) -> Option<audio::Source> {
+    if !self.enabled {
+      return None;
+    }
+
+    return match audio::Source::new(ctx, path) {
+      Ok(source) => Some(source),
+      Err(e) => {
+        self.warn(path, &e.to_string());
+        None
+      },
+    };
+  }
+
+  fn report(&mut self, what: &str, result: GameResult) {
+    if let Err(e) = result {
+      self.warn(what, &e.to_string());
+    }
+  }
+
+  pub fn play_theme(&mut self, pitch: f32, fade_in: Duration) {
+    self.theme_started = true;
+    self.theme_pitch = pitch;
+    let result = match self.theme.as_mut() {
+      Some(theme) => {
+        theme.stop();
+        theme.set_pitch(pitch);
+        theme.set_fade_in(fade_in);
+        theme.play()
+      },
+      None => Ok(()),
+    };
+    self.report("theme", result);
+  }
+
+  pub fn stop_theme(&mut self) {
+    self.theme_started = false;
+    if let Some(theme) = self.theme.as_mut() {
+      theme.stop();
+    }
+  }
+
+  pub fn pause_theme(&mut self) {
+    if let Some(theme) = self.theme.as_ref() {
+      theme.pause();
+    }
+  }
+
+  pub fn resume_theme(&mut self) {
+    if let Some(theme) = self.theme.as_ref() {
+      theme.resume();
+    }
+  }
+
+  // A fade of no time sets the volume at once.
+  pub fn fade_theme_in(&mut self, volume: f32, seconds: f32) {
+    self.theme_target = volume;
+    if seconds > 0.0 {
+      self.theme_volume = 0.0;
+      self.theme_fade = volume / seconds;
+    } else {
+      self.theme_volume = volume;
+      self.theme_fade = 0.0;
+    }
+    if let Some(theme) = self.theme.as_mut() {
+      theme.set_volume(self.theme_volume);
+    }
+  }
+
+  pub fn fade_theme_out(&mut self, seconds: f32) {
+    self.theme_target = 0.0;
+    if seconds > 0.0 && self.theme_volume > 0.0 {
+      self.theme_fade = -self.theme_volume / seconds;
+      return;
+    }
+    self.theme_volume = 0.0;
+    self.theme_fade = 0.0;
+    self.stop_theme();
+    if let Some(theme) = self.theme.as_mut() {
+      theme.set_volume(self.theme_volume);
+    }
+  }
+
+  // Heard at once, and never louder. A fade in then goes up to `volume`, a
+  // fade out keeps going to silence.
+  pub fn set_theme_volume(&mut self, volume: f32) {
+    if self.theme_fade == 0.0 {
+      self.theme_volume = volume;
+    } else {
+      self.theme_volume = self.theme_volume.min(volume);
+    }
+    if self.theme_fade >= 0.0 {
+      self.theme_target = volume;
+    }
+    if let Some(theme) = self.theme.as_mut() {
+      theme.set_volume(self.theme_volume);
+    }
+  }
+
+  pub fn update(&mut self, ctx: &mut Context, delta: Duration) {
+    self.next_track(ctx);
+    if self.theme_fade == 0.0 {
+      return;
+    }
+
+    // A fade out always goes to silence, and stops the theme there.
+    let fading_out = self.theme_fade < 0.0;
+    self.theme_volume += self.theme_fade * delta.as_secs_f32();
+    let reached = if fading_out { self.theme_volume <= self.theme_target } else { self.theme_volume >= self.theme_target };
+    if reached {
+      self.theme_volume = self.theme_target;
+      self.theme_fade = 0.0;
+      if fading_out {
+        self.stop_theme();
+      }
+    }
+    if let Some(theme) = self.theme.as_mut() {
+      theme.set_volume(self.theme_volume);
+    }
+  }
+
+  // A source without repeat stops at its end, the next track then starts
+  // where the last one left the pitch and volume. A track that does not load
+  // is skipped, once every track failed the theme of the settings plays.
+  fn next_track(&mut self, ctx: &mut Context) {
+    let ended = self.theme_started && self.playlist.is_some() && self.theme.as_ref().is_none_or(|theme| theme.stopped());
+    if !ended {
+      return;
+    }
+    let count = self.playlist.as_ref().map_or(0, |playlist| playlist.tracks.len());
+    for _ in 0..count {
+      let path = match self.playlist.as_mut() {
+        Some(playlist) => {
+          playlist.advance();
+          playlist.current().to_string()
+        },
+        None => return,
+      };
+      if let Some(mut theme) = self.load(ctx, &path) {
+        theme.set_pitch(self.theme_pitch);
+        theme.set_volume(self.theme_volume);
+        let result = theme.play();
+        self.theme = Some(theme);
+        self.report(&path, result);
+        return;
+      }
+    }
+    self.playlist = None;
+    self.theme = self.load_theme(ctx);
+    if let Some(theme) = self.theme.as_mut() {
+      theme.set_pitch(self.theme_pitch);
+      theme.set_volume(self.theme_volume);
+      let result = theme.play();
+      self.report("theme", result);
+    }
+  }
+
+  pub fn play_effect(&mut self, ctx: &mut Context, path: &str, volume: f32) {
+    if let Some(mut sound) = self.load(ctx, path) {
+      sound.set_volume(volume);
+      let result = sound.play_detached();
+      self.report(path, result);
+    }
+  }
+}
@@ -0,0 +1,411 @@
+// The same game in a terminal, for play over SSH: colored blocks drawn with
+// ANSI escapes, keys read from the terminal in raw mode, no sound.
+//
+//   cargo run --bin tetris_tui -- --mode sprint
+//
+// Takes the game options of the window version; the others are ignored.
+
+#[cfg(unix)]
+fn main() {
+  std::process::exit(tui::run());
+}
+
+#[cfg(not(unix))]
+fn main() {
+  eprintln!("The terminal version needs a Unix terminal");
+  std::process::exit(1);
+}
+
+#[cfg(unix)]
+mod tui {
+  use std::env;
+  use std::fs;
+  use std::io::{self, Read, Write};
+  use std::sync::mpsc::{self, Receiver};
+  use std::thread;
+  use std::time::{Duration, Instant};
+
+  use tetris_rs::config::GameConfig;
+  use tetris_rs::game::{Game, Input};
+  use tetris_rs::options::{Options, USAGE};
+  use tetris_rs::piece::{Case, Piece, Tetromino};
+  use tetris_rs::timestep::FixedTimestep;
+
+  const CONFIG_FILE: &str = "resources/config.toml";
+
+  // About 60 frames a second; the logic keeps its own 120 Hz ticks.
+  const FRAME: Duration = Duration::from_millis(16);
+
+  // Columns of the panels beside the board.
+  const HOLD_WIDTH: usize = 12;
+  const SIDE_WIDTH: usize = 24;
+
+  #[derive(Clone,Copy,Debug,PartialEq)]
+  enum Key {
+    Input(Input),
+    Pause,
+    Restart,
+    Quit,
+  }
+
+  // Raw mode and the alternate screen for as long as it lives, so the
+  // terminal is given back as it was even when the game panics.
+  struct Terminal {
+    original: libc::termios,
+  }
+
+  impl Terminal {
+    fn open() -> io::Result<Terminal> {
+      let mut original: libc::termios = unsafe { std::mem::zeroed() };
+      if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+        return Err(io::Error::last_os_error());
+      }
+      let mut raw = original;
+      unsafe { libc::cfmakeraw(&mut raw) };
+      if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+        return Err(io::Error::last_os_error());
+      }
+      // Alternate screen, hidden cursor.
+      print!("\x1b[?1049h\x1b[?25l");
+      io::stdout().flush()?;
+      return Ok(Terminal { original: original });
+    }
+
+    // Columns and rows, 80x24 when the terminal does not say.
+    fn size(&self) -> (usize, usize) {
+      let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+      if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 || size.ws_col == 0 {
+        return (80, 24);
+      }
+      return (size.ws_col as usize, size.ws_row as usize);
+    }
+  }
+
+  impl Drop for Terminal {
+    fn drop(&mut self) {
+      print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+      let _ = io::stdout().flush();
+      unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+  }
+
+  // Reads the terminal on a thread of its own, the loop only polls the keys.
+  fn spawn_reader() -> Receiver<Key> {
+    let (sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+      let mut stdin = io::stdin();
+      let mut buffer = [0; 64];
+      loop {
+        let read = match stdin.read(&mut buffer) {
+          Ok(0) | Err(_) => return,
+          Ok(read) => read,
+        };
+        for key in parse_keys(&buffer[..read]) {
+          if sender.send(key).is_err() {
+            return;
+          }
+        }
+      }
+    });
+    return keys;
+  }
+
+  // Arrows come as ESC [ A to D, the rest as plain bytes.
+  fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+      if bytes[i] == 0x1b && i + 2 < bytes.len() && bytes[i + 1] == b'[' {
+        let input = match bytes[i + 2] {
+          b'A' => Some(Input::RotateCW),
+          b'B' => Some(Input::SoftDrop),
+          b'C' => Some(Input::MoveRight),
+          b'D' => Some(Input::MoveLeft),
+          _ => None,
+        };
+        keys.extend(input.map(Key::Input));
+        i += 3;
+        continue;
+      }
+      let key = match bytes[i] {
+        b'z' | b'Z' => Some(Key::Input(Input::RotateCCW)),
+        b'x' | b'X' => Some(Key::Input(Input::RotateCW)),
+        b'c' | b'C' => Some(Key::Input(Input::Hold)),
+        b'v' | b'V' => Some(Key::Input(Input::UseItem)),
+        b' ' => Some(Key::Input(Input::HardDrop)),
+        b'p' | b'P' => Some(Key::Pause),
+        b'r' | b'R' => Some(Key::Restart),
+        // q, Escape and Ctrl-C, raw mode turns off the signal.
+        b'q' | b'Q' | 0x1b | 0x03 => Some(Key::Quit),
+        _ => None,
+      };
+      keys.extend(key);
+      i += 1;
+    }
+    return keys;
+  }
+
+  // Same hues as the window version, as ANSI foreground codes.
+  fn tetromino_color(tetromino: Tetromino) -> u8 {
+    return match tetromino {
+      Tetromino::Z => 91,
+      Tetromino::S => 92,
+      Tetromino::J => 94,
+      Tetromino::L => 33,
+      Tetromino::O => 93,
+      Tetromino::T => 35,
+      Tetromino::I => 96,
+      Tetromino::F | Tetromino::W => 31,
+      Tetromino::N | Tetromino::Z5 => 32,
+      Tetromino::P | Tetromino::U => 34,
+      Tetromino::L5 | Tetromino::V => 37,
+      Tetromino::X => 97,
+      Tetromino::T5 | Tetromino::Y => 95,
+      Tetromino::I5 => 36,
+    };
+  }
+
+  const GARBAGE_COLOR: u8 = 90;
+
+  // Two characters per case so the board is about square.
+  fn block(color: u8, text: &str) -> String {
+    return format!("\x1b[{}m{}\x1b[0m", color, text);
+  }
+
+  // Rows of a small preview of the piece, 5 cases wide for the pentominoes.
+  fn preview(piece: Option<&Piece>) -> Vec<String> {
+    let height = piece.map_or(2, |piece| piece.height().max(2) as usize);
+    let mut rows = vec![String::new(); height];
+    for (y, row) in rows.iter_mut().enumerate() {
+      for x in 0..5 {
+        let filled = piece.is_some_and(|piece| {
+          let left = piece.cells().iter().map(|&(x, _)| x).min().unwrap_or(0);
+          let top = piece.cells().iter().map(|&(_, y)| y).min().unwrap_or(0);
+          piece.cells().iter().any(|&(cx, cy)| cx - left == x && cy - top == y as i32)
+        });
+        match piece {
+          Some(piece) if filled => row.push_str(&block(tetromino_color(piece.tetromino), "██")),
+          _ => row.push_str("  "),
+        }
+      }
+    }
+    return rows;
+  }
+
+  // How the board is drawn, from the options.
+  struct Look {
+    // With --big every case of the game is `size` cases wide and high.
+    size: usize,
+    // With --invisible, locked cases are hidden that long after they locked.
+    invisible_delay: Option<Duration>,
+  }
+
+  fn board_lines(game: &Game, look: &Look) -> Vec<String> {
+    let size = look.size;
+    let grid = game.grid();
+    let reveal = game.is_clearing() || game.is_over();
+    let hidden = |x: i32, y: i32| !reveal && look.invisible_delay.is_some_and(|delay| game.filled_at(x, y) + delay < game.time_played());
+    let piece = game.current_piece();
+    let ghost = piece.map(|piece| {
+      let mut ghost = piece.clone();
+      ghost.y = game.ghost_y();
+      ghost
+    });
+    let covers = |piece: Option<&Piece>, x: i32, y: i32| piece.is_some_and(|piece| piece.cells().iter().any(|&(cx, cy)| piece.x + cx == x && piece.y + cy == y));
+
+    let mut lines = Vec::new();
+    lines.push(format!("┌{}┐", "─".repeat(grid.width() * size * 2)));
+    for y in 0..grid.height() as i32 {
+      let mut line = String::from("│");
+      for x in 0..grid.width() as i32 {
+        let case = match grid.get(x, y) {
+          Some(_) if hidden(x, y) && !covers(piece, x, y) => " .".repeat(size),
+          Some(Case::Filled(tetromino)) => block(tetromino_color(tetromino), &"██".repeat(size)),
+          Some(Case::Garbage) => block(GARBAGE_COLOR, &"██".repeat(size)),
+          _ if covers(piece, x, y) => block(tetromino_color(piece.unwrap().tetromino), &"██".repeat(size)),
+          _ if covers(ghost.as_ref(), x, y) => block(tetromino_color(piece.unwrap().tetromino), &"░░".repeat(size)),
+          _ => " .".repeat(size),
+        };
+        line.push_str(&case);
+      }
+      line.push('│');
+      lines.extend(std::iter::repeat_n(line, size));
+    }
+    lines.push(format!("└{}┘", "─".repeat(grid.width() * size * 2)));
+    return lines;
+  }
+
+  fn side_lines(game: &Game, paused: bool) -> Vec<String> {
+    let score = game.current_score();
+    let mut lines = vec![String::from("Next"), String::new()];
+    for piece in game.next_pieces() {
+      lines.extend(preview(Some(piece)));
+      lines.push(String::new());
+    }
+    lines.push(game.mode().name().to_string());
+    lines.push(format!("Score: {}", score.score));
+    lines.push(format!("Level: {}", score.level));
+    lines.push(format!("Lines: {}", score.line_removed));
+    if game.items() {
+      lines.push(format!("Item:  {}", game.item().map_or("none", |item| item.name())));
+    }
+    lines.push(format!("Time:  {}:{:02}", game.time_played().as_secs() / 60, game.time_played().as_secs() % 60));
+    lines.push(String::new());
+    lines.push(String::from(match (game.is_over(), paused) {
+      (true, _) => "Game over, r to restart",
+      (_, true) => "Paused, p to resume",
+      _ => "",
+    }));
+    return lines;
+  }
+
+  fn hold_lines(game: &Game) -> Vec<String> {
+    let mut lines = vec![String::from("Hold"), String::new()];
+    lines.extend(preview(game.hold_piece()));
+    lines.push(String::new());
+    lines.push(String::from("← → move"));
+    lines.push(String::from("↑ x rotate"));
+    lines.push(String::from("z rotate ⟲"));
+    lines.push(String::from("↓ soft drop"));
+    lines.push(String::from("space drop"));
+    lines.push(String::from("c hold"));
+    lines.push(String::from("v item"));
+    lines.push(String::from("p pause"));
+    lines.push(String::from("q quit"));
+    return lines;
+  }
+
+  // Pads a line holding escape codes to `width` visible columns.
+  fn pad(line: &str, width: usize) -> String {
+    let mut visible = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+      match c {
+        '\x1b' => in_escape = true,
+        'm' if in_escape => in_escape = false,
+        _ if in_escape => (),
+        _ => visible += 1,
+      }
+    }
+    return format!("{}{}", line, " ".repeat(width.saturating_sub(visible)));
+  }
+
+  // The whole screen in one write, centered; it is cleared when the size changed.
+  fn draw(terminal: &Terminal, game: &Game, look: &Look, paused: bool, last_size: &mut (usize, usize)) -> io::Result<()> {
+    let terminal_size = terminal.size();
+    let mut screen = String::new();
+    if terminal_size != *last_size {
+      screen.push_str("\x1b[2J");
+      *last_size = terminal_size;
+    }
+
+    let board = board_lines(game, look);
+    let hold = hold_lines(game);
+    let side = side_lines(game, paused);
+    let board_width = game.grid().width() * look.size * 2 + 2;
+    let width = HOLD_WIDTH + board_width + 2 + SIDE_WIDTH;
+    let left = terminal_size.0.saturating_sub(width) / 2;
+    let top = terminal_size.1.saturating_sub(board.len()) / 2;
+    for (row, line) in board.iter().enumerate() {
+      let hold_line = hold.get(row).map(String::as_str).unwrap_or("");
+      let side_line = side.get(row).map(String::as_str).unwrap_or("");
+      screen.push_str(&format!("\x1b[{};{}H{}{}  {}", top + row + 1, left + 1, pad(hold_line, HOLD_WIDTH), line, pad(side_line, SIDE_WIDTH)));
+    }
+
+    let mut stdout = io::stdout();
+    stdout.write_all(screen.as_bytes())?;
+    return stdout.flush();
+  }
+
+  fn load_config(options: &Options) -> Result<GameConfig, String> {
+    let mut config = match fs::read_to_string(CONFIG_FILE) {
+      Ok(text) => GameConfig::from_toml(&text).map_err(|e| format!("{}: {}", CONFIG_FILE, e))?,
+      Err(_) => GameConfig::default(),
+    };
+    options.configure(&mut config);
+    config.validate()?;
+    return Ok(config);
+  }
+
+  pub fn run() -> i32 {
+    let options = match Options::parse(env::args().skip(1)) {
+      Ok(options) => options,
+      Err(e) => {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        return 2;
+      },
+    };
+    if options.help {
+      println!("{}", USAGE);
+      return 0;
+    }
+    let config = match load_config(&options) {
+      Ok(config) => config,
+      Err(e) => {
+        eprintln!("Error: invalid config ({})", e);
+        return 1;
+      },
+    };
+
+    let mut game = Game::with_config(config.clone(), options.seed);
+    game.set_mode(options.mode);
+    game.set_start_level(options.level);
+    game.set_items(options.items);
+    game.set_mirror(options.mirror);
+    game.set_handicap_rows(options.handicap.unwrap_or(0));
+    game.reset();
+
+    let terminal = match Terminal::open() {
+      Ok(terminal) => terminal,
+      Err(e) => {
+        eprintln!("Error: cannot set up the terminal ({})", e);
+        return 1;
+      },
+    };
+    let look = Look {
+      size: if options.big { 2 } else { 1 },
+      invisible_delay: if options.invisible { Some(Duration::from_secs_f64(config.invisible_delay)) } else { None },
+    };
+    let keys = spawn_reader();
+    let mut timestep = FixedTimestep::default();
+    let mut last_frame = Instant::now();
+    let mut last_size = (0, 0);
+    let mut paused = false;
+    loop {
+      let mut inputs = Vec::new();
+      for key in keys.try_iter() {
+        match key {
+          Key::Input(input) if !paused => inputs.push(input),
+          Key::Input(_) => (),
+          Key::Pause => paused = !paused && !game.is_over(),
+          Key::Restart => {
+            game.reset();
+            paused = false;
+          },
+          Key::Quit => return 0,
+        }
+      }
+
+      for input in inputs {
+        game.apply(if game.mirror() { input.mirrored() } else { input });
+      }
+      let now = Instant::now();
+      let ticks = timestep.advance(now - last_frame);
+      last_frame = now;
+      for _ in 0..ticks {
+        if !paused {
+          game.step(timestep.tick());
+        }
+      }
+      // No sound here.
+      game.drain_events();
+
+      if let Err(e) = draw(&terminal, &game, &look, paused, &mut last_size) {
+        drop(terminal);
+        eprintln!("Error: cannot draw ({})", e);
+        return 1;
+      }
+      thread::sleep(FRAME.checked_sub(now.elapsed()).unwrap_or_default());
+    }
+  }
+}
@@ -0,0 +1,154 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::game::Game;
+use crate::piece::Tetromino;
+
+// At most 20 states a second, fewer when nothing changes.
+pub const STATE_INTERVAL: Duration = Duration::from_millis(50);
+
+// How often the server looks for new clients when no state arrives.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+// A client that does not read for this long is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Clone,Debug,PartialEq,Serialize)]
+pub struct PieceState {
+  pub tetromino: Tetromino,
+  // Cases of the piece on the board, as (x, y) from the top left.
+  pub cells: Vec<(i32, i32)>,
+}
+
+// What a stream overlay needs to draw the board of the local player, sent
+// as one JSON object per line.
+#[derive(Clone,Debug,PartialEq,Serialize)]
+pub struct GameState {
+  pub width: usize,
+  pub height: usize,
+  // Rows from the top, '.' for an empty case, '#' for garbage and the
+  // tetromino letter otherwise.
+  pub grid: String,
+  pub piece: Option<PieceState>,
+  pub next: Vec<Tetromino>,
+  pub hold: Option<Tetromino>,
+  pub score: i64,
+  pub level: u32,
+  pub lines: u32,
+  pub pps: f64,
+}
+
+impl GameState {
+  pub fn from_game(game: &Game) -> GameState {
+    let score = game.current_score();
+    return GameState {
+      width: game.grid().width(),
+      height: game.grid().height(),
+      grid: game.grid().codes(),
+      piece: game.current_piece().map(|piece| PieceState {
+        tetromino: piece.tetromino,
+        cells: piece.cells().iter().map(|&(x, y)| (piece.x + x, piece.y + y)).collect(),
+      }),
+      next: game.next_pieces().iter().map(|piece| piece.tetromino).collect(),
+      hold: game.hold_piece().map(|piece| piece.tetromino),
+      score: score.score,
+      level: score.level,
+      lines: score.line_removed,
+      pps: (game.pieces_per_second() * 100.0).round() / 100.0,
+    };
+  }
+}
+
+// Sends the states to every TCP client from a thread of its own, so a slow
+// client never holds the game loop: `publish` only queues the line.
+pub struct StateServer {
+  sender: Sender<String>,
+  last: String,
+}
+
+impl StateServer {
+  pub fn start(port: u16) -> io::Result<StateServer> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    listener.set_nonblocking(true)?;
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || serve(listener, lines));
+    info!("Serving the game state on port {}", port);
+    return Ok(StateServer { sender: sender, last: String::new() });
+  }
+
+  // Ignored when the state did not change since the last one sent.
+  pub fn publish(&mut self, state: &GameState) {
+    let line = match serde_json::to_string(state) {
+      Ok(line) => line,
+      Err(e) => {
+        warn!("Cannot encode the game state ({})", e);
+        return;
+      },
+    };
+    if line == self.last {
+      return;
+    }
+    // The thread only stops when the server is dropped.
+    let _ = self.sender.send(line.clone());
+    self.last = line;
+  }
+}
+
+// Runs until the StateServer is dropped. A new client gets the last state
+// right away, a client that fails a write is forgotten.
+fn serve(listener: TcpListener, lines: Receiver<String>) {
+  let mut clients: Vec<TcpStream> = Vec::new();
+  let mut last: Option<String> = None;
+  loop {
+    loop {
+      match listener.accept() {
+        Ok((mut stream, address)) => {
+          let set_up = stream.set_nonblocking(false)
+            .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+            .and_then(|()| match &last {
+              Some(line) => write_line(&mut stream, line),
+              None => Ok(()),
+            });
+          match set_up {
+            Ok(()) => {
+              info!("State client connected from {}", address);
+              clients.push(stream);
+            },
+            Err(e) => warn!("Cannot send the state to {} ({})", address, e),
+          }
+        },
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+        Err(e) => {
+          warn!("Cannot accept a state client ({})", e);
+          break;
+        },
+      }
+    }
+
+    match lines.recv_timeout(ACCEPT_INTERVAL) {
+      Ok(line) => {
+        clients.retain_mut(|stream| match write_line(stream, &line) {
+          Ok(()) => true,
+          Err(e) => {
+            info!("State client gone ({})", e);
+            false
+          },
+        });
+        last = Some(line);
+      },
+      Err(RecvTimeoutError::Timeout) => (),
+      Err(RecvTimeoutError::Disconnected) => return,
+    }
+  }
+}
+
+fn write_line(stream: &mut TcpStream, line: &str) -> io::Result<()> {
+  stream.write_all(line.as_bytes())?;
+  stream.write_all(b"\n")?;
+  return stream.flush();
+}
@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use gif::SetParameter;
+use log::{info, warn};
+
+use crate::broadcast::GameState;
+use crate::game::Game;
+use crate::grid::case_from_code;
+use crate::piece::Case;
+use crate::screenshot::{create_unique, now_seconds, timestamped_name};
+use crate::skin::{tile_index, SKIN_TILES};
+
+// Under the user data directory.
+pub const CLIPS_DIRECTORY: &str = "clips";
+// 20 states a second for the last 15 seconds.
+pub const CLIP_INTERVAL: Duration = Duration::from_millis(50);
+pub const CLIP_LENGTH: Duration = Duration::from_secs(15);
+// Pixels of a case in the GIF, the last one is the gap of the grid.
+pub const CLIP_CASE_SIZE: usize = 8;
+
+// The palette of the GIF: the grid, an empty case, then the tiles of
+// skin::tile_index.
+pub const CLIP_COLORS: usize = SKIN_TILES + 2;
+pub type ClipColors = [[u8; 3]; CLIP_COLORS];
+
+fn frames_kept() -> usize {
+  return (CLIP_LENGTH.as_millis() / CLIP_INTERVAL.as_millis()) as usize;
+}
+
+// Index in ClipColors of a case of the board.
+fn color_index(case: Case) -> u8 {
+  return tile_index(case).map_or(1, |index| index as u8 + 2);
+}
+
+// The board of `state` and its piece as indexed pixels, row by row.
+pub fn clip_pixels(state: &GameState) -> Vec<u8> {
+  let width = state.width * CLIP_CASE_SIZE;
+  let mut pixels = vec![0; width * state.height * CLIP_CASE_SIZE];
+  let mut fill = |x: i32, y: i32, index: u8| {
+    if x < 0 || y < 0 || x as usize >= state.width || y as usize >= state.height {
+      return;
+    }
+    for row in 0..CLIP_CASE_SIZE - 1 {
+      let start = (y as usize * CLIP_CASE_SIZE + row) * width + x as usize * CLIP_CASE_SIZE;
+      pixels[start..start + CLIP_CASE_SIZE - 1].iter_mut().for_each(|pixel| *pixel = index);
+    }
+  };
+  for (i, code) in state.grid.chars().enumerate() {
+    fill((i % state.width) as i32, (i / state.width) as i32, color_index(case_from_code(code).unwrap_or(Case::Empty)));
+  }
+  if let Some(piece) = &state.piece {
+    for &(x, y) in &piece.cells {
+      fill(x, y, color_index(Case::Filled(piece.tetromino)));
+    }
+  }
+  return pixels;
+}
+
+// The states of the last CLIP_LENGTH of a game, drawn again into a GIF from
+// a thread when asked, so the game only pays for a few strings a second.
+pub struct ClipRecorder {
+  directory: PathBuf,
+  states: VecDeque<GameState>,
+  since_state: Duration,
+  saving: bool,
+  sender: Sender<Result<String, String>>,
+  results: Receiver<Result<String, String>>,
+}
+
+impl ClipRecorder {
+  pub fn new(user_data: &Path) -> ClipRecorder {
+    let (sender, results) = mpsc::channel();
+    return ClipRecorder {
+      directory: user_data.join(CLIPS_DIRECTORY),
+      states: VecDeque::new(),
+      since_state: Duration::from_secs(0),
+      saving: false,
+      sender: sender,
+      results: results,
+    };
+  }
+
+  // Called with the time the game has just advanced.
+  pub fn record(&mut self, delta: Duration, game: &Game) {
+    self.since_state += delta;
+    if !self.states.is_empty() && self.since_state < CLIP_INTERVAL {
+      return;
+    }
+    self.since_state = Duration::from_secs(0);
+    if self.states.len() == frames_kept() {
+      self.states.pop_front();
+    }
+    self.states.push_back(GameState::from_game(game));
+  }
+
+  pub fn clear(&mut self) {
+    self.states.clear();
+    self.since_state = Duration::from_secs(0);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    return self.states.is_empty();
+  }
+
+  // One clip at a time: false while the last one is still being written.
+  pub fn save(&mut self, colors: ClipColors) -> bool {
+    if self.saving || self.states.is_empty() {
+      return false;
+    }
+    self.saving = true;
+    let states: Vec<GameState> = self.states.iter().cloned().collect();
+    let directory = self.directory.clone();
+    let sender = self.sender.clone();
+    thread::spawn(move || {
+      let result = write_gif(&directory, &timestamped_name(now_seconds(), "gif"), &states, &colors);
+      match &result {
+        Ok(name) => info!("Clip saved to {}", directory.join(name).display()),
+        Err(e) => warn!("Cannot save the clip in {} ({})", directory.display(), e),
+      }
+      // Nobody to tell once the game has quit.
+      let _ = sender.send(result);
+    });
+    return true;
+  }
+
+  // The outcome of a clip, when one has been written since the last call.
+  pub fn poll(&mut self) -> Option<Result<String, String>> {
+    let result = self.results.try_recv().ok();
+    if result.is_some() {
+      self.saving = false;
+    }
+    return result;
+  }
+}
+
+// Returns the name of the file written.
+fn write_gif(directory: &Path, name: &str, states: &[GameState], colors: &ClipColors) -> Result<String, String> {
+  let (name, file) = create_unique(directory, name)?;
+  let written = (|| -> io::Result<()> {
+    // Every state of a clip comes from the same board.
+    let (width, height) = (states[0].width * CLIP_CASE_SIZE, states[0].height * CLIP_CASE_SIZE);
+    let palette: Vec<u8> = colors.iter().flatten().copied().collect();
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &palette)?;
+    encoder.set(gif::Repeat::Infinite)?;
+    for state in states {
+      let mut frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, &clip_pixels(state), None);
+      frame.delay = (CLIP_INTERVAL.as_millis() / 10) as u16;
+      encoder.write_frame(&frame)?;
+    }
+    return Ok(());
+  })();
+  if let Err(e) = written {
+    // No half written file left behind.
+    let _ = fs::remove_file(directory.join(&name));
+    return Err(e.to_string());
+  }
+  return Ok(name);
+}
@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gravity::GravityCurve;
+use crate::grid::{DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use crate::leaderboard::{sends_token, validate_url};
+use crate::piece::{PieceSet, Tetromino};
+use crate::randomizer::RandomizerKind;
+
+pub const DEFAULT_FONT: &str = "/DejaVuSerif.ttf";
+pub const DEFAULT_FONT_SIZE: f32 = 18.0;
+// Larger texts no longer fit beside the board in the default window.
+pub const MAX_FONT_SIZE: f32 = 40.0;
+pub const MIN_FONT_SIZE: f32 = 8.0;
+
+// Red, green, blue and alpha.
+pub type Rgba = (u8, u8, u8, u8);
+
+// "#rrggbb" or "#rrggbbaa".
+pub fn parse_color(text: &str) -> Result<Rgba, String> {
+  let digits = text.strip_prefix('#').filter(|digits| (digits.len() == 6 || digits.len() == 8) && digits.chars().all(|digit| digit.is_ascii_hexdigit()))
+    .ok_or(format!("invalid color \"{}\", expected #rrggbb or #rrggbbaa", text))?;
+  let channel = |index: usize| u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).unwrap_or(255);
+  let alpha = if digits.len() == 8 { channel(3) } else { 255 };
+  return Ok((channel(0), channel(1), channel(2), alpha));
+}
+
+// Tunable constants of the game. Unlike `Settings` this file is not written
+// back by the game: it is meant to be edited by hand, and a bad value stops
+// the game at startup instead of being silently replaced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+  pub grid_width: usize,
+  pub grid_height: usize,
+  pub case_size: f32,
+  pub next_pieces_count: usize,
+  // A level is gained every `lines_per_level` lines from the starting level.
+  pub lines_per_level: u32,
+  // "classic", "nes" or "tgm", the --gravity option takes precedence.
+  pub gravity: GravityCurve,
+  // Gravity of the classic curve in seconds per row:
+  // (drop_speed_base - (level - 1) * drop_speed_step) ^ (level - 1)
+  pub drop_speed_base: f64,
+  pub drop_speed_step: f64,
+  // Seconds between a lock and the next piece, shorter after a hard drop.
+  pub spawn_delay: f64,
+  pub hard_drop_spawn_delay: f64,
+  // Seconds after a spawn a hard drop is ignored in, with the misdrop
+  // protection of the settings: mashed for the piece before, it would drop
+  // the new one.
+  pub misdrop_grace: f64,
+  // "uniform", "bag" or "tgm", the --randomizer option takes precedence.
+  pub randomizer: RandomizerKind,
+  // "tetromino" or "pentomino", the --pieces option takes precedence.
+  pub pieces: PieceSet,
+  // Seconds a locked case stays visible with --invisible.
+  pub invisible_delay: f64,
+  // After a clear the groups of cases left fall on their own and may clear
+  // more rows, the --cascade option turns it on too.
+  pub cascade: bool,
+  // Scores of solo games are posted there when set, as `player_name`. The
+  // token, if any, is sent as a bearer token, over https only.
+  pub leaderboard_url: Option<String>,
+  pub leaderboard_token: Option<String>,
+  pub player_name: String,
+  // Id of the Discord application the presence is shown as, see the
+  // discord feature.
+  pub discord_client_id: Option<String>,
+  // Path of the font in the resources. A font that does not load falls
+  // back to the built-in one, with a warning.
+  pub font: String,
+  // Of the texts, banners are twice as big and high contrast a bit more.
+  pub font_size: f32,
+  // "#rrggbb" by piece letter, lowercase for pentominoes; the built-in
+  // palette for the others. A table, so it stays last for the TOML writer.
+  pub colors: BTreeMap<String, String>,
+}
+
+impl Default for GameConfig {
+  fn default() -> GameConfig {
+    return GameConfig {
+      grid_width: DEFAULT_GRID_WIDTH,
+      grid_height: DEFAULT_GRID_HEIGHT,
+      case_size: 20.0,
+      next_pieces_count: 3,
+      lines_per_level: 10,
+      gravity: GravityCurve::Classic,
+      drop_speed_base: 0.8,
+      drop_speed_step: 0.007,
+      spawn_delay: 0.2,
+      hard_drop_spawn_delay: 0.1,
+      misdrop_grace: 0.08,
+      randomizer: RandomizerKind::Uniform,
+      pieces: PieceSet::Tetromino,
+      invisible_delay: 1.0,
+      cascade: false,
+      leaderboard_url: None,
+      leaderboard_token: None,
+      player_name: String::from("Player"),
+      discord_client_id: None,
+      font: String::from(DEFAULT_FONT),
+      font_size: DEFAULT_FONT_SIZE,
+      colors: BTreeMap::new(),
+    };
+  }
+}
+
+impl GameConfig {
+  pub fn from_toml(text: &str) -> Result<GameConfig, String> {
+    let config: GameConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+    config.validate()?;
+    return Ok(config);
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    // The I piece lies flat on spawn and stands 4 cases high once rotated, 5
+    // for the pentomino one.
+    let size = self.pieces.max_size() as usize;
+    if self.grid_width < size || self.grid_height < size {
+      return Err(format!("grid must be at least {}x{} with these pieces, got {}x{}", size, size, self.grid_width, self.grid_height));
+    }
+    if !self.case_size.is_finite() || self.case_size <= 0.0 {
+      return Err(format!("case_size must be positive, got {}", self.case_size));
+    }
+    if self.next_pieces_count == 0 {
+      return Err(String::from("next_pieces_count must be at least 1"));
+    }
+    if self.lines_per_level == 0 {
+      return Err(String::from("lines_per_level must be at least 1"));
+    }
+    if !self.drop_speed_base.is_finite() || self.drop_speed_base <= 0.0 {
+      return Err(format!("drop_speed_base must be positive, got {}", self.drop_speed_base));
+    }
+    if !self.drop_speed_step.is_finite() || self.drop_speed_step < 0.0 {
+      return Err(format!("drop_speed_step cannot be negative, got {}", self.drop_speed_step));
+    }
+    if !self.spawn_delay.is_finite() || self.spawn_delay < 0.0 {
+      return Err(format!("spawn_delay cannot be negative, got {}", self.spawn_delay));
+    }
+    if !self.hard_drop_spawn_delay.is_finite() || self.hard_drop_spawn_delay < 0.0 {
+      return Err(format!("hard_drop_spawn_delay cannot be negative, got {}", self.hard_drop_spawn_delay));
+    }
+    if !self.misdrop_grace.is_finite() || self.misdrop_grace < 0.0 {
+      return Err(format!("misdrop_grace cannot be negative, got {}", self.misdrop_grace));
+    }
+    if !self.invisible_delay.is_finite() || self.invisible_delay < 0.0 {
+      return Err(format!("invisible_delay cannot be negative, got {}", self.invisible_delay));
+    }
+    if !(MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&self.font_size) {
+      return Err(format!("font_size must be between {} and {}, got {}", MIN_FONT_SIZE, MAX_FONT_SIZE, self.font_size));
+    }
+    if let Some(url) = &self.leaderboard_url {
+      validate_url(url).map_err(|e| format!("leaderboard_url: {}", e))?;
+      if self.leaderboard_token.is_some() && !sends_token(url) {
+        return Err(format!("leaderboard_token needs an https:// leaderboard_url, it would be sent in the clear to {}", url));
+      }
+    }
+    if let Some(id) = &self.discord_client_id {
+      if id.is_empty() || !id.chars().all(|digit| digit.is_ascii_digit()) {
+        return Err(format!("discord_client_id must be the digits of an application id, got {:?}", id));
+      }
+    }
+    self.piece_colors()?;
+    return Ok(());
+  }
+
+  // The [colors] table, errors naming the key at fault.
+  pub fn piece_colors(&self) -> Result<HashMap<Tetromino, Rgba>, String> {
+    let mut colors = HashMap::new();
+    for (key, color) in &self.colors {
+      let tetromino = key.chars().next().filter(|_| key.chars().count() == 1).and_then(Tetromino::from_letter)
+        .ok_or(format!("colors.{}: unknown piece, expected one of IOTSZJL or a lowercase pentomino letter", key))?;
+      colors.insert(tetromino, parse_color(color).map_err(|e| format!("colors.{}: {}", key, e))?);
+    }
+    return Ok(colors);
+  }
+
+  // Built-in defaults when the file is absent, an error when it is invalid.
+  pub fn load_file(path: &Path) -> Result<GameConfig, String> {
+    return match fs::read_to_string(path) {
+      Ok(text) => GameConfig::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(GameConfig::default()),
+      Err(e) => Err(format!("{}: {}", path.display(), e)),
+    };
+  }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::randomizer::RandomizerKind;
+use crate::stats::{write_atomically, GameSummary};
+
+// The daily challenge is a marathon that ends with this many lines.
+pub const DAILY_LINES: u32 = 150;
+pub const DAILY_FILE: &str = "daily.json";
+
+// A day of the proleptic Gregorian calendar, in UTC.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub struct Date {
+  pub year: i64,
+  pub month: u32,
+  pub day: u32,
+}
+
+impl Date {
+  // `days` since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+  pub fn from_days(days: i64) -> Date {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return Date { year: year, month: month, day: day };
+  }
+
+  pub fn today() -> Date {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+    return Date::from_days((seconds / 86_400) as i64);
+  }
+}
+
+// 2026-10-14, also the key of the day in daily.json.
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day);
+  }
+}
+
+// Seed of the pieces of the day, the 64 bit FNV-1a hash of the date as
+// written above. It must never change: everyone plays the same pieces on the
+// same day, whatever their version.
+pub fn daily_seed(date: Date) -> u64 {
+  let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+  for byte in date.to_string().bytes() {
+    hash ^= byte as u64;
+    hash = hash.wrapping_mul(0x0100_0000_01b3);
+  }
+  return hash;
+}
+
+// The rules of the daily challenge, whatever config.toml says. Only what does
+// not change the game is kept.
+pub fn daily_config(config: &GameConfig) -> GameConfig {
+  return GameConfig {
+    case_size: config.case_size,
+    randomizer: RandomizerKind::Bag,
+    leaderboard_url: config.leaderboard_url.clone(),
+    leaderboard_token: config.leaderboard_token.clone(),
+    player_name: config.player_name.clone(),
+    discord_client_id: config.discord_client_id.clone(),
+    font: config.font.clone(),
+    font_size: config.font_size,
+    colors: config.colors.clone(),
+    ..GameConfig::default()
+  };
+}
+
+// The official attempt of a day, the first one played. Later attempts of the
+// same day are practice and only counted.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct DailyResult {
+  pub score: i64,
+  pub lines: u32,
+  pub level: u32,
+  pub seconds: f64,
+  // All DAILY_LINES lines were cleared.
+  pub completed: bool,
+  #[serde(default)]
+  pub practice_attempts: u32,
+}
+
+// Content of daily.json, by date.
+#[derive(Clone,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct DailyRecords {
+  #[serde(default)]
+  pub days: BTreeMap<String, DailyResult>,
+}
+
+impl DailyRecords {
+  // Empty when the file does not exist yet.
+  pub fn load(directory: &Path) -> io::Result<DailyRecords> {
+    let text = match fs::read_to_string(directory.join(DAILY_FILE)) {
+      Ok(text) => text,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(DailyRecords::default()),
+      Err(e) => return Err(e),
+    };
+    return Ok(serde_json::from_str(&text)?);
+  }
+
+  pub fn save(&self, directory: &Path) -> io::Result<()> {
+    return write_atomically(&directory.join(DAILY_FILE), &serde_json::to_vec_pretty(self)?);
+  }
+
+  pub fn official(&self, date: Date) -> Option<&DailyResult> {
+    return self.days.get(&date.to_string());
+  }
+
+  // The game becomes the official attempt of `date` when there is none yet,
+  // and a practice one otherwise. Returns whether it was official.
+  pub fn record(&mut self, date: Date, summary: &GameSummary) -> bool {
+    if let Some(result) = self.days.get_mut(&date.to_string()) {
+      result.practice_attempts += 1;
+      return false;
+    }
+    self.days.insert(date.to_string(), DailyResult {
+      score: summary.score,
+      lines: summary.lines,
+      level: summary.level,
+      seconds: summary.seconds,
+      completed: summary.lines >= DAILY_LINES,
+      practice_attempts: 0,
+    });
+    return true;
+  }
+}
@@ -0,0 +1,230 @@
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde_json::json;
+
+// Discord accepts 5 activity updates every 20 seconds, the latest text waits
+// for the next slot.
+pub const PRESENCE_INTERVAL: Duration = Duration::from_secs(4);
+// For each read and write on the socket.
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+// How long quitting waits for the activity to be cleared.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+// The two lines under the name of the game,
+// e.g. "Marathon" and "Level 7, 64 lines".
+#[derive(Clone,Debug,PartialEq)]
+pub struct Activity {
+  pub details: String,
+  pub state: String,
+}
+
+trait Pipe: Read + Write + Send {}
+impl<T: Read + Write + Send> Pipe for T {}
+
+// discord-ipc-0 to 9 in the runtime directory of the client.
+#[cfg(unix)]
+fn connect() -> io::Result<Box<dyn Pipe>> {
+  use std::env;
+  use std::os::unix::net::UnixStream;
+  let directory = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"].iter().find_map(|name| env::var(name).ok()).unwrap_or_else(|| String::from("/tmp"));
+  let mut last = io::Error::new(io::ErrorKind::NotFound, "Discord is not running");
+  for index in 0..10 {
+    match UnixStream::connect(format!("{}/discord-ipc-{}", directory, index)) {
+      Ok(stream) => {
+        stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+        stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+        return Ok(Box::new(stream));
+      },
+      Err(e) => last = e,
+    }
+  }
+  return Err(last);
+}
+
+// The named pipes of the client.
+#[cfg(windows)]
+fn connect() -> io::Result<Box<dyn Pipe>> {
+  use std::fs::OpenOptions;
+  let mut last = io::Error::new(io::ErrorKind::NotFound, "Discord is not running");
+  for index in 0..10 {
+    match OpenOptions::new().read(true).write(true).open(format!(r"\\?\pipe\discord-ipc-{}", index)) {
+      Ok(pipe) => return Ok(Box::new(NamedPipe(pipe))),
+      Err(e) => last = e,
+    }
+  }
+  return Err(last);
+}
+
+// A named pipe has no read timeout, a read waits with PeekNamedPipe until
+// there is something to read. The frames written are far smaller than the
+// pipe buffer, a write does not wait on the client.
+#[cfg(windows)]
+struct NamedPipe(std::fs::File);
+
+#[cfg(windows)]
+impl Read for NamedPipe {
+  fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr;
+    use windows_sys::Win32::System::Pipes::PeekNamedPipe;
+    if buffer.is_empty() {
+      return Ok(0);
+    }
+    let start = Instant::now();
+    loop {
+      let mut available = 0;
+      let peeked = unsafe { PeekNamedPipe(self.0.as_raw_handle(), ptr::null_mut(), 0, ptr::null_mut(), &mut available, ptr::null_mut()) };
+      if peeked == 0 {
+        return Err(io::Error::last_os_error());
+      }
+      if available > 0 {
+        let length = buffer.len().min(available as usize);
+        return self.0.read(&mut buffer[..length]);
+      }
+      if start.elapsed() >= IPC_TIMEOUT {
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Discord did not answer"));
+      }
+      thread::sleep(Duration::from_millis(10));
+    }
+  }
+}
+
+#[cfg(windows)]
+impl Write for NamedPipe {
+  fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+    return self.0.write(buffer);
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    return self.0.flush();
+  }
+}
+
+// A frame is its opcode and length as little endian u32, then JSON.
+fn send(pipe: &mut dyn Pipe, opcode: u32, body: &serde_json::Value) -> io::Result<serde_json::Value> {
+  let body = body.to_string();
+  let mut frame = Vec::with_capacity(8 + body.len());
+  frame.extend_from_slice(&opcode.to_le_bytes());
+  frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+  frame.extend_from_slice(body.as_bytes());
+  pipe.write_all(&frame)?;
+  // Every frame is answered, read so the socket never fills up.
+  let mut header = [0; 8];
+  pipe.read_exact(&mut header)?;
+  let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+  let mut answer = vec![0; length];
+  pipe.read_exact(&mut answer)?;
+  let answer: serde_json::Value = serde_json::from_slice(&answer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+  if answer["evt"] == "ERROR" {
+    return Err(io::Error::other(answer["data"]["message"].as_str().unwrap_or("error").to_string()));
+  }
+  return Ok(answer);
+}
+
+fn set_activity(pipe: &mut dyn Pipe, nonce: u64, activity: Option<&Activity>) -> io::Result<()> {
+  // Discord refuses a line shorter than 2 letters, an empty one is left out.
+  let activity = activity.map(|activity| {
+    let mut value = json!({ "details": activity.details });
+    if !activity.state.is_empty() {
+      value["state"] = json!(activity.state);
+    }
+    value
+  });
+  send(pipe, OP_FRAME, &json!({
+    "cmd": "SET_ACTIVITY",
+    "args": { "pid": std::process::id(), "activity": activity },
+    "nonce": nonce.to_string(),
+  }))?;
+  return Ok(());
+}
+
+// Talks to the Discord client from a thread of its own, `update` only queues
+// the activity. The first failure, the client not running included, is
+// logged once and the presence stays off for the session.
+pub struct DiscordPresence {
+  sender: Option<Sender<Activity>>,
+  // Disconnected once the thread is done.
+  done: Option<Receiver<()>>,
+  last: Option<Activity>,
+}
+
+impl DiscordPresence {
+  // `client_id` is the id of the Discord application.
+  pub fn start(client_id: &str) -> DiscordPresence {
+    let (sender, activities) = mpsc::channel();
+    let (finished, done) = mpsc::channel::<()>();
+    let client_id = client_id.to_string();
+    thread::spawn(move || {
+      let _finished = finished;
+      if let Err(e) = serve(&client_id, activities) {
+        warn!("Discord presence disabled ({})", e);
+      }
+    });
+    return DiscordPresence { sender: Some(sender), done: Some(done), last: None };
+  }
+
+  pub fn update(&mut self, activity: Activity) {
+    if self.last.as_ref() == Some(&activity) {
+      return;
+    }
+    self.last = Some(activity.clone());
+    if let Some(sender) = &self.sender {
+      // The thread is gone after a failure, the presence stays off.
+      let _ = sender.send(activity);
+    }
+  }
+
+  // Clears the activity and closes the connection. A client that does not
+  // answer in time is left to the thread, which is not waited for.
+  pub fn stop(&mut self) {
+    self.sender = None;
+    if let Some(done) = self.done.take() {
+      if let Err(RecvTimeoutError::Timeout) = done.recv_timeout(STOP_TIMEOUT) {
+        warn!("Discord did not answer, its presence goes with the game");
+      }
+    }
+  }
+}
+
+impl Drop for DiscordPresence {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+fn serve(client_id: &str, activities: Receiver<Activity>) -> io::Result<()> {
+  let mut pipe = connect()?;
+  send(pipe.as_mut(), OP_HANDSHAKE, &json!({ "v": 1, "client_id": client_id }))?;
+  info!("Connected to Discord");
+  let mut nonce = 0;
+  let mut pending: Option<Activity> = None;
+  let mut last_sent: Option<Instant> = None;
+  loop {
+    let wait = match (&pending, last_sent) {
+      (Some(_), Some(sent)) => PRESENCE_INTERVAL.checked_sub(sent.elapsed()).unwrap_or_default(),
+      (Some(_), None) => Duration::from_secs(0),
+      (None, _) => PRESENCE_INTERVAL,
+    };
+    match activities.recv_timeout(wait) {
+      Ok(activity) => pending = Some(activity),
+      Err(RecvTimeoutError::Timeout) => (),
+      Err(RecvTimeoutError::Disconnected) => break,
+    }
+    if pending.is_some() && last_sent.is_none_or(|sent| sent.elapsed() >= PRESENCE_INTERVAL) {
+      nonce += 1;
+      set_activity(pipe.as_mut(), nonce, pending.take().as_ref())?;
+      last_sent = Some(Instant::now());
+    }
+  }
+  nonce += 1;
+  set_activity(pipe.as_mut(), nonce, None)?;
+  info!("Disconnected from Discord");
+  return Ok(());
+}
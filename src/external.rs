@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{placement_inputs, Controller};
+use crate::game::{Game, Input};
+use crate::grid::Grid;
+use crate::piece::{Piece, Tetromino};
+
+// Time the program has to answer before the piece is forfeited.
+pub const BOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Written on one line to the standard input of the program after each spawn.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct BotRequest {
+  pub grid: Grid,
+  pub current_piece: Piece,
+  pub next_pieces: Vec<Tetromino>,
+  pub hold_piece: Option<Tetromino>,
+}
+
+// Read back as one line from its standard output: either where to drop the
+// piece, `{"rotation": 1, "x": 3}` with the rotation counted clockwise from
+// the spawn orientation, or the inputs to play, `{"inputs": ["MoveLeft", "HardDrop"]}`.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(untagged)]
+pub enum BotResponse {
+  Placement { rotation: usize, x: i32 },
+  Inputs { inputs: Vec<Input> },
+}
+
+// A bot in another process, talking JSON lines. Its answers are read by a
+// thread so a slow or stuck program never blocks the event loop: past
+// BOT_TIMEOUT, or on anything but a valid answer, the piece is hard dropped
+// where it is.
+pub struct ProcessBot {
+  child: Child,
+  stdin: Option<ChildStdin>,
+  lines: Receiver<String>,
+  plan: VecDeque<Input>,
+  // The request for the piece in play was sent, and answered or forfeited.
+  asked: bool,
+  answered: bool,
+  waited: Duration,
+}
+
+impl ProcessBot {
+  // `command` is the program followed by its arguments, split on spaces.
+  pub fn spawn(command: &str) -> io::Result<ProcessBot> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty bot command"))?;
+    let mut child = Command::new(program)
+      .args(words)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()?;
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take().unwrap();
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+      for line in BufReader::new(stdout).lines() {
+        match line {
+          Ok(line) => if sender.send(line).is_err() { break },
+          Err(_) => break,
+        }
+      }
+    });
+    info!("Bot started: {}", command);
+    return Ok(ProcessBot { child: child, stdin: stdin, lines: lines, plan: VecDeque::new(), asked: false, answered: false, waited: Duration::from_secs(0) });
+  }
+
+  fn ask(&mut self, game: &Game, piece: &Piece) {
+    let request = BotRequest {
+      grid: game.grid().clone(),
+      current_piece: piece.clone(),
+      next_pieces: game.next_pieces().iter().map(|piece| piece.tetromino).collect(),
+      hold_piece: game.hold_piece().map(|piece| piece.tetromino),
+    };
+    let sent = match self.stdin.as_mut() {
+      Some(stdin) => serde_json::to_writer(&mut *stdin, &request).map_err(io::Error::from)
+        .and_then(|()| stdin.write_all(b"\n"))
+        .and_then(|()| stdin.flush()),
+      None => return,
+    };
+    if let Err(e) = sent {
+      warn!("Bot stopped reading ({}), its pieces are forfeited", e);
+      self.stdin = None;
+    }
+  }
+
+  fn forfeit(&mut self, reason: &str) {
+    warn!("Bot forfeits the piece: {}", reason);
+    self.plan = VecDeque::from(vec![Input::HardDrop]);
+    self.answered = true;
+  }
+}
+
+impl Controller for ProcessBot {
+  fn update(&mut self, game: &Game, delta: Duration) -> Option<Input> {
+    let piece = match game.current_piece() {
+      Some(piece) if !game.is_over() => piece,
+      _ => {
+        self.plan.clear();
+        self.asked = false;
+        self.answered = false;
+        return None;
+      },
+    };
+    if !self.asked {
+      // An answer arriving after the timeout belongs to a forfeited piece.
+      while self.lines.try_recv().is_ok() {}
+      self.ask(game, piece);
+      self.asked = true;
+      self.waited = Duration::from_secs(0);
+      self.plan.clear();
+      // Already reported when it stopped.
+      if self.stdin.is_none() {
+        self.plan.push_back(Input::HardDrop);
+        self.answered = true;
+      }
+    }
+
+    if !self.answered {
+      self.waited += delta;
+      match self.lines.try_recv() {
+        Ok(line) => match serde_json::from_str(&line) {
+          Ok(BotResponse::Placement { rotation, x }) => {
+            self.plan = placement_inputs(piece, rotation, x);
+            self.answered = true;
+          },
+          Ok(BotResponse::Inputs { inputs }) => {
+            self.plan = inputs.into();
+            self.answered = true;
+          },
+          Err(e) => self.forfeit(&format!("invalid answer {:?} ({})", line, e)),
+        },
+        Err(TryRecvError::Empty) if self.waited < BOT_TIMEOUT => (),
+        Err(TryRecvError::Empty) => self.forfeit("no answer in time"),
+        Err(TryRecvError::Disconnected) => {
+          self.stdin = None;
+          self.forfeit("the program exited");
+        },
+      }
+    }
+    return self.plan.pop_front();
+  }
+}
+
+impl Drop for ProcessBot {
+  fn drop(&mut self) {
+    // Closing its input lets a well behaved bot exit on its own.
+    self.stdin = None;
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
@@ -0,0 +1,1314 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::garbage::{attack, is_difficult_clear, GarbageQueue};
+use crate::grid::Grid;
+use crate::item::{roll_item, Item, SLOW_GRAVITY_DURATION, SLOW_GRAVITY_FACTOR};
+use crate::piece::{create_piece, Case, Piece, Tetromino};
+use crate::randomizer::{create_randomizer, create_rng, Randomizer};
+use crate::scoring::{level_for_lines, lines_to_next_level, score_for_clear, ClearFlags};
+use crate::stats::ClearCounts;
+
+const LINE_CLEAR_DELAY: Duration = Duration::from_millis(200);
+
+// Range of the practice speed multiplier.
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 2.0;
+
+// Rows a handicap always leaves free at the top, room for a piece to spawn.
+pub const HANDICAP_FREE_ROWS: usize = 4;
+
+// Shifts tried in order when a rotated piece does not fit where it is. The
+// I piece moves 2 cases between its flat and standing positions, so it needs
+// 2 cases of room from a wall or the stack.
+const KICKS: [(i32, i32); 4] = [(0, 0), (-1, 0), (1, 0), (0, -1)];
+const KICKS_I: [(i32, i32); 7] = [(0, 0), (-1, 0), (1, 0), (-2, 0), (2, 0), (0, -1), (0, -2)];
+
+// Pieces kept by Game::piece_history.
+pub const PIECE_HISTORY: usize = 8;
+
+pub const SPRINT_LINES: u32 = 40;
+pub const ULTRA_DURATION: Duration = Duration::from_secs(120);
+
+// Time to fall one row at `level`, on the gravity curve of the config.
+pub fn drop_speed(config: &GameConfig, level: u32) -> Duration {
+  return config.gravity.row_time(config, level);
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct ScoreStats {
+  pub score: i64,
+  pub level: u32,
+  pub line_removed: u32,
+}
+
+// Marathon: play until top-out.
+// Sprint: clear SPRINT_LINES lines as fast as possible.
+// Ultra: score as much as possible in ULTRA_DURATION.
+// Zen: no top-out, the board is emptied instead, and the level never changes.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Mode {
+  Marathon,
+  Sprint,
+  Ultra,
+  Zen,
+}
+
+impl Mode {
+  pub const ALL: [Mode; 4] = [Mode::Marathon, Mode::Sprint, Mode::Ultra, Mode::Zen];
+
+  pub fn from_name(name: &str) -> Option<Mode> {
+    return match name {
+      "marathon" => Some(Mode::Marathon),
+      "sprint" => Some(Mode::Sprint),
+      "ultra" => Some(Mode::Ultra),
+      "zen" => Some(Mode::Zen),
+      _ => None,
+    };
+  }
+
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Mode::Marathon => "Marathon",
+      Mode::Sprint => "Sprint",
+      Mode::Ultra => "Ultra",
+      Mode::Zen => "Zen",
+    };
+  }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Input {
+  MoveLeft,
+  MoveRight,
+  SoftDrop,
+  HardDrop,
+  RotateCW,
+  RotateCCW,
+  Hold,
+  // Only does something in the items mode, with an item in the slot.
+  UseItem,
+}
+
+impl Input {
+  // Mirror mode reverses the keys that move sideways.
+  pub fn mirrored(self) -> Input {
+    return match self {
+      Input::MoveLeft => Input::MoveRight,
+      Input::MoveRight => Input::MoveLeft,
+      input => input,
+    };
+  }
+}
+
+// Inputs pressed while there is no piece, for the next spawn, the latest of
+// each kind only. Not part of snapshots: it lasts a spawn delay at most.
+#[derive(Clone,Copy,Debug,Default,PartialEq)]
+struct SpawnInputs {
+  // Counterclockwise or not.
+  rotation: Option<bool>,
+  hold: bool,
+  direction: Option<i32>,
+}
+
+// Emitted by `step` and `apply`, in the order things happened, and kept
+// until `drain_events` so the caller decides how to present them.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum GameEvent {
+  PieceLocked,
+  // Number of rows the piece fell.
+  HardDrop(u32),
+  Hold,
+  ItemWon(Item),
+  ItemUsed(Item),
+  // A T piece locked right after a rotation with 3 of its corners filled.
+  TSpin,
+  LinesCleared(u32),
+  // The clear just emitted and the one before were both a tetris or a T-spin.
+  BackToBack,
+  // Rows of garbage to send to the opponent, what is left of a clear once
+  // the pending garbage was cancelled.
+  Attack(u32),
+  LevelUp(u32),
+  ScoreChanged(i64),
+  // Top-out.
+  GameOver,
+  // The goal of the mode was reached, the game is over without a top-out.
+  Finished,
+}
+
+// Everything needed to resume a game, for save games, replays and network
+// play. The randomizer state is not part of it: pieces after the preview
+// come from the randomizer of the game the snapshot is restored into.
+// Fields missing from an older snapshot get their default, unknown fields
+// from a newer one are ignored. Plain values come before nested ones so
+// the snapshot can be written as TOML.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
+pub struct GameSnapshot {
+  pub mode: Mode,
+  pub hold_used: bool,
+  pub over: bool,
+  pub won: bool,
+  pub combo: Option<u32>,
+  pub back_to_back: bool,
+  pub pending_garbage: Vec<u32>,
+  pub pieces_locked: u32,
+  pub chain: u32,
+  pub score: ScoreStats,
+  pub time_played: Duration,
+  pub timer_piece_generation: Duration,
+  pub spawned_at: Duration,
+  pub spawn_delay: Duration,
+  pub timer_line_clear: Option<Duration>,
+  pub item: Option<Item>,
+  pub slow_gravity: Option<Duration>,
+  pub grid: Grid,
+  pub current_piece: Option<Piece>,
+  pub hold_piece: Option<Piece>,
+  pub next_pieces: Vec<Piece>,
+  pub piece_history: Vec<Piece>,
+}
+
+impl Default for GameSnapshot {
+  fn default() -> GameSnapshot {
+    return GameSnapshot {
+      mode: Mode::Marathon,
+      hold_used: false,
+      over: false,
+      won: false,
+      combo: None,
+      back_to_back: false,
+      pending_garbage: Vec::new(),
+      pieces_locked: 0,
+      chain: 0,
+      score: ScoreStats { score: 0, level: 1, line_removed: 0 },
+      time_played: Duration::from_secs(0),
+      timer_piece_generation: Duration::from_secs(0),
+      spawned_at: Duration::from_secs(0),
+      spawn_delay: Duration::from_secs(0),
+      timer_line_clear: None,
+      item: None,
+      slow_gravity: None,
+      grid: Grid::default(),
+      current_piece: None,
+      hold_piece: None,
+      next_pieces: Vec::new(),
+      piece_history: Vec::new(),
+    };
+  }
+}
+
+// The rules of the game, with no dependency on ggez so they can run headless.
+pub struct Game {
+  config: GameConfig,
+  mode: Mode,
+  start_level: u32,
+  time_played: Duration,
+  grid: Grid,
+  // Game time each case of the grid was filled at, by row then column, for
+  // the invisible mode. Moves with the rows on clears and garbage.
+  filled_at: Vec<Vec<Duration>>,
+  current_piece: Option<Piece>,
+  current_piece_ghost_offset_y: i32,
+  // The rows the current piece fills up when dropped at the ghost.
+  ghost_full_rows: Vec<i32>,
+  next_pieces: Vec<Piece>,
+  // The last PIECE_HISTORY pieces spawned, oldest first.
+  piece_history: VecDeque<Piece>,
+  hold_piece: Option<Piece>,
+  hold_used: bool,
+  move_speed: Duration,
+  timer_piece_generation: Duration,
+  // Game time the current piece spawned at. Hard drops are ignored during
+  // the misdrop grace after it when `misdrop_protection` is on.
+  spawned_at: Duration,
+  misdrop_protection: bool,
+  // Inputs of the spawn delay are buffered for the next piece when on,
+  // dropped otherwise.
+  buffer_spawn_inputs: bool,
+  spawn_inputs: SpawnInputs,
+  // Wait before the next spawn, chosen when the previous piece locked.
+  spawn_delay: Duration,
+  line_clear_delay: Duration,
+  // Gravity, spawn and clear delays go this many times faster.
+  speed: f64,
+  timer_line_clear: Option<Duration>,
+  over: bool,
+  won: bool,
+  randomizer: Box<dyn Randomizer>,
+  // Every piece the randomizer gave since the reset, the preview included.
+  dealt: Vec<Tetromino>,
+  // Received from the opponent, entering the board on the next lock that does not clear.
+  garbage: GarbageQueue,
+  // Picks the hole column of incoming garbage.
+  garbage_rng: StdRng,
+  // Items mode: clears may fill the slot of one item, used with Input::UseItem.
+  items: bool,
+  item: Option<Item>,
+  item_rng: StdRng,
+  // Pieces are flipped left-right, see --mirror.
+  mirror: bool,
+  // Rows of garbage at the bottom of the board on reset.
+  handicap_rows: usize,
+  // Time left on the slow gravity item.
+  slow_gravity: Option<Duration>,
+  // Clears in a row before the last one, None once a piece locks without clearing.
+  combo: Option<u32>,
+  // The last clear was a Tetris or a T-spin.
+  back_to_back: bool,
+  // The piece being cleared locked as a T-spin.
+  t_spin_lock: bool,
+  // Cases of the last locked piece still on the board, following the rows
+  // as they fall or get pushed up.
+  last_locked: Vec<(i32, i32)>,
+  // The last piece locked, where it locked.
+  last_piece: Option<Piece>,
+  // Clears caused by the last lock, more than one only with cascade gravity.
+  chain: u32,
+  pieces_locked: u32,
+  // For the summary written at the end of the game.
+  clear_counts: ClearCounts,
+  piece_counts: HashMap<Tetromino, u32>,
+  current_score: ScoreStats,
+  previous_score: ScoreStats,
+  best_score: ScoreStats,
+  // The last successful action on the current piece was a rotation.
+  rotated_last: bool,
+  events: Vec<GameEvent>,
+}
+
+impl Default for Game {
+  fn default() -> Game {
+    return Game::new();
+  }
+}
+
+impl Game {
+  pub fn new() -> Game {
+    return Game::with_config(GameConfig::default(), None);
+  }
+
+  pub fn with_seed(seed: u64) -> Game {
+    return Game::with_config(GameConfig::default(), Some(seed));
+  }
+
+  // The config is expected to be valid, see `GameConfig::validate`.
+  pub fn with_config(config: GameConfig, seed: Option<u64>) -> Game {
+    let randomizer = create_randomizer(config.randomizer, config.pieces, seed);
+    let mut game = Game {
+      grid: Grid::new(config.grid_width, config.grid_height),
+      filled_at: Vec::new(),
+      config: config,
+      mode: Mode::Marathon,
+      start_level: 1,
+      time_played: Duration::from_secs(0),
+      current_piece: None,
+      current_piece_ghost_offset_y: 0,
+      ghost_full_rows: Vec::new(),
+      next_pieces: Vec::new(),
+      piece_history: VecDeque::new(),
+      hold_piece: None,
+      hold_used: false,
+      move_speed: Duration::from_secs(0),
+      timer_piece_generation: Duration::from_secs(0),
+      spawned_at: Duration::from_secs(0),
+      misdrop_protection: false,
+      buffer_spawn_inputs: false,
+      spawn_inputs: SpawnInputs::default(),
+      spawn_delay: Duration::from_secs(0),
+      line_clear_delay: LINE_CLEAR_DELAY,
+      speed: 1.0,
+      timer_line_clear: None,
+      over: false,
+      won: false,
+      randomizer: randomizer,
+      dealt: Vec::new(),
+      garbage: GarbageQueue::new(),
+      garbage_rng: create_rng(seed),
+      items: false,
+      item: None,
+      item_rng: create_rng(seed),
+      mirror: false,
+      handicap_rows: 0,
+      slow_gravity: None,
+      combo: None,
+      back_to_back: false,
+      t_spin_lock: false,
+      last_locked: Vec::new(),
+      last_piece: None,
+      chain: 0,
+      pieces_locked: 0,
+      clear_counts: ClearCounts::default(),
+      piece_counts: HashMap::new(),
+      current_score: ScoreStats::default(),
+      previous_score: ScoreStats::default(),
+      best_score: ScoreStats::default(),
+      rotated_last: false,
+      events: Vec::new(),
+    };
+    game.reset();
+    return game;
+  }
+
+  pub fn reset(&mut self) {
+    self.grid = Grid::new(self.config.grid_width, self.config.grid_height);
+    self.last_locked.clear();
+    self.last_piece = None;
+    // Like incoming garbage, one hole per row.
+    for _ in 0..self.handicap_rows {
+      let hole = self.garbage_rng.gen_range(0, self.grid.width());
+      self.grid.insert_garbage(1, hole);
+    }
+    self.reset_filled_at();
+    self.current_piece = None;
+    self.hold_piece = None;
+    self.hold_used = false;
+    self.move_speed = drop_speed(&self.config, self.start_level);
+    self.timer_piece_generation = Duration::from_secs(0);
+    self.spawned_at = Duration::from_secs(0);
+    self.spawn_inputs = SpawnInputs::default();
+    self.spawn_delay = Duration::from_secs_f64(self.config.spawn_delay);
+    self.timer_line_clear = None;
+    self.time_played = Duration::from_secs(0);
+    self.over = false;
+    self.won = false;
+    self.garbage = GarbageQueue::new();
+    self.item = None;
+    self.slow_gravity = None;
+    self.combo = None;
+    self.back_to_back = false;
+    self.t_spin_lock = false;
+    self.chain = 0;
+    self.pieces_locked = 0;
+    self.clear_counts = ClearCounts::default();
+    self.piece_counts.clear();
+    self.current_score.level = self.start_level;
+    self.current_score.score = 0;
+    self.current_score.line_removed = 0;
+    self.events.clear();
+    self.piece_history.clear();
+    self.next_pieces.clear();
+    self.dealt.clear();
+    for _ in 0..self.config.next_pieces_count {
+      let piece = self.random_piece();
+      self.next_pieces.push(piece);
+    }
+  }
+
+  // Replaces the piece source, the preview is dealt again from it.
+  pub fn set_randomizer(&mut self, randomizer: Box<dyn Randomizer>) {
+    self.randomizer = randomizer;
+    self.reset();
+  }
+
+  // Dealt again in that order, e.g. by a SequenceRandomizer, the game gets
+  // the same pieces.
+  pub fn dealt(&self) -> &[Tetromino] {
+    return &self.dealt;
+  }
+
+  pub fn drain_events(&mut self) -> Vec<GameEvent> {
+    return std::mem::take(&mut self.events);
+  }
+
+  // Mode and starting level take effect on the next `reset`.
+  pub fn set_mode(&mut self, mode: Mode) {
+    self.mode = mode;
+  }
+
+  pub fn set_start_level(&mut self, level: u32) {
+    self.start_level = level.max(1);
+  }
+
+  pub fn start_level(&self) -> u32 {
+    return self.start_level;
+  }
+
+  pub fn set_items(&mut self, items: bool) {
+    self.items = items;
+  }
+
+  pub fn items(&self) -> bool {
+    return self.items;
+  }
+
+  // The pieces of the preview are flipped at once, the one in play is kept.
+  pub fn set_mirror(&mut self, mirror: bool) {
+    self.mirror = mirror;
+    for piece in self.next_pieces.iter_mut() {
+      piece.mirrored = mirror;
+    }
+  }
+
+  pub fn mirror(&self) -> bool {
+    return self.mirror;
+  }
+
+  // Takes effect on the next `reset`. At most the height of the board minus
+  // HANDICAP_FREE_ROWS, so the pieces can still spawn.
+  pub fn set_handicap_rows(&mut self, rows: usize) {
+    self.handicap_rows = rows.min(self.config.grid_height.saturating_sub(HANDICAP_FREE_ROWS));
+  }
+
+  pub fn handicap_rows(&self) -> usize {
+    return self.handicap_rows;
+  }
+
+  pub fn set_misdrop_protection(&mut self, on: bool) {
+    self.misdrop_protection = on;
+  }
+
+  pub fn misdrop_protection(&self) -> bool {
+    return self.misdrop_protection;
+  }
+
+  pub fn set_buffer_spawn_inputs(&mut self, on: bool) {
+    self.buffer_spawn_inputs = on;
+  }
+
+  pub fn buffer_spawn_inputs(&self) -> bool {
+    return self.buffer_spawn_inputs;
+  }
+
+  pub fn item(&self) -> Option<Item> {
+    return self.item;
+  }
+
+  pub fn mode(&self) -> Mode {
+    return self.mode;
+  }
+
+  pub fn time_played(&self) -> Duration {
+    return self.time_played;
+  }
+
+  // Lines to clear before the next level, shown with the score.
+  pub fn lines_to_next_level(&self) -> u32 {
+    return lines_to_next_level(self.start_level, self.current_score.level, self.current_score.line_removed, self.config.lines_per_level);
+  }
+
+  // Lines still to clear in Sprint, time left in Ultra.
+  pub fn lines_left(&self) -> u32 {
+    return SPRINT_LINES.saturating_sub(self.current_score.line_removed);
+  }
+
+  pub fn time_left(&self) -> Duration {
+    return ULTRA_DURATION.checked_sub(self.time_played).unwrap_or_default();
+  }
+
+  // How long completed rows stay on the board before being removed.
+  pub fn set_line_clear_delay(&mut self, delay: Duration) {
+    self.line_clear_delay = delay;
+  }
+
+  // Clamped to MIN_SPEED..MAX_SPEED. Scores are not recorded off 1.0.
+  pub fn set_speed(&mut self, speed: f64) {
+    self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+  }
+
+  pub fn speed(&self) -> f64 {
+    return self.speed;
+  }
+
+  // Time the piece in play takes to fall a row, the game speed included.
+  pub fn row_time(&self) -> Duration {
+    return self.scaled(self.move_speed());
+  }
+
+  // Every delay of the rules is compared through this.
+  fn scaled(&self, duration: Duration) -> Duration {
+    return duration.div_f64(self.speed);
+  }
+
+  pub fn config(&self) -> &GameConfig {
+    return &self.config;
+  }
+
+  pub fn grid(&self) -> &Grid {
+    return &self.grid;
+  }
+
+  // Cases of the last locked piece, while they are on the board.
+  pub fn last_locked(&self) -> &[(i32, i32)] {
+    return &self.last_locked;
+  }
+
+  pub fn last_piece(&self) -> Option<&Piece> {
+    return self.last_piece.as_ref();
+  }
+
+  pub fn snapshot(&self) -> GameSnapshot {
+    return GameSnapshot {
+      mode: self.mode,
+      hold_used: self.hold_used,
+      over: self.over,
+      won: self.won,
+      combo: self.combo,
+      back_to_back: self.back_to_back,
+      pending_garbage: self.garbage.batches().collect(),
+      pieces_locked: self.pieces_locked,
+      chain: self.chain,
+      score: self.current_score,
+      time_played: self.time_played,
+      timer_piece_generation: self.timer_piece_generation,
+      spawned_at: self.spawned_at,
+      spawn_delay: self.spawn_delay,
+      timer_line_clear: self.timer_line_clear,
+      item: self.item,
+      slow_gravity: self.slow_gravity,
+      grid: self.grid.clone(),
+      current_piece: self.current_piece.clone(),
+      hold_piece: self.hold_piece.clone(),
+      next_pieces: self.next_pieces.clone(),
+      piece_history: self.piece_history.iter().cloned().collect(),
+    };
+  }
+
+  // The board size of the snapshot wins over the one of the config.
+  pub fn restore(&mut self, snapshot: &GameSnapshot) {
+    self.mode = snapshot.mode;
+    self.config.grid_width = snapshot.grid.width();
+    self.config.grid_height = snapshot.grid.height();
+    self.grid = snapshot.grid.clone();
+    self.last_locked.clear();
+    self.reset_filled_at();
+    self.current_piece = snapshot.current_piece.clone();
+    self.next_pieces = snapshot.next_pieces.clone();
+    self.piece_history = snapshot.piece_history.iter().cloned().collect();
+    self.hold_piece = snapshot.hold_piece.clone();
+    self.hold_used = snapshot.hold_used;
+    self.current_score = snapshot.score;
+    self.move_speed = drop_speed(&self.config, self.current_score.level.max(1));
+    self.time_played = snapshot.time_played;
+    self.timer_piece_generation = snapshot.timer_piece_generation;
+    self.spawned_at = snapshot.spawned_at;
+    self.spawn_inputs = SpawnInputs::default();
+    self.spawn_delay = snapshot.spawn_delay;
+    self.timer_line_clear = snapshot.timer_line_clear;
+    self.item = snapshot.item;
+    self.slow_gravity = snapshot.slow_gravity;
+    self.over = snapshot.over;
+    self.won = snapshot.won;
+    self.combo = snapshot.combo;
+    self.back_to_back = snapshot.back_to_back;
+    self.pieces_locked = snapshot.pieces_locked;
+    self.chain = snapshot.chain;
+    self.garbage = GarbageQueue::new();
+    for &rows in &snapshot.pending_garbage {
+      self.garbage.push(rows);
+    }
+    self.t_spin_lock = false;
+    self.rotated_last = false;
+    self.events.clear();
+    while self.next_pieces.len() < self.config.next_pieces_count {
+      let piece = self.random_piece();
+      self.next_pieces.push(piece);
+    }
+    self.update_current_piece_ghost();
+  }
+
+  // Replaces the board, keeping the current piece, e.g. to load a fixture.
+  pub fn set_grid(&mut self, grid: Grid) {
+    self.grid = grid;
+    self.last_locked.clear();
+    self.reset_filled_at();
+    self.update_current_piece_ghost();
+  }
+
+  // When the case was filled, in game time; zero for the cases of a restored
+  // or loaded board.
+  pub fn filled_at(&self, x: i32, y: i32) -> Duration {
+    if x < 0 || y < 0 {
+      return Duration::from_secs(0);
+    }
+    return self.filled_at.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or_default();
+  }
+
+  fn reset_filled_at(&mut self) {
+    self.filled_at = vec![vec![Duration::from_secs(0); self.grid.width()]; self.grid.height()];
+  }
+
+  pub fn current_piece(&self) -> Option<&Piece> {
+    return self.current_piece.as_ref();
+  }
+
+  // Row of the ghost piece, never above the piece in play.
+  pub fn ghost_y(&self) -> i32 {
+    return self.current_piece_ghost_offset_y;
+  }
+
+  // From the top, none when the drop clears nothing.
+  pub fn ghost_full_rows(&self) -> &[i32] {
+    return &self.ghost_full_rows;
+  }
+
+  pub fn next_pieces(&self) -> &[Piece] {
+    return &self.next_pieces;
+  }
+
+  pub fn hold_piece(&self) -> Option<&Piece> {
+    return self.hold_piece.as_ref();
+  }
+
+  pub fn pieces_locked(&self) -> u32 {
+    return self.pieces_locked;
+  }
+
+  pub fn clear_counts(&self) -> &ClearCounts {
+    return &self.clear_counts;
+  }
+
+  pub fn piece_counts(&self) -> &HashMap<Tetromino, u32> {
+    return &self.piece_counts;
+  }
+
+  pub fn piece_history(&self) -> &VecDeque<Piece> {
+    return &self.piece_history;
+  }
+
+  // Pieces locked per second of play, 0 before the first second.
+  pub fn pieces_per_second(&self) -> f64 {
+    let seconds = self.time_played.as_secs_f64();
+    if seconds < 1.0 {
+      return 0.0;
+    }
+    return self.pieces_locked as f64 / seconds;
+  }
+
+  pub fn current_score(&self) -> &ScoreStats {
+    return &self.current_score;
+  }
+
+  pub fn previous_score(&self) -> &ScoreStats {
+    return &self.previous_score;
+  }
+
+  pub fn best_score(&self) -> &ScoreStats {
+    return &self.best_score;
+  }
+
+  // Gravity of the level, slower while the slow gravity item lasts.
+  pub fn move_speed(&self) -> Duration {
+    if self.slow_gravity.is_some() {
+      return self.move_speed * SLOW_GRAVITY_FACTOR;
+    }
+    return self.move_speed;
+  }
+
+  pub fn is_clearing(&self) -> bool {
+    return self.timer_line_clear.is_some();
+  }
+
+  // The timers running, for the debug overlay: each with the time it ran and
+  // the time it goes off at.
+  pub fn timers(&self) -> Vec<(&'static str, Duration, Duration)> {
+    let mut timers = Vec::new();
+    if self.over {
+      return timers;
+    }
+    if let Some(timer) = self.timer_line_clear {
+      timers.push(("line clear", timer, self.scaled(self.line_clear_delay)));
+    } else if let Some(piece) = &self.current_piece {
+      let name = if self.grid.collides(piece, 0, 1) { "lock" } else { "gravity" };
+      timers.push((name, piece.last_move, self.row_time()));
+    } else {
+      timers.push(("spawn", self.timer_piece_generation, self.scaled(self.spawn_delay)));
+    }
+    if let Some(left) = self.slow_gravity {
+      timers.push(("slow gravity", SLOW_GRAVITY_DURATION.checked_sub(left).unwrap_or_default(), SLOW_GRAVITY_DURATION));
+    }
+    if self.mode == Mode::Ultra {
+      timers.push(("ultra", self.time_played, ULTRA_DURATION));
+    }
+    return timers;
+  }
+
+  pub fn is_over(&self) -> bool {
+    return self.over;
+  }
+
+  // Over because the goal of the mode was reached rather than by a top-out.
+  pub fn is_won(&self) -> bool {
+    return self.won;
+  }
+
+  pub fn stack_height(&self) -> usize {
+    return self.grid.stack_height();
+  }
+
+  fn random_piece(&mut self) -> Piece {
+    let tetromino = self.randomizer.next();
+    self.dealt.push(tetromino);
+    return self.new_piece(tetromino);
+  }
+
+  fn new_piece(&self, tetromino: Tetromino) -> Piece {
+    let mut piece = create_piece(tetromino, self.grid.width());
+    piece.mirrored = self.mirror;
+    return piece;
+  }
+
+  pub fn step(&mut self, delta: Duration) {
+    if self.over {
+      return;
+    }
+
+    self.time_played += delta;
+    if let Some(left) = self.slow_gravity {
+      self.slow_gravity = left.checked_sub(delta).filter(|left| *left > Duration::from_secs(0));
+    }
+    if self.mode == Mode::Ultra && self.time_played >= ULTRA_DURATION {
+      self.finish();
+      return;
+    }
+
+    if let Some(timer) = self.timer_line_clear {
+      let timer = timer + delta;
+      if timer < self.scaled(self.line_clear_delay) {
+        self.timer_line_clear = Some(timer);
+      } else {
+        self.timer_line_clear = None;
+        self.remove_complete_lines();
+      }
+      return;
+    }
+
+    if self.current_piece.is_none() {
+      if !self.generate_piece(delta) {
+        if self.mode == Mode::Zen {
+          self.grid = Grid::new(self.config.grid_width, self.config.grid_height);
+          self.last_locked.clear();
+          self.update_current_piece_ghost();
+        } else {
+          self.top_out();
+        }
+      }
+      // A new piece waits a full gravity interval before its first fall.
+      return;
+    }
+
+    self.piece_move_down(delta);
+  }
+
+  pub fn apply(&mut self, input: Input) {
+    if self.over {
+      return;
+    }
+    if self.current_piece.is_none() && self.buffer_spawn_inputs {
+      self.buffer_spawn_input(input);
+      return;
+    }
+
+    match input {
+      Input::MoveLeft => self.piece_move_horizontally(-1),
+      Input::MoveRight => self.piece_move_horizontally(1),
+      Input::SoftDrop => self.piece_move_vertically(1),
+      Input::HardDrop => self.piece_drop(),
+      Input::RotateCW => self.rotate(false),
+      Input::RotateCCW => self.rotate(true),
+      Input::Hold => self.hold(),
+      Input::UseItem => self.use_item(),
+    }
+    self.update_current_piece_ghost();
+  }
+
+  // Hard drops, soft drops and items still need a piece.
+  fn buffer_spawn_input(&mut self, input: Input) {
+    match input {
+      Input::MoveLeft => self.spawn_inputs.direction = Some(-1),
+      Input::MoveRight => self.spawn_inputs.direction = Some(1),
+      Input::RotateCW => self.spawn_inputs.rotation = Some(false),
+      Input::RotateCCW => self.spawn_inputs.rotation = Some(true),
+      Input::Hold => self.spawn_inputs.hold = true,
+      Input::SoftDrop | Input::HardDrop | Input::UseItem => (),
+    }
+  }
+
+  // Garbage sent by the opponent, queued as one batch of `rows` rows until
+  // a piece locks without clearing.
+  pub fn add_garbage(&mut self, rows: u32) {
+    if !self.over {
+      self.garbage.push(rows);
+    }
+  }
+
+  pub fn pending_garbage(&self) -> u32 {
+    return self.garbage.pending();
+  }
+
+  // Each batch is pushed under the stack with its own hole, the game is lost
+  // when the stack is pushed out of the board.
+  fn insert_pending_garbage(&mut self) {
+    for rows in self.garbage.take() {
+      let hole = self.garbage_rng.gen_range(0, self.grid.width());
+      debug!("Garbage: {} rows, hole at {}", rows, hole);
+      let pushed = self.grid.insert_garbage(rows as usize, hole);
+      let rows = (rows as usize).min(self.filled_at.len());
+      self.filled_at.drain(..rows);
+      for case in self.last_locked.iter_mut() {
+        case.1 -= rows as i32;
+      }
+      self.last_locked.retain(|&(_, y)| y >= 0);
+      let now = self.time_played;
+      let width = self.grid.width();
+      self.filled_at.extend(std::iter::repeat_n(vec![now; width], rows));
+      if !pushed {
+        self.top_out();
+        return;
+      }
+    }
+  }
+
+  fn rotate(&mut self, counterclockwise: bool) {
+    if self.current_piece.is_none() {
+      return;
+    }
+
+    let current_piece = self.current_piece.as_ref().unwrap();
+    let piece = if counterclockwise { current_piece.rotated_counterclockwise() } else { current_piece.rotated() };
+    // The long pieces may need two cases to get off a wall.
+    let kicks: &[(i32, i32)] = if piece.size() >= 4 { &KICKS_I } else { &KICKS };
+    // A mirrored piece tries the kicks the other way round.
+    let side = if piece.mirrored { -1 } else { 1 };
+    let kick = kicks.iter().map(|&(dx, dy)| (dx * side, dy)).find(|&(dx, dy)| !self.grid.collides(&piece, dx, dy));
+    if let Some((dx, dy)) = kick {
+      let mut piece = piece;
+      piece.x += dx;
+      piece.y += dy;
+      self.current_piece = Some(piece);
+      self.rotated_last = true;
+    }
+  }
+
+  // The item is kept when it cannot be used right now.
+  fn use_item(&mut self) {
+    let item = match self.item {
+      Some(item) if self.items && !self.is_clearing() => item,
+      _ => return,
+    };
+    let height = self.grid.height();
+    match item {
+      Item::ClearBottomRow => {
+        let rows: Vec<bool> = (0..height).map(|y| y == height - 1).collect();
+        self.remove_rows(&rows);
+      },
+      Item::ShrinkStack => {
+        let stack_height = self.grid.stack_height();
+        if stack_height == 0 {
+          return;
+        }
+        let rows: Vec<bool> = (0..height).map(|y| y == height - stack_height).collect();
+        self.remove_rows(&rows);
+      },
+      Item::SlowGravity => self.slow_gravity = Some(SLOW_GRAVITY_DURATION),
+      Item::SwapPiece => {
+        let current = match self.current_piece.as_ref() {
+          Some(current) => current,
+          None => return,
+        };
+        let mut next = self.next_pieces[0].clone();
+        next.x = current.x;
+        next.y = current.y;
+        if self.grid.collides(&next, 0, 0) {
+          return;
+        }
+        let current = self.current_piece.replace(next).unwrap();
+        self.next_pieces[0] = self.new_piece(current.tetromino);
+        self.rotated_last = false;
+      },
+    }
+    self.item = None;
+    debug!("Item used: {:?}", item);
+    self.events.push(GameEvent::ItemUsed(item));
+  }
+
+  fn hold(&mut self) {
+    if self.current_piece.is_none() || self.hold_used {
+      return;
+    }
+    // The piece coming in spawns again, it must fit there.
+    let incoming = self.hold_piece.as_ref().unwrap_or(&self.next_pieces[0]);
+    if self.grid.collides(incoming, 0, 0) {
+      debug!("Hold refused, {:?} does not fit", incoming.tetromino);
+      return;
+    }
+
+    let current_piece = self.current_piece.take().unwrap();
+    let piece = match self.hold_piece.take() {
+      Some(piece) => piece,
+      None => {
+        let next_piece = self.random_piece();
+        self.next_pieces.push(next_piece);
+        self.next_pieces.remove(0)
+      },
+    };
+    self.hold_piece = Some(self.new_piece(current_piece.tetromino));
+    self.current_piece = Some(piece);
+    self.hold_used = true;
+    self.rotated_last = false;
+    debug!("Hold {:?}", current_piece.tetromino);
+    self.events.push(GameEvent::Hold);
+  }
+
+  // Ends the game as lost, also when a puzzle runs out of pieces.
+  pub fn top_out(&mut self) {
+    self.over = true;
+    self.record_scores();
+    info!("Game over, score {}", self.current_score.score);
+    self.events.push(GameEvent::GameOver);
+  }
+
+  // Ends the game as won: the goal of the mode, or of a mission, was reached.
+  pub fn finish(&mut self) {
+    self.over = true;
+    self.won = true;
+    self.timer_line_clear = None;
+    self.record_scores();
+    info!("{} complete in {:?}, score {}", self.mode.name(), self.time_played, self.current_score.score);
+    self.events.push(GameEvent::Finished);
+  }
+
+  fn add_score(&mut self, points: i64) {
+    if points > 0 {
+      self.current_score.score += points;
+      self.events.push(GameEvent::ScoreChanged(self.current_score.score));
+    }
+  }
+
+  // Removes the flagged rows of the grid, one flag per row from the top, and
+  // keeps `filled_at` in step. Returns how many were removed.
+  fn remove_rows(&mut self, removed: &[bool]) -> u32 {
+    self.last_locked.retain(|&(_, y)| !removed.get(y as usize).copied().unwrap_or(false));
+    for case in self.last_locked.iter_mut() {
+      case.1 += removed.iter().skip(case.1 as usize + 1).filter(|&&removed| removed).count() as i32;
+    }
+    let kept: Vec<Vec<Duration>> = std::mem::take(&mut self.filled_at).into_iter().enumerate()
+      .filter(|(y, _)| !removed.get(*y).copied().unwrap_or(false)).map(|(_, row)| row).collect();
+    let width = self.grid.width();
+    self.filled_at = std::iter::repeat_n(vec![Duration::from_secs(0); width], self.grid.height() - kept.len()).chain(kept).collect();
+    return self.grid.remove_rows(removed);
+  }
+
+  // Cascade gravity: the groups left after the clear fall into the holes,
+  // and keep the state of the cases they carry.
+  fn settle(&mut self) {
+    for fall in self.grid.settle() {
+      let times: Vec<Duration> = fall.cases.iter().map(|&(x, y)| self.filled_at(x, y)).collect();
+      for (&(x, y), time) in fall.cases.iter().zip(times) {
+        self.filled_at[(y + fall.rows) as usize][x as usize] = time;
+      }
+      for case in self.last_locked.iter_mut().filter(|case| fall.cases.contains(case)) {
+        case.1 += fall.rows;
+      }
+    }
+  }
+
+  fn remove_complete_lines(&mut self) {
+    let full: Vec<bool> = (0..self.grid.height() as i32).map(|y| self.grid.is_row_full(y)).collect();
+    // With cascade gravity the rows above do not move down as a whole, the
+    // groups fall once the rows are gone.
+    let line_removed = if self.config.cascade {
+      self.last_locked.retain(|&(_, y)| !full[y as usize]);
+      self.grid.empty_rows(&full)
+    } else {
+      self.remove_rows(&full)
+    };
+    self.chain += 1;
+    self.current_score.line_removed += line_removed;
+    self.events.push(GameEvent::LinesCleared(line_removed));
+    if self.items && self.item.is_none() {
+      self.item = roll_item(&mut self.item_rng, line_removed);
+      if let Some(item) = self.item {
+        debug!("Item won: {:?}", item);
+        self.events.push(GameEvent::ItemWon(item));
+      }
+    }
+    self.clear_counts.record(line_removed, self.t_spin_lock);
+    self.compute_score(line_removed);
+    self.compute_attack(line_removed);
+    if self.mode != Mode::Zen && self.increase_level() {
+      self.events.push(GameEvent::LevelUp(self.current_score.level));
+    }
+    if self.mode == Mode::Sprint && self.lines_left() == 0 {
+      self.finish();
+      return;
+    }
+    if self.config.cascade {
+      self.settle();
+      // The falls filled rows: they clear in turn, after the same delay.
+      if self.grid.has_full_row() {
+        self.t_spin_lock = false;
+        if self.line_clear_delay > Duration::from_secs(0) {
+          self.timer_line_clear = Some(Duration::from_secs(0));
+        } else {
+          self.remove_complete_lines();
+        }
+      }
+    }
+  }
+
+  // Three corner rule: the 4 corners of the T bounding box, walls included.
+  fn is_t_spin(&self, piece: &Piece) -> bool {
+    if piece.tetromino != Tetromino::T || !self.rotated_last {
+      return false;
+    }
+    let corners = [(0, 0), (2, 0), (0, 2), (2, 2)];
+    let filled = corners.iter().filter(|&&(x, y)| self.grid.get(piece.x + x, piece.y + y) != Some(Case::Empty)).count();
+    return filled >= 3;
+  }
+
+  fn compute_attack(&mut self, line_removed: u32) {
+    let difficult = is_difficult_clear(line_removed, self.t_spin_lock);
+    let combo = self.combo.map_or(0, |combo| combo + 1);
+    let rows = attack(line_removed, self.t_spin_lock, difficult && self.back_to_back, combo);
+    if difficult && self.back_to_back {
+      self.events.push(GameEvent::BackToBack);
+    }
+    self.combo = Some(combo);
+    self.back_to_back = difficult;
+    let rows = self.garbage.cancel(rows);
+    if rows > 0 {
+      self.events.push(GameEvent::Attack(rows));
+    }
+  }
+
+  // Before `compute_attack`, which moves the back-to-back chain on.
+  fn compute_score(&mut self, line_removed: u32) {
+    let flags = ClearFlags {
+      chain: self.chain,
+      t_spin: self.t_spin_lock,
+      back_to_back: self.back_to_back && is_difficult_clear(line_removed, self.t_spin_lock),
+    };
+    self.add_score(score_for_clear(line_removed, self.current_score.level, flags));
+    debug!("Score: {}", self.current_score.score);
+  }
+
+  // Returns true when the level went up, by one level or more.
+  fn increase_level(&mut self) -> bool {
+    let level = level_for_lines(self.start_level, self.current_score.line_removed, self.config.lines_per_level);
+    if level <= self.current_score.level {
+      return false;
+    }
+    self.current_score.level = level;
+    self.move_speed = drop_speed(&self.config, level);
+    info!("Level: {}, speed: {:?}", level, self.move_speed);
+    return true;
+  }
+
+  fn record_scores(&mut self) {
+    if self.speed != 1.0 {
+      return;
+    }
+    if self.current_score.score > 0 {
+      self.previous_score = self.current_score;
+    }
+    if self.current_score.score > self.best_score.score {
+      self.best_score = self.current_score;
+    }
+  }
+
+  // Returns false when the new piece does not fit, true otherwise, spawned or not.
+  fn generate_piece(&mut self, delta: Duration) -> bool {
+    self.timer_piece_generation += delta;
+    if self.timer_piece_generation >= self.scaled(self.spawn_delay) {
+      let mut piece = self.next_pieces.remove(0);
+      piece.last_move = Duration::from_secs(0);
+      self.timer_piece_generation = Duration::from_secs(0);
+      self.spawned_at = self.time_played;
+      debug!("Spawn {:?}", piece.tetromino);
+      if self.piece_history.len() == PIECE_HISTORY {
+        self.piece_history.pop_front();
+      }
+      self.piece_history.push_back(piece.clone());
+      self.current_piece = Some(piece);
+      self.hold_used = false;
+      // The buffered hold, then the rotation before the piece must fit, so
+      // it may save a top-out; the direction once it is in.
+      let buffered = std::mem::take(&mut self.spawn_inputs);
+      if buffered.hold {
+        self.hold();
+      }
+      if let Some(counterclockwise) = buffered.rotation {
+        self.rotate(counterclockwise);
+      }
+      self.rotated_last = false;
+      let fit_in_grid = !self.grid.collides(self.current_piece.as_ref().unwrap(), 0, 0);
+      if let Some(dx) = buffered.direction.filter(|_| fit_in_grid) {
+        self.piece_move_horizontally(dx);
+      }
+      self.update_current_piece_ghost();
+
+      let next_piece = self.random_piece();
+      self.next_pieces.push(next_piece);
+
+      return fit_in_grid;
+    }
+    return true;
+  }
+
+  fn update_current_piece_ghost(&mut self) {
+    self.ghost_full_rows.clear();
+    if self.current_piece.is_none() {
+      return;
+    }
+
+    // Down from the piece to the last free row, the board has a floor so this
+    // ends. A piece already overlapping the stack keeps the ghost on itself.
+    let piece = self.current_piece.as_ref().unwrap();
+    let drop = (0..).take_while(|&dy| !self.grid.collides(piece, 0, dy)).last().unwrap_or(0);
+    self.current_piece_ghost_offset_y = piece.y + drop;
+
+    // The cases of the piece added to the fill of their rows, at most one
+    // row per case of the piece.
+    let mut rows: Vec<(i32, usize)> = Vec::new();
+    for &(x, y) in piece.cells() {
+      let (x, y) = (piece.x + x, self.current_piece_ghost_offset_y + y);
+      if self.grid.get(x, y) != Some(Case::Empty) {
+        continue;
+      }
+      match rows.iter_mut().find(|(row, _)| *row == y) {
+        Some((_, filled)) => *filled += 1,
+        None => rows.push((y, self.grid.row_fill(y) + 1)),
+      }
+    }
+    rows.retain(|&(_, filled)| filled == self.grid.width());
+    rows.sort_unstable();
+    self.ghost_full_rows = rows.into_iter().map(|(row, _)| row).collect();
+  }
+
+  fn piece_move_horizontally(&mut self, dx: i32) {
+    if self.current_piece.is_none() {
+      return;
+    }
+
+    if !self.check_collision(dx, 0) {
+      let piece = self.current_piece.as_mut().unwrap();
+      piece.x += dx;
+      self.rotated_last = false;
+    }
+  }
+
+  fn piece_move_vertically(&mut self, dy: i32) {
+    if self.current_piece.is_none() {
+      return;
+    }
+
+    if !self.check_collision(0, dy) {
+      let piece = self.current_piece.as_mut().unwrap();
+      piece.y += dy;
+      piece.last_move = Duration::from_secs(0);
+      self.rotated_last = false;
+    }
+  }
+
+  fn piece_drop(&mut self) {
+    if self.current_piece.is_none() {
+      return;
+    }
+    if self.misdrop_protection && self.time_played < self.spawned_at + Duration::from_secs_f64(self.config.misdrop_grace) {
+      debug!("Hard drop ignored, {:?} after the spawn", self.time_played - self.spawned_at);
+      return;
+    }
+
+    let mut rows = 0;
+    while !self.check_collision(0, 1) {
+      let piece = self.current_piece.as_mut().unwrap();
+      piece.y += 1;
+      rows += 1;
+    }
+    if rows > 0 {
+      self.rotated_last = false;
+    }
+    self.events.push(GameEvent::HardDrop(rows));
+    self.lock_piece(true);
+  }
+
+  fn lock_piece(&mut self, hard_drop: bool) {
+    let piece = self.current_piece.take().unwrap();
+    self.t_spin_lock = self.is_t_spin(&piece);
+    if self.t_spin_lock {
+      self.events.push(GameEvent::TSpin);
+    }
+    self.grid.put_piece(&piece);
+    self.last_locked = piece.cells().iter().map(|&(x, y)| (piece.x + x, piece.y + y)).filter(|&(_, y)| y >= 0).collect();
+    self.last_piece = Some(piece.clone());
+    for &(x, y) in piece.cells() {
+      let (x, y) = (piece.x + x, piece.y + y);
+      if x >= 0 && y >= 0 {
+        if let Some(case) = self.filled_at.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) {
+          *case = self.time_played;
+        }
+      }
+    }
+    // The cases above the board are lost, so locking there ends the game.
+    if piece.cells().iter().any(|&(_, y)| piece.y + y < 0) {
+      if self.mode != Mode::Zen {
+        self.top_out();
+        return;
+      }
+      self.grid = Grid::new(self.config.grid_width, self.config.grid_height);
+      self.last_locked.clear();
+    }
+    self.pieces_locked += 1;
+    *self.piece_counts.entry(piece.tetromino).or_insert(0) += 1;
+    debug!("Locked {:?} at ({}, {})", piece.tetromino, piece.x, piece.y);
+    self.events.push(GameEvent::PieceLocked);
+
+    let spawn_delay = if hard_drop { self.config.hard_drop_spawn_delay } else { self.config.spawn_delay };
+    self.spawn_delay = Duration::from_secs_f64(spawn_delay);
+    self.timer_piece_generation = Duration::from_secs(0);
+    self.chain = 0;
+    if self.grid.has_full_row() {
+      if self.line_clear_delay > Duration::from_secs(0) {
+        self.timer_line_clear = Some(Duration::from_secs(0));
+      } else {
+        self.remove_complete_lines();
+      }
+    } else {
+      self.combo = None;
+      self.insert_pending_garbage();
+    }
+  }
+
+  fn piece_move_down(&mut self, delta: Duration) {
+    if self.current_piece.is_none() {
+      return;
+    }
+
+    let piece = self.current_piece.as_ref().unwrap();
+    let elapsed = piece.last_move + delta;
+    let row_time = self.row_time();
+    if elapsed <= row_time {
+      let piece = self.current_piece.as_mut().unwrap();
+      piece.last_move = elapsed;
+      return;
+    }
+
+    // Gravity faster than the steps covers several rows at once, down to the
+    // whole board for a zero row time.
+    let rows = if row_time.as_nanos() == 0 { self.grid.height() as u128 } else { elapsed.as_nanos() / row_time.as_nanos() };
+    let dy = (0..rows.min(self.grid.height() as u128) as i32).take_while(|&dy| !self.check_collision(0, dy + 1)).count() as i32;
+    if dy == 0 {
+      self.lock_piece(false);
+    } else {
+      let piece = self.current_piece.as_mut().unwrap();
+      piece.y += dy;
+      piece.last_move = Duration::from_secs(0);
+      self.rotated_last = false;
+    }
+  }
+
+  fn check_collision(&self, dx: i32, dy: i32) -> bool {
+    return match &self.current_piece {
+      Some(piece) => self.grid.collides(piece, dx, dy),
+      None => false,
+    };
+  }
+}
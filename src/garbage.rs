@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+
+// Rows sent for a clear of 0 to 4 lines, without and with a T-spin.
+const ATTACK_LINES: [u32; 5] = [0, 0, 1, 2, 4];
+const ATTACK_T_SPIN: [u32; 4] = [0, 2, 4, 6];
+// Extra rows for the nth clear in a row, the last value applies past the end.
+const COMBO_BONUS: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const BACK_TO_BACK_BONUS: u32 = 1;
+
+// A Tetris or a T-spin clear, which keeps the back-to-back chain going.
+pub fn is_difficult_clear(lines: u32, t_spin: bool) -> bool {
+  return lines == 4 || (t_spin && lines > 0);
+}
+
+// Rows of garbage sent for a clear, before cancellation. `combo` is the
+// number of clears in a row before this one, `back_to_back` that this clear
+// and the previous one were both difficult.
+pub fn attack(lines: u32, t_spin: bool, back_to_back: bool, combo: u32) -> u32 {
+  if lines == 0 {
+    return 0;
+  }
+  let table: &[u32] = if t_spin { &ATTACK_T_SPIN } else { &ATTACK_LINES };
+  let base = table.get(lines as usize).or(table.last()).copied().unwrap_or(0);
+  let combo_bonus = COMBO_BONUS.get(combo as usize).or(COMBO_BONUS.last()).copied().unwrap_or(0);
+  let back_to_back_bonus = if back_to_back { BACK_TO_BACK_BONUS } else { 0 };
+  return base + combo_bonus + back_to_back_bonus;
+}
+
+// Garbage received and not yet on the board, in batches of rows that share a
+// hole. An attack first cancels the oldest batches, the rest is sent.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct GarbageQueue {
+  batches: VecDeque<u32>,
+}
+
+impl GarbageQueue {
+  pub fn new() -> GarbageQueue {
+    return GarbageQueue::default();
+  }
+
+  pub fn push(&mut self, rows: u32) {
+    if rows > 0 {
+      self.batches.push_back(rows);
+    }
+  }
+
+  // Rows waiting, shown in the meter beside the board.
+  pub fn pending(&self) -> u32 {
+    return self.batches.iter().sum();
+  }
+
+  pub fn batches(&self) -> impl Iterator<Item = u32> + '_ {
+    return self.batches.iter().copied();
+  }
+
+  // Removes up to `attack` rows, oldest first, and returns the rows of the
+  // attack left to send to the opponent.
+  pub fn cancel(&mut self, attack: u32) -> u32 {
+    let mut attack = attack;
+    while attack > 0 {
+      let front = match self.batches.front_mut() {
+        Some(front) => front,
+        None => break,
+      };
+      let cancelled = attack.min(*front);
+      *front -= cancelled;
+      attack -= cancelled;
+      if *front == 0 {
+        self.batches.pop_front();
+      }
+    }
+    return attack;
+  }
+
+  // Empties the queue, oldest batch first.
+  pub fn take(&mut self) -> Vec<u32> {
+    return self.batches.drain(..).collect();
+  }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+
+// The tables of the other games count in frames of a 60 Hz screen.
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// Frames per row of the NES version from its level 0, which is our level 1.
+// Level 29 and above stay at 1 frame.
+const NES_FRAMES: [u32; 29] = [48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2];
+
+// Internal gravity of TGM, in 1/256 row per frame, from the internal level it
+// applies to. Each of our levels covers TGM_LEVELS_PER_LEVEL of them, so 20G
+// (20 rows a frame) is reached at level 11.
+const TGM_GRAVITY: [(u32, u32); 30] = [
+  (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48), (90, 64), (100, 80),
+  (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32), (230, 64), (233, 96), (236, 128), (239, 160),
+  (243, 192), (247, 224), (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024), (450, 768), (500, 5120),
+];
+const TGM_LEVELS_PER_LEVEL: u32 = 50;
+
+// How the time a piece takes to fall one row goes down with the level.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GravityCurve {
+  // (drop_speed_base - (level - 1) * drop_speed_step) ^ (level - 1) seconds.
+  Classic,
+  // The frames per row table of the NES version.
+  Nes,
+  // The internal gravity ramp of TGM, up to 20 rows a frame.
+  Tgm,
+}
+
+impl GravityCurve {
+  pub fn from_name(name: &str) -> Option<GravityCurve> {
+    return match name {
+      "classic" => Some(GravityCurve::Classic),
+      "nes" => Some(GravityCurve::Nes),
+      "tgm" => Some(GravityCurve::Tgm),
+      _ => None,
+    };
+  }
+
+  // Time to fall one row at `level`, from 1. Below a frame, pieces fall
+  // several rows in one step of the game.
+  pub fn row_time(&self, config: &GameConfig, level: u32) -> Duration {
+    let level = level.max(1);
+    return match self {
+      GravityCurve::Classic => {
+        let level_f64 = (level - 1) as f64;
+        // Past the level where the base reaches zero pieces fall the whole board at once.
+        let base = (config.drop_speed_base - (level_f64 * config.drop_speed_step)).max(0.0);
+        Duration::from_secs_f64(base.powf(level_f64))
+      },
+      GravityCurve::Nes => FRAME * NES_FRAMES.get(level as usize - 1).copied().unwrap_or(1),
+      GravityCurve::Tgm => {
+        let internal = (level - 1).saturating_mul(TGM_LEVELS_PER_LEVEL);
+        let gravity = TGM_GRAVITY.iter().rev().find(|&&(from, _)| from <= internal).map_or(4, |&(_, gravity)| gravity);
+        FRAME * 256 / gravity
+      },
+    };
+  }
+}
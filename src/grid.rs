@@ -0,0 +1,376 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::piece::{Case, Piece, Tetromino};
+
+pub const DEFAULT_GRID_WIDTH: usize = 10;
+pub const DEFAULT_GRID_HEIGHT: usize = 20;
+// Default width with the pentomino set.
+pub const PENTOMINO_GRID_WIDTH: usize = 12;
+
+// The board, indexed by column then row, row 0 being the top.
+// Coordinates are signed so piece arithmetic can go out of range safely:
+// `get` returns None there, and `collides` treats the walls and the floor as
+// solid and the rows above the board as empty.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(into = "GridData", try_from = "GridData")]
+pub struct Grid {
+  width: usize,
+  height: usize,
+  cases: Vec<Vec<Case>>,
+  // Cases filled in each row, kept in step by every change to `cases`.
+  filled: Vec<usize>,
+}
+
+// Serialized form of a Grid: one string per row from the top, '.' for an
+// empty case, '#' for garbage and the piece letter otherwise. Checked when read back so
+// a hand-edited or truncated file cannot produce rows of different lengths.
+#[derive(Serialize,Deserialize)]
+struct GridData {
+  rows: Vec<String>,
+}
+
+fn case_code(case: Option<Case>) -> char {
+  return match case {
+    Some(Case::Filled(tetromino)) => tetromino.letter(),
+    Some(Case::Garbage) => '#',
+    _ => '.',
+  };
+}
+
+// Read back from `case_code`, None for an unknown letter.
+pub fn case_from_code(letter: char) -> Option<Case> {
+  return match letter {
+    '.' => Some(Case::Empty),
+    '#' => Some(Case::Garbage),
+    _ => Tetromino::from_letter(letter).map(Case::Filled),
+  };
+}
+
+impl From<Grid> for GridData {
+  fn from(grid: Grid) -> GridData {
+    let rows = (0..grid.height as i32).map(|y| (0..grid.width as i32).map(|x| case_code(grid.get(x, y))).collect()).collect();
+    return GridData { rows: rows };
+  }
+}
+
+impl TryFrom<GridData> for Grid {
+  type Error = String;
+
+  fn try_from(data: GridData) -> Result<Grid, String> {
+    let width = data.rows.first().map(|row| row.chars().count()).unwrap_or(0);
+    if width == 0 || data.rows.iter().any(|row| row.chars().count() != width) {
+      return Err(String::from("grid rows must all have the same, non-zero length"));
+    }
+    let mut grid = Grid::new(width, data.rows.len());
+    for (y, row) in data.rows.iter().enumerate() {
+      for (x, letter) in row.chars().enumerate() {
+        let case = case_from_code(letter).ok_or(format!("unknown case '{}' in grid", letter))?;
+        grid.set(x as i32, y as i32, case);
+      }
+    }
+    return Ok(grid);
+  }
+}
+
+// Why a text is not a board, `line` and `column` counted from 1.
+#[derive(Clone,Debug,PartialEq)]
+pub enum ParseError {
+  Empty,
+  WrongWidth { line: usize, width: usize, expected: usize },
+  UnknownCharacter { line: usize, column: usize, character: char },
+  WrongRowCount { rows: usize, expected: usize },
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return match self {
+      ParseError::Empty => write!(f, "no row"),
+      ParseError::WrongWidth { line, width, expected } => write!(f, "line {}: row is {} cases wide, expected {}", line, width, expected),
+      ParseError::UnknownCharacter { line, column, character } => write!(f, "line {}, column {}: unknown case '{}'", line, column, character),
+      ParseError::WrongRowCount { rows, expected } => write!(f, "{} rows, expected {}", rows, expected),
+    };
+  }
+}
+
+// A group of cases connected by their sides, moved down by `rows` rows
+// during `Grid::settle`.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Fall {
+  pub cases: Vec<(i32, i32)>,
+  pub rows: i32,
+}
+
+impl Default for Grid {
+  fn default() -> Grid {
+    return Grid::new(DEFAULT_GRID_WIDTH, DEFAULT_GRID_HEIGHT);
+  }
+}
+
+impl Grid {
+  pub fn new(width: usize, height: usize) -> Grid {
+    return Grid { width: width, height: height, cases: vec![vec![Case::Empty; height]; width], filled: vec![0; height] };
+  }
+
+  pub fn width(&self) -> usize {
+    return self.width;
+  }
+
+  pub fn height(&self) -> usize {
+    return self.height;
+  }
+
+  fn in_range(&self, x: i32, y: i32) -> bool {
+    return x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32;
+  }
+
+  pub fn get(&self, x: i32, y: i32) -> Option<Case> {
+    if !self.in_range(x, y) {
+      return None;
+    }
+    return Some(self.cases[x as usize][y as usize]);
+  }
+
+  // Out of range writes are ignored.
+  pub fn set(&mut self, x: i32, y: i32, case: Case) {
+    if self.in_range(x, y) {
+      let old = std::mem::replace(&mut self.cases[x as usize][y as usize], case);
+      if old != Case::Empty {
+        self.filled[y as usize] -= 1;
+      }
+      if case != Case::Empty {
+        self.filled[y as usize] += 1;
+      }
+    }
+  }
+
+  // Filled cases of the row, 0 out of the board.
+  pub fn row_fill(&self, y: i32) -> usize {
+    return if y >= 0 && y < self.height as i32 { self.filled[y as usize] } else { 0 };
+  }
+
+  // Every row from the top in a single string, with the codes of GridData.
+  pub fn codes(&self) -> String {
+    return (0..self.height as i32).flat_map(|y| (0..self.width as i32).map(move |x| case_code(self.get(x, y)))).collect();
+  }
+
+  // One line per row from the top, with the codes of GridData.
+  pub fn to_ascii(&self) -> String {
+    let codes: Vec<char> = self.codes().chars().collect();
+    return codes.chunks(self.width.max(1)).map(|row| row.iter().collect::<String>() + "\n").collect();
+  }
+
+  // Read back from `to_ascii`, as wide as its first row and as high as its
+  // rows. Blank lines and trailing spaces are ignored, CRLF is read as LF.
+  pub fn from_ascii(text: &str) -> Result<Grid, ParseError> {
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+      let line = line.trim_end();
+      if line.is_empty() {
+        continue;
+      }
+      let row = line.chars().enumerate()
+        .map(|(column, character)| case_from_code(character).ok_or(ParseError::UnknownCharacter { line: index + 1, column: column + 1, character: character }))
+        .collect::<Result<Vec<Case>, ParseError>>()?;
+      if let Some(first) = rows.first().map(|first: &Vec<Case>| first.len()).filter(|&first| first != row.len()) {
+        return Err(ParseError::WrongWidth { line: index + 1, width: row.len(), expected: first });
+      }
+      rows.push(row);
+    }
+    if rows.is_empty() {
+      return Err(ParseError::Empty);
+    }
+    let mut grid = Grid::new(rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+      for (x, &case) in row.iter().enumerate() {
+        grid.set(x as i32, y as i32, case);
+      }
+    }
+    return Ok(grid);
+  }
+
+  // `from_ascii` for a board of the given size.
+  pub fn from_ascii_sized(text: &str, width: usize, height: usize) -> Result<Grid, ParseError> {
+    let grid = Grid::from_ascii(text)?;
+    if grid.width != width {
+      // The first row, the others are as wide.
+      let line = text.lines().position(|line| !line.trim_end().is_empty()).unwrap_or(0) + 1;
+      return Err(ParseError::WrongWidth { line: line, width: grid.width, expected: width });
+    }
+    if grid.height != height {
+      return Err(ParseError::WrongRowCount { rows: grid.height, expected: height });
+    }
+    return Ok(grid);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    return self.cases.iter().all(|column| column.iter().all(|&case| case == Case::Empty));
+  }
+
+  pub fn is_row_full(&self, y: i32) -> bool {
+    return y >= 0 && y < self.height as i32 && self.filled[y as usize] == self.width;
+  }
+
+  pub fn has_full_row(&self) -> bool {
+    return (0..self.height as i32).any(|y| self.is_row_full(y));
+  }
+
+  // Removes every full row, moving the rows above down, and returns how many were removed.
+  pub fn clear_full_rows(&mut self) -> u32 {
+    let full: Vec<bool> = (0..self.height as i32).map(|y| self.is_row_full(y)).collect();
+    return self.remove_rows(&full);
+  }
+
+  // Removes the rows flagged in `removed`, one flag per row from the top,
+  // moving the rows above down. Returns how many were removed.
+  pub fn remove_rows(&mut self, removed: &[bool]) -> u32 {
+    let mut line_removed: u32 = 0;
+    let mut y_to = self.height;
+    for y in (0..self.height).rev() {
+      if removed.get(y).copied().unwrap_or(false) {
+        line_removed += 1;
+        continue;
+      }
+      y_to -= 1;
+      if y_to != y {
+        for column in self.cases.iter_mut() {
+          column[y_to] = column[y];
+        }
+        self.filled[y_to] = self.filled[y];
+      }
+    }
+    for column in self.cases.iter_mut() {
+      for case in column.iter_mut().take(y_to) {
+        *case = Case::Empty;
+      }
+    }
+    for filled in self.filled.iter_mut().take(y_to) {
+      *filled = 0;
+    }
+
+    return line_removed;
+  }
+
+  // Empties the rows flagged in `removed`, one flag per row from the top,
+  // without moving the others. Returns how many were emptied.
+  pub fn empty_rows(&mut self, removed: &[bool]) -> u32 {
+    let mut line_removed: u32 = 0;
+    for (y, _) in removed.iter().enumerate().take(self.height).filter(|(_, &removed)| removed) {
+      line_removed += 1;
+      for column in self.cases.iter_mut() {
+        column[y] = Case::Empty;
+      }
+      self.filled[y] = 0;
+    }
+    return line_removed;
+  }
+
+  // Cascade gravity: every group of connected cases falls as far as it can,
+  // until none can fall any more. Returns the falls in the order they
+  // happened, with the cases where each group was before it fell.
+  pub fn settle(&mut self) -> Vec<Fall> {
+    let mut falls = Vec::new();
+    loop {
+      let mut groups = self.groups();
+      // The lowest groups first, so the ones above land on them.
+      groups.sort_by_key(|group| -group.iter().map(|&(_, y)| y).max().unwrap());
+      let mut moved = false;
+      for group in groups {
+        let rows = (1..).take_while(|&dy| group.iter().all(|&(x, y)| {
+          self.get(x, y + dy) == Some(Case::Empty) || group.contains(&(x, y + dy))
+        })).last().unwrap_or(0);
+        if rows == 0 {
+          continue;
+        }
+        let cases: Vec<Case> = group.iter().map(|&(x, y)| self.get(x, y).unwrap()).collect();
+        for &(x, y) in &group {
+          self.set(x, y, Case::Empty);
+        }
+        for (&(x, y), &case) in group.iter().zip(cases.iter()) {
+          self.set(x, y + rows, case);
+        }
+        falls.push(Fall { cases: group, rows: rows });
+        moved = true;
+      }
+      if !moved {
+        return falls;
+      }
+    }
+  }
+
+  // The filled cases, grouped by flood fill through their sides.
+  fn groups(&self) -> Vec<Vec<(i32, i32)>> {
+    let mut seen = vec![vec![false; self.height]; self.width];
+    let mut groups = Vec::new();
+    for x in 0..self.width as i32 {
+      for y in 0..self.height as i32 {
+        if seen[x as usize][y as usize] || self.get(x, y) == Some(Case::Empty) {
+          continue;
+        }
+        seen[x as usize][y as usize] = true;
+        let mut group = Vec::new();
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+          group.push((x, y));
+          for &(nx, ny) in &[(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if self.get(nx, ny).is_some_and(|case| case != Case::Empty) && !seen[nx as usize][ny as usize] {
+              seen[nx as usize][ny as usize] = true;
+              stack.push((nx, ny));
+            }
+          }
+        }
+        groups.push(group);
+      }
+    }
+    return groups;
+  }
+
+  // Pushes the stack up by `rows` and fills the bottom rows with garbage,
+  // leaving the `hole` column empty. Returns false when filled cases were
+  // pushed out of the top of the board.
+  pub fn insert_garbage(&mut self, rows: usize, hole: usize) -> bool {
+    let rows = rows.min(self.height);
+    let overflow = self.cases.iter().any(|column| column[..rows].iter().any(|&case| case != Case::Empty));
+    for (x, column) in self.cases.iter_mut().enumerate() {
+      column.rotate_left(rows);
+      let garbage = if x == hole { Case::Empty } else { Case::Garbage };
+      for case in column.iter_mut().skip(self.height - rows) {
+        *case = garbage;
+      }
+    }
+    self.filled.rotate_left(rows);
+    let garbage = if hole < self.width { self.width - 1 } else { self.width };
+    for filled in self.filled.iter_mut().skip(self.height - rows) {
+      *filled = garbage;
+    }
+    return !overflow;
+  }
+
+  pub fn collides(&self, piece: &Piece, dx: i32, dy: i32) -> bool {
+    return piece.cells().iter().any(|&(x, y)| {
+      let (x, y) = (piece.x + dx + x, piece.y + dy + y);
+      if x < 0 || x >= self.width as i32 || y >= self.height as i32 {
+        return true;
+      }
+      y >= 0 && self.get(x, y) != Some(Case::Empty)
+    });
+  }
+
+  pub fn put_piece(&mut self, piece: &Piece) {
+    for &(x, y) in piece.cells() {
+      self.set(piece.x + x, piece.y + y, Case::Filled(piece.tetromino));
+    }
+  }
+
+  // Number of rows between the floor and the highest filled case.
+  pub fn stack_height(&self) -> usize {
+    for y in 0..self.height {
+      if self.cases.iter().any(|column| column[y] != Case::Empty) {
+        return self.height - y;
+      }
+    }
+    return 0;
+  }
+}
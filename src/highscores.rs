@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::{Game, Mode};
+use crate::piece::PieceSet;
+use crate::stats::{write_atomically, GameSummary};
+
+pub const HIGH_SCORES_FILE: &str = "highscores.json";
+// Entries kept in each table.
+pub const HIGH_SCORES: usize = 10;
+
+// One game of a table.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct HighScore {
+  // Seconds since the Unix epoch.
+  pub ended_at: u64,
+  pub score: i64,
+  pub level: u32,
+  pub lines: u32,
+  pub seconds: f64,
+}
+
+impl HighScore {
+  pub fn from_summary(summary: &GameSummary) -> HighScore {
+    return HighScore { ended_at: summary.ended_at, score: summary.score, level: summary.level, lines: summary.lines, seconds: summary.seconds };
+  }
+}
+
+// Sprints are ranked by time, only once the lines are done; the other modes
+// by score.
+fn better(mode: Mode, entry: &HighScore, other: &HighScore) -> bool {
+  return match mode {
+    Mode::Sprint => entry.seconds < other.seconds,
+    _ => entry.score > other.score,
+  };
+}
+
+// The table of the games of `mode` with the rules of `game`: its name is the
+// mode, followed by what changes the scores, e.g. "Sprint 12x24 pentomino".
+pub fn table_name(mode: Mode, game: &Game) -> String {
+  let config = game.config();
+  let default = GameConfig::default();
+  let mut name = mode.name().to_string();
+  if (config.grid_width, config.grid_height) != (default.grid_width, default.grid_height) {
+    name = format!("{} {}x{}", name, config.grid_width, config.grid_height);
+  }
+  if config.pieces == PieceSet::Pentomino {
+    name = format!("{} pentomino", name);
+  }
+  if config.cascade {
+    name = format!("{} cascade", name);
+  }
+  if game.items() {
+    name = format!("{} items", name);
+  }
+  return name;
+}
+
+// Content of highscores.json, by table name.
+#[derive(Clone,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct HighScores {
+  #[serde(default)]
+  pub tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+  // Empty when the file does not exist yet.
+  pub fn load(directory: &Path) -> io::Result<HighScores> {
+    let text = match fs::read_to_string(directory.join(HIGH_SCORES_FILE)) {
+      Ok(text) => text,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HighScores::default()),
+      Err(e) => return Err(e),
+    };
+    return Ok(serde_json::from_str(&text)?);
+  }
+
+  pub fn save(&self, directory: &Path) -> io::Result<()> {
+    return write_atomically(&directory.join(HIGH_SCORES_FILE), &serde_json::to_vec_pretty(self)?);
+  }
+
+  // Best first.
+  pub fn table(&self, name: &str) -> &[HighScore] {
+    return self.tables.get(name).map_or(&[], |table| table.as_slice());
+  }
+
+  // The rank of `entry` from 0 when it made it into the table. A tie goes
+  // after the games already there.
+  pub fn record(&mut self, name: &str, mode: Mode, entry: HighScore) -> Option<usize> {
+    let table = self.tables.entry(name.to_string()).or_default();
+    let rank = table.iter().position(|other| better(mode, &entry, other)).unwrap_or(table.len());
+    if rank >= HIGH_SCORES {
+      return None;
+    }
+    table.insert(rank, entry);
+    table.truncate(HIGH_SCORES);
+    return Some(rank);
+  }
+}
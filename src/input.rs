@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crate::game::Input;
+
+// A direction held moves again after DAS_DELAY, then every DAS_INTERVAL.
+pub const DAS_DELAY: Duration = Duration::from_millis(170);
+pub const DAS_INTERVAL: Duration = Duration::from_millis(50);
+// A soft drop held goes down a row every SOFT_DROP_INTERVAL.
+pub const SOFT_DROP_INTERVAL: Duration = Duration::from_millis(50);
+
+// A key of a piece held down, with the time since it was pressed.
+#[derive(Clone,Copy,Debug,PartialEq)]
+struct Held {
+  input: Input,
+  time: Duration,
+}
+
+// The keys of a player held down, read once per logic tick instead of the
+// repeats of the system, so chords work: a direction and a soft drop repeat
+// together, and a rotation leaves both held. Of Left and Right held together,
+// the last one pressed moves; released, the other goes on at once when it
+// was held long enough.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct HeldInputs {
+  // In the order they were pressed.
+  held: Vec<Held>,
+  // Time held the moving direction repeats at next, from its press.
+  next_move: Duration,
+  next_drop: Duration,
+}
+
+fn is_direction(input: Input) -> bool {
+  return input == Input::MoveLeft || input == Input::MoveRight;
+}
+
+impl HeldInputs {
+  pub fn new() -> HeldInputs {
+    return HeldInputs::default();
+  }
+
+  // The input to apply now, None when the key is already down: the system
+  // repeats a key held with more presses.
+  pub fn press(&mut self, input: Input) -> Option<Input> {
+    if self.is_held(input) {
+      return None;
+    }
+    self.held.push(Held { input: input, time: Duration::from_secs(0) });
+    match input {
+      Input::MoveLeft | Input::MoveRight => self.next_move = DAS_DELAY,
+      Input::SoftDrop => self.next_drop = SOFT_DROP_INTERVAL,
+      _ => (),
+    }
+    return Some(input);
+  }
+
+  pub fn release(&mut self, input: Input) {
+    let moving = self.direction();
+    self.held.retain(|held| held.input != input);
+    // The other direction takes over, held long enough or not.
+    if moving.map(|held| held.input) == Some(input) {
+      if let Some(held) = self.direction() {
+        self.next_move = held.time.max(DAS_DELAY);
+      }
+    }
+  }
+
+  // Everything is up, e.g. when the window loses the focus.
+  pub fn clear(&mut self) {
+    self.held.clear();
+  }
+
+  pub fn is_held(&self, input: Input) -> bool {
+    return self.held.iter().any(|held| held.input == input);
+  }
+
+  // The direction that moves: the last one pressed of those held.
+  fn direction(&self) -> Option<Held> {
+    return self.held.iter().rev().copied().find(|held| is_direction(held.input));
+  }
+
+  // The repeats of the keys held during `delta`, in the order of a tick:
+  // the move before the drop.
+  pub fn update(&mut self, delta: Duration) -> Vec<Input> {
+    for held in self.held.iter_mut() {
+      held.time += delta;
+    }
+    let mut inputs = Vec::new();
+    if let Some(held) = self.direction() {
+      while held.time >= self.next_move {
+        inputs.push(held.input);
+        self.next_move += DAS_INTERVAL;
+      }
+    }
+    if let Some(held) = self.held.iter().find(|held| held.input == Input::SoftDrop) {
+      while held.time >= self.next_drop {
+        inputs.push(Input::SoftDrop);
+        self.next_drop += SOFT_DROP_INTERVAL;
+      }
+    }
+    return inputs;
+  }
+}
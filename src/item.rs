@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+// Chance to win an item for each line of a clear, when the slot is free.
+const CHANCE_PER_LINE: f64 = 0.15;
+// How long and how much the slow gravity item slows the pieces down.
+pub const SLOW_GRAVITY_DURATION: Duration = Duration::from_secs(10);
+pub const SLOW_GRAVITY_FACTOR: u32 = 2;
+
+// Single-use power-ups of the items mode, kept in a slot of one until used.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Item {
+  // Removes the bottom row, whatever it holds.
+  ClearBottomRow,
+  // Removes the top row of the stack.
+  ShrinkStack,
+  // Halves the gravity for SLOW_GRAVITY_DURATION.
+  SlowGravity,
+  // Swaps the piece in play with the next one.
+  SwapPiece,
+}
+
+impl Item {
+  pub const ALL: [Item; 4] = [Item::ClearBottomRow, Item::ShrinkStack, Item::SlowGravity, Item::SwapPiece];
+
+  // Of its name in resources/lang.
+  pub fn id(&self) -> &'static str {
+    return match self {
+      Item::ClearBottomRow => "clear_bottom_row",
+      Item::ShrinkStack => "shrink_stack",
+      Item::SlowGravity => "slow_gravity",
+      Item::SwapPiece => "swap_piece",
+    };
+  }
+
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Item::ClearBottomRow => "Clear bottom row",
+      Item::ShrinkStack => "Shrink stack",
+      Item::SlowGravity => "Slow gravity",
+      Item::SwapPiece => "Swap piece",
+    };
+  }
+}
+
+// The item won by a clear of `lines` lines, if any.
+pub fn roll_item<R: Rng + ?Sized>(rng: &mut R, lines: u32) -> Option<Item> {
+  if lines == 0 || !rng.gen_bool((CHANCE_PER_LINE * lines as f64).min(1.0)) {
+    return None;
+  }
+  return Some(Item::ALL[rng.gen_range(0, Item::ALL.len())]);
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use ggez::event::KeyCode;
+
+use crate::game::Input;
+
+// Keys of a player alone at the keyboard, picked in the settings and cycled with F4.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Keymap {
+  // Arrows, Up to rotate, Z, C, Space and V for the item.
+  Arrows,
+  // A D S W to move and rotate, Q, E, Space and R.
+  Wasd,
+  // The arrows layout mirrored: A D S W, then / , and . where Z C and V were.
+  LeftHanded,
+}
+
+impl Keymap {
+  pub const ALL: [Keymap; 3] = [Keymap::Arrows, Keymap::Wasd, Keymap::LeftHanded];
+
+  pub fn from_name(name: &str) -> Option<Keymap> {
+    return Keymap::ALL.iter().copied().find(|keymap| keymap.name() == name);
+  }
+
+  pub fn name(&self) -> &'static str {
+    return match self {
+      Keymap::Arrows => "arrows",
+      Keymap::Wasd => "wasd",
+      Keymap::LeftHanded => "left-handed",
+    };
+  }
+
+  pub fn next(&self) -> Keymap {
+    let index = Keymap::ALL.iter().position(|keymap| keymap == self).unwrap_or(0);
+    return Keymap::ALL[(index + 1) % Keymap::ALL.len()];
+  }
+
+  pub fn input(&self, key: KeyCode) -> Option<Input> {
+    let input = match (self, key) {
+      (Keymap::Arrows, KeyCode::Left) | (Keymap::Wasd, KeyCode::A) | (Keymap::LeftHanded, KeyCode::A) => Input::MoveLeft,
+      (Keymap::Arrows, KeyCode::Right) | (Keymap::Wasd, KeyCode::D) | (Keymap::LeftHanded, KeyCode::D) => Input::MoveRight,
+      (Keymap::Arrows, KeyCode::Down) | (Keymap::Wasd, KeyCode::S) | (Keymap::LeftHanded, KeyCode::S) => Input::SoftDrop,
+      (Keymap::Arrows, KeyCode::Up) | (Keymap::Wasd, KeyCode::W) | (Keymap::LeftHanded, KeyCode::W) => Input::RotateCW,
+      (Keymap::Arrows, KeyCode::Z) | (Keymap::Wasd, KeyCode::Q) | (Keymap::LeftHanded, KeyCode::Slash) => Input::RotateCCW,
+      (Keymap::Arrows, KeyCode::C) | (Keymap::Wasd, KeyCode::E) | (Keymap::LeftHanded, KeyCode::Comma) => Input::Hold,
+      (Keymap::Arrows, KeyCode::V) | (Keymap::Wasd, KeyCode::R) | (Keymap::LeftHanded, KeyCode::Period) => Input::UseItem,
+      (_, KeyCode::Space) => Input::HardDrop,
+      _ => return None,
+    };
+    return Some(input);
+  }
+}
+
+// Every input a player alone has a key for, in the order of the controls screen.
+pub const ACTIONS: [Input; 8] = [
+  Input::MoveLeft,
+  Input::MoveRight,
+  Input::SoftDrop,
+  Input::HardDrop,
+  Input::RotateCW,
+  Input::RotateCCW,
+  Input::Hold,
+  Input::UseItem,
+];
+
+// Keys that can be bound, by the name they are saved under.
+const KEY_NAMES: [(KeyCode, &str); 74] = [
+  (KeyCode::A, "A"), (KeyCode::B, "B"), (KeyCode::C, "C"), (KeyCode::D, "D"), (KeyCode::E, "E"), (KeyCode::F, "F"),
+  (KeyCode::G, "G"), (KeyCode::H, "H"), (KeyCode::I, "I"), (KeyCode::J, "J"), (KeyCode::K, "K"), (KeyCode::L, "L"),
+  (KeyCode::M, "M"), (KeyCode::N, "N"), (KeyCode::O, "O"), (KeyCode::P, "P"), (KeyCode::Q, "Q"), (KeyCode::R, "R"),
+  (KeyCode::S, "S"), (KeyCode::T, "T"), (KeyCode::U, "U"), (KeyCode::V, "V"), (KeyCode::W, "W"), (KeyCode::X, "X"),
+  (KeyCode::Y, "Y"), (KeyCode::Z, "Z"),
+  (KeyCode::Key0, "0"), (KeyCode::Key1, "1"), (KeyCode::Key2, "2"), (KeyCode::Key3, "3"), (KeyCode::Key4, "4"),
+  (KeyCode::Key5, "5"), (KeyCode::Key6, "6"), (KeyCode::Key7, "7"), (KeyCode::Key8, "8"), (KeyCode::Key9, "9"),
+  (KeyCode::Numpad0, "Numpad0"), (KeyCode::Numpad1, "Numpad1"), (KeyCode::Numpad2, "Numpad2"), (KeyCode::Numpad3, "Numpad3"),
+  (KeyCode::Numpad4, "Numpad4"), (KeyCode::Numpad5, "Numpad5"), (KeyCode::Numpad6, "Numpad6"), (KeyCode::Numpad7, "Numpad7"),
+  (KeyCode::Numpad8, "Numpad8"), (KeyCode::Numpad9, "Numpad9"),
+  (KeyCode::Left, "Left"), (KeyCode::Right, "Right"), (KeyCode::Up, "Up"), (KeyCode::Down, "Down"),
+  (KeyCode::Space, "Space"), (KeyCode::Return, "Return"), (KeyCode::Tab, "Tab"), (KeyCode::Back, "Backspace"),
+  (KeyCode::LShift, "LShift"), (KeyCode::RShift, "RShift"), (KeyCode::LControl, "LControl"), (KeyCode::RControl, "RControl"),
+  (KeyCode::LAlt, "LAlt"), (KeyCode::RAlt, "RAlt"),
+  (KeyCode::Comma, "Comma"), (KeyCode::Period, "Period"), (KeyCode::Slash, "Slash"), (KeyCode::Semicolon, "Semicolon"),
+  (KeyCode::Apostrophe, "Apostrophe"), (KeyCode::LBracket, "LBracket"), (KeyCode::RBracket, "RBracket"),
+  (KeyCode::Backslash, "Backslash"), (KeyCode::Insert, "Insert"), (KeyCode::Delete, "Delete"), (KeyCode::Home, "Home"),
+  (KeyCode::End, "End"), (KeyCode::PageUp, "PageUp"), (KeyCode::PageDown, "PageDown"),
+];
+
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+  return KEY_NAMES.iter().find(|&&(other, _)| other == key).map(|&(_, name)| name);
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+  return KEY_NAMES.iter().find(|&&(_, other)| other == name).map(|&(key, _)| key);
+}
+
+// Saved in the settings under this, and named under action.ID in resources/lang.
+pub fn action_id(input: Input) -> &'static str {
+  return match input {
+    Input::MoveLeft => "move_left",
+    Input::MoveRight => "move_right",
+    Input::SoftDrop => "soft_drop",
+    Input::HardDrop => "hard_drop",
+    Input::RotateCW => "rotate_cw",
+    Input::RotateCCW => "rotate_ccw",
+    Input::Hold => "hold",
+    Input::UseItem => "use_item",
+  };
+}
+
+// One key per action: the keys of a keymap, with the changes made on the
+// controls screen.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Bindings {
+  keys: Vec<(Input, KeyCode)>,
+}
+
+impl Bindings {
+  pub fn of(keymap: Keymap) -> Bindings {
+    let keys = ACTIONS.iter().map(|&input| {
+      let key = KEY_NAMES.iter().map(|&(key, _)| key).find(|&key| keymap.input(key) == Some(input)).unwrap_or(KeyCode::Space);
+      (input, key)
+    }).collect();
+    return Bindings { keys: keys };
+  }
+
+  // `names` maps action ids to key names, as saved by `to_names`. Unknown
+  // entries are skipped.
+  pub fn from_names(keymap: Keymap, names: &BTreeMap<String, String>) -> Bindings {
+    let mut bindings = Bindings::of(keymap);
+    for (id, name) in names {
+      let input = ACTIONS.iter().copied().find(|&input| action_id(input) == id);
+      if let (Some(input), Some(key)) = (input, key_from_name(name)) {
+        bindings.bind(input, key);
+      }
+    }
+    return bindings;
+  }
+
+  pub fn to_names(&self) -> BTreeMap<String, String> {
+    return self.keys.iter().filter_map(|&(input, key)| key_name(key).map(|name| (action_id(input).to_string(), name.to_string()))).collect();
+  }
+
+  pub fn input(&self, key: KeyCode) -> Option<Input> {
+    return self.keys.iter().find(|&&(_, other)| other == key).map(|&(input, _)| input);
+  }
+
+  pub fn key(&self, input: Input) -> KeyCode {
+    return self.keys.iter().find(|&&(other, _)| other == input).map_or(KeyCode::Space, |&(_, key)| key);
+  }
+
+  // The action that had `key` before, if any, takes the old key of `input`.
+  pub fn bind(&mut self, input: Input, key: KeyCode) {
+    let old = self.key(input);
+    for binding in self.keys.iter_mut() {
+      if binding.0 == input {
+        binding.1 = key;
+      } else if binding.1 == key {
+        binding.1 = old;
+      }
+    }
+  }
+}
@@ -0,0 +1,139 @@
+use ggez::graphics::Rect;
+
+use crate::config::GameConfig;
+use crate::settings::{MAX_ZOOM, MIN_ZOOM};
+
+pub const GRID_STROKE_SIZE: f32 = 1.0;
+
+pub const CASE_BORDER: f32 = 2.0;
+
+// Part of the height of the layout the boards take with the fit_board
+// setting, and how small they may get for a board too big for the window.
+pub const FIT_HEIGHT: f32 = 0.9;
+pub const MIN_FIT_ZOOM: f32 = 0.25;
+// Texts taller or wider than this part of the window are shrunk to fit.
+pub const OVERLAY_FIT: f32 = 0.95;
+// Height of the hold panel, in cases.
+const HOLD_CASES: f32 = 4.5;
+// Part of the margin right of the board the preview takes, centered in it.
+const NEXT_FIT: f32 = 0.9;
+
+// How big the boards are drawn.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Zoom {
+  // Times their size, down to what fits.
+  Fixed(f32),
+  // Most of the height of the layout, see Settings::fit_board.
+  Fit,
+}
+
+// Where one board and its panels go, in the coordinates of its player,
+// before `scale`.
+#[derive(Clone,Copy,Debug,Default,PartialEq)]
+pub struct BoardLayout {
+  pub scale: f32,
+  // Part of the layout given to the player, the board is centered in it.
+  pub region: Rect,
+  pub grid_frame: Rect,
+  // Left of the board, the hold piece then the score under it. The preview
+  // and the pieces played right of it.
+  pub hold: Rect,
+  pub score: Rect,
+  pub next: Rect,
+}
+
+impl BoardLayout {
+  // The board in the coordinates of the layout.
+  pub fn board(&self) -> Rect {
+    let frame = self.grid_frame;
+    return Rect::new(frame.x * self.scale, frame.y * self.scale, frame.w * self.scale, frame.h * self.scale);
+  }
+}
+
+// Everything drawn takes its place from here, computed once for the layout
+// and again when the window or the zoom changes.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Layout {
+  // Everything is drawn in these coordinates, the default window of the
+  // layout, scaled to the actual window with bars to keep its proportions.
+  pub frame: Rect,
+  pub case_size: f32,
+  // Cases drawn on each side of a case of the game.
+  pub cases_per_case: i32,
+  pub zoom: f32,
+  // Banners and overlays are centered in it, shrunk when bigger.
+  pub banner: Rect,
+  // One column per player, as wide as its scale, left to right.
+  pub boards: Vec<BoardLayout>,
+}
+
+impl Layout {
+  // The boards of `config`, twice as big with `big`, at the `scales` of
+  // their player before the zoom.
+  pub fn new(frame: Rect, config: &GameConfig, big: bool, scales: &[f32], zoom: Zoom) -> Layout {
+    let mut layout = Layout {
+      frame: frame,
+      case_size: config.case_size,
+      cases_per_case: if big { 2 } else { 1 },
+      zoom: 1.0,
+      banner: Rect::new(
+        frame.x + frame.w * (1.0 - OVERLAY_FIT) / 2.0,
+        frame.y + frame.h * (1.0 - OVERLAY_FIT) / 2.0,
+        frame.w * OVERLAY_FIT,
+        frame.h * OVERLAY_FIT,
+      ),
+      boards: Vec::new(),
+    };
+    let width = layout.pixel_x(config.grid_width as i32 * layout.cases_per_case) - layout.pixel_x(0);
+    let height = layout.pixel_y(config.grid_height as i32 * layout.cases_per_case) - layout.pixel_y(0);
+    layout.zoom = fit_zoom(frame, width, height, scales, zoom);
+    let pitch = layout.case_pitch();
+    let total: f32 = scales.iter().sum();
+    let mut x = frame.x;
+    for &base in scales {
+      let column_width = frame.w * base / total;
+      let scale = base * layout.zoom;
+      let region = Rect::new(x / scale, frame.y / scale, column_width / scale, frame.h / scale);
+      let grid_frame = Rect::new(region.x + (region.w - width) / 2.0, region.y + (region.h - height) / 2.0, width, height);
+      let margin = grid_frame.x - region.x;
+      // Under the hold piece, unless it would go above the region.
+      let top = region.y + region.h / 4.0;
+      let hold = Rect::new(region.x, (top - HOLD_CASES * pitch).max(region.y), margin, HOLD_CASES * pitch);
+      let score = Rect::new(region.x + margin / 4.0, hold.bottom(), margin * 3.0 / 4.0 - CASE_BORDER, region.bottom() - hold.bottom());
+      let next = Rect::new(grid_frame.right() + margin * (1.0 - NEXT_FIT) / 2.0, top, margin * NEXT_FIT, region.bottom() - top);
+      layout.boards.push(BoardLayout { scale: scale, region: region, grid_frame: grid_frame, hold: hold, score: score, next: next });
+      x += column_width;
+    }
+    return layout;
+  }
+
+  pub fn pixel_x(&self, x: i32) -> f32 {
+    GRID_STROKE_SIZE + CASE_BORDER + (x as f32) * (GRID_STROKE_SIZE + CASE_BORDER + self.case_size + CASE_BORDER)
+  }
+
+  pub fn pixel_y(&self, y: i32) -> f32 {
+    self.pixel_x(y)
+  }
+
+  // Distance between two neighbour cases.
+  pub fn case_pitch(&self) -> f32 {
+    self.pixel_x(1) - self.pixel_x(0)
+  }
+}
+
+// The zoom asked, down to what leaves every board of `width` x `height` the
+// height of the layout and half of its column for the panels. Boards that do
+// not fit without it are not made smaller, unless they fit the window: they
+// then take FIT_HEIGHT of it, smaller or bigger than at zoom 1.
+fn fit_zoom(frame: Rect, width: f32, height: f32, scales: &[f32], zoom: Zoom) -> f32 {
+  let total: f32 = scales.iter().sum();
+  let room = if zoom == Zoom::Fit { FIT_HEIGHT } else { 1.0 };
+  let fit = scales.iter().map(|&scale| {
+    let column_width = frame.w * scale / total;
+    (frame.h * room / (height * scale)).min(column_width / (2.0 * width * scale))
+  }).fold(MAX_ZOOM, f32::min);
+  return match zoom {
+    Zoom::Fixed(zoom) => zoom.min(fit).max(MIN_ZOOM),
+    Zoom::Fit => fit.max(MIN_FIT_ZOOM),
+  };
+}
@@ -0,0 +1,122 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::Serialize;
+use url::Url;
+
+use crate::game::{Game, Mode};
+
+// For the connection, and then for each read and write.
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Body of the POST request, as JSON.
+#[derive(Clone,Debug,PartialEq,Serialize)]
+pub struct ScoreSubmission {
+  pub name: String,
+  pub score: i64,
+  pub lines: u32,
+  pub level: u32,
+  pub mode: Mode,
+  pub seed: Option<u64>,
+  pub seconds: f64,
+  // Date of the daily challenge, only for its official attempt.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub daily: Option<String>,
+}
+
+impl ScoreSubmission {
+  pub fn from_game(game: &Game, name: &str, seed: Option<u64>) -> ScoreSubmission {
+    let score = game.current_score();
+    return ScoreSubmission {
+      name: name.to_string(),
+      score: score.score,
+      lines: score.line_removed,
+      level: score.level,
+      mode: game.mode(),
+      seed: seed,
+      seconds: game.time_played().as_secs_f64(),
+      daily: None,
+    };
+  }
+}
+
+// An http:// or https:// URL with a host.
+fn parse_url(url: &str) -> Result<Url, String> {
+  let parsed = Url::parse(url).map_err(|e| format!("invalid URL {} ({})", url, e))?;
+  if parsed.scheme() != "http" && parsed.scheme() != "https" {
+    return Err(format!("only http:// and https:// URLs are supported, got {}", url));
+  }
+  if parsed.host_str().is_none_or(str::is_empty) {
+    return Err(format!("missing host in {}", url));
+  }
+  return Ok(parsed);
+}
+
+pub fn validate_url(url: &str) -> Result<(), String> {
+  return parse_url(url).map(|_| ());
+}
+
+// The token is only sent over https, it would travel in the clear otherwise.
+pub fn sends_token(url: &str) -> bool {
+  return parse_url(url).is_ok_and(|url| url.scheme() == "https");
+}
+
+// Posts the scores of finished games from a thread each, so a slow or dead
+// server never holds the game; the outcome is read back with `poll`.
+pub struct Leaderboard {
+  url: String,
+  token: Option<String>,
+  sender: Sender<Result<(), String>>,
+  results: Receiver<Result<(), String>>,
+}
+
+impl Leaderboard {
+  // `url` is expected to be valid, see `validate_url`.
+  pub fn new(url: &str, token: Option<&str>) -> Leaderboard {
+    let (sender, results) = mpsc::channel();
+    return Leaderboard { url: url.to_string(), token: token.map(String::from), sender: sender, results: results };
+  }
+
+  pub fn submit(&self, submission: &ScoreSubmission) {
+    let body = match serde_json::to_string(submission) {
+      Ok(body) => body,
+      Err(e) => {
+        warn!("Cannot encode the score ({})", e);
+        return;
+      },
+    };
+    let url = self.url.clone();
+    let token = self.token.clone();
+    let sender = self.sender.clone();
+    thread::spawn(move || {
+      let result = post(&url, token.as_deref(), &body);
+      match &result {
+        Ok(()) => info!("Score submitted to {}", url),
+        Err(e) => warn!("Cannot submit the score to {} ({})", url, e),
+      }
+      // Nobody to tell once the game has quit.
+      let _ = sender.send(result);
+    });
+  }
+
+  // The outcome of a submission, when one has arrived since the last call.
+  pub fn poll(&self) -> Option<Result<(), String>> {
+    return self.results.try_recv().ok();
+  }
+}
+
+fn post(url: &str, token: Option<&str>, body: &str) -> Result<(), String> {
+  let agent = ureq::AgentBuilder::new().timeout_connect(SUBMIT_TIMEOUT).timeout_read(SUBMIT_TIMEOUT).timeout_write(SUBMIT_TIMEOUT).build();
+  let mut request = agent.post(url).set("Content-Type", "application/json");
+  if let Some(token) = token.filter(|_| sends_token(url)) {
+    request = request.set("Authorization", &format!("Bearer {}", token));
+  }
+  return match request.send_string(body) {
+    Ok(response) if response.status() / 100 == 2 => Ok(()),
+    Ok(response) => Err(format!("server answered {} {}", response.status(), response.status_text())),
+    Err(ureq::Error::Status(code, response)) => Err(format!("server answered {} {}", code, response.status_text())),
+    Err(e) => Err(e.to_string()),
+  };
+}
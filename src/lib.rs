@@ -0,0 +1,39 @@
+pub mod achievement;
+pub mod ai;
+pub mod audio;
+pub mod broadcast;
+pub mod clip;
+pub mod config;
+pub mod daily;
+#[cfg(feature = "discord")]
+pub mod discord;
+pub mod external;
+pub mod game;
+pub mod garbage;
+pub mod gravity;
+pub mod grid;
+pub mod highscores;
+pub mod input;
+pub mod item;
+pub mod keymap;
+pub mod layout;
+pub mod leaderboard;
+pub mod locale;
+pub mod mission;
+pub mod network;
+pub mod opener;
+pub mod options;
+pub mod phase;
+pub mod piece;
+pub mod puzzle;
+pub mod randomizer;
+pub mod replay;
+pub mod render;
+pub mod rumble;
+pub mod scoring;
+pub mod screenshot;
+pub mod settings;
+pub mod simulate;
+pub mod skin;
+pub mod stats;
+pub mod timestep;
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use ggez::filesystem;
+use ggez::{Context, GameError, GameResult};
+
+// Built in, read without any file.
+pub const DEFAULT_LANGUAGE: &str = "en";
+const LANGUAGES_DIRECTORY: &str = "/lang";
+const ENGLISH: &str = include_str!("../resources/lang/en.toml");
+
+// Every text of the screen by key, e.g. "banner.game_over" for the text
+// `game_over` of the table `[banner]`. A key the language leaves out reads
+// as in English, a key English does not have either reads as itself.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Strings {
+  pub language: String,
+  texts: HashMap<String, String>,
+}
+
+// The tables of `value` as dotted keys into `texts`.
+fn flatten(prefix: &str, value: &toml::Value, texts: &mut HashMap<String, String>) -> Result<(), String> {
+  match value {
+    toml::Value::String(text) => {
+      texts.insert(prefix.to_string(), text.clone());
+    },
+    toml::Value::Table(table) => {
+      for (key, value) in table {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        flatten(&key, value, texts)?;
+      }
+    },
+    _ => return Err(format!("{}: expected a text", prefix)),
+  }
+  return Ok(());
+}
+
+impl Strings {
+  pub fn english() -> Strings {
+    // Tested with the game, cannot fail once built.
+    return Strings::from_toml(DEFAULT_LANGUAGE, "").expect("invalid resources/lang/en.toml");
+  }
+
+  // The texts of `text` over those of English.
+  pub fn from_toml(language: &str, text: &str) -> Result<Strings, String> {
+    let mut texts = HashMap::new();
+    flatten("", &toml::from_str(ENGLISH).map_err(|e| format!("en.toml: {}", e))?, &mut texts)?;
+    flatten("", &toml::from_str(text).map_err(|e| e.to_string())?, &mut texts)?;
+    return Ok(Strings { language: language.to_string(), texts: texts });
+  }
+
+  pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+    return self.texts.get(key).map_or(key, |text| text.as_str());
+  }
+
+  // The text of `key`, each {name} of `values` replaced by its value.
+  pub fn fill(&self, key: &str, values: &[(&str, String)]) -> String {
+    let mut text = self.get(key).to_string();
+    for (name, value) in values {
+      text = text.replace(&format!("{{{}}}", name), value);
+    }
+    return text;
+  }
+
+  // 45,300 in English, the groups of three digits apart with number.thousands.
+  pub fn number(&self, number: i64) -> String {
+    let separator = self.get("number.thousands");
+    let digits = number.unsigned_abs().to_string();
+    let mut text = String::from(if number < 0 { "-" } else { "" });
+    for (index, digit) in digits.chars().enumerate() {
+      if index > 0 && (digits.len() - index).is_multiple_of(3) {
+        text.push_str(separator);
+      }
+      text.push(digit);
+    }
+    return text;
+  }
+}
+
+// English first, then the files of resources/lang by name.
+pub fn language_names(ctx: &mut Context) -> Vec<String> {
+  let mut names: Vec<String> = match filesystem::read_dir(ctx, LANGUAGES_DIRECTORY) {
+    Ok(paths) => paths.filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+      .filter_map(|path| path.file_stem().and_then(|name| name.to_str()).map(String::from)).collect(),
+    Err(_) => Vec::new(),
+  };
+  names.retain(|name| name != DEFAULT_LANGUAGE);
+  names.sort();
+  names.insert(0, String::from(DEFAULT_LANGUAGE));
+  return names;
+}
+
+// resources/lang/NAME.toml, English being built in.
+pub fn load_strings(ctx: &mut Context, name: &str) -> GameResult<Strings> {
+  if name == DEFAULT_LANGUAGE {
+    return Ok(Strings::english());
+  }
+  // Like skins, it cannot leave its directory.
+  if name.is_empty() || !name.chars().all(|letter| letter.is_ascii_alphanumeric() || letter == '-' || letter == '_') {
+    return Err(GameError::ConfigError(format!("invalid language name: {}", name)));
+  }
+  let path = format!("{}/{}.toml", LANGUAGES_DIRECTORY, name);
+  let mut text = String::new();
+  filesystem::open(ctx, &path)?.read_to_string(&mut text).map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path, e)))?;
+  return Strings::from_toml(name, &text).map_err(|e| GameError::ConfigError(format!("{}: {}", path, e)));
+}
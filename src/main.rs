@@ -1,696 +1,204 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
 use std::path;
-use std::time::Duration;
+use std::process;
 
-use ggez;
-use ggez::audio;
-use ggez::audio::SoundSource;
 use ggez::conf;
 use ggez::event;
 use ggez::graphics;
-use ggez::nalgebra as na;
-use ggez::timer;
-use ggez::{Context, GameResult};
-
-use rand::{ distributions::{Distribution, Standard}, Rng};
-
-const GRID_WIDTH: usize = 10;
-const GRID_HEIGHT: usize = 20;
-const GRID_STROKE_SIZE: f32 = 1.0;
-
-#[derive(Clone,Copy,PartialEq)]
-enum Case {
-  Empty,
-  Red,
-  Green,
-  Blue,
-  Yellow,
-  DarkYellow,
-  Purple,
-  Cyan,
-}
-
-impl Distribution<Case> for Standard {
-  fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Case {
-    match rng.gen_range(1, 8) {
-      1 => Case::Red,
-      2 => Case::Green,
-      3 => Case::Blue,
-      4 => Case::Yellow,
-      5 => Case::DarkYellow,
-      6 => Case::Purple,
-      7 => Case::Cyan,
-      _ => Case::Empty,
-    }
-  }
-}
-const CASE_SIZE:   f32 = 20.0;
-const CASE_BORDER: f32 = 2.0;
-
-const FONT_NAME: &str = "/DejaVuSerif.ttf";
-const FONT_SIZE: f32 = 18.0;
-
-const NEXT_PIECES_COUNT: usize = 3;
-
-fn case_color(case: Case) -> graphics::Color {
-  return match case {
-    Case::Red => graphics::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 },
-    Case::Green => graphics::Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 },
-    Case::Blue => graphics::Color { r: 0.0, g: 0.0, b: 1.0, a: 1.0 },
-    Case::Yellow => graphics::Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },
-    Case::DarkYellow => graphics::Color { r: 1.0, g: 0.85, b: 0.0, a: 1.0 },
-    Case::Purple => graphics::Color { r: 0.5, g: 0.0, b: 0.5, a: 1.0 },
-    Case::Cyan => graphics::Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 },
-    _ => panic!("Unknow case type"),
+use ggez::{Context, GameError, GameResult};
+
+use log::{error, warn};
+
+use tetris_rs::ai::Controller;
+use tetris_rs::broadcast::StateServer;
+use tetris_rs::config::GameConfig;
+use tetris_rs::external::ProcessBot;
+use tetris_rs::network::Connection;
+use tetris_rs::options::{NetworkRole, Options, WindowSize, USAGE};
+use tetris_rs::render::{MainState, WINDOW_TITLE};
+use tetris_rs::settings::Settings;
+use tetris_rs::simulate::{simulate, Simulation};
+
+// Also read by --simulate, which opens no ggez context.
+const RESOURCE_DIRECTORY: &str = "./resources";
+
+fn context_builder(options: &Options, audio_enabled: bool) -> ggez::ContextBuilder {
+  let resource_dir = path::PathBuf::from(RESOURCE_DIRECTORY);
+  let window_mode = match options.window {
+    WindowSize::Windowed(width, height) => conf::WindowMode::default()
+      .fullscreen_type(conf::FullscreenType::Windowed)
+      .dimensions(width, height),
+    WindowSize::Fullscreen => conf::WindowMode::default()
+      .fullscreen_type(conf::FullscreenType::Desktop),
   };
-}
 
-#[derive(Clone,Copy)]
-struct Offset {
-  x: i32,
-  y: i32,
-}
-const ROTATION_OFFSET_DEFAULT: [Offset; 4] = [Offset { x: 1, y: 0}, Offset { x: -1, y: 1}, Offset { x: 0, y: -1}, Offset { x: 0, y: 0}, ];
-const ROTATION_OFFSET_CYAN: [Offset; 4] = [Offset { x: 2, y: -1}, Offset { x: -2, y: 2}, Offset { x: 1, y: -2}, Offset { x: -1, y: 1}, ];
-
-fn cases_rotation_offset(case: Case, index: usize) -> Offset {
-  return match case {
-    Case::DarkYellow => Offset { x: 0, y: 0},
-    Case::Cyan => ROTATION_OFFSET_CYAN[index],
-    _ => ROTATION_OFFSET_DEFAULT[index],
-  };
-}
-
-fn piece_cases(case: Case) -> Vec<Vec<Case>> {
-  return match case {
-    Case::Red => vec![
-      vec![Case::Red, Case::Red, Case::Empty], 
-      vec![Case::Empty, Case::Red, Case::Red],
-    ],
-    Case::Green => vec![
-      vec![Case::Empty, Case::Green, Case::Green],
-      vec![Case::Green, Case::Green, Case::Empty], 
-    ],
-    Case::Blue => vec![
-      vec![Case::Blue, Case::Empty, Case::Empty],
-      vec![Case::Blue, Case::Blue, Case::Blue], 
-    ],
-    Case::Yellow => vec![
-      vec![Case::Empty, Case::Empty, Case::Yellow],
-      vec![Case::Yellow, Case::Yellow, Case::Yellow], 
-    ],
-    Case::DarkYellow => vec![
-      vec![Case::DarkYellow, Case::DarkYellow],
-      vec![Case::DarkYellow, Case::DarkYellow], 
-    ],
-    Case::Purple => vec![
-      vec![Case::Empty, Case::Purple, Case::Empty],
-      vec![Case::Purple, Case::Purple, Case::Purple], 
-    ],
-    Case::Cyan => vec![
-      vec![Case::Cyan, Case::Cyan, Case::Cyan, Case::Cyan],
-    ],
-    _ => panic!("Unknow case type"),
+  ggez::ContextBuilder::new("Tetris", "Datoh")
+    .add_resource_path(resource_dir)
+    .modules(conf::ModuleConf::default().audio(audio_enabled))
+    .window_setup(
+      conf::WindowSetup::default()
+      .title(WINDOW_TITLE))
+    .window_mode(window_mode.resizable(true))
+}
+
+// The window of the settings, made to fit the monitor it opens on. Returns
+// the window in use, unchanged when none was saved.
+fn restore_window(ctx: &mut Context, settings: &Settings, window_size: WindowSize) -> GameResult<WindowSize> {
+  let window = graphics::window(ctx);
+  let factor = window.get_hidpi_factor();
+  let monitor = window.get_current_monitor();
+  let desktop = monitor.get_dimensions().to_logical(factor);
+  let origin = monitor.get_position().to_logical(factor);
+  return match (settings.fullscreen, settings.window_size) {
+    (true, _) => {
+      graphics::set_fullscreen(ctx, conf::FullscreenType::Desktop)?;
+      Ok(WindowSize::Fullscreen)
+    },
+    (false, Some((width, height))) => {
+      let (width, height) = (width.min(desktop.width as f32), height.min(desktop.height as f32));
+      graphics::set_drawable_size(ctx, width, height)?;
+      // Back on the monitor when it got smaller or moved away.
+      if let Some((x, y)) = settings.window_position {
+        let x = x.min(origin.x + desktop.width - width as f64).max(origin.x);
+        let y = y.min(origin.y + desktop.height - height as f64).max(origin.y);
+        graphics::window(ctx).set_position((x, y).into());
+      }
+      Ok(WindowSize::Windowed(width, height))
+    },
+    (false, None) => Ok(window_size),
   };
 }
 
-struct Piece {
-  case: Case,
-  x: i32,
-  y: i32,
-  last_move: Duration,
-  cases: Vec<Vec<Case>>,
-  index_rotation: usize,
-}
-
-impl Piece {
-  fn width(&self) -> i32 {
-    return self.cases[0].len() as i32;
-  }
-  fn height(&self) -> i32 {
-    return self.cases.len() as i32;
-  }
-}
-
-fn create_piece(case: Case) -> Piece {
-  let cases = piece_cases(case);
-  return Piece { case: case, x: ((GRID_WIDTH - cases[0].len()) / 2) as i32, y: 0, last_move: Duration::from_secs(0), cases: cases, index_rotation: 0 };
-}
-
-fn drop_speed(level: u32) -> Duration {
-  let level_f64 = (level - 1) as f64;
-  Duration::from_secs_f64((0.8 - (level_f64 * 0.007)).powf(level_f64))
-}
-
-fn pixel_x(x: usize) -> f32 {
-  GRID_STROKE_SIZE + CASE_BORDER + (x as f32) * (GRID_STROKE_SIZE + CASE_BORDER + CASE_SIZE + CASE_BORDER)
-}
-
-fn pixel_y(y: usize) -> f32 {
-  pixel_x(y)
-}
-
-struct ScoreStats {
-  score: i64,
-  level: u32,
-  line_removed: u32,
-}
-
-struct MainState {
-  frame: graphics::Rect,
-  grid: [[Case; GRID_HEIGHT]; GRID_WIDTH],
-  grid_frame: graphics::Rect,
-  current_piece: Option<Piece>,
-  current_piece_ghost_offset_y: i32,
-  next_pieces: Vec<Piece>,
-  move_speed: Duration,
-  timer_piece_generation: Duration,
-  text: graphics::Text,
-  sound_theme: audio::Source,
-  sound_theme_on: bool,
-  current_score: ScoreStats,
-  previous_score: ScoreStats,
-  best_score: ScoreStats,
+// Sends the log to stderr and to a file.
+struct Tee {
+  file: File,
 }
 
-impl MainState {
-  fn new(ctx: &mut Context) -> GameResult<MainState> {
-    let width = pixel_x(GRID_WIDTH) - pixel_x(0);
-    let height = pixel_y(GRID_HEIGHT) - pixel_y(0);
-    let frame = graphics::screen_coordinates(ctx);
-    let left = (frame.w - width) / 2.0;
-    let top = (frame.h - height) / 2.0;
-
-    let font = graphics::Font::new(ctx, FONT_NAME)?;
-
-    let mut s = MainState {
-      frame: frame,
-      grid: [[Case::Empty; GRID_HEIGHT]; GRID_WIDTH],
-      grid_frame: graphics::Rect::new(left, top, width, height),
-      current_piece: None,
-      current_piece_ghost_offset_y: 0,
-      next_pieces: Vec::new(),
-      move_speed: Duration::from_secs(0),
-      timer_piece_generation: Duration::from_secs(0),
-      text: graphics::Text::new(("", font, FONT_SIZE)),
-      sound_theme: audio::Source::new(ctx, "/theme.ogg")?,
-      sound_theme_on: true,
-      current_score: ScoreStats { score: 0, level: 0, line_removed: 0, },
-      previous_score: ScoreStats { score: 0, level: 0, line_removed: 0, },
-      best_score: ScoreStats { score: 0, level: 0, line_removed: 0, },
-    };
-
-    s.sound_theme.set_repeat(true);
-    s.sound_theme.set_volume(0.3);
-    s.reset(ctx)?;
-
-    Ok(s)
-  }
-
-  fn reset(&mut self, ctx: &mut Context) -> GameResult {
-    self.grid = [[Case::Empty; GRID_HEIGHT]; GRID_WIDTH];
-    self.current_piece = None;
-    self.move_speed = drop_speed(1);
-    self.timer_piece_generation = Duration::from_secs(0);
-    self.current_score.level = 1;
-    self.current_score.score = 0;
-    self.current_score.line_removed = 0;
-    self.create_score_text(ctx)?;
-    self.next_pieces.clear();
-    for _ in 0..NEXT_PIECES_COUNT {
-      self.next_pieces.push(create_piece(rand::random()));
-    }
-    self.sound_theme.stop();
-    self.sound_theme.set_pitch(1.0);
-    if self.sound_theme_on {
-      self.sound_theme.play()?;
-    }
-
-    Ok(())
-  }
-
-  fn rotate(&mut self) {
-    if self.current_piece.is_none() {
-      return;
-    }
-
-    let old_piece = self.current_piece.as_ref().unwrap();
-    let mut tmp_cases: Vec<Vec<Case>> = Vec::new();
-    let height = old_piece.cases.len();
-    let width = old_piece.cases[0].len();
-    for x in 0..width {
-      let mut current_row: Vec<Case> = Vec::new();
-      for y in 0..height {
-        current_row.push(old_piece.cases[y][x]);
-      }
-      current_row.reverse();
-      tmp_cases.push(current_row);
-    }
-    let mut piece = Piece { case: old_piece.case, x: old_piece.x, y: old_piece.y, last_move: old_piece.last_move, cases: tmp_cases, index_rotation: old_piece.index_rotation };
-    let offset = cases_rotation_offset(piece.case, piece.index_rotation);
-    piece.x += offset.x;
-    piece.y += offset.y;
-    piece.y = piece.y.max(0);
-    piece.index_rotation = (piece.index_rotation + 1) % 4;
-
-    let mut ok = !self.check_collision(0, 0);
-    if !ok {
-      piece.x -= 1;
-      ok = !self.check_collision(0, 0);
-    }
-    if !ok {
-      piece.x += 2;
-      ok = !self.check_collision(0, 0);
-    }
-    if !ok {
-      piece.x -= 1;
-      piece.y -= 1;
-      ok = !self.check_collision(0, 0);
-    }
-    if ok {
-      self.current_piece = Some(piece);
-    }
-  }
-
-  fn put_piece_in_grid(&mut self) {
-    let piece = self.current_piece.as_ref().unwrap();
-    for (i_v_y, line) in piece.cases.iter().enumerate() {
-      let i_y = piece.y as usize + i_v_y;
-      for (i_v_x, &case) in line.iter().enumerate() {
-        if case != Case::Empty {
-          let i_x = piece.x as usize + i_v_x;
-          self.grid[i_x][i_y] = case;
-        }
-      }
-    }
-  }
-
-  fn remove_complete_lines(&mut self) -> u32 {
-    let mut line_removed: u32 = 0;
-    for y in 0..GRID_HEIGHT {
-      let mut all_in_line = true;
-      for x in 0..GRID_WIDTH {
-        all_in_line &= self.grid[x][y] != Case::Empty;
-      }
-      if all_in_line {
-        line_removed += 1;
-        let mut y_to_move = (y -1) as i32;
-        while y_to_move >= 0 {
-          for x in 0..GRID_WIDTH {
-            self.grid[x][y_to_move as usize + 1] = self.grid[x][y_to_move as usize];
-          }
-          y_to_move -= 1;
-        }
-      }
-    }
-
-    return line_removed;
-  }
-
-  fn compute_score(&mut self, line_removed: u32) {
-    let factor = match line_removed {
-      1 => 40,
-      2 => 100,
-      3 => 300,
-      4 => 1200,
-      _ => 0,
-    };
-    self.current_score.score += factor * (self.current_score.level as i64);
-    println!("Score: {}", self.current_score.score);
-  }
-
-  fn increase_level(&mut self) {
-    if self.current_score.line_removed > self.current_score.level * 5 {
-      self.current_score.level += 1;
-      self.move_speed = drop_speed(self.current_score.level);
-      self.sound_theme.stop();
-      self.sound_theme.set_pitch(1.0 + (0.1 * (self.current_score.level - 1) as f32));
-      if self.sound_theme_on {
-        self.sound_theme.play().unwrap();
-      }
-      println!("Level: {}", self.current_score.level);
-      println!("Speed: {:?}", self.move_speed);
-    }
-  }
-
-  fn generate_piece(&mut self, delta: Duration) -> bool {
-    if self.current_piece.is_some() {
-      return true;
-    }
-
-    self.timer_piece_generation += delta;
-    if self.timer_piece_generation > self.move_speed {
-      let piece = self.next_pieces.remove(0);
-      self.timer_piece_generation = Duration::from_secs(0);
-      let fit_in_grid = !self.check_collision(0, 0);
-      self.current_piece = Some(piece);
-      self.update_current_piece_ghost();
-
-      self.next_pieces.push(create_piece(rand::random()));
-
-      return fit_in_grid;
-    }
-    return true;
-  }
-
-  fn update_current_piece_ghost(&mut self) {
-    if self.current_piece.is_none() {
-      return;
-    }
-
-    self.current_piece_ghost_offset_y = (0..(GRID_HEIGHT as i32 + 1)).find(|&offset_y|
-      self.check_collision(0, offset_y)
-    ).unwrap();
-    let piece = self.current_piece.as_ref().unwrap();
-    self.current_piece_ghost_offset_y += piece.y - 1;
-    self.current_piece_ghost_offset_y.min(piece.y);
-  }
-
-  fn piece_move_horizontally(&mut self, dx: i32) {
-    if self.current_piece.is_none() {
-      return;
-    }
-
-    if !self.check_collision(dx, 0) {
-      let piece = self.current_piece.as_mut().unwrap();
-      piece.x += dx;
-    }
-  }
-
-  fn piece_move_vertically(&mut self, dy: i32) {
-    if self.current_piece.is_none() {
-      return;
-    }
-
-    if !self.check_collision(0, dy) {
-      let piece = self.current_piece.as_mut().unwrap();
-      piece.y += dy;
-      piece.last_move = Duration::from_secs(0);
-    }
-  }
-
-  fn piece_drop(&mut self) {
-    if self.current_piece.is_none() {
-      return;
-    }
-
-    while !self.check_collision(0, 1) {
-      let piece = self.current_piece.as_mut().unwrap();
-      piece.y += 1;
-    }
-  }
-
-  fn piece_move_down(&mut self, delta: Duration) -> bool {
-    if self.current_piece.is_none() {
-      return false;
-    }
-
-    let dy: i32 = 1;
-    let piece = self.current_piece.as_ref().unwrap();
-    let should_move = piece.last_move + delta > self.move_speed;
-    let can_move = should_move && !self.check_collision(0, dy);
-
-    if should_move && !can_move {
-      self.put_piece_in_grid();
-      self.current_piece = None;
-    } else if should_move && can_move {
-      let piece = self.current_piece.as_mut().unwrap();
-      piece.y += dy;
-      piece.last_move = Duration::from_secs(0);
-    } else {
-      let piece = self.current_piece.as_mut().unwrap();
-      piece.last_move += delta;
-    }
-    return should_move && !can_move;
-  }
-
-  fn check_collision(&mut self, dx: i32, dy: i32) -> bool {
-    if self.current_piece.is_none() {
-      return false;
-    }
-
-    let piece = self.current_piece.as_ref().unwrap();
-    let piece_x = piece.x + dx;
-    let piece_y = piece.y + dy;
-
-    if piece_y + piece.height() > GRID_HEIGHT as i32 {
-      return true;
-    }
-    if piece_x < 0 || piece_x + piece.width() > GRID_WIDTH as i32 {
-      return true;
-    }
-
-    for (i_v_y, line) in piece.cases.iter().enumerate() {
-      let i_y = piece_y as usize + i_v_y;
-      for (i_v_x, &case) in line.iter().enumerate() {
-        if case != Case::Empty {
-          let i_x = piece_x as usize + i_v_x;
-          if self.grid[i_x][i_y] != Case::Empty {
-            return true;
-          }
-        }
-      }
-    }
-
-    return false;
+impl Write for Tee {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    io::stderr().write_all(buf)?;
+    self.file.write_all(buf)?;
+    return Ok(buf.len());
   }
 
-  fn draw_grid(&mut self, ctx: &mut Context) -> GameResult {
-    let gridmesh_builder = &mut graphics::MeshBuilder::new();
-    gridmesh_builder.rectangle(
-      graphics::DrawMode::stroke(GRID_STROKE_SIZE),
-      graphics::Rect::new(0.0, 0.0, self.grid_frame.w, self.grid_frame.h),
-      graphics::WHITE,
-    );
-    for i_y in 1..GRID_HEIGHT {
-      let y = pixel_y(i_y) - pixel_y(0);
-      gridmesh_builder.line(
-        &[na::Point2::new(0.0, y), na::Point2::new(self.grid_frame.w, y)],
-        GRID_STROKE_SIZE,
-        graphics::WHITE
-      )?;
-    }
-    for i_x in 1..GRID_WIDTH {
-      let x = pixel_y(i_x) - pixel_y(0);
-      gridmesh_builder.line(
-        &[na::Point2::new(x, 0.0), na::Point2::new(x, self.grid_frame.h)],
-        GRID_STROKE_SIZE,
-        graphics::WHITE
-      )?;
-    }
-    let grid_mesh = gridmesh_builder.build(ctx)?;
-
-    graphics::draw(ctx, &grid_mesh, (na::Point2::new(self.grid_frame.x, self.grid_frame.y),))?;
-
-    Ok(())
-  }
-
-  fn draw_cases(&mut self, ctx: &mut Context) -> GameResult {
-    for i_x in 0..GRID_WIDTH {
-      let x = pixel_x(i_x);
-      for i_y in 0..GRID_HEIGHT {
-        let case = self.grid[i_x][i_y];
-        if case != Case::Empty {
-          let y = pixel_y(i_y);
-          let mesh_case = graphics::Mesh::new_rectangle(
-            ctx, 
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(x, y, CASE_SIZE as f32, CASE_SIZE as f32),
-            case_color(case),
-          )?;
-          graphics::draw(ctx, &mesh_case, (na::Point2::new(self.grid_frame.x, self.grid_frame.y),))?;
-        }
-      }
-    }
-
-    Ok(())
-  }
-
-  fn create_score_text(&mut self, ctx: &mut Context) -> GameResult {
-    let font = graphics::Font::new(ctx, FONT_NAME)?;
-    let text = format!(
-"Score: {}\n\nLevel: {}\n\nLines: {}\n\n\n
-Previous: {} / {} / {}\n
-Best: {} / {} / {}",
-      self.current_score.score, self.current_score.level, self.current_score.line_removed,
-      self.previous_score.score, self.previous_score.level, self.previous_score.line_removed,
-      self.best_score.score, self.best_score.level, self.best_score.line_removed);
-    self.text = graphics::Text::new((text, font, FONT_SIZE));
-
-    Ok(())
-  }
-
-  fn draw_score(&mut self, ctx: &mut Context) -> GameResult {
-    graphics::draw(ctx, &self.text, (na::Point2::new(self.grid_frame.x / 4.0, self.frame.h / 4.0),))?;
-
-    Ok(())
-  }
-
-  fn draw_current_piece(&mut self, ctx: &mut Context) -> GameResult {
-    match &self.current_piece {
-      Some (piece) => {
-        let global_x = self.grid_frame.x + pixel_x(piece.x as usize) - pixel_x(0);
-        let global_y = self.grid_frame.y + pixel_y(piece.y as usize) - pixel_y(0);
-        self.draw_piece(ctx, piece, graphics::DrawMode::fill(), global_x, global_y)?;
-      },
-      None => {},
-    };
-
-    Ok(())
-  }
-
-  fn draw_current_piece_ghost(&mut self, ctx: &mut Context) -> GameResult {
-    match &self.current_piece {
-      Some (piece) => {
-        let global_x = self.grid_frame.x + pixel_x(piece.x as usize) - pixel_x(0);
-        let global_y = self.grid_frame.y + pixel_y(self.current_piece_ghost_offset_y as usize) - pixel_y(0);
-        self.draw_piece(ctx, piece, graphics::DrawMode::stroke(1.0), global_x, global_y)?;
-      },
-      None => {},
-    };
-
-    Ok(())
-  }
-
-  fn draw_next_pieces(&self, ctx: &mut Context) -> GameResult {
-    let global_x = self.grid_frame.x + self.grid_frame.w + (self.grid_frame.x / 2.0);
-    let mut global_y = self.frame.h / 4.0;
-    for piece in &self.next_pieces {
-      let piece_x = global_x - (piece.width() as f32 * (CASE_SIZE + CASE_BORDER * 2.0) / 2.0);
-      self.draw_piece(ctx, piece, graphics::DrawMode::fill(), piece_x, global_y)?;
-      global_y += 100.0;
-    }
-
-    Ok(())
+  fn flush(&mut self) -> io::Result<()> {
+    io::stderr().flush()?;
+    return self.file.flush();
   }
+}
 
-  fn draw_piece(&self, ctx: &mut Context, piece: &Piece, draw_mode: graphics::DrawMode, global_x: f32, global_y: f32) -> GameResult {
-    for (i_y, line) in piece.cases.iter().enumerate() {
-      let y = pixel_y(i_y);
-      for (i_x, &case) in line.iter().enumerate() {
-        if case != Case::Empty {
-          let x = pixel_x(i_x);
-          let mesh_case = graphics::Mesh::new_rectangle(
-            ctx,
-            draw_mode,
-            graphics::Rect::new(x, y, CASE_SIZE, CASE_SIZE),
-            case_color(case),
-          )?;
-          graphics::draw(ctx, &mesh_case, (na::Point2::new(global_x, global_y),))?;
-        }
-      }
-    }
-    
-    Ok(())
+// Only warnings and errors unless RUST_LOG says otherwise.
+fn init_logger(log_file: Option<&str>) -> io::Result<()> {
+  let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"));
+  if let Some(path) = log_file {
+    let tee = Tee { file: File::create(path)? };
+    builder.target(env_logger::Target::Pipe(Box::new(tee)));
   }
+  builder.init();
+  return Ok(());
+}
 
-  fn play_line_removed(&mut self, ctx: &mut Context, line_removed: u32) -> GameResult {
-    if line_removed > 0 {
-      let mut sound = match line_removed {
-        4 => audio::Source::new(ctx, "/tetris.wav")?,
-        _ => audio::Source::new(ctx, "/line.wav")?,
-      };        
-      sound.play_detached()?;
-    }
-    Ok(())
+// --simulate: the games are played in this thread, as fast as it goes.
+fn run_simulation(options: &Options, games: u32) {
+  let mut config = match GameConfig::load_file(&path::Path::new(RESOURCE_DIRECTORY).join("config.toml")) {
+    Ok(config) => config,
+    Err(e) => {
+      error!("Invalid config ({})", e);
+      process::exit(1);
+    },
+  };
+  options.configure(&mut config);
+  if let Err(e) = config.validate() {
+    error!("Invalid config ({})", e);
+    process::exit(1);
   }
-
-  fn play_lost(&mut self, ctx: &mut Context) -> GameResult {
-    let mut sound = audio::Source::new(ctx, "/lost.mp3")?;
-    sound.play_detached()?;
-    Ok(())
+  let simulation = Simulation { config: config, mode: options.mode, level: options.level, policy: options.policy, max_pieces: options.max_pieces };
+  let summary = simulate(&simulation, games, options.seed.unwrap_or_else(rand::random));
+  if options.json {
+    println!("{}", serde_json::to_string(&summary).unwrap());
+  } else {
+    println!("{}", summary.text());
   }
 }
 
-impl event::EventHandler for MainState {
-  fn update(&mut self, ctx: &mut Context) -> GameResult {
-    let delta = timer::delta(ctx);
-
-    let lost = !self.generate_piece(delta);
-    let piece_is_done = !lost && self.piece_move_down(delta);
-    
-    if piece_is_done {
-      let line_removed = self.remove_complete_lines();
-      if line_removed > 0 {
-        self.play_line_removed(ctx, line_removed)?;
-        self.compute_score(line_removed);
-        self.current_score.line_removed += line_removed;
-        self.increase_level();
-        self.create_score_text(ctx)?;
-      }
-    }
-
-    if lost {
-      if self.current_score.score > 0 {
-        self.previous_score.score = self.current_score.score;
-        self.previous_score.level = self.current_score.level;
-        self.previous_score.line_removed = self.current_score.line_removed;
-      }
-      if self.current_score.score > self.best_score.score {
-        self.best_score.score = self.current_score.score;
-        self.best_score.level = self.current_score.level;
-        self.best_score.line_removed = self.current_score.line_removed;
-      }
-      self.play_lost(ctx)?;
-      self.reset(ctx)?;
-    }
-
-    Ok(())
+pub fn main() -> GameResult {
+  let mut options = match Options::parse(env::args().skip(1)) {
+    Ok(options) => options,
+    Err(e) => {
+      eprintln!("Error: {}\n\n{}", e, USAGE);
+      process::exit(2);
+    },
+  };
+  if options.help {
+    println!("{}", USAGE);
+    return Ok(());
   }
-
-  fn resize_event(&mut self, ctx: &mut Context, _width: f32, _height: f32) {
-    self.frame = graphics::screen_coordinates(ctx);
+  if let Err(e) = init_logger(options.log_file.as_deref()) {
+    eprintln!("Error: cannot create log file ({})", e);
+    process::exit(2);
   }
 
-  fn key_down_event(&mut self, ctx: &mut Context, key: event::KeyCode, _mods: event::KeyMods, _: bool) {
-    match key {
-      event::KeyCode::M => {
-        self.sound_theme_on = !self.sound_theme_on;
-        if self.sound_theme_on {
-          self.sound_theme.play().unwrap();
-        } else {
-          self.sound_theme.stop();
-        }
-      },
-      event::KeyCode::R => self.reset(ctx).unwrap(),
-      event::KeyCode::Left => self.piece_move_horizontally(-1),
-      event::KeyCode::Right => self.piece_move_horizontally(1),
-      event::KeyCode::Down => self.piece_move_vertically(1),
-      event::KeyCode::Up => self.rotate(),
-      event::KeyCode::Space => self.piece_drop(),
-      _ => (),
-    }
-    self.update_current_piece_ghost();
+  if let Some(games) = options.simulate {
+    run_simulation(&options, games);
+    return Ok(());
   }
 
-  fn draw(&mut self, ctx: &mut Context) -> GameResult {
-    graphics::clear(ctx, [0.1, 0.2, 0.3, 1.0].into());
-
-    self.draw_grid(ctx)?;
-    self.draw_cases(ctx)?;
-    self.draw_current_piece_ghost(ctx)?;
-    self.draw_current_piece(ctx)?;
-    self.draw_score(ctx)?;
-    self.draw_next_pieces(ctx)?;
+  // Connected before the window opens, so a bad address fails right away.
+  let connection = match &options.network {
+    Some(NetworkRole::Host(port)) => Some(Connection::host(*port)),
+    Some(NetworkRole::Join(address)) => Some(Connection::join(address)),
+    None => None,
+  };
+  let connection = match connection.transpose() {
+    Ok(connection) => connection,
+    Err(e) => {
+      error!("Cannot start the network match ({})", e);
+      process::exit(1);
+    },
+  };
 
-    graphics::present(ctx)?;
-    Ok(())
-  }
-}
+  let state_server = match options.serve_state.map(StateServer::start).transpose() {
+    Ok(state_server) => state_server,
+    Err(e) => {
+      error!("Cannot serve the game state ({})", e);
+      process::exit(1);
+    },
+  };
 
-pub fn main() -> GameResult {
-  let resource_dir = path::PathBuf::from("./resources");
+  let bot: Option<Box<dyn Controller>> = match &options.bot_command {
+    Some(command) => match ProcessBot::spawn(command) {
+      Ok(bot) => Some(Box::new(bot)),
+      Err(e) => {
+        error!("Cannot start the bot {} ({})", command, e);
+        process::exit(1);
+      },
+    },
+    None => None,
+  };
 
-  let cb = ggez::ContextBuilder::new("Tetris", "Datoh")
-    .add_resource_path(resource_dir)
-    .window_setup(
-      conf::WindowSetup::default()
-      .title("TetrisRS 2019 by Datoh - https://twitter.com/datoh - https://github.com/Datoh/TetrisRS"))
-    .window_mode(
-      conf::WindowMode::default()
-          .fullscreen_type(conf::FullscreenType::Windowed)
-          .resizable(false)
-          .dimensions(800.0, 600.0));
-  let (ctx, event_loop) = &mut cb.build()?;
-  let state = &mut MainState::new(ctx)?;
+  let mut audio_enabled = !options.mute;
+  let (ctx, event_loop) = &mut match context_builder(&options, audio_enabled).build() {
+    Ok(context) => context,
+    Err(GameError::AudioError(e)) => {
+      warn!("Cannot open audio device ({}), playing without sound", e);
+      audio_enabled = false;
+      context_builder(&options, audio_enabled).build()?
+    },
+    Err(e) => return Err(e),
+  };
+  if options.restore_window {
+    let settings = Settings::load(ctx);
+    options.window = restore_window(ctx, &settings, options.window)?;
+  }
+  let state = &mut match MainState::new(ctx, &options, audio_enabled, connection, bot, state_server) {
+    Ok(state) => state,
+    Err(GameError::ConfigError(e)) => {
+      error!("Invalid config ({})", e);
+      process::exit(1);
+    },
+    Err(e) => return Err(e),
+  };
   event::run(ctx, event_loop, state)
-}
\ No newline at end of file
+}
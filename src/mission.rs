@@ -0,0 +1,158 @@
+use std::io::Read;
+use std::time::Duration;
+
+use ggez::filesystem;
+use ggez::{Context, GameError, GameResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::Game;
+use crate::grid::{case_from_code, Grid};
+use crate::locale::Strings;
+use crate::piece::Tetromino;
+
+const MISSIONS_FILE: &str = "/missions.toml";
+
+// What a mission asks for. The game is won as soon as it is met, and lost on
+// a top-out.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Objective {
+  // Clear 4 lines at once `count` times.
+  Tetrises { count: u32 },
+  Lines { count: u32 },
+  // Do not top out for `seconds`.
+  Survive { seconds: f64 },
+}
+
+impl Objective {
+  pub fn is_met(&self, game: &Game) -> bool {
+    return match *self {
+      Objective::Tetrises { count } => game.clear_counts().tetrises >= count,
+      Objective::Lines { count } => game.current_score().line_removed >= count,
+      Objective::Survive { seconds } => game.time_played() >= Duration::from_secs_f64(seconds),
+    };
+  }
+
+  // Shown with the score, e.g. "Tetrises: 1/3".
+  pub fn progress(&self, game: &Game, strings: &Strings) -> String {
+    return match *self {
+      Objective::Tetrises { count } => strings.fill("mission.tetrises", &[("done", game.clear_counts().tetrises.min(count).to_string()), ("count", count.to_string())]),
+      Objective::Lines { count } => strings.fill("mission.lines", &[("done", game.current_score().line_removed.min(count).to_string()), ("count", count.to_string())]),
+      Objective::Survive { seconds } => {
+        let left = Duration::from_secs_f64(seconds).checked_sub(game.time_played()).unwrap_or_default();
+        strings.fill("mission.survive", &[("seconds", left.as_secs_f64().ceil().to_string())])
+      },
+    };
+  }
+}
+
+fn default_level() -> u32 {
+  return 1;
+}
+
+// One entry of missions.toml.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Mission {
+  pub name: String,
+  #[serde(default)]
+  pub description: String,
+  pub objective: Objective,
+  #[serde(default = "default_level")]
+  pub level: u32,
+  // Letters of the only pieces dealt, e.g. "SZ"; every piece of the set when absent.
+  #[serde(default)]
+  pub pieces: Option<String>,
+  // Starting board, one string per row from the top as in a saved grid,
+  // laid on the bottom of the board. Empty for an empty board.
+  #[serde(default)]
+  pub board: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct MissionFile {
+  missions: Vec<Mission>,
+}
+
+impl Mission {
+  // None when every piece is dealt.
+  pub fn pieces(&self) -> Result<Option<Vec<Tetromino>>, String> {
+    let letters = match &self.pieces {
+      Some(letters) => letters,
+      None => return Ok(None),
+    };
+    let mut pieces = Vec::new();
+    for letter in letters.chars() {
+      let tetromino = Tetromino::from_letter(letter).ok_or(format!("unknown piece '{}'", letter))?;
+      if !pieces.contains(&tetromino) {
+        pieces.push(tetromino);
+      }
+    }
+    if pieces.is_empty() {
+      return Err(String::from("pieces cannot be empty"));
+    }
+    return Ok(Some(pieces));
+  }
+
+  // None when the board starts empty.
+  pub fn grid(&self, width: usize, height: usize) -> Result<Option<Grid>, String> {
+    if self.board.is_empty() {
+      return Ok(None);
+    }
+    if self.board.len() > height {
+      return Err(format!("board has {} rows, the grid only {}", self.board.len(), height));
+    }
+    let mut grid = Grid::new(width, height);
+    let top = height - self.board.len();
+    for (y, row) in self.board.iter().enumerate() {
+      if row.chars().count() != width {
+        return Err(format!("board row {} is {} cases wide, the grid {}", y + 1, row.chars().count(), width));
+      }
+      for (x, letter) in row.chars().enumerate() {
+        let case = case_from_code(letter).ok_or(format!("unknown case '{}' in board row {}", letter, y + 1))?;
+        grid.set(x as i32, (top + y) as i32, case);
+      }
+    }
+    return Ok(Some(grid));
+  }
+
+  pub fn validate(&self, config: &GameConfig) -> Result<(), String> {
+    if self.level == 0 {
+      return Err(String::from("level must be at least 1"));
+    }
+    match self.objective {
+      Objective::Tetrises { count: 0 } | Objective::Lines { count: 0 } => return Err(String::from("objective count must be at least 1")),
+      Objective::Survive { seconds } if !seconds.is_finite() || seconds <= 0.0 => return Err(format!("objective seconds must be positive, got {}", seconds)),
+      _ => (),
+    }
+    if let Some(pieces) = self.pieces()? {
+      if let Some(tetromino) = pieces.iter().find(|tetromino| !config.pieces.pieces().contains(tetromino)) {
+        return Err(format!("piece '{}' is not part of the pieces of config.toml", tetromino.letter()));
+      }
+    }
+    self.grid(config.grid_width, config.grid_height)?;
+    return Ok(());
+  }
+}
+
+// Every mission of the file, checked against the board and pieces of `config`.
+pub fn missions_from_toml(text: &str, config: &GameConfig) -> Result<Vec<Mission>, String> {
+  let file: MissionFile = toml::from_str(text).map_err(|e| e.to_string())?;
+  if file.missions.is_empty() {
+    return Err(String::from("no mission"));
+  }
+  for mission in &file.missions {
+    mission.validate(config).map_err(|e| format!("mission {:?}: {}", mission.name, e))?;
+  }
+  return Ok(file.missions);
+}
+
+// Unlike config.toml the file is required: --missions has nothing to play without it.
+pub fn load_missions(ctx: &mut Context, config: &GameConfig) -> GameResult<Vec<Mission>> {
+  let mut text = String::new();
+  let mut file = filesystem::open(ctx, MISSIONS_FILE)?;
+  file.read_to_string(&mut text)?;
+  return missions_from_toml(&text, config)
+    .map_err(|e| GameError::ConfigError(format!("{}: {}", MISSIONS_FILE, e)));
+}
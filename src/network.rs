@@ -0,0 +1,176 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameSnapshot;
+
+pub const DEFAULT_PORT: u16 = 7878;
+pub const PROTOCOL_VERSION: u32 = 2;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// A board snapshot takes a few KB, anything longer is not a message.
+const MAX_FRAME: usize = 1 << 20;
+
+// Exchanged by the two peers of a networked match, each encoded with bincode
+// after its length as 4 bytes little endian. Each peer runs its own game,
+// only the results cross the wire.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Message {
+  // Ready for a match. The seed of the host gives both players the same pieces.
+  Hello { version: u32, seed: u64 },
+  // The board of the sender, drawn small beside the local one.
+  BoardSnapshot(Box<GameSnapshot>),
+  // Rows to push under the board of the receiver.
+  Garbage(u32),
+  // The sender topped out, or reached the goal of the mode when `won`.
+  GameOver { won: bool },
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum ConnectionState {
+  // The host has no opponent yet.
+  Waiting,
+  Connected,
+  Disconnected,
+}
+
+// Non-blocking TCP link to the opponent, polled once per logic tick.
+pub struct Connection {
+  // Kept by the host so the opponent can connect again after a disconnection.
+  listener: Option<TcpListener>,
+  stream: Option<TcpStream>,
+  input: Vec<u8>,
+  output: Vec<u8>,
+}
+
+impl Connection {
+  pub fn host(port: u16) -> io::Result<Connection> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    listener.set_nonblocking(true)?;
+    info!("Waiting for an opponent on port {}", port);
+    return Ok(Connection { listener: Some(listener), stream: None, input: Vec::new(), output: Vec::new() });
+  }
+
+  // Blocks for at most CONNECT_TIMEOUT, before the window is opened.
+  pub fn join(address: &str) -> io::Result<Connection> {
+    let socket_address = address.to_socket_addrs()?.next()
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("cannot resolve {}", address)))?;
+    let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)?;
+    Connection::set_up(&stream)?;
+    info!("Connected to {}", socket_address);
+    return Ok(Connection { listener: None, stream: Some(stream), input: Vec::new(), output: Vec::new() });
+  }
+
+  fn set_up(stream: &TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(true)?;
+    return stream.set_nodelay(true);
+  }
+
+  // Of the host, the one picked by the system for port 0.
+  pub fn port(&self) -> Option<u16> {
+    return self.listener.as_ref().and_then(|listener| listener.local_addr().ok()).map(|address| address.port());
+  }
+
+  pub fn is_host(&self) -> bool {
+    return self.listener.is_some();
+  }
+
+  pub fn state(&self) -> ConnectionState {
+    return match (&self.stream, &self.listener) {
+      (Some(_), _) => ConnectionState::Connected,
+      (None, Some(_)) => ConnectionState::Waiting,
+      (None, None) => ConnectionState::Disconnected,
+    };
+  }
+
+  // Queued until the next `poll`, dropped when not connected.
+  pub fn send(&mut self, message: &Message) {
+    if self.stream.is_none() {
+      return;
+    }
+    match bincode::serialize(message) {
+      Ok(frame) => {
+        self.output.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.output.extend_from_slice(&frame);
+      },
+      Err(e) => warn!("Cannot encode {:?} ({})", message, e),
+    }
+  }
+
+  // Never blocks: accepts a waiting opponent, sends what the socket takes and
+  // returns the messages received since the last call. Any error, including a
+  // malformed message, closes the connection.
+  pub fn poll(&mut self) -> Vec<Message> {
+    if self.stream.is_none() {
+      if let Some(listener) = &self.listener {
+        match listener.accept() {
+          Ok((stream, address)) => match Connection::set_up(&stream) {
+            Ok(()) => {
+              info!("Opponent connected from {}", address);
+              self.stream = Some(stream);
+            },
+            Err(e) => warn!("Cannot set up the connection of {} ({})", address, e),
+          },
+          Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => (),
+          Err(e) => warn!("Cannot accept an opponent ({})", e),
+        }
+      }
+    }
+
+    let mut messages = Vec::new();
+    if let Err(e) = self.exchange(&mut messages) {
+      warn!("Connection lost ({})", e);
+      self.stream = None;
+      self.input.clear();
+      self.output.clear();
+    }
+    return messages;
+  }
+
+  fn exchange(&mut self, messages: &mut Vec<Message>) -> io::Result<()> {
+    let stream = match self.stream.as_mut() {
+      Some(stream) => stream,
+      None => return Ok(()),
+    };
+
+    while !self.output.is_empty() {
+      match stream.write(&self.output) {
+        Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "connection closed")),
+        Ok(written) => {
+          self.output.drain(..written);
+        },
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+        Err(e) => return Err(e),
+      }
+    }
+
+    let mut buffer = [0; 4096];
+    loop {
+      match stream.read(&mut buffer) {
+        Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "closed by the opponent")),
+        Ok(read) => self.input.extend_from_slice(&buffer[..read]),
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+        Err(e) => return Err(e),
+      }
+    }
+
+    while self.input.len() >= 4 {
+      let length = u32::from_le_bytes([self.input[0], self.input[1], self.input[2], self.input[3]]) as usize;
+      if length > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("message of {} bytes", length)));
+      }
+      if self.input.len() < 4 + length {
+        break;
+      }
+      let message = bincode::deserialize(&self.input[4..4 + length]).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+      self.input.drain(..4 + length);
+      messages.push(message);
+    }
+    return Ok(());
+  }
+}
@@ -0,0 +1,181 @@
+use std::io::Read;
+
+use ggez::filesystem;
+use ggez::{Context, GameError, GameResult};
+
+use crate::grid::Grid;
+use crate::piece::{create_piece, Case, Piece, Tetromino};
+
+const OPENERS_DIRECTORY: &str = "/openers";
+// Pieces of an opener are numbered with these, in the order of the sequence.
+const RANKS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+// The opening of a game to practice, read from resources/openers/NAME.txt:
+//
+//   // Comment lines start with two slashes, blank lines are ignored.
+//   pieces: IOL
+//   2.........
+//   22..3.....
+//   21113.....
+//
+// The rows are laid on the bottom of the board and must be as wide as it.
+// Each piece of the sequence goes where its rank is, 1 to 9 then a to z;
+// '.' is an empty case and '#' garbage already there. A clear moves the
+// stack, so no row may be full before the last piece is in place.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Opener {
+  pub name: String,
+  pub pieces: Vec<Tetromino>,
+  // The cases of each piece of `pieces` on the board, sorted.
+  pub targets: Vec<Vec<(i32, i32)>>,
+  pub grid: Grid,
+}
+
+// How the pieces of an opener were placed so far.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct OpenerRun {
+  // By target, filled exactly by a piece.
+  pub filled: Vec<bool>,
+  // By piece locked, in order: whether it went to a target of its kind.
+  pub placements: Vec<bool>,
+}
+
+impl OpenerRun {
+  pub fn right(&self) -> usize {
+    return self.placements.iter().filter(|&&right| right).count();
+  }
+}
+
+// The cases relative to the top left corner of their bounding box, sorted.
+fn shape(cases: &[(i32, i32)]) -> Vec<(i32, i32)> {
+  let left = cases.iter().map(|&(x, _)| x).min().unwrap_or(0);
+  let top = cases.iter().map(|&(_, y)| y).min().unwrap_or(0);
+  let mut shape: Vec<(i32, i32)> = cases.iter().map(|&(x, y)| (x - left, y - top)).collect();
+  shape.sort_unstable();
+  return shape;
+}
+
+// Any rotation of the piece, unflipped: openers are not for --mirror.
+fn is_placement(tetromino: Tetromino, cases: &[(i32, i32)]) -> bool {
+  let mut piece = create_piece(tetromino, 0);
+  let wanted = shape(cases);
+  return (0..4).any(|rotation| {
+    piece.index_rotation = rotation;
+    shape(piece.cells()) == wanted
+  });
+}
+
+impl Opener {
+  // Errors name the line of the file at fault.
+  pub fn parse(name: &str, text: &str, width: usize, height: usize) -> Result<Opener, String> {
+    let mut pieces: Option<Vec<Tetromino>> = None;
+    let mut rows: Vec<String> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+      let number = index + 1;
+      let line = line.trim();
+      if line.is_empty() || line.starts_with("//") {
+        continue;
+      }
+      if let Some(letters) = line.strip_prefix("pieces:") {
+        if pieces.is_some() {
+          return Err(format!("line {}: pieces are given twice", number));
+        }
+        let letters = letters.trim();
+        if letters.is_empty() {
+          return Err(format!("line {}: no piece", number));
+        }
+        if letters.chars().count() > RANKS.len() {
+          return Err(format!("line {}: more than {} pieces", number, RANKS.len()));
+        }
+        pieces = Some(letters.chars().map(|letter| Tetromino::from_letter(letter).ok_or(format!("line {}: unknown piece '{}'", number, letter))).collect::<Result<Vec<_>, _>>()?);
+        continue;
+      }
+      if line.chars().count() != width {
+        return Err(format!("line {}: row is {} cases wide, the board {}", number, line.chars().count(), width));
+      }
+      if let Some(letter) = line.chars().find(|&letter| letter != '.' && letter != '#' && !RANKS.contains(letter)) {
+        return Err(format!("line {}: unknown case '{}'", number, letter));
+      }
+      rows.push(line.to_string());
+    }
+    let pieces = pieces.ok_or_else(|| String::from("missing a line pieces: with the sequence of pieces"))?;
+    if rows.len() > height {
+      return Err(format!("{} rows, the board only has {}", rows.len(), height));
+    }
+    let top = (height - rows.len()) as i32;
+    let mut grid = Grid::new(width, height);
+    let mut targets = vec![Vec::new(); pieces.len()];
+    for (y, row) in rows.iter().enumerate() {
+      for (x, letter) in row.chars().enumerate() {
+        let case = (x as i32, top + y as i32);
+        match letter {
+          '.' => (),
+          '#' => grid.set(case.0, case.1, Case::Garbage),
+          _ => {
+            let rank = RANKS.find(letter).unwrap();
+            if rank >= pieces.len() {
+              return Err(format!("piece {} is after the {} of the sequence", letter, pieces.len()));
+            }
+            targets[rank].push(case);
+          },
+        }
+      }
+    }
+    for (index, (&tetromino, cases)) in pieces.iter().zip(targets.iter_mut()).enumerate() {
+      let rank = RANKS.chars().nth(index).unwrap();
+      if !is_placement(tetromino, cases) {
+        return Err(format!("the cases of piece {} are not a {} piece", rank, tetromino.letter()));
+      }
+      cases.sort_unstable();
+    }
+    // Every piece but the last in place.
+    let mut stack = grid.clone();
+    for cases in &targets[..targets.len() - 1] {
+      for &(x, y) in cases {
+        stack.set(x, y, Case::Garbage);
+      }
+    }
+    if stack.has_full_row() {
+      return Err(String::from("a row is full before the last piece"));
+    }
+    return Ok(Opener { name: name.to_string(), pieces: pieces, targets: targets, grid: grid });
+  }
+
+  pub fn start(&self) -> OpenerRun {
+    return OpenerRun { filled: vec![false; self.targets.len()], placements: Vec::new() };
+  }
+
+  // Records where `piece` locked: right when it fills a target of its kind
+  // still empty, whatever its rank, as the hold changes the order.
+  pub fn place(&self, run: &mut OpenerRun, piece: &Piece) -> bool {
+    if self.is_done(run) {
+      return false;
+    }
+    let mut cases: Vec<(i32, i32)> = piece.cells().iter().map(|&(x, y)| (piece.x + x, piece.y + y)).collect();
+    cases.sort_unstable();
+    let target = (0..self.targets.len()).find(|&index| !run.filled[index] && self.pieces[index] == piece.tetromino && self.targets[index] == cases);
+    if let Some(index) = target {
+      run.filled[index] = true;
+    }
+    run.placements.push(target.is_some());
+    return target.is_some();
+  }
+
+  // Every piece of the sequence locked, right or wrong.
+  pub fn is_done(&self, run: &OpenerRun) -> bool {
+    return run.placements.len() >= self.pieces.len();
+  }
+}
+
+// `name` is the file name without .txt, it cannot leave the openers directory.
+pub fn load_opener(ctx: &mut Context, name: &str, width: usize, height: usize) -> GameResult<Opener> {
+  if name.is_empty() || !name.chars().all(|letter| letter.is_ascii_alphanumeric() || letter == '-' || letter == '_') {
+    return Err(GameError::ConfigError(format!("invalid opener name: {}", name)));
+  }
+  let path = format!("{}/{}.txt", OPENERS_DIRECTORY, name);
+  let mut text = String::new();
+  let mut file = filesystem::open(ctx, &path)?;
+  file.read_to_string(&mut text)?;
+  return Opener::parse(name, &text, width, height)
+    .map_err(|e| GameError::ConfigError(format!("{}: {}", path, e)));
+}
@@ -0,0 +1,408 @@
+use crate::ai::Difficulty;
+use crate::config::GameConfig;
+use crate::game::Mode;
+use crate::gravity::GravityCurve;
+use crate::grid::{DEFAULT_GRID_WIDTH, PENTOMINO_GRID_WIDTH};
+use crate::network::DEFAULT_PORT;
+use crate::piece::PieceSet;
+use crate::randomizer::RandomizerKind;
+use crate::simulate::{Policy, DEFAULT_MAX_PIECES};
+
+pub const USAGE: &str = "Usage: tetris_rs [OPTIONS]
+
+Options:
+  --seed N           seed of the piece generator, for reproducible games
+  --level N          starting level (default 1)
+  --mode MODE        marathon, sprint, ultra or zen (default marathon)
+  --randomizer NAME  uniform, bag or tgm (default from config.toml)
+  --gravity CURVE    classic, nes or tgm: how fast pieces fall at each level
+                     (default from config.toml)
+  --pieces SET       tetromino or pentomino (default from config.toml);
+                     pentominoes get a 12 columns board unless one is given
+  --board SIZE       classic (10x20), wide (12x24), tiny (6x12) or WxH
+                     (default from config.toml)
+  --big              every case of a piece covers 2x2 cases of the board, as
+                     if playing on a board half as wide and half as high
+  --items            clears may win an item, used with V (R on the left board
+                     in versus, Right Alt on the right one)
+  --invisible        locked pieces fade out after invisible_delay seconds
+                     (config.toml), the stack shows on clears and at the end
+  --handicap ROWS    the board starts with ROWS rows of garbage, the left one in
+                     versus (default from the settings)
+  --handicap2 ROWS   the same for the right board in versus or against the
+                     computer (default 0)
+  --cascade          after a clear, blocks fall on their own into the holes
+                     below and may clear more rows, for a bonus (config.toml)
+  --mirror           pieces are flipped left-right and the keys that move them
+                     sideways are swapped
+  --missions         pick one of the missions of resources/missions.toml in
+                     the menu instead of a mode, solo only
+  --puzzle NAME      clear the board of resources/puzzles/NAME.txt with its
+                     sequence of pieces, solo only
+  --opener NAME      practice the opener of resources/openers/NAME.txt: its
+                     pieces in order and an outline where each one goes,
+                     solo only
+  --load-board PATH  start a zen game on the board of the text file PATH, one
+                     line per row: '.' empty, '#' garbage or a piece letter
+                     (F7 saves the board on screen that way), solo only
+  --replay PATH      watch the game of the replay file PATH, games alone being
+                     saved to replays in the user data directory. Space
+                     pauses, Left and Right go a piece back or forth, 0 to 9
+                     jump to a tenth of the game
+  --daily            the challenge of the day: a marathon to 150 lines with the
+                     same pieces for everyone, on fixed rules. The first game
+                     of the day counts, the next ones are practice
+  --versus           two players on one keyboard: A D S W Q E Space on the
+                     left board, arrows Right Shift Right Ctrl Enter on the right
+  --ai LEVEL         play against the computer, easy, normal or hard
+  --bot-cmd COMMAND  let COMMAND play the game, talking JSON lines on its
+                     standard input and output (see examples/flat_bot.rs)
+  --no-garbage       in versus, line clears do not send garbage
+  --host             wait for an opponent on TCP port 7878
+  --join HOST:PORT   play against the game hosted at HOST:PORT
+  --serve-state PORT send the board and stats of the game to every TCP client
+                     on PORT, one JSON object per line, for stream overlays
+  --windowed WxH     window size in pixels (default 800x600, 1600x600 in local
+                     or computer versus, 1200x600 in network versus); alone,
+                     the window of the last game alone is reopened without
+                     --windowed nor --fullscreen
+  --fullscreen       use the whole screen
+  --mute             play without any sound
+  --no-stats         do not append a summary of each game to games.jsonl in
+                     the user data directory
+  --log-file PATH    also write the log to PATH (filtered by RUST_LOG)
+  --simulate GAMES   play GAMES games with the computer, without any window
+                     nor sound, and print their scores; --seed, --mode,
+                     --level and the rules options apply
+  --policy NAME      how --simulate places the pieces, greedy (the computer
+                     opponent) or random (default greedy)
+  --max-pieces N     a simulated game stops after N pieces (default 10000)
+  --json             print the results of --simulate as JSON
+  --help             print this message";
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum NetworkRole {
+  Host(u16),
+  Join(String),
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum WindowSize {
+  Windowed(f32, f32),
+  Fullscreen,
+}
+
+// Command line options, parsed once in `main`.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Options {
+  pub seed: Option<u64>,
+  pub level: u32,
+  pub mode: Mode,
+  pub randomizer: Option<RandomizerKind>,
+  pub gravity: Option<GravityCurve>,
+  pub pieces: Option<PieceSet>,
+  // Width and height of the board, in cases.
+  pub board: Option<(usize, usize)>,
+  pub big: bool,
+  pub invisible: bool,
+  pub items: bool,
+  // Rows of garbage of the first and second boards, see Game::set_handicap_rows.
+  pub handicap: Option<usize>,
+  pub handicap2: usize,
+  pub cascade: bool,
+  pub mirror: bool,
+  pub missions: bool,
+  pub puzzle: Option<String>,
+  pub opener: Option<String>,
+  // Path of a board of Grid::from_ascii, played in zen.
+  pub load_board: Option<String>,
+  // Path of a replay of replay.rs, watched instead of played.
+  pub replay: Option<String>,
+  pub daily: bool,
+  pub versus: bool,
+  pub ai: Option<Difficulty>,
+  pub bot_command: Option<String>,
+  pub garbage: bool,
+  pub network: Option<NetworkRole>,
+  pub serve_state: Option<u16>,
+  pub window: WindowSize,
+  // Neither --windowed nor --fullscreen for a single player: the window of
+  // the settings replaces the default one.
+  pub restore_window: bool,
+  pub mute: bool,
+  pub stats: bool,
+  pub log_file: Option<String>,
+  // Games of --simulate, played instead of opening the window.
+  pub simulate: Option<u32>,
+  pub policy: Policy,
+  pub max_pieces: u32,
+  pub json: bool,
+  pub help: bool,
+}
+
+impl Default for Options {
+  fn default() -> Options {
+    return Options {
+      seed: None,
+      level: 1,
+      mode: Mode::Marathon,
+      randomizer: None,
+      gravity: None,
+      pieces: None,
+      board: None,
+      big: false,
+      invisible: false,
+      items: false,
+      handicap: None,
+      handicap2: 0,
+      cascade: false,
+      mirror: false,
+      missions: false,
+      puzzle: None,
+      opener: None,
+      load_board: None,
+      replay: None,
+      daily: false,
+      versus: false,
+      ai: None,
+      bot_command: None,
+      garbage: true,
+      network: None,
+      serve_state: None,
+      window: WindowSize::Windowed(800.0, 600.0),
+      restore_window: false,
+      mute: false,
+      stats: true,
+      log_file: None,
+      simulate: None,
+      policy: Policy::Greedy,
+      max_pieces: DEFAULT_MAX_PIECES,
+      json: false,
+      help: false,
+    };
+  }
+}
+
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+  let mut parts = value.split('x');
+  let width: usize = parts.next()?.parse().ok()?;
+  let height: usize = parts.next()?.parse().ok()?;
+  if parts.next().is_some() || width == 0 || height == 0 {
+    return None;
+  }
+  return Some((width, height));
+}
+
+fn parse_board_size(value: &str) -> Option<(usize, usize)> {
+  let (width, height) = match value {
+    "classic" => (10, 20),
+    "wide" => (12, 24),
+    "tiny" => (6, 12),
+    _ => parse_size(value)?,
+  };
+  // Same limit as GameConfig: the I piece needs 4 cases both ways.
+  if width < 4 || height < 4 {
+    return None;
+  }
+  return Some((width, height));
+}
+
+impl Options {
+  // `args` must not contain the program name.
+  pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    let mut window = None;
+    while let Some(arg) = args.next() {
+      let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+      match arg.as_str() {
+        "--seed" => {
+          let seed = value("--seed")?;
+          options.seed = Some(seed.parse().map_err(|_| format!("invalid seed: {}", seed))?);
+        },
+        "--level" => {
+          let level = value("--level")?;
+          options.level = match level.parse() {
+            Ok(level) if level >= 1 => level,
+            _ => return Err(format!("invalid level: {}", level)),
+          };
+        },
+        "--mode" => {
+          let mode = value("--mode")?;
+          options.mode = Mode::from_name(&mode).ok_or(format!("unknown mode: {}", mode))?;
+        },
+        "--randomizer" => {
+          let name = value("--randomizer")?;
+          options.randomizer = Some(RandomizerKind::from_name(&name).ok_or(format!("unknown randomizer: {}", name))?);
+        },
+        "--gravity" => {
+          let name = value("--gravity")?;
+          options.gravity = Some(GravityCurve::from_name(&name).ok_or(format!("unknown gravity curve: {}", name))?);
+        },
+        "--pieces" => {
+          let name = value("--pieces")?;
+          options.pieces = Some(PieceSet::from_name(&name).ok_or(format!("unknown piece set: {}", name))?);
+        },
+        "--board" => {
+          let size = value("--board")?;
+          options.board = Some(parse_board_size(&size).ok_or(format!("invalid board size: {}", size))?);
+        },
+        "--big" => options.big = true,
+        "--invisible" => options.invisible = true,
+        "--items" => options.items = true,
+        "--handicap" => {
+          let rows = value("--handicap")?;
+          options.handicap = Some(rows.parse().map_err(|_| format!("invalid handicap: {}", rows))?);
+        },
+        "--handicap2" => {
+          let rows = value("--handicap2")?;
+          options.handicap2 = rows.parse().map_err(|_| format!("invalid handicap: {}", rows))?;
+        },
+        "--cascade" => options.cascade = true,
+        "--mirror" => options.mirror = true,
+        "--missions" => options.missions = true,
+        "--puzzle" => options.puzzle = Some(value("--puzzle")?),
+        "--opener" => options.opener = Some(value("--opener")?),
+        "--load-board" => options.load_board = Some(value("--load-board")?),
+        "--replay" => options.replay = Some(value("--replay")?),
+        "--daily" => options.daily = true,
+        "--windowed" => {
+          let size = value("--windowed")?;
+          let (width, height) = parse_size(&size).ok_or(format!("invalid window size: {}", size))?;
+          window = Some(WindowSize::Windowed(width as f32, height as f32));
+        },
+        "--fullscreen" => window = Some(WindowSize::Fullscreen),
+        "--versus" => options.versus = true,
+        "--ai" => {
+          let level = value("--ai")?;
+          options.ai = Some(Difficulty::from_name(&level).ok_or(format!("unknown AI level: {}", level))?);
+        },
+        "--bot-cmd" => options.bot_command = Some(value("--bot-cmd")?),
+        "--no-garbage" => options.garbage = false,
+        "--host" => options.network = Some(NetworkRole::Host(DEFAULT_PORT)),
+        "--join" => options.network = Some(NetworkRole::Join(value("--join")?)),
+        "--serve-state" => {
+          let port = value("--serve-state")?;
+          options.serve_state = Some(port.parse().map_err(|_| format!("invalid port: {}", port))?);
+        },
+        "--mute" => options.mute = true,
+        "--no-stats" => options.stats = false,
+        "--log-file" => options.log_file = Some(value("--log-file")?),
+        "--simulate" => {
+          let games = value("--simulate")?;
+          options.simulate = match games.parse() {
+            Ok(games) if games >= 1 => Some(games),
+            _ => return Err(format!("invalid number of games: {}", games)),
+          };
+        },
+        "--policy" => {
+          let name = value("--policy")?;
+          options.policy = Policy::from_name(&name).ok_or(format!("unknown policy: {}", name))?;
+        },
+        "--max-pieces" => {
+          let pieces = value("--max-pieces")?;
+          options.max_pieces = match pieces.parse() {
+            Ok(pieces) if pieces >= 1 => pieces,
+            _ => return Err(format!("invalid number of pieces: {}", pieces)),
+          };
+        },
+        "--json" => options.json = true,
+        "--help" | "-h" => options.help = true,
+        _ => return Err(format!("unknown argument: {}", arg)),
+      }
+    }
+    let opponents = [options.versus, options.ai.is_some(), options.network.is_some()];
+    if opponents.iter().filter(|&&opponent| opponent).count() > 1 {
+      return Err(String::from("choose one of --versus, --ai and --host or --join"));
+    }
+    if options.missions && opponents.contains(&true) {
+      return Err(String::from("--missions is for a single player"));
+    }
+    if options.puzzle.is_some() && (options.missions || opponents.contains(&true)) {
+      return Err(String::from("--puzzle is for a single player, without --missions"));
+    }
+    // The outlines are those of the pieces unflipped.
+    if options.opener.is_some() && (options.missions || options.puzzle.is_some() || options.mirror || opponents.contains(&true)) {
+      return Err(String::from("--opener is for a single player, without --missions, --puzzle nor --mirror"));
+    }
+    if options.load_board.is_some() {
+      if options.missions || options.puzzle.is_some() || options.opener.is_some() || options.daily || options.simulate.is_some() || opponents.contains(&true) {
+        return Err(String::from("--load-board is for a single player, without --missions, --puzzle, --opener, --daily nor --simulate"));
+      }
+      if options.mode != Mode::Marathon && options.mode != Mode::Zen {
+        return Err(String::from("--load-board plays zen"));
+      }
+      options.mode = Mode::Zen;
+    }
+    let special = options.missions || options.puzzle.is_some() || options.opener.is_some() || options.load_board.is_some() || options.daily || options.simulate.is_some();
+    if options.replay.is_some() && (special || opponents.contains(&true)) {
+      return Err(String::from("--replay plays alone, without an opponent, --missions, --puzzle, --opener, --load-board, --daily nor --simulate"));
+    }
+    // The daily challenge is the same game for everyone.
+    let rules = options.seed.is_some() || options.mode != Mode::Marathon || options.level != 1 || options.randomizer.is_some()
+      || options.gravity.is_some() || options.pieces.is_some() || options.board.is_some() || options.big || options.invisible
+      || options.items || options.handicap.is_some() || options.cascade || options.mirror || options.missions || options.puzzle.is_some()
+      || options.opener.is_some();
+    if options.daily && (rules || opponents.contains(&true)) {
+      return Err(String::from("--daily is for a single player, on its own rules"));
+    }
+    // A simulation has no window, no opponent and none of the special games.
+    if options.simulate.is_some() && (opponents.contains(&true) || options.missions || options.puzzle.is_some() || options.opener.is_some() || options.daily || options.bot_command.is_some()) {
+      return Err(String::from("--simulate plays alone, without an opponent, --bot-cmd, --missions, --puzzle, --opener nor --daily"));
+    }
+    let simulation = options.policy != Policy::Greedy || options.max_pieces != DEFAULT_MAX_PIECES || options.json;
+    if simulation && options.simulate.is_none() {
+      return Err(String::from("--policy, --max-pieces and --json are for --simulate"));
+    }
+    let (width, height) = options.layout_size();
+    options.restore_window = window.is_none() && !opponents.contains(&true);
+    options.window = window.unwrap_or(WindowSize::Windowed(width, height));
+    return Ok(options);
+  }
+
+  // Default window of the boards on screen, and the coordinates the game is
+  // drawn in whatever the size of the window. Each full size board gets the
+  // room of a solo window.
+  pub fn layout_size(&self) -> (f32, f32) {
+    return layout_size(self.versus || self.ai.is_some(), self.network.is_some());
+  }
+
+  // Overrides the values of config.toml given on the command line.
+  pub fn configure(&self, config: &mut GameConfig) {
+    if let Some(randomizer) = self.randomizer {
+      config.randomizer = randomizer;
+    }
+    if let Some(gravity) = self.gravity {
+      config.gravity = gravity;
+    }
+    if let Some(pieces) = self.pieces {
+      config.pieces = pieces;
+    }
+    if self.cascade {
+      config.cascade = true;
+    }
+    if let Some((width, height)) = self.board {
+      config.grid_width = width;
+      config.grid_height = height;
+    } else if config.pieces == PieceSet::Pentomino && config.grid_width == DEFAULT_GRID_WIDTH {
+      // Pentominoes are too much for 10 columns.
+      config.grid_width = PENTOMINO_GRID_WIDTH;
+    }
+    // The game plays on the board as the player sees it, one case for each
+    // block of 2x2; an odd last row or column is dropped.
+    if self.big {
+      config.grid_width /= 2;
+      config.grid_height /= 2;
+    }
+  }
+}
+
+// See Options::layout_size; two local boards, or one and a network opponent
+// at half size.
+pub fn layout_size(local_versus: bool, network: bool) -> (f32, f32) {
+  return match (local_versus, network) {
+    (true, _) => (1600.0, 600.0),
+    (_, true) => (1200.0, 600.0),
+    _ => (800.0, 600.0),
+  };
+}
@@ -0,0 +1,32 @@
+// What the game is currently doing; `MainState` dispatches update, draw and
+// key handling on it.
+//
+// Transitions:
+// - Menu -> Countdown or Playing when a game is started
+// - Menu -> Playing when the demo starts after a while without input, and
+//   any -> Menu when a key or the end of its game stops it
+// - Countdown -> Playing when the countdown ends
+// - Playing -> LineClear when the locked piece completed rows
+// - LineClear -> Playing when the clear animation ends
+// - Playing -> GameOver when a new piece does not fit, or in versus when
+//   either board tops out or reaches the goal of the mode
+// - GameOver -> Playing once the theme has faded out
+// - GameOver or Paused -> Menu with Escape, when the menu is the title screen
+// - any -> Paused and back to the phase it was paused from
+// - Menu or Paused -> Settings and back to the phase it was opened from
+// - Settings -> Controls and back
+// - Menu -> Statistics, Achievements or HighScores and back
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum GamePhase {
+  Menu,
+  Countdown,
+  Playing,
+  LineClear,
+  Paused,
+  Settings,
+  Controls,
+  Statistics,
+  Achievements,
+  HighScores,
+  GameOver,
+}
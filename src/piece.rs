@@ -0,0 +1,334 @@
+use std::time::Duration;
+
+use rand::{ distributions::{Distribution, Standard}, Rng};
+
+use serde::{Deserialize, Serialize};
+
+// Identity of a piece, used to look up its shapes and its color. The
+// pentominoes of the pentomino set come after the 7 tetrominoes; those whose
+// letter is taken by a tetromino get a 5 in their name.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,Serialize,Deserialize)]
+pub enum Tetromino {
+  I,
+  O,
+  T,
+  S,
+  Z,
+  J,
+  L,
+  F,
+  I5,
+  L5,
+  N,
+  P,
+  T5,
+  U,
+  V,
+  W,
+  X,
+  Y,
+  Z5,
+}
+
+impl Tetromino {
+  pub const ALL: [Tetromino; 7] = [Tetromino::I, Tetromino::O, Tetromino::T, Tetromino::S, Tetromino::Z, Tetromino::J, Tetromino::L];
+  pub const PENTOMINOES: [Tetromino; 12] = [
+    Tetromino::F, Tetromino::I5, Tetromino::L5, Tetromino::N, Tetromino::P, Tetromino::T5,
+    Tetromino::U, Tetromino::V, Tetromino::W, Tetromino::X, Tetromino::Y, Tetromino::Z5,
+  ];
+
+  // Pentominoes are lowercase so every piece has a letter of its own.
+  pub fn letter(&self) -> char {
+    return match self {
+      Tetromino::I => 'I',
+      Tetromino::O => 'O',
+      Tetromino::T => 'T',
+      Tetromino::S => 'S',
+      Tetromino::Z => 'Z',
+      Tetromino::J => 'J',
+      Tetromino::L => 'L',
+      Tetromino::F => 'f',
+      Tetromino::I5 => 'i',
+      Tetromino::L5 => 'l',
+      Tetromino::N => 'n',
+      Tetromino::P => 'p',
+      Tetromino::T5 => 't',
+      Tetromino::U => 'u',
+      Tetromino::V => 'v',
+      Tetromino::W => 'w',
+      Tetromino::X => 'x',
+      Tetromino::Y => 'y',
+      Tetromino::Z5 => 'z',
+    };
+  }
+
+  pub fn from_letter(letter: char) -> Option<Tetromino> {
+    return Tetromino::ALL.iter().chain(Tetromino::PENTOMINOES.iter()).copied().find(|tetromino| tetromino.letter() == letter);
+  }
+}
+
+// The pieces a game deals, from the config file or --pieces.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PieceSet {
+  Tetromino,
+  Pentomino,
+}
+
+impl PieceSet {
+  pub fn from_name(name: &str) -> Option<PieceSet> {
+    return match name {
+      "tetromino" => Some(PieceSet::Tetromino),
+      "pentomino" => Some(PieceSet::Pentomino),
+      _ => None,
+    };
+  }
+
+  pub fn pieces(&self) -> &'static [Tetromino] {
+    return match self {
+      PieceSet::Tetromino => &Tetromino::ALL,
+      PieceSet::Pentomino => &Tetromino::PENTOMINOES,
+    };
+  }
+
+  // Side of the largest bounding box, the board must be at least that big.
+  pub fn max_size(&self) -> i32 {
+    return self.pieces().iter().map(|&tetromino| piece_size(tetromino)).max().unwrap();
+  }
+}
+
+impl Distribution<Tetromino> for Standard {
+  fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tetromino {
+    return Tetromino::ALL[rng.gen_range(0, Tetromino::ALL.len())];
+  }
+}
+
+// A cell of the board, remembering which piece filled it for its color.
+// Garbage rows are sent by the opponent in versus play.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub enum Case {
+  Empty,
+  Filled(Tetromino),
+  Garbage,
+}
+
+// (x, y) of the filled cells inside the piece bounding box, one entry per rotation.
+type Shapes<const N: usize> = [[(i32, i32); N]; 4];
+
+// S, Z and I only have two positions, as in the classic games: a turn from
+// the second goes back to the first in place rather than a row or a column
+// aside.
+const SHAPES_Z: Shapes<4> = [
+  [(0, 0), (1, 0), (1, 1), (2, 1)],
+  [(2, 0), (1, 1), (2, 1), (1, 2)],
+  [(0, 0), (1, 0), (1, 1), (2, 1)],
+  [(2, 0), (1, 1), (2, 1), (1, 2)],
+];
+const SHAPES_S: Shapes<4> = [
+  [(1, 0), (2, 0), (0, 1), (1, 1)],
+  [(1, 0), (1, 1), (2, 1), (2, 2)],
+  [(1, 0), (2, 0), (0, 1), (1, 1)],
+  [(1, 0), (1, 1), (2, 1), (2, 2)],
+];
+const SHAPES_J: Shapes<4> = [
+  [(0, 0), (0, 1), (1, 1), (2, 1)],
+  [(1, 0), (2, 0), (1, 1), (1, 2)],
+  [(0, 1), (1, 1), (2, 1), (2, 2)],
+  [(1, 0), (1, 1), (0, 2), (1, 2)],
+];
+const SHAPES_L: Shapes<4> = [
+  [(2, 0), (0, 1), (1, 1), (2, 1)],
+  [(1, 0), (1, 1), (1, 2), (2, 2)],
+  [(0, 1), (1, 1), (2, 1), (0, 2)],
+  [(0, 0), (1, 0), (1, 1), (1, 2)],
+];
+const SHAPES_O: Shapes<4> = [
+  [(0, 0), (1, 0), (0, 1), (1, 1)],
+  [(0, 0), (1, 0), (0, 1), (1, 1)],
+  [(0, 0), (1, 0), (0, 1), (1, 1)],
+  [(0, 0), (1, 0), (0, 1), (1, 1)],
+];
+const SHAPES_T: Shapes<4> = [
+  [(1, 0), (0, 1), (1, 1), (2, 1)],
+  [(1, 0), (1, 1), (2, 1), (1, 2)],
+  [(0, 1), (1, 1), (2, 1), (1, 2)],
+  [(1, 0), (0, 1), (1, 1), (1, 2)],
+];
+const SHAPES_I: Shapes<4> = [
+  [(0, 1), (1, 1), (2, 1), (3, 1)],
+  [(2, 0), (2, 1), (2, 2), (2, 3)],
+  [(0, 1), (1, 1), (2, 1), (3, 1)],
+  [(2, 0), (2, 1), (2, 2), (2, 3)],
+];
+
+// Pentominoes are only given in their spawn orientation, the other three are
+// turned clockwise in the bounding box of side `size`.
+const fn rotations(shape: [(i32, i32); 5], size: i32) -> Shapes<5> {
+  let mut shapes = [shape; 4];
+  let mut rotation = 1;
+  while rotation < 4 {
+    let mut cell = 0;
+    while cell < 5 {
+      let (x, y) = shapes[rotation - 1][cell];
+      shapes[rotation][cell] = (size - 1 - y, x);
+      cell += 1;
+    }
+    rotation += 1;
+  }
+  return shapes;
+}
+
+const SHAPES_F: Shapes<5> = rotations([(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)], 3);
+const SHAPES_I5: Shapes<5> = rotations([(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)], 5);
+const SHAPES_L5: Shapes<5> = rotations([(0, 1), (0, 2), (1, 2), (2, 2), (3, 2)], 4);
+const SHAPES_N: Shapes<5> = rotations([(0, 1), (1, 1), (1, 2), (2, 2), (3, 2)], 4);
+const SHAPES_P: Shapes<5> = rotations([(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)], 3);
+const SHAPES_T5: Shapes<5> = rotations([(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)], 3);
+const SHAPES_U: Shapes<5> = rotations([(0, 0), (2, 0), (0, 1), (1, 1), (2, 1)], 3);
+const SHAPES_V: Shapes<5> = rotations([(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)], 3);
+const SHAPES_W: Shapes<5> = rotations([(0, 0), (0, 1), (1, 1), (1, 2), (2, 2)], 3);
+const SHAPES_X: Shapes<5> = rotations([(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)], 3);
+const SHAPES_Y: Shapes<5> = rotations([(1, 1), (0, 2), (1, 2), (2, 2), (3, 2)], 4);
+const SHAPES_Z5: Shapes<5> = rotations([(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)], 3);
+
+// Mirror mode: each shape flipped left-right in its bounding box. The
+// rotations are listed backwards so the next one is still a clockwise turn.
+const fn mirrored<const N: usize>(shapes: Shapes<N>, size: i32) -> Shapes<N> {
+  let mut mirrored = shapes;
+  let mut rotation = 0;
+  while rotation < 4 {
+    let mut cell = 0;
+    while cell < N {
+      let (x, y) = shapes[(4 - rotation) % 4][cell];
+      mirrored[rotation][cell] = (size - 1 - x, y);
+      cell += 1;
+    }
+    rotation += 1;
+  }
+  return mirrored;
+}
+
+const MIRRORED_I: Shapes<4> = mirrored(SHAPES_I, 4);
+const MIRRORED_O: Shapes<4> = mirrored(SHAPES_O, 2);
+const MIRRORED_T: Shapes<4> = mirrored(SHAPES_T, 3);
+const MIRRORED_S: Shapes<4> = mirrored(SHAPES_S, 3);
+const MIRRORED_Z: Shapes<4> = mirrored(SHAPES_Z, 3);
+const MIRRORED_J: Shapes<4> = mirrored(SHAPES_J, 3);
+const MIRRORED_L: Shapes<4> = mirrored(SHAPES_L, 3);
+const MIRRORED_F: Shapes<5> = mirrored(SHAPES_F, 3);
+const MIRRORED_I5: Shapes<5> = mirrored(SHAPES_I5, 5);
+const MIRRORED_L5: Shapes<5> = mirrored(SHAPES_L5, 4);
+const MIRRORED_N: Shapes<5> = mirrored(SHAPES_N, 4);
+const MIRRORED_P: Shapes<5> = mirrored(SHAPES_P, 3);
+const MIRRORED_T5: Shapes<5> = mirrored(SHAPES_T5, 3);
+const MIRRORED_U: Shapes<5> = mirrored(SHAPES_U, 3);
+const MIRRORED_V: Shapes<5> = mirrored(SHAPES_V, 3);
+const MIRRORED_W: Shapes<5> = mirrored(SHAPES_W, 3);
+const MIRRORED_X: Shapes<5> = mirrored(SHAPES_X, 3);
+const MIRRORED_Y: Shapes<5> = mirrored(SHAPES_Y, 4);
+const MIRRORED_Z5: Shapes<5> = mirrored(SHAPES_Z5, 3);
+
+fn piece_cells(tetromino: Tetromino, rotation: usize, mirrored: bool) -> &'static [(i32, i32)] {
+  return match (tetromino, mirrored) {
+
+    (Tetromino::I, false) => &SHAPES_I[rotation],
+    (Tetromino::O, false) => &SHAPES_O[rotation],
+    (Tetromino::T, false) => &SHAPES_T[rotation],
+    (Tetromino::S, false) => &SHAPES_S[rotation],
+    (Tetromino::Z, false) => &SHAPES_Z[rotation],
+    (Tetromino::J, false) => &SHAPES_J[rotation],
+    (Tetromino::L, false) => &SHAPES_L[rotation],
+    (Tetromino::F, false) => &SHAPES_F[rotation],
+    (Tetromino::I5, false) => &SHAPES_I5[rotation],
+    (Tetromino::L5, false) => &SHAPES_L5[rotation],
+    (Tetromino::N, false) => &SHAPES_N[rotation],
+    (Tetromino::P, false) => &SHAPES_P[rotation],
+    (Tetromino::T5, false) => &SHAPES_T5[rotation],
+    (Tetromino::U, false) => &SHAPES_U[rotation],
+    (Tetromino::V, false) => &SHAPES_V[rotation],
+    (Tetromino::W, false) => &SHAPES_W[rotation],
+    (Tetromino::X, false) => &SHAPES_X[rotation],
+    (Tetromino::Y, false) => &SHAPES_Y[rotation],
+    (Tetromino::Z5, false) => &SHAPES_Z5[rotation],
+    (Tetromino::I, true) => &MIRRORED_I[rotation],
+    (Tetromino::O, true) => &MIRRORED_O[rotation],
+    (Tetromino::T, true) => &MIRRORED_T[rotation],
+    (Tetromino::S, true) => &MIRRORED_S[rotation],
+    (Tetromino::Z, true) => &MIRRORED_Z[rotation],
+    (Tetromino::J, true) => &MIRRORED_J[rotation],
+    (Tetromino::L, true) => &MIRRORED_L[rotation],
+    (Tetromino::F, true) => &MIRRORED_F[rotation],
+    (Tetromino::I5, true) => &MIRRORED_I5[rotation],
+    (Tetromino::L5, true) => &MIRRORED_L5[rotation],
+    (Tetromino::N, true) => &MIRRORED_N[rotation],
+    (Tetromino::P, true) => &MIRRORED_P[rotation],
+    (Tetromino::T5, true) => &MIRRORED_T5[rotation],
+    (Tetromino::U, true) => &MIRRORED_U[rotation],
+    (Tetromino::V, true) => &MIRRORED_V[rotation],
+    (Tetromino::W, true) => &MIRRORED_W[rotation],
+    (Tetromino::X, true) => &MIRRORED_X[rotation],
+    (Tetromino::Y, true) => &MIRRORED_Y[rotation],
+    (Tetromino::Z5, true) => &MIRRORED_Z5[rotation],
+  };
+}
+
+// Side of the square bounding box the piece rotates in.
+fn piece_size(tetromino: Tetromino) -> i32 {
+  return match tetromino {
+    Tetromino::O => 2,
+    Tetromino::I | Tetromino::L5 | Tetromino::N | Tetromino::Y => 4,
+    Tetromino::I5 => 5,
+    _ => 3,
+  };
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Piece {
+  pub tetromino: Tetromino,
+  pub x: i32,
+  pub y: i32,
+  pub index_rotation: usize,
+  pub last_move: Duration,
+  // Flipped left-right, see --mirror. Absent from older snapshots.
+  #[serde(default)]
+  pub mirrored: bool,
+}
+
+impl Piece {
+  pub fn size(&self) -> i32 {
+    return piece_size(self.tetromino);
+  }
+
+  pub fn cells(&self) -> &'static [(i32, i32)] {
+    return piece_cells(self.tetromino, self.index_rotation, self.mirrored);
+  }
+
+  pub fn width(&self) -> i32 {
+    let cells = self.cells();
+    return cells.iter().map(|c| c.0).max().unwrap() - cells.iter().map(|c| c.0).min().unwrap() + 1;
+  }
+
+  pub fn height(&self) -> i32 {
+    let cells = self.cells();
+    return cells.iter().map(|c| c.1).max().unwrap() - cells.iter().map(|c| c.1).min().unwrap() + 1;
+  }
+
+  // The piece turned clockwise, before any wall kick is tried.
+  pub fn rotated(&self) -> Piece {
+    let mut piece = self.clone();
+    piece.index_rotation = (piece.index_rotation + 1) % 4;
+    return piece;
+  }
+
+  pub fn rotated_counterclockwise(&self) -> Piece {
+    let mut piece = self.clone();
+    piece.index_rotation = (piece.index_rotation + 3) % 4;
+    return piece;
+  }
+}
+
+// A piece in its spawn position, centered on a grid `grid_width` cases wide.
+pub fn create_piece(tetromino: Tetromino, grid_width: usize) -> Piece {
+  let size = piece_size(tetromino);
+  return Piece { tetromino: tetromino, x: (grid_width as i32 - size) / 2, y: 0, index_rotation: 0, last_move: Duration::from_secs(0), mirrored: false };
+}
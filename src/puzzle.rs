@@ -0,0 +1,123 @@
+use std::io::Read;
+
+use ggez::filesystem;
+use ggez::{Context, GameError, GameResult};
+
+use crate::game::Game;
+use crate::grid::{case_from_code, Grid};
+use crate::piece::{Case, Tetromino};
+
+const PUZZLES_DIRECTORY: &str = "/puzzles";
+// Under the user data directory, where the board editor writes.
+pub const EXPORTS_DIRECTORY: &str = "puzzles";
+
+// A board to clear with a fixed sequence of pieces, read from
+// resources/puzzles/NAME.txt:
+//
+//   // Comment lines start with two slashes, blank lines are ignored.
+//   pieces: IOTL
+//   ..........
+//   ####.#####
+//
+// The rows are laid on the bottom of the board, '.' for an empty case, '#'
+// for garbage or a piece letter, and must be as wide as the board.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Puzzle {
+  pub name: String,
+  pub pieces: Vec<Tetromino>,
+  pub grid: Grid,
+}
+
+impl Puzzle {
+  // Errors name the line of the file at fault.
+  pub fn parse(name: &str, text: &str, width: usize, height: usize) -> Result<Puzzle, String> {
+    let mut pieces = None;
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+      let number = index + 1;
+      let line = line.trim();
+      if line.is_empty() || line.starts_with("//") {
+        continue;
+      }
+      if let Some(letters) = line.strip_prefix("pieces:") {
+        if pieces.is_some() {
+          return Err(format!("line {}: pieces are given twice", number));
+        }
+        let letters = letters.trim();
+        if letters.is_empty() {
+          return Err(format!("line {}: no piece", number));
+        }
+        pieces = Some(letters.chars().map(|letter| Tetromino::from_letter(letter).ok_or(format!("line {}: unknown piece '{}'", number, letter))).collect::<Result<Vec<_>, _>>()?);
+        continue;
+      }
+      if line.chars().count() != width {
+        return Err(format!("line {}: row is {} cases wide, the board {}", number, line.chars().count(), width));
+      }
+      let row = line.chars().map(|letter| case_from_code(letter).ok_or(format!("line {}: unknown case '{}'", number, letter))).collect::<Result<Vec<_>, _>>()?;
+      rows.push(row);
+    }
+    let pieces = pieces.ok_or_else(|| String::from("missing a line pieces: with the sequence of pieces"))?;
+    if rows.len() > height {
+      return Err(format!("{} rows, the board only has {}", rows.len(), height));
+    }
+    if !rows.iter().flatten().any(|&case| case != Case::Empty) {
+      return Err(String::from("the board has nothing to clear"));
+    }
+    let mut grid = Grid::new(width, height);
+    let top = height - rows.len();
+    for (y, row) in rows.iter().enumerate() {
+      for (x, &case) in row.iter().enumerate() {
+        grid.set(x as i32, (top + y) as i32, case);
+      }
+    }
+    return Ok(Puzzle { name: name.to_string(), pieces: pieces, grid: grid });
+  }
+
+  // Cases of the puzzle still on the board: they are the only ones filled at
+  // time zero, locked pieces are stamped once the game has started.
+  pub fn cases_left(&self, game: &Game) -> usize {
+    let grid = game.grid();
+    return (0..grid.height() as i32)
+      .flat_map(|y| (0..grid.width() as i32).map(move |x| (x, y)))
+      .filter(|&(x, y)| grid.get(x, y).is_some_and(|case| case != Case::Empty) && game.filled_at(x, y).as_nanos() == 0)
+      .count();
+  }
+
+  // Pieces of the sequence not locked nor held yet, the one in play included.
+  pub fn pieces_left(&self, game: &Game) -> usize {
+    let used = game.pieces_locked() as usize + game.hold_piece().map_or(0, |_| 1);
+    return self.pieces.len().saturating_sub(used);
+  }
+
+  pub fn is_solved(&self, game: &Game) -> bool {
+    return self.cases_left(game) == 0;
+  }
+
+  // Every piece was used, the one in play comes from after the sequence.
+  pub fn is_failed(&self, game: &Game) -> bool {
+    return !self.is_solved(game) && self.pieces_left(game) == 0;
+  }
+}
+
+// The board as a puzzle file, its rows from the highest filled one down,
+// which Puzzle::parse reads back.
+pub fn puzzle_text(grid: &Grid, pieces: &[Tetromino]) -> String {
+  let codes: Vec<char> = grid.codes().chars().collect();
+  let rows: Vec<String> = codes.chunks(grid.width()).map(|row| row.iter().collect()).collect();
+  let top = rows.iter().position(|row| row.chars().any(|code| code != '.')).unwrap_or(rows.len());
+  let letters: String = pieces.iter().map(|tetromino| tetromino.letter()).collect();
+  return format!("pieces: {}\n{}\n", letters, rows[top..].join("\n"));
+}
+
+// `name` is the file name without .txt, it cannot leave the puzzles directory.
+pub fn load_puzzle(ctx: &mut Context, name: &str, width: usize, height: usize) -> GameResult<Puzzle> {
+  if name.is_empty() || !name.chars().all(|letter| letter.is_ascii_alphanumeric() || letter == '-' || letter == '_') {
+    return Err(GameError::ConfigError(format!("invalid puzzle name: {}", name)));
+  }
+  let path = format!("{}/{}.txt", PUZZLES_DIRECTORY, name);
+  let mut text = String::new();
+  let mut file = filesystem::open(ctx, &path)?;
+  file.read_to_string(&mut text)?;
+  return Puzzle::parse(name, &text, width, height)
+    .map_err(|e| GameError::ConfigError(format!("{}: {}", path, e)));
+}
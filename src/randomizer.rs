@@ -0,0 +1,199 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::piece::{PieceSet, Tetromino};
+
+// Source of the next piece type. Implementations own their RNG so a seeded
+// game replays the same sequence whatever the other sources of randomness.
+pub trait Randomizer {
+  fn next(&mut self) -> Tetromino;
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RandomizerKind {
+  Uniform,
+  Bag,
+  Tgm,
+}
+
+impl RandomizerKind {
+  pub fn from_name(name: &str) -> Option<RandomizerKind> {
+    return match name {
+      "uniform" => Some(RandomizerKind::Uniform),
+      "bag" => Some(RandomizerKind::Bag),
+      "tgm" => Some(RandomizerKind::Tgm),
+      _ => None,
+    };
+  }
+}
+
+pub(crate) fn create_rng(seed: Option<u64>) -> StdRng {
+  return match seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_entropy(),
+  };
+}
+
+// Same draw as the Standard distribution of Tetromino, so seeded games deal
+// the pieces they always did.
+fn draw(rng: &mut StdRng, pieces: &[Tetromino]) -> Tetromino {
+  return pieces[rng.gen_range(0, pieces.len())];
+}
+
+pub fn create_randomizer(kind: RandomizerKind, pieces: PieceSet, seed: Option<u64>) -> Box<dyn Randomizer> {
+  return create_randomizer_of(kind, pieces.pieces(), seed);
+}
+
+// Deals only `pieces`, which must not be empty, e.g. for a mission.
+pub fn create_randomizer_of(kind: RandomizerKind, pieces: &[Tetromino], seed: Option<u64>) -> Box<dyn Randomizer> {
+  let rng = create_rng(seed);
+  return match kind {
+    RandomizerKind::Uniform => Box::new(UniformRandomizer::new(rng, pieces)),
+    RandomizerKind::Bag => Box::new(BagRandomizer::new(rng, pieces)),
+    RandomizerKind::Tgm => Box::new(HistoryRandomizer::new(rng, pieces)),
+  };
+}
+
+// Rerolls of the uniform randomizer for a piece that would come a third time
+// in a row.
+const UNIFORM_ROLLS: usize = 3;
+
+// Every piece has the same probability, except that a piece dealt twice in a
+// row is drawn again up to UNIFORM_ROLLS times. The repeat is kept once the
+// rerolls are spent, which keeps the draw fair and allows sets of one piece.
+pub struct UniformRandomizer {
+  rng: StdRng,
+  pieces: Vec<Tetromino>,
+  // The last two pieces dealt, the latest second.
+  last: [Option<Tetromino>; 2],
+}
+
+impl UniformRandomizer {
+  pub fn new(rng: StdRng, pieces: &[Tetromino]) -> UniformRandomizer {
+    return UniformRandomizer { rng: rng, pieces: pieces.to_vec(), last: [None, None] };
+  }
+}
+
+impl Randomizer for UniformRandomizer {
+  fn next(&mut self) -> Tetromino {
+    let mut tetromino = draw(&mut self.rng, &self.pieces);
+    if self.last[0] == self.last[1] {
+      for _ in 0..UNIFORM_ROLLS {
+        if self.last[1] != Some(tetromino) {
+          break;
+        }
+        tetromino = draw(&mut self.rng, &self.pieces);
+      }
+    }
+    self.last = [self.last[1], Some(tetromino)];
+    return tetromino;
+  }
+}
+
+// Deals every piece of the set in a shuffled order, then shuffles again.
+pub struct BagRandomizer {
+  rng: StdRng,
+  pieces: Vec<Tetromino>,
+  bag: Vec<Tetromino>,
+}
+
+impl BagRandomizer {
+  pub fn new(rng: StdRng, pieces: &[Tetromino]) -> BagRandomizer {
+    return BagRandomizer { rng: rng, pieces: pieces.to_vec(), bag: Vec::new() };
+  }
+}
+
+impl Randomizer for BagRandomizer {
+  fn next(&mut self) -> Tetromino {
+    if self.bag.is_empty() {
+      self.bag = self.pieces.clone();
+      self.bag.shuffle(&mut self.rng);
+    }
+    return self.bag.pop().unwrap();
+  }
+}
+
+const HISTORY_SIZE: usize = 4;
+const HISTORY_ROLLS: usize = 6;
+
+// TGM style: draws again, up to HISTORY_ROLLS times, while the piece is one
+// of the last HISTORY_SIZE ones. The history starts with S and Z pieces and
+// the first piece is never S, Z or O, so the game never opens with an overhang.
+// Pentominoes get the same treatment with the pieces that leave the worst holes.
+// With only some of the pieces, the first one is any of them when none of the
+// usual ones is there.
+pub struct HistoryRandomizer {
+  rng: StdRng,
+  pieces: Vec<Tetromino>,
+  openers: Vec<Tetromino>,
+  history: [Tetromino; HISTORY_SIZE],
+  first: bool,
+}
+
+impl HistoryRandomizer {
+  pub fn new(rng: StdRng, pieces: &[Tetromino]) -> HistoryRandomizer {
+    let pentominoes = pieces.iter().any(|tetromino| Tetromino::PENTOMINOES.contains(tetromino));
+    let (openers, history) = match pentominoes {
+      false => ([Tetromino::I, Tetromino::J, Tetromino::L, Tetromino::T], [Tetromino::Z, Tetromino::S, Tetromino::S, Tetromino::Z]),
+      true => ([Tetromino::I5, Tetromino::L5, Tetromino::P, Tetromino::U], [Tetromino::X, Tetromino::W, Tetromino::W, Tetromino::X]),
+    };
+    let mut openers: Vec<Tetromino> = openers.iter().copied().filter(|tetromino| pieces.contains(tetromino)).collect();
+    if openers.is_empty() {
+      openers = pieces.to_vec();
+    }
+    return HistoryRandomizer {
+      rng: rng,
+      pieces: pieces.to_vec(),
+      openers: openers,
+      history: history,
+      first: true,
+    };
+  }
+}
+
+impl Randomizer for HistoryRandomizer {
+  fn next(&mut self) -> Tetromino {
+    let tetromino = if self.first {
+      self.first = false;
+      *self.openers.choose(&mut self.rng).unwrap()
+    } else {
+      let mut tetromino = draw(&mut self.rng, &self.pieces);
+      for _ in 1..HISTORY_ROLLS {
+        if !self.history.contains(&tetromino) {
+          break;
+        }
+        tetromino = draw(&mut self.rng, &self.pieces);
+      }
+      tetromino
+    };
+    self.history.rotate_left(1);
+    self.history[HISTORY_SIZE - 1] = tetromino;
+    return tetromino;
+  }
+}
+
+// Deals a fixed sequence, e.g. the pieces of a puzzle, starting over once
+// it runs out.
+pub struct SequenceRandomizer {
+  pieces: Vec<Tetromino>,
+  index: usize,
+}
+
+impl SequenceRandomizer {
+  // `pieces` must not be empty.
+  pub fn new(pieces: &[Tetromino]) -> SequenceRandomizer {
+    return SequenceRandomizer { pieces: pieces.to_vec(), index: 0 };
+  }
+}
+
+impl Randomizer for SequenceRandomizer {
+  fn next(&mut self) -> Tetromino {
+    let tetromino = self.pieces[self.index];
+    self.index = (self.index + 1) % self.pieces.len();
+    return tetromino;
+  }
+}
@@ -0,0 +1,1301 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ggez::event;
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::timer;
+use ggez::{Context, GameError, GameResult};
+
+use log::{debug, trace, warn};
+
+use crate::achievement::{Achievement, AchievementTracker};
+use crate::ai::{Bot, Controller, Difficulty};
+use crate::audio::AudioSystem;
+use crate::broadcast::StateServer;
+use crate::clip::ClipRecorder;
+use crate::config::GameConfig;
+use crate::daily::{daily_config, daily_seed, Date, DailyRecords, DAILY_LINES};
+#[cfg(feature = "discord")]
+use crate::discord::DiscordPresence;
+use crate::game::{Game, GameEvent, Input, Mode};
+use crate::grid::Grid;
+use crate::highscores::HighScores;
+use crate::input::HeldInputs;
+use crate::keymap::{Bindings, Keymap};
+use crate::layout::{Layout, Zoom};
+use crate::leaderboard::Leaderboard;
+use crate::locale::{load_strings, Strings};
+use crate::mission::{load_missions, Mission};
+use crate::network::{Connection, ConnectionState, Message};
+use crate::opener::{load_opener, Opener, OpenerRun};
+use crate::options::{layout_size, Options, WindowSize};
+use crate::phase::GamePhase;
+use crate::piece::{Case, Tetromino};
+use crate::puzzle::{load_puzzle, Puzzle};
+use crate::randomizer::{create_randomizer, create_randomizer_of, SequenceRandomizer};
+use crate::replay::{load_replay, Replay, ReplayPosition};
+use crate::rumble::Rumble;
+use crate::screenshot::Screenshots;
+use crate::settings::Settings;
+use crate::skin::{load_skin, Skin};
+use crate::stats::{completed_missions, LifetimeStats};
+use crate::timestep::FixedTimestep;
+
+// The methods of MainState by what they do, each file with its own
+// `impl MainState`. This one keeps the state, the flow of the games and the
+// event handler.
+// The boards: grid, stack, pieces and previews.
+mod board;
+// F8, F9 and the F10 overlay.
+mod debug;
+// The board editor of zen games.
+mod editor;
+// Sounds, rumble and the shake of the boards.
+mod effects;
+// Texts and bars over and around the boards: scores, banners, notices,
+// toasts, callouts, splits and meters.
+mod hud;
+// Keys, mouse and touch buttons.
+mod input;
+// The title screen, the texts of the other screens, the settings, the key
+// help and the quit question.
+mod menus;
+// The network match, the state server and the Discord presence.
+mod online;
+// What is read from and written to disk: config.toml, settings, games,
+// high scores, the leaderboard, screenshots and clips.
+mod persistence;
+
+use effects::{rumble_pulse, SHAKE_DURATION};
+use hud::{CALLOUT_DURATION, TETRIS_FLASH};
+use input::KeyCapture;
+use menus::{TitleItem, TITLE_ITEMS};
+use persistence::load_config;
+
+pub const WINDOW_TITLE: &str = "TetrisRS 2019 by Datoh - https://twitter.com/datoh - https://github.com/Datoh/TetrisRS";
+
+// Times the font size of the config.
+const BANNER_FONT_SCALE: f32 = 2.0;
+const HIGH_CONTRAST_FONT_SCALE: f32 = 26.0 / 18.0;
+
+const BACKGROUND_COLOR: graphics::Color = graphics::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 };
+
+const COUNTDOWN_DURATION: Duration = Duration::from_secs(3);
+
+// The computer plays by itself after this long without a key on the menu.
+const DEMO_IDLE: Duration = Duration::from_secs(30);
+const DEMO_DIFFICULTY: Difficulty = Difficulty::Normal;
+
+const THEME_FADE_SECONDS: f32 = 1.0;
+// The result of a game stays that long, the theme fading out meanwhile,
+// before the next one.
+const GAME_OVER_DELAY: Duration = Duration::from_secs(1);
+// Part of the theme volume of the settings the title screen plays it at.
+const MENU_THEME_VOLUME: f32 = 0.5;
+
+// The board of a network opponent is drawn at this size.
+const REMOTE_SCALE: f32 = 0.5;
+
+// Size of a board before the zoom.
+fn base_scale(controls: Controls) -> f32 {
+  return if controls == Controls::Remote { REMOTE_SCALE } else { 1.0 };
+}
+
+fn board_scales(players: &[Player]) -> Vec<f32> {
+  return players.iter().map(|player| base_scale(player.controls)).collect();
+}
+
+fn layout_zoom(settings: &Settings) -> Zoom {
+  return if settings.fit_board { Zoom::Fit } else { Zoom::Fixed(settings.zoom) };
+}
+
+// "mode.marathon", the name of `mode` in resources/lang.
+fn mode_key(mode: Mode) -> String {
+  return format!("mode.{}", mode.name().to_lowercase());
+}
+
+// Which keys drive a board: the keymap of the settings when playing alone,
+// the left and right halves of the keyboard in versus. A remote board is only
+// updated from the snapshots of the network opponent, a computer board by its bot.
+#[derive(Clone,Copy,Debug,PartialEq)]
+enum Controls {
+  Solo,
+  Left,
+  Right,
+  Remote,
+  Computer,
+}
+
+// One board on screen with its own game, keys and animations.
+struct Player {
+  game: Game,
+  controls: Controls,
+  text: graphics::Text,
+  shake_timer: Duration,
+  callout: Option<graphics::Text>,
+  callout_timer: Duration,
+  tetris_flash: Duration,
+  // Rows of the garbage meter as drawn, on their way to the queue of the
+  // game, and the size of that queue at the last tick.
+  garbage_shown: f32,
+  garbage_queued: u32,
+  garbage_pulse: Duration,
+  // Inputs received since the last logic tick, applied at the start of the next one.
+  pending_inputs: Vec<Input>,
+  // Keys of the pieces held down, repeated by the logic ticks.
+  held: HeldInputs,
+  // Plays instead of the keys: the computer opponent or an external bot.
+  bot: Option<Box<dyn Controller>>,
+}
+
+impl Player {
+  // Placed by `update_layout`.
+  fn new(game: Game, controls: Controls, text: graphics::Text, bot: Option<Box<dyn Controller>>) -> Player {
+    return Player {
+      game: game,
+      controls: controls,
+      text: text,
+      shake_timer: Duration::from_secs(0),
+      callout: None,
+      callout_timer: Duration::from_secs(0),
+      tetris_flash: Duration::from_secs(0),
+      garbage_shown: 0.0,
+      garbage_queued: 0,
+      garbage_pulse: Duration::from_secs(0),
+      pending_inputs: Vec::new(),
+      held: HeldInputs::new(),
+      bot: bot,
+    };
+  }
+}
+
+// What the demo took from a player, given back when it ends.
+struct Parked {
+  game: Game,
+  bot: Option<Box<dyn Controller>>,
+}
+
+pub struct MainState {
+  // The boards and their panels, see `update_layout`.
+  layout: Layout,
+  // Loaded once: every Font::new adds a copy of the file to the glyph cache.
+  font: graphics::Font,
+  // Of the settings, or the default one when it does not load.
+  skin: Skin,
+  // Of the language of the settings, or English when it does not load.
+  strings: Strings,
+  // The [colors] of config.toml, over the built-in ones.
+  palette: HashMap<Tetromino, graphics::Color>,
+  // One player, or two side by side in versus.
+  players: Vec<Player>,
+  // Line clears send garbage to the opponent.
+  garbage: bool,
+  // The title screen and what its Versus plays: the rule of garbage, the
+  // computer opponent and the handicap of the second board.
+  title_index: usize,
+  garbage_rule: bool,
+  opponent: Option<Difficulty>,
+  handicap2: usize,
+  // Index of the player who won the versus match.
+  winner: Option<usize>,
+  // The games of the players while the demo plays in their place, empty otherwise.
+  demo: Vec<Parked>,
+  // Link to the opponent of a network match, whose board is the second player.
+  network: Option<Connection>,
+  network_state: ConnectionState,
+  // The Hello of each side for the next match, and the seed it uses.
+  hello_sent: bool,
+  peer_ready: bool,
+  match_seed: u64,
+  seed: Option<u64>,
+  snapshot_timer: Duration,
+  // Sends the board of the first player to stream overlays.
+  state_server: Option<StateServer>,
+  state_timer: Duration,
+  // Where games.jsonl is written, None with --no-stats.
+  stats_directory: Option<PathBuf>,
+  lifetime: LifetimeStats,
+  // By mode and rules. The rank of the last game in its table, if it made it,
+  // and the mode of the table on the high scores screen.
+  high_scores: HighScores,
+  high_score_rank: Option<usize>,
+  high_score_mode: Mode,
+  // Time played in the games recorded since the launch.
+  session_time: Duration,
+  // Times of the game of the first board at each SPLIT_LINES lines, and the
+  // best splits of its mode when it started.
+  splits: Vec<Duration>,
+  best_splits: Vec<f64>,
+  // Achievements of the first board, shown one at a time.
+  achievements: AchievementTracker,
+  toasts: VecDeque<Achievement>,
+  toast: Option<graphics::Text>,
+  toast_timer: Duration,
+  leaderboard: Option<Leaderboard>,
+  screenshots: Screenshots,
+  // F12 was pressed, the frame is taken once drawn.
+  screenshot_requested: bool,
+  // The last seconds of the first board, for F11.
+  clip: ClipRecorder,
+  // As last set, the window is only told when it changes.
+  window_title: String,
+  // Follows the discord_presence setting.
+  #[cfg(feature = "discord")]
+  discord: Option<DiscordPresence>,
+  // How the submission of the last score went, shown under Game Over.
+  submission: Option<String>,
+  banner: Option<graphics::Text>,
+  notice: Option<graphics::Text>,
+  notice_timer: Duration,
+  // Hidden until F10. Only reads the game: the texts are cached between
+  // refreshes, each label with the top left corner of its case.
+  debug_overlay: bool,
+  debug_timer: Duration,
+  debug_text: Option<graphics::Text>,
+  debug_labels: Vec<(na::Point2<f32>, graphics::Text)>,
+  // F8 stops the logic, then F9 plays one tick per press. Keys still queue
+  // their inputs for the next tick.
+  frozen: bool,
+  frame_steps: u32,
+  // How long R has been held during a game, and the time since it was last
+  // pressed, see RESET_HOLD.
+  reset_held: Option<Duration>,
+  reset_pressed: Option<Duration>,
+  // Logic ticks since the start, all phases.
+  logic_ticks: u64,
+  keymap: Keymap,
+  // Keys of a player alone: the keymap with the changes of the controls screen.
+  bindings: Bindings,
+  // The key help while it is open, and whether opening it paused the game.
+  help: Option<graphics::Text>,
+  help_paused: bool,
+  // The same for the question asked before quitting a game in progress.
+  quit_question: Option<graphics::Text>,
+  quit_paused: bool,
+  // The board editor of zen games while it is open: the case a left click
+  // paints, and the one put down by the drag in progress. An edited board
+  // keeps the game off the leaderboard and the achievements.
+  editor: Option<Case>,
+  editor_paused: bool,
+  editor_stroke: Option<Case>,
+  editor_text: Option<graphics::Text>,
+  // The touch button pressed, and for how long.
+  touch_held: Option<Input>,
+  touch_timer: Duration,
+  board_edited: bool,
+  controls_index: usize,
+  key_capture: Option<KeyCapture>,
+  audio: AudioSystem,
+  rumble: Rumble,
+  phase: GamePhase,
+  phase_before_pause: GamePhase,
+  // Where Escape leaves the settings menu for, and the line picked in it.
+  phase_before_settings: GamePhase,
+  settings_index: usize,
+  phase_timer: Duration,
+  danger: bool,
+  timestep: FixedTimestep,
+  settings: Settings,
+  config: GameConfig,
+  // Each case of the game is drawn as 2x2 cases, see --big.
+  big: bool,
+  // Locked cases fade out, see --invisible.
+  invisible: bool,
+  fullscreen: bool,
+  // Picked in the menu with --missions, empty otherwise.
+  missions: Vec<Mission>,
+  mission_index: usize,
+  // Names of the missions passed, in games.jsonl or since the start.
+  completed_missions: HashSet<String>,
+  // Played instead of a mode with --puzzle.
+  puzzle: Option<Puzzle>,
+  // Practiced with --opener, and how its pieces went so far.
+  opener: Option<Opener>,
+  opener_run: OpenerRun,
+  // The board of --load-board, every zen game starts on it.
+  start_board: Option<Grid>,
+  // The game of --replay being watched, or the one being played, saved with
+  // the statistics once over.
+  watching: Option<ReplayPosition>,
+  recording: Option<Replay>,
+  // The challenge of the day with --daily: the date of the game, the first
+  // attempt of each day, and whether the game only is a practice one.
+  daily: bool,
+  daily_date: Date,
+  daily_records: DailyRecords,
+  daily_practice: bool,
+}
+
+impl MainState {
+  // `bot`, when given, plays the board of the first player.
+  pub fn new(ctx: &mut Context, options: &Options, audio_enabled: bool, network: Option<Connection>, bot: Option<Box<dyn Controller>>, state_server: Option<StateServer>) -> GameResult<MainState> {
+    let mut config = load_config(ctx)?;
+    options.configure(&mut config);
+    if options.daily {
+      config = daily_config(&config);
+    }
+    let watching = options.replay.as_ref().map(|path| load_replay(Path::new(path)).map(ReplayPosition::new)
+      .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path, e)))).transpose()?;
+    if let Some(position) = &watching {
+      config = position.replay.watching_config(&config);
+    }
+    config.validate().map_err(GameError::ConfigError)?;
+    // Checked by validate.
+    let palette = config.piece_colors().unwrap_or_default().into_iter().map(|(tetromino, color)| (tetromino, graphics::Color::from(color))).collect();
+    let settings = Settings::load(ctx);
+    let font = graphics::Font::new(ctx, &config.font).unwrap_or_else(|e| {
+      warn!("Cannot load the font {} ({}), using the built-in one", config.font, e);
+      graphics::Font::default()
+    });
+    let missions = if options.missions { load_missions(ctx, &config)? } else { Vec::new() };
+    let puzzle = options.puzzle.as_ref().map(|name| load_puzzle(ctx, name, config.grid_width, config.grid_height)).transpose()?;
+    let opener = options.opener.as_ref().map(|name| load_opener(ctx, name, config.grid_width, config.grid_height)).transpose()?;
+    let start_board = options.load_board.as_ref().map(|path| {
+      let text = fs::read_to_string(path).map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path, e)))?;
+      Grid::from_ascii_sized(&text, config.grid_width, config.grid_height).map_err(|e| GameError::ConfigError(format!("{}: {}", path, e)))
+    }).transpose()?;
+    let stats_directory = if options.stats { Some(filesystem::user_data_dir(ctx).to_path_buf()) } else { None };
+    let completed = match stats_directory.as_ref().map(|directory| completed_missions(directory)) {
+      Some(Ok(completed)) => completed,
+      Some(Err(e)) => {
+        warn!("Cannot read the passed missions ({})", e);
+        HashSet::new()
+      },
+      None => HashSet::new(),
+    };
+    let lifetime = match stats_directory.as_ref().map(|directory| LifetimeStats::load(directory)) {
+      Some(Ok(lifetime)) => lifetime,
+      Some(Err(e)) => {
+        warn!("Cannot read the lifetime statistics ({})", e);
+        LifetimeStats::default()
+      },
+      None => LifetimeStats::default(),
+    };
+    let high_scores = match stats_directory.as_ref().map(|directory| HighScores::load(directory)) {
+      Some(Ok(high_scores)) => high_scores,
+      Some(Err(e)) => {
+        warn!("Cannot read the high scores ({})", e);
+        HighScores::default()
+      },
+      None => HighScores::default(),
+    };
+    let daily_records = match stats_directory.as_ref().filter(|_| options.daily).map(|directory| DailyRecords::load(directory)) {
+      Some(Ok(records)) => records,
+      Some(Err(e)) => {
+        warn!("Cannot read the daily challenges ({})", e);
+        DailyRecords::default()
+      },
+      None => DailyRecords::default(),
+    };
+
+    // Both versus players get the same pieces.
+    let local_versus = options.versus || options.ai.is_some();
+    let seed = if local_versus { Some(options.seed.unwrap_or_else(rand::random)) } else { options.seed };
+    let controls = match (options.versus, options.ai, &network) {
+      (true, _, _) => vec![Controls::Left, Controls::Right],
+      (_, Some(_), _) => vec![Controls::Solo, Controls::Computer],
+      (_, _, Some(_)) => vec![Controls::Solo, Controls::Remote],
+      _ => vec![Controls::Solo],
+    };
+    let mut bot = bot;
+    // Both sides of a network match play at the same speed, as everyone does
+    // in the daily challenge.
+    let speed = if network.is_some() || options.daily { 1.0 } else { settings.game_speed };
+    let players: Vec<Player> = controls.into_iter().enumerate().map(|(index, controls)| {
+      let mut game = Game::with_config(config.clone(), seed);
+      // Missions, puzzles and openers have their own goal, the game itself
+      // never ends but by a top-out.
+      game.set_mode(if options.missions || puzzle.is_some() || opener.is_some() { Mode::Marathon } else { options.mode });
+      game.set_start_level(options.level);
+      game.set_speed(speed);
+      game.set_items(options.items);
+      game.set_mirror(options.mirror);
+      // The board of a network opponent comes from its snapshots.
+      let handicap = match index {
+        _ if options.daily => 0,
+        0 => options.handicap.unwrap_or(settings.handicap_rows),
+        _ => options.handicap2,
+      };
+      if controls != Controls::Remote {
+        game.set_handicap_rows(handicap);
+      }
+      let bot = match controls {
+        Controls::Computer => options.ai.map(|difficulty| Box::new(Bot::new(difficulty)) as Box<dyn Controller>),
+        _ => bot.take(),
+      };
+      // Bots drop as soon as they are ready, and play pieces once there.
+      let human = controls != Controls::Remote && bot.is_none();
+      game.set_misdrop_protection(settings.misdrop_protection && human);
+      game.set_buffer_spawn_inputs(settings.buffer_spawn_inputs && human);
+      Player::new(game, controls, graphics::Text::new(("", font, config.font_size)), bot)
+    }).collect();
+
+    let (layout_width, layout_height) = options.layout_size();
+    let frame = graphics::Rect::new(0.0, 0.0, layout_width, layout_height);
+    let layout = Layout::new(frame, &config, options.big, &board_scales(&players), layout_zoom(&settings));
+    let mut s = MainState {
+      layout: layout,
+      font: font,
+      skin: Skin::default(),
+      strings: Strings::english(),
+      palette: palette,
+      players: players,
+      garbage: (local_versus || network.is_some()) && options.garbage,
+      title_index: TITLE_ITEMS.iter().position(|&item| item == if local_versus { TitleItem::Versus } else { TitleItem::Mode(options.mode) }).unwrap_or(0),
+      garbage_rule: options.garbage,
+      opponent: options.ai,
+      handicap2: options.handicap2,
+      winner: None,
+      demo: Vec::new(),
+      network_state: network.as_ref().map(|network| network.state()).unwrap_or(ConnectionState::Disconnected),
+      network: network,
+      hello_sent: false,
+      peer_ready: false,
+      match_seed: 0,
+      seed: seed,
+      snapshot_timer: Duration::from_secs(0),
+      state_server: state_server,
+      state_timer: Duration::from_secs(0),
+      stats_directory: stats_directory,
+      lifetime: lifetime,
+      high_scores: high_scores,
+      high_score_rank: None,
+      high_score_mode: Mode::Marathon,
+      session_time: Duration::from_secs(0),
+      splits: Vec::new(),
+      best_splits: Vec::new(),
+      achievements: AchievementTracker::default(),
+      toasts: VecDeque::new(),
+      toast: None,
+      toast_timer: Duration::from_secs(0),
+      leaderboard: config.leaderboard_url.as_ref().map(|url| Leaderboard::new(url, config.leaderboard_token.as_deref())),
+      screenshots: Screenshots::new(filesystem::user_data_dir(ctx)),
+      screenshot_requested: false,
+      clip: ClipRecorder::new(filesystem::user_data_dir(ctx)),
+      window_title: String::from(WINDOW_TITLE),
+      #[cfg(feature = "discord")]
+      discord: None,
+      submission: None,
+      banner: None,
+      notice: None,
+      notice_timer: Duration::from_secs(0),
+      debug_overlay: false,
+      debug_timer: Duration::from_secs(0),
+      debug_text: None,
+      debug_labels: Vec::new(),
+      frozen: false,
+      frame_steps: 0,
+      reset_held: None,
+      reset_pressed: None,
+      logic_ticks: 0,
+      keymap: Keymap::from_name(&settings.keymap).unwrap_or_else(|| {
+        warn!("Unknown keymap {:?} in the settings, using arrows", settings.keymap);
+        Keymap::Arrows
+      }),
+      bindings: Bindings::from_names(Keymap::from_name(&settings.keymap).unwrap_or(Keymap::Arrows), &settings.bindings),
+      help: None,
+      help_paused: false,
+      quit_question: None,
+      quit_paused: false,
+      editor: None,
+      editor_paused: false,
+      editor_stroke: None,
+      editor_text: None,
+      touch_held: None,
+      touch_timer: Duration::from_secs(0),
+      board_edited: false,
+      controls_index: 0,
+      key_capture: None,
+      audio: AudioSystem::new(ctx, audio_enabled, &settings.theme),
+      rumble: Rumble::new(),
+      phase: GamePhase::Playing,
+      phase_before_pause: GamePhase::Playing,
+      phase_before_settings: GamePhase::Menu,
+      settings_index: 0,
+      phase_timer: Duration::from_secs(0),
+      danger: false,
+      timestep: FixedTimestep::default(),
+      settings: settings,
+      config: config,
+      big: options.big,
+      invisible: options.invisible,
+      fullscreen: options.window == WindowSize::Fullscreen,
+      missions: missions,
+      mission_index: 0,
+      completed_missions: completed,
+      puzzle: puzzle,
+      opener: opener,
+      opener_run: OpenerRun::default(),
+      start_board: start_board,
+      watching: watching,
+      recording: None,
+      daily: options.daily,
+      daily_date: Date::today(),
+      daily_records: daily_records,
+      daily_practice: false,
+    };
+
+    s.apply_language(ctx);
+    s.apply_skin(ctx);
+    s.apply_discord();
+    let (width, height) = graphics::drawable_size(ctx);
+    s.update_letterbox(ctx, width, height)?;
+    s.reset()?;
+    s.enter_menu()?;
+    if !s.settings.help_seen {
+      s.settings.help_seen = true;
+      s.save_settings(ctx);
+      s.show_help()?;
+    }
+
+    Ok(s)
+  }
+
+  // Shows `frame` as large as a window of `width` x `height` allows. The
+  // bars are the parts of the screen coordinates outside of it.
+  fn update_letterbox(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult {
+    // Minimized.
+    if width <= 0.0 || height <= 0.0 {
+      return Ok(());
+    }
+    let scale = (width / self.layout.frame.w).min(height / self.layout.frame.h);
+    let (screen_width, screen_height) = (width / scale, height / scale);
+    let screen = graphics::Rect::new(
+      self.layout.frame.x - (screen_width - self.layout.frame.w) / 2.0,
+      self.layout.frame.y - (screen_height - self.layout.frame.h) / 2.0,
+      screen_width,
+      screen_height,
+    );
+    debug!("Window {}x{}, drawn at scale {}", width, height, scale);
+    return graphics::set_screen_coordinates(ctx, screen);
+  }
+
+  // Again after the boards, the zoom or the window change.
+  fn update_layout(&mut self) {
+    self.layout = Layout::new(self.layout.frame, &self.config, self.big, &board_scales(&self.players), layout_zoom(&self.settings));
+  }
+
+  fn versus(&self) -> bool {
+    return self.players.len() > 1;
+  }
+
+  // A single local player against the network or the computer.
+  fn against_opponent(&self) -> bool {
+    return self.players.iter().any(|player| player.controls == Controls::Remote || player.controls == Controls::Computer);
+  }
+
+  // The mission being played or picked, none in the demo.
+  fn mission(&self) -> Option<&Mission> {
+    return if self.in_demo() { None } else { self.missions.get(self.mission_index) };
+  }
+
+  // Level, pieces and board of the mission, after a reset of the game.
+  fn apply_mission(&mut self) {
+    let mission = match self.mission() {
+      Some(mission) => mission.clone(),
+      None => return,
+    };
+    // Both were checked when the file was loaded.
+    let pieces = mission.pieces().unwrap_or(None).unwrap_or_else(|| self.config.pieces.pieces().to_vec());
+    let grid = mission.grid(self.config.grid_width, self.config.grid_height).unwrap_or(None);
+    let game = &mut self.players[0].game;
+    game.set_start_level(mission.level);
+    game.set_randomizer(create_randomizer_of(self.config.randomizer, &pieces, self.seed));
+    if let Some(grid) = grid {
+      game.set_grid(grid);
+    }
+  }
+
+  // The puzzle being played, none in the demo.
+  fn puzzle(&self) -> Option<&Puzzle> {
+    return if self.in_demo() { None } else { self.puzzle.as_ref() };
+  }
+
+  // Board and pieces of the puzzle, after a reset of the game.
+  fn apply_puzzle(&mut self) {
+    let (pieces, grid) = match self.puzzle() {
+      Some(puzzle) => (puzzle.pieces.clone(), puzzle.grid.clone()),
+      None => return,
+    };
+    let game = &mut self.players[0].game;
+    game.set_randomizer(Box::new(SequenceRandomizer::new(&pieces)));
+    game.set_grid(grid);
+  }
+
+  // The opener being practiced, none in the demo.
+  fn opener(&self) -> Option<&Opener> {
+    return if self.in_demo() { None } else { self.opener.as_ref() };
+  }
+
+  // Board and pieces of the opener, after a reset of the game.
+  fn apply_opener(&mut self) {
+    let (pieces, grid, run) = match self.opener() {
+      Some(opener) => (opener.pieces.clone(), opener.grid.clone(), opener.start()),
+      None => return,
+    };
+    let game = &mut self.players[0].game;
+    game.set_randomizer(Box::new(SequenceRandomizer::new(&pieces)));
+    game.set_grid(grid);
+    self.opener_run = run;
+  }
+
+  // The board of --load-board, after a reset of the game. It counts as
+  // edited, see the board editor.
+  fn apply_board(&mut self) {
+    let grid = match &self.start_board {
+      Some(grid) if !self.in_demo() => grid.clone(),
+      _ => return,
+    };
+    self.players[0].game.set_grid(grid);
+    self.board_edited = true;
+  }
+
+  // The date of the daily challenge being played or picked, none in the demo.
+  fn daily(&self) -> Option<Date> {
+    return if self.daily && !self.in_demo() { Some(self.daily_date) } else { None };
+  }
+
+  // The pieces of today, after a reset of the game: a game left open past
+  // midnight gets the next challenge.
+  fn apply_daily(&mut self) {
+    if self.daily().is_none() {
+      return;
+    }
+    let date = Date::today();
+    let seed = daily_seed(date);
+    self.daily_date = date;
+    self.daily_practice = self.daily_records.official(date).is_some();
+    self.seed = Some(seed);
+    self.players[0].game.set_randomizer(create_randomizer(self.config.randomizer, self.config.pieces, Some(seed)));
+  }
+
+  // The game of the replay being watched, from its start.
+  fn apply_replay(&mut self) {
+    if self.in_demo() {
+      return;
+    }
+    if let Some(position) = self.watching.as_mut() {
+      position.paused = false;
+      self.players[0].game = position.seek(0);
+    }
+  }
+
+  // A game alone from this machine, on rules a fresh game plays the same:
+  // missions, puzzles, openers and the daily challenge end it from here
+  // rather than in the game, items draw from a generator of their own.
+  fn records_replay(&self) -> bool {
+    let player = &self.players[0];
+    return self.stats_directory.is_some() && !self.in_demo() && self.watching.is_none() && !self.versus() && player.controls == Controls::Solo
+      && player.bot.is_none() && self.mission().is_none() && self.puzzle().is_none() && self.opener().is_none() && self.daily().is_none()
+      && !self.board_edited && !self.big && !player.game.items();
+  }
+
+  // One board alone, or two sharing a seed, keeping the rules of the first
+  // board. The layout follows, the window too unless in fullscreen.
+  fn set_boards(&mut self, ctx: &mut Context, versus: bool) -> GameResult {
+    let controls = match (versus, self.opponent) {
+      (false, _) => vec![Controls::Solo],
+      (true, Some(_)) => vec![Controls::Solo, Controls::Computer],
+      (true, None) => vec![Controls::Left, Controls::Right],
+    };
+    if self.players.iter().map(|player| player.controls).eq(controls.iter().copied()) {
+      return Ok(());
+    }
+    debug!("Boards: {:?}", controls);
+    let template = &self.players[0].game;
+    let (mode, level, speed, items, mirror, handicap) =
+      (template.mode(), template.start_level(), template.speed(), template.items(), template.mirror(), template.handicap_rows());
+    if versus {
+      self.seed = Some(self.seed.unwrap_or_else(rand::random));
+    }
+    let mut players = Vec::new();
+    for (index, &controls) in controls.iter().enumerate() {
+      let mut game = Game::with_config(self.config.clone(), self.seed);
+      game.set_mode(mode);
+      game.set_start_level(level);
+      game.set_speed(speed);
+      game.set_items(items);
+      game.set_mirror(mirror);
+      game.set_handicap_rows(if index == 0 { handicap } else { self.handicap2 });
+      let bot = match controls {
+        Controls::Computer => self.opponent.map(|difficulty| Box::new(Bot::new(difficulty)) as Box<dyn Controller>),
+        _ => None,
+      };
+      game.set_misdrop_protection(self.settings.misdrop_protection && bot.is_none());
+      game.set_buffer_spawn_inputs(self.settings.buffer_spawn_inputs && bot.is_none());
+      players.push(Player::new(game, controls, graphics::Text::new(("", self.font(), self.font_size())), bot));
+    }
+    self.players = players;
+    self.garbage = versus && self.garbage_rule;
+
+    let (width, height) = layout_size(versus, false);
+    self.layout.frame = graphics::Rect::new(0.0, 0.0, width, height);
+    if !self.fullscreen {
+      graphics::set_drawable_size(ctx, width, height)?;
+    }
+    let (width, height) = graphics::drawable_size(ctx);
+    self.update_letterbox(ctx, width, height)?;
+    self.update_layout();
+
+    Ok(())
+  }
+
+  fn reset(&mut self) -> GameResult {
+    for player in self.players.iter_mut() {
+      player.game.reset();
+      player.pending_inputs.clear();
+      player.shake_timer = Duration::from_secs(0);
+      player.callout = None;
+      player.tetris_flash = Duration::from_secs(0);
+    }
+    self.board_edited = false;
+    self.touch_held = None;
+    self.apply_mission();
+    self.apply_puzzle();
+    self.apply_opener();
+    self.apply_board();
+    self.apply_daily();
+    self.apply_replay();
+    self.recording = if self.records_replay() { Some(Replay::start(&self.players[0].game)) } else { None };
+    self.splits.clear();
+    self.best_splits = self.lifetime.best_splits(self.players[0].game.mode()).to_vec();
+    self.achievements.reset();
+    self.clip.clear();
+    self.winner = None;
+    self.submission = None;
+    // A network match waits in the menu for both sides to say Hello.
+    self.hello_sent = false;
+    self.set_phase(if self.network.is_some() { GamePhase::Menu } else { GamePhase::Playing })?;
+    self.danger = false;
+    for index in 0..self.players.len() {
+      self.create_score_text(index);
+    }
+    self.audio.stop_theme();
+    self.audio.fade_theme_in(self.settings.theme_volume, THEME_FADE_SECONDS);
+    if self.settings.theme_on {
+      self.audio.play_theme(self.sound_theme_pitch(), Duration::from_secs(0));
+    }
+
+    Ok(())
+  }
+
+  fn set_phase(&mut self, phase: GamePhase) -> GameResult {
+    debug!("Phase: {:?}", phase);
+    self.phase = phase;
+    self.phase_timer = Duration::from_secs(0);
+    self.banner = self.create_phase_banner();
+
+    Ok(())
+  }
+
+  // Closing the window or Escape during a game asks first, the game is not
+  // kept. Anywhere else the game quits at once.
+  fn game_in_progress(&self) -> bool {
+    let phase = self.game_phase();
+    return !self.in_demo() && (phase == GamePhase::Playing || phase == GamePhase::LineClear || phase == GamePhase::Countdown || phase == GamePhase::Paused);
+  }
+
+  // The game under the pause and the menus.
+  fn game_phase(&self) -> GamePhase {
+    let phase = match self.phase {
+      GamePhase::Settings | GamePhase::Controls => self.phase_before_settings,
+      GamePhase::Statistics | GamePhase::Achievements | GamePhase::HighScores => GamePhase::Menu,
+      phase => phase,
+    };
+    return if phase == GamePhase::Paused { self.phase_before_pause } else { phase };
+  }
+
+  fn toggle_pause(&mut self) -> GameResult {
+    if self.phase == GamePhase::Paused {
+      let phase = self.phase_before_pause;
+      let timer = self.phase_timer;
+      self.set_phase(phase)?;
+      self.phase_timer = timer;
+      self.audio.resume_theme();
+    } else {
+      self.phase_before_pause = self.phase;
+      let timer = self.phase_timer;
+      self.set_phase(GamePhase::Paused)?;
+      self.phase_timer = timer;
+      self.audio.pause_theme();
+    }
+
+    Ok(())
+  }
+
+  fn font(&self) -> graphics::Font {
+    return self.skin.font.unwrap_or(self.font);
+  }
+
+  // Loads every file of the skin of the settings before anything is drawn
+  // with it, so a broken skin only costs a notice.
+  fn apply_skin(&mut self, ctx: &mut Context) {
+    match load_skin(ctx, &self.settings.skin) {
+      Ok(skin) => self.skin = skin,
+      Err(e) => {
+        warn!("Cannot load the skin {} ({}), using the default", self.settings.skin, e);
+        self.skin = Skin::default();
+        self.show_notice(&self.strings.fill("notice.skin_not_loaded", &[("skin", self.settings.skin.clone()), ("error", e.to_string())]));
+      },
+    }
+    for index in 0..self.players.len() {
+      self.create_score_text(index);
+    }
+  }
+
+  // The texts of the language of the settings, English when its file does
+  // not load. Everything already on screen is written again.
+  fn apply_language(&mut self, ctx: &mut Context) {
+    match load_strings(ctx, &self.settings.language) {
+      Ok(strings) => self.strings = strings,
+      Err(e) => {
+        warn!("Cannot load the language {} ({}), using English", self.settings.language, e);
+        self.strings = Strings::english();
+        self.show_notice(&self.strings.fill("notice.language_not_loaded", &[("language", self.settings.language.clone()), ("error", e.to_string())]));
+      },
+    }
+    for index in 0..self.players.len() {
+      self.create_score_text(index);
+    }
+  }
+
+  fn font_size(&self) -> f32 {
+    return if self.settings.high_contrast { self.config.font_size * HIGH_CONTRAST_FONT_SCALE } else { self.config.font_size };
+  }
+
+  fn update_countdown(&mut self, delta: Duration) -> GameResult {
+    if self.phase_timer >= COUNTDOWN_DURATION {
+      return self.set_phase(GamePhase::Playing);
+    }
+
+    let seconds_left = |elapsed: Duration| (COUNTDOWN_DURATION - elapsed).as_secs_f32().ceil();
+    let shown = seconds_left(self.phase_timer);
+    if shown != seconds_left(self.phase_timer.checked_sub(delta).unwrap_or_default()) {
+      let text = format!("{}", shown);
+      self.banner = Some(self.create_banner_text(&text));
+    }
+
+    Ok(())
+  }
+
+  // Shared by the Playing and LineClear phases: the game owns the clear delay,
+  // the phase only mirrors it so the rows can be highlighted.
+  fn update_game(&mut self, ctx: &mut Context, delta: Duration) -> GameResult {
+    for index in 0..self.players.len() {
+      let player = &mut self.players[index];
+      if player.controls == Controls::Remote {
+        continue;
+      }
+      let seconds = player.game.time_played().as_secs();
+      let watching = if index == 0 && self.demo.is_empty() { self.watching.as_mut() } else { None };
+      let recording = if index == 0 { self.recording.as_mut() } else { None };
+      if let Some(position) = watching {
+        // The inputs come from the replay alone.
+        player.pending_inputs.clear();
+        if !position.paused {
+          position.advance(&mut player.game);
+        }
+      } else {
+        let repeats = player.held.update(delta);
+        player.pending_inputs.extend(repeats);
+        if let Some(bot) = player.bot.as_mut() {
+          if let Some(input) = bot.update(&player.game, delta) {
+            player.pending_inputs.push(input);
+          }
+        }
+        let mut recording = recording;
+        for input in player.pending_inputs.drain(..) {
+          if let Some(replay) = recording.as_mut() {
+            replay.record(input);
+          }
+          player.game.apply(input);
+        }
+        player.game.step(delta);
+        if let Some(replay) = recording {
+          replay.step();
+        }
+      }
+      player.shake_timer = player.shake_timer.checked_sub(delta).unwrap_or_default();
+      player.tetris_flash = player.tetris_flash.checked_sub(delta).unwrap_or_default();
+      player.callout_timer = player.callout_timer.checked_sub(delta).unwrap_or_default();
+      if player.callout_timer == Duration::from_secs(0) {
+        player.callout = None;
+      }
+      if seconds != player.game.time_played().as_secs() {
+        self.create_score_text(index);
+      }
+      // Checked after each step, so the game ends on the clear or the second that met it.
+      let game = &self.players[index].game;
+      if index == 0 && !game.is_over() {
+        let met = self.mission().is_some_and(|mission| mission.objective.is_met(game)) || self.puzzle().is_some_and(|puzzle| puzzle.is_solved(game))
+          || (self.daily().is_some() && game.current_score().line_removed >= DAILY_LINES)
+          || (!game.is_clearing() && self.opener().is_some_and(|opener| opener.is_done(&self.opener_run)));
+        // The last piece may still be clearing the last rows.
+        let failed = !game.is_clearing() && self.puzzle().is_some_and(|puzzle| puzzle.is_failed(game));
+        if met {
+          self.players[0].game.finish();
+        } else if failed {
+          self.players[0].game.top_out();
+        }
+      }
+
+      if index == 0 {
+        self.clip.record(delta, &self.players[0].game);
+      }
+
+      for event in self.players[index].game.drain_events() {
+        self.handle_event(ctx, index, event)?;
+      }
+      if self.phase == GamePhase::GameOver {
+        return Ok(());
+      }
+    }
+
+    let clearing = self.players.iter().any(|player| player.game.is_clearing());
+    let phase = if clearing { GamePhase::LineClear } else { GamePhase::Playing };
+    if self.phase != phase {
+      self.set_phase(phase)?;
+    }
+
+    Ok(())
+  }
+
+  // All the feedback of the game of a player: sounds, texts, theme, board
+  // animations, and in versus the garbage and the end of the match.
+  fn handle_event(&mut self, ctx: &mut Context, index: usize, event: GameEvent) -> GameResult {
+    // Only for the first board played from the keyboard.
+    if index == 0 && !self.in_demo() && self.players[0].bot.is_none() && !self.board_edited && self.watching.is_none() {
+      for achievement in self.achievements.check(&self.players[0].game, event, &self.lifetime) {
+        self.unlock(achievement);
+      }
+    }
+    if let Some(pulse) = rumble_pulse(event) {
+      self.play_rumble(index, pulse);
+    }
+    match event {
+      GameEvent::PieceLocked => {
+        self.update_danger();
+        // The pieces left of a puzzle.
+        if self.puzzle().is_some() {
+          self.create_score_text(index);
+        }
+        if index == 0 {
+          self.place_opener_piece();
+        }
+      },
+      GameEvent::HardDrop(rows) => {
+        if rows > 0 && !self.settings.reduced_motion {
+          self.players[index].shake_timer = SHAKE_DURATION;
+        }
+      },
+      GameEvent::LinesCleared(line_removed) => {
+        self.play_line_removed(ctx, line_removed);
+        if index == 0 {
+          self.record_splits();
+        }
+        self.create_score_text(index);
+        self.update_danger();
+        if line_removed >= 4 {
+          self.show_callout(index, "callout.tetris");
+          if !self.settings.reduced_motion {
+            self.players[index].tetris_flash = TETRIS_FLASH;
+          }
+        }
+      },
+      // Right after the LinesCleared of the same clear.
+      GameEvent::BackToBack if self.players[index].callout_timer == CALLOUT_DURATION => self.show_callout(index, "callout.back_to_back"),
+      GameEvent::Attack(rows) => {
+        if self.garbage {
+          match self.network.as_mut() {
+            Some(network) => network.send(&Message::Garbage(rows)),
+            None => self.players[1 - index].game.add_garbage(rows),
+          }
+        }
+      },
+      GameEvent::LevelUp(_) => {
+        self.restart_sound_theme(Duration::from_secs(0));
+        self.create_score_text(index);
+      },
+      GameEvent::ScoreChanged(_) => self.create_score_text(index),
+      GameEvent::GameOver | GameEvent::Finished => {
+        if event == GameEvent::GameOver {
+          self.play_lost(ctx);
+        }
+        if self.versus() {
+          // Topping out loses the match, reaching the goal of the mode wins it.
+          self.winner = Some(if event == GameEvent::Finished { index } else { 1 - index });
+        }
+        if let Some(network) = self.network.as_mut() {
+          network.send(&Message::BoardSnapshot(Box::new(self.players[index].game.snapshot())));
+          network.send(&Message::GameOver { won: event == GameEvent::Finished });
+        }
+        self.create_score_text(index);
+        self.end_match()?;
+      },
+      GameEvent::ItemWon(_) | GameEvent::ItemUsed(_) => self.create_score_text(index),
+      GameEvent::Hold if self.puzzle().is_some() => self.create_score_text(index),
+      GameEvent::Hold | GameEvent::TSpin | GameEvent::BackToBack => (),
+    }
+
+    Ok(())
+  }
+
+  // Tells whether the piece just locked went where the opener wants it.
+  fn place_opener_piece(&mut self) {
+    let right = match (self.opener.as_ref().filter(|_| !self.in_demo()), self.players[0].game.last_piece()) {
+      (Some(opener), Some(piece)) if !opener.is_done(&self.opener_run) => opener.place(&mut self.opener_run, piece),
+      _ => return,
+    };
+    self.show_callout(0, if right { "callout.opener_right" } else { "callout.opener_wrong" });
+    self.create_score_text(0);
+  }
+
+  fn restart(&mut self) -> GameResult {
+    self.reset_held = None;
+    self.reset_pressed = None;
+    self.abandon_games();
+    self.reset()?;
+
+    Ok(())
+  }
+
+  fn abandon_games(&mut self) {
+    let phase = self.game_phase();
+    if phase == GamePhase::Playing || phase == GamePhase::LineClear {
+      self.record_games(true);
+    }
+  }
+
+  // "TetrisRS — Lv 7 · 45,300 pts" for the first board during a game.
+  fn update_window_title(&mut self, ctx: &mut Context) {
+    let title = if self.settings.title_status && self.game_in_progress() {
+      let score = self.players[0].game.current_score();
+      self.strings.fill("title.window", &[("level", score.level.to_string()), ("score", self.strings.number(score.score))])
+    } else {
+      String::from(WINDOW_TITLE)
+    };
+    if title != self.window_title {
+      graphics::set_window_title(ctx, &title);
+      self.window_title = title;
+    }
+  }
+
+  fn update_game_over(&mut self) -> GameResult {
+    if self.phase_timer >= GAME_OVER_DELAY {
+      if self.in_demo() {
+        return self.stop_demo();
+      }
+      self.reset()?;
+      // Back to the list, to retry or pick the next one, or to the result of the day.
+      if !self.missions.is_empty() || self.daily {
+        self.set_phase(GamePhase::Menu)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn update_menu(&mut self) -> GameResult {
+    if self.network.is_none() && self.help.is_none() && self.phase_timer >= DEMO_IDLE {
+      return self.start_demo();
+    }
+
+    Ok(())
+  }
+
+  fn in_demo(&self) -> bool {
+    return !self.demo.is_empty();
+  }
+
+  // Every board is played by the computer on a game of its own, so the
+  // demo neither records scores nor disturbs the next real game.
+  fn start_demo(&mut self) -> GameResult {
+    debug!("Demo starts");
+    for player in self.players.iter_mut() {
+      let mut game = Game::with_config(self.config.clone(), None);
+      game.set_mode(player.game.mode());
+      game.set_start_level(player.game.start_level());
+      let game = std::mem::replace(&mut player.game, game);
+      let bot = player.bot.replace(Box::new(Bot::new(DEMO_DIFFICULTY)));
+      self.demo.push(Parked { game: game, bot: bot });
+    }
+    self.reset()?;
+
+    Ok(())
+  }
+
+  fn stop_demo(&mut self) -> GameResult {
+    debug!("Demo stops");
+    for (player, parked) in self.players.iter_mut().zip(self.demo.drain(..)) {
+      player.game = parked.game;
+      player.bot = parked.bot;
+    }
+    self.reset()?;
+    self.enter_menu()?;
+
+    Ok(())
+  }
+
+  // One fixed step of the logic of the current phase.
+  fn tick(&mut self, ctx: &mut Context, delta: Duration) -> GameResult {
+    self.logic_ticks += 1;
+    self.update_network(delta)?;
+    if self.phase != GamePhase::Paused {
+      self.phase_timer += delta;
+    }
+    match self.phase {
+      GamePhase::Menu => self.update_menu()?,
+      GamePhase::Countdown => self.update_countdown(delta)?,
+      GamePhase::Playing | GamePhase::LineClear => {
+        self.update_touch(delta);
+        self.update_game(ctx, delta)?;
+      },
+      GamePhase::Paused | GamePhase::Settings | GamePhase::Controls | GamePhase::Statistics | GamePhase::Achievements | GamePhase::HighScores => (),
+      GamePhase::GameOver => self.update_game_over()?,
+    }
+    self.update_state_server(delta);
+    self.update_leaderboard();
+    self.update_screenshots();
+    self.update_window_title(ctx);
+    #[cfg(feature = "discord")]
+    self.update_discord();
+    self.update_notice(delta);
+    self.update_toast(delta);
+    self.update_debug_overlay(delta);
+    self.update_garbage_meters(delta);
+
+    Ok(())
+  }
+}
+
+impl event::EventHandler for MainState {
+  fn update(&mut self, ctx: &mut Context) -> GameResult {
+    let delta = timer::delta(ctx);
+    trace!("Update {:?} after {:?}", self.phase, delta);
+
+    self.audio.update(ctx, delta);
+    self.rumble.update();
+    self.update_reset_key(delta)?;
+    let tick = self.timestep.tick();
+    if self.frozen {
+      for _ in 0..std::mem::take(&mut self.frame_steps) {
+        self.tick(ctx, tick)?;
+      }
+      // Every frame, for the inputs queued since.
+      self.debug_timer = Duration::from_secs(0);
+      self.update_debug_overlay(Duration::from_secs(0));
+    } else {
+      for _ in 0..self.timestep.advance(delta) {
+        self.tick(ctx, tick)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // True keeps the window open, to ask first during a game.
+  fn quit_event(&mut self, ctx: &mut Context) -> bool {
+    if self.game_in_progress() {
+      if self.quit_question.is_none() {
+        self.ask_quit().unwrap();
+      }
+      return true;
+    }
+    self.before_quit(ctx);
+    false
+  }
+
+  fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+    self.remember_window(ctx);
+    if let Err(e) = self.update_letterbox(ctx, width, height) {
+      warn!("Cannot fit the window ({})", e);
+    }
+    self.update_layout();
+  }
+
+  // Keys pressed before leaving are dropped, and the game waits for P once
+  // the focus is back. A network opponent would not wait.
+  fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
+    if gained {
+      return;
+    }
+    for player in self.players.iter_mut() {
+      player.pending_inputs.clear();
+      player.held.clear();
+    }
+    self.reset_held = None;
+    let phase = self.phase;
+    let playing = phase == GamePhase::Playing || phase == GamePhase::LineClear || phase == GamePhase::Countdown;
+    if self.settings.focus_pause && playing && self.network.is_none() && !self.in_demo() {
+      debug!("Focus lost, pausing");
+      self.toggle_pause().unwrap();
+    }
+  }
+
+  fn key_down_event(&mut self, ctx: &mut Context, key: event::KeyCode, _mods: event::KeyMods, repeat: bool) {
+    self.key_down(ctx, key, repeat);
+  }
+
+  fn key_up_event(&mut self, _ctx: &mut Context, key: event::KeyCode, _mods: event::KeyMods) {
+    self.key_up(key);
+  }
+
+  fn mouse_button_down_event(&mut self, ctx: &mut Context, button: event::MouseButton, x: f32, y: f32) {
+    self.mouse_down(ctx, button, x, y);
+  }
+
+  fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: event::MouseButton, _x: f32, _y: f32) {
+    self.mouse_up(button);
+  }
+
+  fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+    self.mouse_motion(ctx, x, y);
+  }
+
+  fn draw(&mut self, ctx: &mut Context) -> GameResult {
+    if self.settings.high_contrast {
+      graphics::clear(ctx, graphics::BLACK);
+    } else {
+      graphics::clear(ctx, self.skin.background_color.unwrap_or(BACKGROUND_COLOR));
+      // Stretched over the bars of the letterbox too.
+      if let Some(background) = &self.skin.background {
+        let screen = graphics::screen_coordinates(ctx);
+        let scale = na::Vector2::new(screen.w / background.width() as f32, screen.h / background.height() as f32);
+        graphics::draw(ctx, background, graphics::DrawParam::new().dest(na::Point2::new(screen.x, screen.y)).scale(scale))?;
+      }
+    }
+
+    if self.game_phase() != GamePhase::Menu {
+      for (player, board) in self.players.iter().zip(&self.layout.boards) {
+        self.draw_playfield(ctx, player, board)?;
+      }
+    }
+    if self.game_phase() != GamePhase::Menu {
+      self.draw_touch_buttons(ctx)?;
+      self.draw_debug_overlay(ctx)?;
+    }
+    if let Some(question) = &self.quit_question {
+      self.draw_overlay(ctx, question)?;
+    } else if let Some(help) = &self.help {
+      self.draw_overlay(ctx, help)?;
+    } else if self.editor.is_some() {
+      self.draw_editor(ctx)?;
+    } else {
+      self.draw_banner(ctx)?;
+    }
+    // The notice of the last screenshot is not part of the next one.
+    if self.screenshot_requested {
+      self.screenshot_requested = false;
+      self.take_screenshot(ctx);
+    }
+    self.draw_notice(ctx)?;
+    self.draw_toast(ctx)?;
+
+    graphics::present(ctx)?;
+    Ok(())
+  }
+}
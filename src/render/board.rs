@@ -0,0 +1,444 @@
+use std::time::Duration;
+
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use crate::game::PIECE_HISTORY;
+use crate::layout::{BoardLayout, CASE_BORDER, GRID_STROKE_SIZE};
+use crate::opener::Opener;
+use crate::piece::{Case, Piece, Tetromino};
+use crate::skin::{tile_index, SKIN_TILES};
+
+use super::{Controls, MainState, Player};
+use super::hud::{TETRIS_FLASH, TETRIS_FLASH_ALPHA};
+
+// Black background, saturated colors, outlined cases, a thicker ghost and
+// bigger text, for low vision.
+const HIGH_CONTRAST_OUTLINE: f32 = 2.0;
+const HIGH_CONTRAST_GHOST: f32 = 3.0;
+const HIGH_CONTRAST_GARBAGE_COLOR: graphics::Color = graphics::Color { r: 0.75, g: 0.75, b: 0.75, a: 1.0 };
+
+// With --invisible, locked cases take that long to fade out once their delay is over.
+const INVISIBLE_FADE: Duration = Duration::from_millis(500);
+
+// Rows the piece would complete, under the piece and its ghost.
+const CLEAR_PREVIEW_COLOR: graphics::Color = graphics::Color { r: 0.2, g: 1.0, b: 0.2, a: 0.25 };
+
+// Outline of the last locked piece, on the grid lines around its cases.
+const LAST_LOCKED_STROKE_SIZE: f32 = 2.0;
+const LAST_LOCKED_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 1.0, b: 0.6, a: 1.0 };
+
+// Outline of the cases where each piece of an opener goes.
+const OPENER_TARGET_STROKE_SIZE: f32 = 2.0;
+const OPENER_TARGET_ALPHA: f32 = 0.45;
+
+const GARBAGE_COLOR: graphics::Color = graphics::Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 };
+
+// The guideline colors: I cyan, O yellow, T purple, S green, Z red, J blue
+// and L orange.
+fn tetromino_color(tetromino: Tetromino, high_contrast: bool) -> graphics::Color {
+  if high_contrast {
+    return match tetromino {
+      Tetromino::Z => graphics::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 },
+      Tetromino::S => graphics::Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 },
+      Tetromino::J => graphics::Color { r: 0.2, g: 0.4, b: 1.0, a: 1.0 },
+      Tetromino::L => graphics::Color { r: 1.0, g: 0.5, b: 0.0, a: 1.0 },
+      Tetromino::O => graphics::Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },
+      Tetromino::T => graphics::Color { r: 1.0, g: 0.0, b: 1.0, a: 1.0 },
+      Tetromino::I => graphics::Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 },
+      Tetromino::F | Tetromino::W => graphics::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 },
+      Tetromino::N | Tetromino::Z5 => graphics::Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 },
+      Tetromino::P | Tetromino::U => graphics::Color { r: 0.2, g: 0.4, b: 1.0, a: 1.0 },
+      Tetromino::L5 | Tetromino::V => graphics::Color { r: 1.0, g: 0.5, b: 0.0, a: 1.0 },
+      Tetromino::X => graphics::Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },
+      Tetromino::T5 | Tetromino::Y => graphics::Color { r: 1.0, g: 0.0, b: 1.0, a: 1.0 },
+      Tetromino::I5 => graphics::Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 },
+    };
+  }
+  return match tetromino {
+    Tetromino::Z => graphics::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 },
+    Tetromino::S => graphics::Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 },
+    Tetromino::J => graphics::Color { r: 0.0, g: 0.0, b: 1.0, a: 1.0 },
+    Tetromino::L => graphics::Color { r: 1.0, g: 0.5, b: 0.0, a: 1.0 },
+    Tetromino::O => graphics::Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },
+    Tetromino::T => graphics::Color { r: 0.5, g: 0.0, b: 0.5, a: 1.0 },
+    Tetromino::I => graphics::Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 },
+    Tetromino::F => graphics::Color { r: 0.8, g: 0.2, b: 0.2, a: 1.0 },
+    Tetromino::I5 => graphics::Color { r: 0.4, g: 0.8, b: 1.0, a: 1.0 },
+    Tetromino::L5 => graphics::Color { r: 1.0, g: 0.6, b: 0.2, a: 1.0 },
+    Tetromino::N => graphics::Color { r: 0.2, g: 0.6, b: 0.2, a: 1.0 },
+    Tetromino::P => graphics::Color { r: 1.0, g: 0.6, b: 0.8, a: 1.0 },
+    Tetromino::T5 => graphics::Color { r: 0.7, g: 0.3, b: 0.9, a: 1.0 },
+    Tetromino::U => graphics::Color { r: 0.6, g: 0.4, b: 0.2, a: 1.0 },
+    Tetromino::V => graphics::Color { r: 0.3, g: 0.3, b: 0.9, a: 1.0 },
+    Tetromino::W => graphics::Color { r: 0.9, g: 0.9, b: 0.5, a: 1.0 },
+    Tetromino::X => graphics::Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+    Tetromino::Y => graphics::Color { r: 0.5, g: 1.0, b: 0.5, a: 1.0 },
+    Tetromino::Z5 => graphics::Color { r: 0.0, g: 0.6, b: 0.6, a: 1.0 },
+  };
+}
+
+impl MainState {
+  fn draw_grid(&self, ctx: &mut Context, board: &BoardLayout) -> GameResult {
+    let color = self.skin.grid_color.filter(|_| !self.settings.high_contrast).unwrap_or(graphics::WHITE);
+    let gridmesh_builder = &mut graphics::MeshBuilder::new();
+    gridmesh_builder.rectangle(
+      graphics::DrawMode::stroke(GRID_STROKE_SIZE),
+      graphics::Rect::new(0.0, 0.0, board.grid_frame.w, board.grid_frame.h),
+      color,
+    );
+    for i_y in 1..self.config.grid_height as i32 * self.layout.cases_per_case {
+      let y = self.layout.pixel_y(i_y) - self.layout.pixel_y(0);
+      gridmesh_builder.line(
+        &[na::Point2::new(0.0, y), na::Point2::new(board.grid_frame.w, y)],
+        GRID_STROKE_SIZE,
+        color
+      )?;
+    }
+    for i_x in 1..self.config.grid_width as i32 * self.layout.cases_per_case {
+      let x = self.layout.pixel_y(i_x) - self.layout.pixel_y(0);
+      gridmesh_builder.line(
+        &[na::Point2::new(x, 0.0), na::Point2::new(x, board.grid_frame.h)],
+        GRID_STROKE_SIZE,
+        color
+      )?;
+    }
+    let grid_mesh = gridmesh_builder.build(ctx)?;
+
+    graphics::draw(ctx, &grid_mesh, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+
+    Ok(())
+  }
+
+  // How visible a locked case is with --invisible: the whole stack shows
+  // while rows clear and once the game is over.
+  fn case_alpha(&self, player: &Player, i_x: i32, i_y: i32) -> f32 {
+    let game = &player.game;
+    if !self.invisible || player.controls == Controls::Remote || self.in_demo() || game.is_clearing() || game.is_over() {
+      return 1.0;
+    }
+    let shown = game.filled_at(i_x, i_y) + Duration::from_secs_f64(self.config.invisible_delay);
+    let fading = game.time_played().checked_sub(shown).unwrap_or_default();
+    return 1.0 - (fading.as_secs_f32() / INVISIBLE_FADE.as_secs_f32()).min(1.0);
+  }
+
+  fn draw_cases(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let size = self.layout.cases_per_case;
+    for i_x in 0..self.config.grid_width as i32 {
+      for i_y in 0..self.config.grid_height as i32 {
+        let case = match player.game.grid().get(i_x, i_y) {
+          Some(Case::Empty) | None => continue,
+          Some(case) => case,
+        };
+        let alpha = self.case_alpha(player, i_x, i_y);
+        if alpha == 0.0 {
+          continue;
+        }
+        self.draw_case(ctx, (i_x, i_y), size, case, alpha, na::Point2::new(board.grid_frame.x, board.grid_frame.y))?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // Only the edges not shared by two cases of the piece are drawn. It fades
+  // with the cases under --invisible.
+  fn draw_last_locked(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let cases = player.game.last_locked();
+    let alpha = cases.first().map_or(0.0, |&(x, y)| self.case_alpha(player, x, y));
+    if alpha == 0.0 {
+      return Ok(());
+    }
+    let size = self.layout.cases_per_case;
+    let edge = |i: i32| self.layout.pixel_x(i * size) - self.layout.pixel_x(0);
+    let color = graphics::Color { a: alpha, ..LAST_LOCKED_COLOR };
+    let builder = &mut graphics::MeshBuilder::new();
+    for &(x, y) in cases {
+      let (left, right, top, bottom) = (edge(x), edge(x + 1), edge(y), edge(y + 1));
+      let edges = [
+        ((x, y - 1), [na::Point2::new(left, top), na::Point2::new(right, top)]),
+        ((x, y + 1), [na::Point2::new(left, bottom), na::Point2::new(right, bottom)]),
+        ((x - 1, y), [na::Point2::new(left, top), na::Point2::new(left, bottom)]),
+        ((x + 1, y), [na::Point2::new(right, top), na::Point2::new(right, bottom)]),
+      ];
+      for (neighbour, points) in edges.iter() {
+        if !cases.contains(neighbour) {
+          builder.line(points, LAST_LOCKED_STROKE_SIZE, color)?;
+        }
+      }
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+
+    Ok(())
+  }
+
+  // The cases where the pieces of the opener still go, each outlined in the
+  // color of its piece.
+  fn draw_opener_targets(&self, ctx: &mut Context, board: &BoardLayout, opener: &Opener) -> GameResult {
+    let size = self.layout.cases_per_case;
+    let edge = |i: i32| self.layout.pixel_x(i * size) - self.layout.pixel_x(0);
+    let builder = &mut graphics::MeshBuilder::new();
+    let mut empty = true;
+    for (index, cases) in opener.targets.iter().enumerate().filter(|&(index, _)| !self.opener_run.filled.get(index).copied().unwrap_or(false)) {
+      let color = graphics::Color { a: OPENER_TARGET_ALPHA, ..self.case_color(Case::Filled(opener.pieces[index])) };
+      for &(x, y) in cases {
+        let (left, right, top, bottom) = (edge(x), edge(x + 1), edge(y), edge(y + 1));
+        let edges = [
+          ((x, y - 1), [na::Point2::new(left, top), na::Point2::new(right, top)]),
+          ((x, y + 1), [na::Point2::new(left, bottom), na::Point2::new(right, bottom)]),
+          ((x - 1, y), [na::Point2::new(left, top), na::Point2::new(left, bottom)]),
+          ((x + 1, y), [na::Point2::new(right, top), na::Point2::new(right, bottom)]),
+        ];
+        for (neighbour, points) in edges.iter() {
+          if !cases.contains(neighbour) {
+            builder.line(points, OPENER_TARGET_STROKE_SIZE, color)?;
+            empty = false;
+          }
+        }
+      }
+    }
+    // A mesh needs a line at least.
+    if empty {
+      return Ok(());
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+
+    Ok(())
+  }
+
+  fn draw_clearing_rows(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let size = self.layout.cases_per_case;
+    for i_y in 0..self.config.grid_height as i32 {
+      if player.game.grid().is_row_full(i_y) {
+        let width = self.layout.pixel_x(self.config.grid_width as i32 * size - 1) + self.config.case_size - self.layout.pixel_x(0);
+        let height = self.layout.pixel_y((i_y + 1) * size - 1) + self.config.case_size - self.layout.pixel_y(i_y * size);
+        let mesh_row = graphics::Mesh::new_rectangle(
+          ctx,
+          graphics::DrawMode::fill(),
+          graphics::Rect::new(self.layout.pixel_x(0), self.layout.pixel_y(i_y * size), width, height),
+          graphics::WHITE,
+        )?;
+        graphics::draw(ctx, &mesh_row, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // Over the rows the piece fills up at the ghost, see Settings::clear_preview.
+  fn draw_ghost_full_rows(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let size = self.layout.cases_per_case;
+    for &i_y in player.game.ghost_full_rows() {
+      let width = self.layout.pixel_x(self.config.grid_width as i32 * size - 1) + self.config.case_size - self.layout.pixel_x(0);
+      let height = self.layout.pixel_y((i_y + 1) * size - 1) + self.config.case_size - self.layout.pixel_y(i_y * size);
+      let mesh_row = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        graphics::Rect::new(self.layout.pixel_x(0), self.layout.pixel_y(i_y * size), width, height),
+        CLEAR_PREVIEW_COLOR,
+      )?;
+      graphics::draw(ctx, &mesh_row, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+    }
+
+    Ok(())
+  }
+
+  #[allow(clippy::single_match)]
+  fn draw_current_piece(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    match player.game.current_piece() {
+      Some (piece) => {
+        let size = self.layout.cases_per_case;
+        let global_x = board.grid_frame.x + self.layout.pixel_x(piece.x * size) - self.layout.pixel_x(0);
+        let global_y = board.grid_frame.y + self.layout.pixel_y(piece.y * size) - self.layout.pixel_y(0);
+        self.draw_piece(ctx, piece, false, size, global_x, global_y)?;
+      },
+      None => {},
+    };
+
+    Ok(())
+  }
+
+  fn draw_current_piece_ghost(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    match player.game.current_piece() {
+      // Nothing to show once the piece rests where it would land.
+      Some (piece) if player.game.ghost_y() > piece.y => {
+        let size = self.layout.cases_per_case;
+        let global_x = board.grid_frame.x + self.layout.pixel_x(piece.x * size) - self.layout.pixel_x(0);
+        let global_y = board.grid_frame.y + self.layout.pixel_y(player.game.ghost_y() * size) - self.layout.pixel_y(0);
+        self.draw_piece(ctx, piece, true, size, global_x, global_y)?;
+      },
+      _ => {},
+    };
+
+    Ok(())
+  }
+
+  fn draw_next_pieces(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let global_x = board.next.x + board.next.w / 2.0;
+    let mut global_y = board.next.y;
+    for piece in player.game.next_pieces() {
+      let piece_x = global_x - (piece.size() as f32 * (self.config.case_size + CASE_BORDER * 2.0) / 2.0);
+      self.draw_piece(ctx, piece, false, 1, piece_x, global_y)?;
+      global_y += piece.size().max(4) as f32 * self.layout.case_pitch();
+    }
+    self.draw_piece_history(ctx, player, board, global_x, global_y)?;
+
+    Ok(())
+  }
+
+  // The last pieces spawned under the preview, shrunk to fit the panel in a
+  // row, the newest on the right.
+  fn draw_piece_history(&self, ctx: &mut Context, player: &Player, board: &BoardLayout, center_x: f32, top: f32) -> GameResult {
+    let history = player.game.piece_history();
+    let slot = board.next.w / PIECE_HISTORY as f32;
+    let mini = slot / (self.config.pieces.max_size() as f32 * self.layout.case_pitch());
+    let left = center_x - slot * PIECE_HISTORY as f32 / 2.0;
+    let first = PIECE_HISTORY - history.len();
+    for (index, piece) in history.iter().enumerate() {
+      let x = left + (first + index) as f32 * slot + (slot - piece.size() as f32 * self.layout.case_pitch() * mini) / 2.0;
+      // On top of the scale of the player, push_transform replaces it.
+      let transform = graphics::DrawParam::new()
+        .dest(na::Point2::new(x * board.scale, top * board.scale))
+        .scale(na::Vector2::new(mini * board.scale, mini * board.scale));
+      graphics::push_transform(ctx, Some(transform.to_matrix()));
+      graphics::apply_transformations(ctx)?;
+      self.draw_piece(ctx, piece, false, 1, 0.0, 0.0)?;
+      graphics::pop_transform(ctx);
+    }
+    graphics::apply_transformations(ctx)?;
+
+    Ok(())
+  }
+
+  fn draw_hold_piece(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    if let Some(piece) = player.game.hold_piece() {
+      let piece_x = board.hold.x + board.hold.w / 2.0 - (piece.size() as f32 * (self.config.case_size + CASE_BORDER * 2.0) / 2.0);
+      self.draw_piece(ctx, piece, false, 1, piece_x, board.hold.y)?;
+    }
+
+    Ok(())
+  }
+
+  // The ghost is only outlined. Each case of the piece covers `size` x `size`
+  // cases on screen.
+  fn draw_piece(&self, ctx: &mut Context, piece: &Piece, ghost: bool, size: i32, global_x: f32, global_y: f32) -> GameResult {
+    let color = self.case_color(Case::Filled(piece.tetromino));
+    let origin = na::Point2::new(global_x, global_y);
+    for &(i_x, i_y) in piece.cells() {
+      if !ghost {
+        self.draw_case(ctx, (i_x, i_y), size, Case::Filled(piece.tetromino), 1.0, origin)?;
+        continue;
+      }
+      let width = if self.settings.high_contrast { HIGH_CONTRAST_GHOST } else { 1.0 };
+      for (x, y) in self.screen_cases(i_x, i_y, size) {
+        let mesh_case = graphics::Mesh::new_rectangle(
+          ctx,
+          graphics::DrawMode::stroke(width),
+          graphics::Rect::new(x, y, self.config.case_size, self.config.case_size),
+          color,
+        )?;
+        graphics::draw(ctx, &mesh_case, (origin,))?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // Top left corners of the cases drawn for the case (i_x, i_y) of the game.
+  pub(super) fn screen_cases(&self, i_x: i32, i_y: i32, size: i32) -> Vec<(f32, f32)> {
+    return (0..size * size).map(|i| (self.layout.pixel_x(i_x * size + i % size), self.layout.pixel_y(i_y * size + i / size))).collect();
+  }
+
+  // Outlined in black in high contrast, so cases of the same color stay apart.
+  // Every case on screen takes its color here. High contrast keeps its own
+  // whatever the skin and config.toml.
+  pub(super) fn case_color(&self, case: Case) -> graphics::Color {
+    let high_contrast = self.settings.high_contrast;
+    if let Some(color) = tile_index(case).and_then(|index| self.skin.colors[index]).filter(|_| !high_contrast) {
+      return color;
+    }
+    return match case {
+      Case::Filled(tetromino) if !high_contrast && self.palette.contains_key(&tetromino) => self.palette[&tetromino],
+      Case::Filled(tetromino) => tetromino_color(tetromino, high_contrast),
+      Case::Garbage | Case::Empty if high_contrast => HIGH_CONTRAST_GARBAGE_COLOR,
+      Case::Garbage | Case::Empty => GARBAGE_COLOR,
+    };
+  }
+
+  // A tile of the blocks of the skin when it has some, a plain square of the
+  // color of the case otherwise.
+  fn draw_case(&self, ctx: &mut Context, (i_x, i_y): (i32, i32), size: i32, case: Case, alpha: f32, origin: na::Point2<f32>) -> GameResult {
+    let color = graphics::Color { a: alpha, ..self.case_color(case) };
+    let tile = match (&self.skin.blocks, tile_index(case)) {
+      (Some(blocks), Some(index)) if !self.settings.high_contrast => Some((blocks, index)),
+      _ => None,
+    };
+    for (x, y) in self.screen_cases(i_x, i_y, size) {
+      if let Some((blocks, index)) = tile {
+        let scale = self.config.case_size / blocks.height() as f32;
+        graphics::draw(ctx, blocks, graphics::DrawParam::new()
+          .src(graphics::Rect::new(index as f32 / SKIN_TILES as f32, 0.0, 1.0 / SKIN_TILES as f32, 1.0))
+          .dest(na::Point2::new(origin.x + x, origin.y + y))
+          .scale(na::Vector2::new(scale, scale))
+          .color(graphics::Color { a: alpha, ..graphics::WHITE }))?;
+        continue;
+      }
+      let rect = graphics::Rect::new(x, y, self.config.case_size, self.config.case_size);
+      let mesh_case = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, color)?;
+      graphics::draw(ctx, &mesh_case, (origin,))?;
+      if self.settings.high_contrast {
+        let mesh_outline = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(HIGH_CONTRAST_OUTLINE), rect, graphics::Color { a: color.a, ..graphics::BLACK })?;
+        graphics::draw(ctx, &mesh_outline, (origin,))?;
+      }
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn draw_playfield(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let scale = na::Vector2::new(board.scale, board.scale);
+    let shake = graphics::DrawParam::new().dest(na::Point2::new(0.0, self.shake_offset(player) * board.scale)).scale(scale);
+    let around = graphics::DrawParam::new().scale(scale);
+    graphics::push_transform(ctx, Some(around.to_matrix()));
+    graphics::push_transform(ctx, Some(shake.to_matrix()));
+    graphics::apply_transformations(ctx)?;
+    self.draw_grid(ctx, board)?;
+    self.draw_cases(ctx, player, board)?;
+    self.draw_last_locked(ctx, player, board)?;
+    if let Some(opener) = self.opener() {
+      self.draw_opener_targets(ctx, board, opener)?;
+    }
+    // The rows still wait the clear delay, only the flash goes.
+    if player.game.is_clearing() && !self.settings.reduced_motion {
+      self.draw_clearing_rows(ctx, player, board)?;
+    }
+    if player.tetris_flash > Duration::from_secs(0) {
+      let alpha = TETRIS_FLASH_ALPHA * player.tetris_flash.as_secs_f32() / TETRIS_FLASH.as_secs_f32();
+      let mesh_flash = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), board.grid_frame, graphics::Color { a: alpha, ..graphics::WHITE })?;
+      graphics::draw(ctx, &mesh_flash, graphics::DrawParam::default())?;
+    }
+    if self.settings.clear_preview {
+      self.draw_ghost_full_rows(ctx, player, board)?;
+    }
+    if self.settings.ghost_on {
+      self.draw_current_piece_ghost(ctx, player, board)?;
+    }
+    self.draw_current_piece(ctx, player, board)?;
+    self.draw_garbage_meter(ctx, player, board)?;
+    graphics::pop_transform(ctx);
+    graphics::apply_transformations(ctx)?;
+    self.draw_score(ctx, player, board)?;
+    self.draw_next_pieces(ctx, player, board)?;
+    self.draw_hold_piece(ctx, player, board)?;
+    self.draw_replay_progress(ctx, board)?;
+    self.draw_reset_progress(ctx, board)?;
+    graphics::pop_transform(ctx);
+    graphics::apply_transformations(ctx)?;
+    self.draw_callout(ctx, player, board)?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use log::debug;
+
+use crate::piece::Case;
+
+use super::MainState;
+use super::hud::TOAST_PADDING;
+use super::input::TOUCH_REPEAT_DELAY;
+
+// F10 shows the internals of the first board, rebuilt that often.
+const DEBUG_REFRESH: Duration = Duration::from_millis(250);
+const DEBUG_FONT_SIZE: f32 = 14.0;
+const DEBUG_LABEL_FONT_SIZE: f32 = 7.0;
+const DEBUG_BACK_COLOR: graphics::Color = graphics::Color { r: 0.0, g: 0.0, b: 0.0, a: 0.6 };
+const DEBUG_PIECE_COLOR: graphics::Color = graphics::Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+const DEBUG_GHOST_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 0.0, b: 1.0, a: 1.0 };
+
+impl MainState {
+  // Frozen, the debug overlay shows the tick and the timers.
+  pub(super) fn toggle_frozen(&mut self) {
+    if self.network.is_some() {
+      return;
+    }
+    self.frozen = !self.frozen;
+    self.frame_steps = 0;
+    debug!("Logic {} at tick {}", if self.frozen { "frozen" } else { "running" }, self.logic_ticks);
+    if self.frozen && !self.debug_overlay {
+      self.toggle_debug_overlay();
+    }
+  }
+
+  pub(super) fn toggle_debug_overlay(&mut self) {
+    self.debug_overlay = !self.debug_overlay;
+    self.debug_timer = Duration::from_secs(0);
+    self.debug_text = None;
+    self.debug_labels.clear();
+    self.update_debug_overlay(Duration::from_secs(0));
+  }
+
+  pub(super) fn update_debug_overlay(&mut self, delta: Duration) {
+    if !self.debug_overlay {
+      return;
+    }
+    self.debug_timer = self.debug_timer.checked_sub(delta).unwrap_or_default();
+    if self.debug_timer > Duration::from_secs(0) {
+      return;
+    }
+    self.debug_timer = DEBUG_REFRESH;
+    let game = &self.players[0].game;
+    let mut text = match game.current_piece() {
+      Some(piece) => {
+        let grounded = game.grid().collides(piece, 0, 1);
+        format!(
+"Piece: {} at ({}, {}), rotation {}{}
+Ghost: row {}, {} below
+Move speed: {:?}, row time {:?}
+Last move: {:?}
+Grounded: {}",
+          piece.tetromino.letter(), piece.x, piece.y, piece.index_rotation, if piece.mirrored { ", mirrored" } else { "" },
+          game.ghost_y(), game.ghost_y() - piece.y,
+          game.move_speed(), game.row_time(),
+          piece.last_move,
+          if grounded { format!("yes, locks in {:?}", game.row_time().checked_sub(piece.last_move).unwrap_or_default()) } else { String::from("no") },
+        )
+      },
+      None => String::from("Piece: none"),
+    };
+    text = format!("{}\nPhase: {:?}\nPieces locked: {}", text, self.phase, game.pieces_locked());
+    text = format!("{}\nTick: {}{}", text, self.logic_ticks, if self.frozen { ", frozen, F9 to step" } else { "" });
+    for (name, elapsed, total) in game.timers() {
+      text = format!("{}\n  {}: {:?} / {:?}", text, name, elapsed, total);
+    }
+    if self.phase_timer > Duration::from_secs(0) {
+      text = format!("{}\n  phase: {:?}", text, self.phase_timer);
+    }
+    if self.touch_held.is_some() {
+      text = format!("{}\n  touch repeat: {:?} / {:?}", text, self.touch_timer, TOUCH_REPEAT_DELAY);
+    }
+    if !self.players[0].pending_inputs.is_empty() {
+      text = format!("{}\nQueued: {:?}", text, self.players[0].pending_inputs);
+    }
+    self.debug_text = Some(graphics::Text::new((text, self.font(), DEBUG_FONT_SIZE)));
+
+    let size = self.layout.cases_per_case;
+    let grid = game.grid();
+    let mut labels = Vec::new();
+    for i_y in 0..grid.height() as i32 {
+      for i_x in 0..grid.width() as i32 {
+        if grid.get(i_x, i_y).is_some_and(|case| case != Case::Empty) {
+          let point = na::Point2::new(self.layout.pixel_x(i_x * size), self.layout.pixel_y(i_y * size));
+          labels.push((point, graphics::Text::new((format!("{},{}", i_x, i_y), self.font(), DEBUG_LABEL_FONT_SIZE))));
+        }
+      }
+    }
+    self.debug_labels = labels;
+  }
+
+  // The state of the first board in the top left corner, its cases labelled
+  // with their coordinates and the piece in play and its ghost outlined.
+  pub(super) fn draw_debug_overlay(&self, ctx: &mut Context) -> GameResult {
+    if !self.debug_overlay {
+      return Ok(());
+    }
+    let (player, board) = (&self.players[0], &self.layout.boards[0]);
+    let origin = na::Point2::new(board.grid_frame.x, board.grid_frame.y);
+    graphics::push_transform(ctx, Some(graphics::DrawParam::new().scale(na::Vector2::new(board.scale, board.scale)).to_matrix()));
+    graphics::apply_transformations(ctx)?;
+    for (point, label) in &self.debug_labels {
+      graphics::queue_text(ctx, label, na::Point2::new(origin.x + point.x, origin.y + point.y), Some(graphics::WHITE));
+    }
+    graphics::draw_queued_text(ctx, graphics::DrawParam::new(), None, graphics::FilterMode::Linear)?;
+    if let Some(piece) = player.game.current_piece() {
+      let builder = &mut graphics::MeshBuilder::new();
+      let size = self.layout.cases_per_case;
+      for &(x, y) in piece.cells() {
+        for (row, color) in [(piece.y, DEBUG_PIECE_COLOR), (player.game.ghost_y(), DEBUG_GHOST_COLOR)].iter() {
+          for (case_x, case_y) in self.screen_cases(piece.x + x, row + y, size) {
+            builder.rectangle(graphics::DrawMode::stroke(1.0), graphics::Rect::new(case_x, case_y, self.config.case_size, self.config.case_size), *color);
+          }
+        }
+      }
+      let mesh = builder.build(ctx)?;
+      graphics::draw(ctx, &mesh, (origin,))?;
+    }
+    graphics::pop_transform(ctx);
+    graphics::apply_transformations(ctx)?;
+
+    if let Some(text) = &self.debug_text {
+      let (width, height) = text.dimensions(ctx);
+      let back = graphics::Rect::new(self.layout.frame.x, self.layout.frame.y, width as f32 + 2.0 * TOAST_PADDING, height as f32 + 2.0 * TOAST_PADDING);
+      let mesh_back = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), back, DEBUG_BACK_COLOR)?;
+      graphics::draw(ctx, &mesh_back, (na::Point2::new(0.0, 0.0),))?;
+      graphics::draw(ctx, text, (na::Point2::new(self.layout.frame.x + TOAST_PADDING, self.layout.frame.y + TOAST_PADDING),))?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,169 @@
+use std::io::Write;
+
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use log::warn;
+
+use crate::game::Mode;
+use crate::grid::Grid;
+use crate::phase::GamePhase;
+use crate::piece::{Case, Tetromino};
+use crate::puzzle::{puzzle_text, EXPORTS_DIRECTORY};
+use crate::screenshot::{create_unique, now_seconds, timestamped_name};
+
+use super::MainState;
+
+// Under the user data directory, where F7 saves the board.
+const BOARDS_DIRECTORY: &str = "boards";
+
+impl MainState {
+  // Painting the board is for zen games alone, where nothing is at stake.
+  fn can_edit(&self) -> bool {
+    let phase = self.phase;
+    return !self.versus() && self.network.is_none() && !self.in_demo() && self.mission().is_none() && self.puzzle().is_none() && self.opener().is_none()
+      && self.daily().is_none() && self.watching.is_none() && self.players[0].bot.is_none() && self.players[0].game.mode() == Mode::Zen
+      && (phase == GamePhase::Playing || phase == GamePhase::Paused) && self.game_phase() == GamePhase::Playing;
+  }
+
+  pub(super) fn open_editor(&mut self) -> GameResult {
+    if !self.can_edit() {
+      self.show_text("editor.zen_only");
+      return Ok(());
+    }
+    if self.phase == GamePhase::Playing {
+      self.toggle_pause()?;
+      self.editor_paused = true;
+    }
+    self.editor = Some(Case::Garbage);
+    self.create_editor_text();
+
+    Ok(())
+  }
+
+  pub(super) fn close_editor(&mut self) -> GameResult {
+    self.editor = None;
+    self.editor_stroke = None;
+    self.editor_text = None;
+    if self.editor_paused && self.phase == GamePhase::Paused {
+      self.toggle_pause()?;
+    }
+    self.editor_paused = false;
+
+    Ok(())
+  }
+
+  fn create_editor_text(&mut self) {
+    let paint = match self.editor {
+      Some(Case::Filled(tetromino)) => self.strings.fill("editor.piece", &[("letter", tetromino.letter().to_string())]),
+      _ => self.strings.get("editor.garbage").to_string(),
+    };
+    let text = self.strings.fill("editor.text", &[("paint", paint)]);
+    self.editor_text = Some(graphics::Text::new((text, self.font(), self.font_size())));
+  }
+
+  // After the pieces, garbage, then the pieces again.
+  pub(super) fn next_paint(&mut self) {
+    self.editor = self.editor.map(|case| match case {
+      Case::Filled(tetromino) => match Tetromino::ALL.iter().position(|&other| other == tetromino) {
+        Some(index) if index + 1 < Tetromino::ALL.len() => Case::Filled(Tetromino::ALL[index + 1]),
+        _ => Case::Garbage,
+      },
+      _ => Case::Filled(Tetromino::ALL[0]),
+    });
+    self.create_editor_text();
+  }
+
+  // A point of the window in the coordinates of the layout.
+  pub(super) fn layout_point(&self, ctx: &Context, x: f32, y: f32) -> na::Point2<f32> {
+    let screen = graphics::screen_coordinates(ctx);
+    let (width, height) = graphics::drawable_size(ctx);
+    return na::Point2::new(screen.x + x * screen.w / width, screen.y + y * screen.h / height);
+  }
+
+  // The case of the first board under a point of the window.
+  pub(super) fn case_at(&self, ctx: &Context, x: f32, y: f32) -> Option<(i32, i32)> {
+    let point = self.layout_point(ctx, x, y);
+    let board = &self.layout.boards[0];
+    let x = point.x / board.scale - board.grid_frame.x;
+    let y = point.y / board.scale - board.grid_frame.y;
+    let size = self.layout.case_pitch() * self.layout.cases_per_case as f32;
+    let (x, y) = ((x / size).floor() as i32, (y / size).floor() as i32);
+    let grid = self.players[0].game.grid();
+    if x < 0 || y < 0 || x as usize >= grid.width() || y as usize >= grid.height() {
+      return None;
+    }
+    return Some((x, y));
+  }
+
+  // The piece in play keeps its place, no case is painted under it.
+  pub(super) fn paint_case(&mut self, x: i32, y: i32, case: Case) {
+    let game = &mut self.players[0].game;
+    if game.grid().get(x, y) == Some(case) {
+      return;
+    }
+    let mut grid = game.grid().clone();
+    grid.set(x, y, case);
+    if game.current_piece().is_some_and(|piece| grid.collides(piece, 0, 0)) {
+      return;
+    }
+    game.set_grid(grid);
+    self.board_edited = true;
+  }
+
+  pub(super) fn clear_board(&mut self) {
+    self.players[0].game.set_grid(Grid::new(self.config.grid_width, self.config.grid_height));
+    self.board_edited = true;
+  }
+
+  // The board as a puzzle in the user data directory, with the pieces of the
+  // game for a sequence, to edit by hand.
+  pub(super) fn export_board(&mut self, ctx: &Context) {
+    let grid = self.players[0].game.grid();
+    if grid.is_empty() {
+      self.show_text("editor.nothing");
+      return;
+    }
+    let text = format!("// Move to resources/puzzles and set the pieces to play.\n{}", puzzle_text(grid, self.config.pieces.pieces()));
+    let directory = filesystem::user_data_dir(ctx).join(EXPORTS_DIRECTORY);
+    let written = create_unique(&directory, &timestamped_name(now_seconds(), "txt"))
+      .and_then(|(name, mut file)| file.write_all(text.as_bytes()).map(|_| name).map_err(|e| e.to_string()));
+    match written {
+      Ok(name) => self.show_notice(&self.strings.fill("notice.saved", &[("name", format!("{}/{}", EXPORTS_DIRECTORY, name))])),
+      Err(e) => {
+        warn!("Cannot export the board to {} ({})", directory.display(), e);
+        self.show_notice(&self.strings.fill("editor.not_exported", &[("error", e)]));
+      },
+    }
+  }
+
+  // The board of the first player as a text of Grid::to_ascii, for bug
+  // reports and --load-board.
+  pub(super) fn save_board(&mut self, ctx: &Context) {
+    let text = self.players[0].game.grid().to_ascii();
+    let directory = filesystem::user_data_dir(ctx).join(BOARDS_DIRECTORY);
+    let written = create_unique(&directory, &timestamped_name(now_seconds(), "txt"))
+      .and_then(|(name, mut file)| file.write_all(text.as_bytes()).map(|_| name).map_err(|e| e.to_string()));
+    match written {
+      Ok(name) => self.show_notice(&self.strings.fill("notice.saved", &[("name", format!("{}/{}", BOARDS_DIRECTORY, name))])),
+      Err(e) => {
+        warn!("Cannot save the board in {} ({})", directory.display(), e);
+        self.show_notice(&self.strings.fill("editor.not_saved", &[("error", e)]));
+      },
+    }
+  }
+
+  // Under the board, so the cases stay in sight.
+  pub(super) fn draw_editor(&self, ctx: &mut Context) -> GameResult {
+    if let Some(text) = &self.editor_text {
+      let (width, height) = text.dimensions(ctx);
+      let x = self.layout.frame.x + (self.layout.frame.w - width as f32) / 2.0;
+      let y = self.layout.frame.y + self.layout.frame.h - height as f32 - self.font_size();
+      graphics::draw(ctx, text, (na::Point2::new(x, y),))?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use ggez::Context;
+
+use log::debug;
+
+use crate::game::GameEvent;
+use crate::rumble::Pulse;
+
+use super::{Controls, MainState, Player};
+
+const DANGER_ROWS: usize = 4;
+const DANGER_PITCH: f32 = 1.15;
+const DANGER_FADE_IN: Duration = Duration::from_millis(400);
+
+// The board jolts down after a hard drop.
+pub(super) const SHAKE_DURATION: Duration = Duration::from_millis(150);
+const SHAKE_AMPLITUDE: f32 = 4.0;
+
+fn theme_pitch(level: u32) -> f32 {
+  1.0 + (0.1 * (level - 1) as f32)
+}
+
+// The pulse that goes with `event`, as its sound does.
+pub(super) fn rumble_pulse(event: GameEvent) -> Option<Pulse> {
+  return match event {
+    GameEvent::PieceLocked => Some(Pulse::Light),
+    GameEvent::LinesCleared(lines) if lines >= 4 => Some(Pulse::Strong),
+    GameEvent::LinesCleared(lines) => Some(Pulse::Lines(lines)),
+    GameEvent::GameOver => Some(Pulse::Strong),
+    _ => None,
+  };
+}
+
+impl MainState {
+  // Follows the highest level on screen.
+  pub(super) fn sound_theme_pitch(&self) -> f32 {
+    let level = self.players.iter().map(|player| player.game.current_score().level).max().unwrap_or(1);
+    let pitch = theme_pitch(level);
+    if self.danger {
+      return pitch * DANGER_PITCH;
+    }
+    return pitch;
+  }
+
+  pub(super) fn restart_sound_theme(&mut self, fade_in: Duration) {
+    self.audio.stop_theme();
+    if self.settings.theme_on {
+      self.audio.play_theme(self.sound_theme_pitch(), fade_in);
+    }
+  }
+
+  // Any board close to the top puts the theme in danger.
+  pub(super) fn update_danger(&mut self) {
+    let danger = self.players.iter().any(|player| player.game.stack_height() + DANGER_ROWS >= self.config.grid_height);
+    if danger == self.danger {
+      return;
+    }
+
+    self.danger = danger;
+    self.restart_sound_theme(DANGER_FADE_IN);
+    debug!("Danger: {}", self.danger);
+  }
+
+  pub(super) fn play_line_removed(&mut self, ctx: &mut Context, line_removed: u32) {
+    if line_removed > 0 && !self.in_demo() {
+      let path = match line_removed {
+        4 => "/tetris.wav",
+        _ => "/line.wav",
+      };
+      self.audio.play_effect(ctx, path, self.settings.effects_volume);
+    }
+  }
+
+  pub(super) fn play_lost(&mut self, ctx: &mut Context) {
+    if self.in_demo() {
+      return;
+    }
+    self.audio.play_effect(ctx, "/lost.mp3", self.settings.effects_volume);
+  }
+
+  // Only for a board played alone, by the one pair of hands holding the
+  // controllers.
+  pub(super) fn play_rumble(&mut self, index: usize, pulse: Pulse) {
+    let player = &self.players[index];
+    if self.settings.rumble && !self.in_demo() && player.controls == Controls::Solo && player.bot.is_none() {
+      self.rumble.pulse(pulse);
+    }
+  }
+
+  // Vertical offset of a board while it shakes.
+  pub(super) fn shake_offset(&self, player: &Player) -> f32 {
+    let left = player.shake_timer.as_secs_f32() / SHAKE_DURATION.as_secs_f32();
+    return SHAKE_AMPLITUDE * left * (left * std::f32::consts::PI * 3.0).sin();
+  }
+}
@@ -0,0 +1,440 @@
+use std::time::Duration;
+
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use log::{debug, warn};
+
+use crate::achievement::Achievement;
+use crate::daily::DAILY_LINES;
+use crate::game::{Mode, SPRINT_LINES};
+use crate::layout::{BoardLayout, CASE_BORDER, GRID_STROKE_SIZE};
+use crate::phase::GamePhase;
+use crate::stats::SPLIT_LINES;
+use crate::timestep::LOGIC_TICK;
+
+use super::{mode_key, Controls, MainState, Player, BANNER_FONT_SCALE};
+use super::input::RESET_HOLD;
+
+// A short message at the top of the window, e.g. the keymap just picked.
+const NOTICE_DURATION: Duration = Duration::from_millis(1500);
+
+// "TETRIS!" over the board after a clear of 4 lines or more, growing in
+// for CALLOUT_GROW then fading out, with a white flash of the board.
+pub(super) const CALLOUT_DURATION: Duration = Duration::from_secs(1);
+const CALLOUT_GROW: Duration = Duration::from_millis(150);
+const CALLOUT_FONT_SCALE: f32 = 3.0;
+// Of the width of the board.
+const CALLOUT_FIT: f32 = 0.9;
+pub(super) const TETRIS_FLASH: Duration = Duration::from_millis(300);
+pub(super) const TETRIS_FLASH_ALPHA: f32 = 0.5;
+
+// An unlocked achievement slides in at the bottom right of the window.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+const TOAST_SLIDE: Duration = Duration::from_millis(250);
+pub(super) const TOAST_PADDING: f32 = 8.0;
+const TOAST_COLOR: graphics::Color = graphics::Color { r: 0.0, g: 0.0, b: 0.0, a: 0.8 };
+
+// The gap of a split of a sprint to the best one, see split_fragments.
+const SPLIT_AHEAD_COLOR: graphics::Color = graphics::Color { r: 0.3, g: 1.0, b: 0.3, a: 1.0 };
+const SPLIT_BEHIND_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 0.35, b: 0.35, a: 1.0 };
+const SPLIT_TO_COME_COLOR: graphics::Color = graphics::Color { r: 0.6, g: 0.6, b: 0.6, a: 1.0 };
+
+// Incoming garbage, as a bar left of the board one case high per row.
+pub(super) const GARBAGE_METER_WIDTH: f32 = 6.0;
+const GARBAGE_METER_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 0.2, b: 0.2, a: 1.0 };
+// The meter grows and shrinks that many rows a second towards the queue, and
+// flashes lighter for a moment when an attack arrives.
+const GARBAGE_METER_SPEED: f32 = 12.0;
+const GARBAGE_METER_GAP: f32 = 1.0;
+const GARBAGE_PULSE: Duration = Duration::from_millis(300);
+
+// The progress of --replay under the board.
+const REPLAY_BAR_HEIGHT: f32 = 4.0;
+const REPLAY_BAR_GAP: f32 = 6.0;
+const REPLAY_BAR_COLOR: graphics::Color = graphics::Color { r: 0.9, g: 0.9, b: 0.9, a: 1.0 };
+const REPLAY_BAR_TRACK_COLOR: graphics::Color = graphics::Color { r: 0.3, g: 0.3, b: 0.3, a: 1.0 };
+
+pub(super) fn format_time(time: Duration) -> String {
+  let seconds = time.as_secs();
+  return format!("{}:{:02}", seconds / 60, seconds % 60);
+}
+
+// 1:05.37, the time of a split.
+pub(super) fn format_split(seconds: f64) -> String {
+  let hundredths = (seconds * 100.0).round() as u64;
+  return format!("{}:{:02}.{:02}", hundredths / 6000, hundredths / 100 % 60, hundredths % 100);
+}
+
+impl MainState {
+  pub(super) fn create_score_text(&mut self, index: usize) {
+    let game = &self.players[index].game;
+    let current_score = game.current_score();
+    let previous_score = game.previous_score();
+    let best_score = game.best_score();
+    let strings = &self.strings;
+    let time = format_time(game.time_played());
+    let run = &self.opener_run;
+    let mut mode = match (self.mission(), self.puzzle(), self.opener(), game.mode()) {
+      _ if self.daily().is_some() => strings.fill(if self.daily_practice { "score.daily_practice" } else { "score.daily" },
+        &[("time", time), ("lines", current_score.line_removed.min(DAILY_LINES).to_string()), ("goal", DAILY_LINES.to_string())]),
+      (Some(mission), _, _, _) => strings.fill("score.mission", &[("name", mission.name.clone()), ("time", time), ("progress", mission.objective.progress(game, strings))]),
+      (_, Some(puzzle), _, _) => strings.fill("score.puzzle", &[("name", puzzle.name.clone()), ("time", time),
+        ("cases", puzzle.cases_left(game).to_string()), ("pieces", puzzle.pieces_left(game).to_string())]),
+      (_, _, Some(opener), _) => strings.fill("score.opener", &[("name", opener.name.clone()), ("time", time), ("right", run.right().to_string()),
+        ("placed", run.placements.len().to_string()), ("pieces", opener.pieces.len().saturating_sub(run.placements.len()).to_string())]),
+      (_, _, _, Mode::Sprint) => strings.fill("score.sprint", &[("lines", game.lines_left().to_string()), ("time", time)]),
+      (_, _, _, Mode::Ultra) => strings.fill("score.ultra", &[("time", format_time(game.time_left()))]),
+      (_, _, _, mode) => strings.fill("score.mode", &[("mode", strings.get(&mode_key(mode)).to_string()), ("time", time)]),
+    };
+    if game.mode() != Mode::Zen {
+      mode = format!("{}\n{}", mode, strings.fill("score.next_level", &[("lines", game.lines_to_next_level().to_string())]));
+    }
+    if game.speed() != 1.0 {
+      mode = format!("{}\n{}", mode, strings.fill("score.speed", &[("speed", game.speed().to_string())]));
+    }
+    if let Some(position) = self.watching.as_ref().filter(|_| index == 0 && !self.in_demo()) {
+      let at = |tick: u32| format_time(LOGIC_TICK * tick);
+      mode = format!("{}\n\n{}", mode, strings.fill(if position.paused { "score.replay_paused" } else { "score.replay" },
+        &[("position", at(position.tick())), ("length", at(position.replay.ticks))]));
+    }
+    if game.items() {
+      let item = game.item().map_or(strings.get("score.no_item").to_string(), |item| strings.get(&format!("item.{}", item.id())).to_string());
+      mode = format!("{}\n{}", mode, strings.fill("score.item", &[("item", item)]));
+    }
+    if self.against_opponent() {
+      let name = match self.players[index].controls {
+        Controls::Remote => "score.opponent",
+        Controls::Computer => "score.computer",
+        _ => "score.you",
+      };
+      mode = format!("{}\n\n{}", strings.get(name), mode);
+    } else if self.versus() {
+      mode = format!("{}\n\n{}", strings.fill("score.player", &[("player", (index + 1).to_string())]), mode);
+    }
+    let text = strings.fill("score.panel", &[
+      ("mode", mode),
+      ("score", strings.number(current_score.score)), ("level", current_score.level.to_string()), ("lines", current_score.line_removed.to_string()),
+      ("previous_score", strings.number(previous_score.score)), ("previous_level", previous_score.level.to_string()), ("previous_lines", previous_score.line_removed.to_string()),
+      ("best_score", strings.number(best_score.score)), ("best_level", best_score.level.to_string()), ("best_lines", best_score.line_removed.to_string()),
+    ]);
+    let mut text = graphics::Text::new((text, self.font(), self.font_size()));
+    if self.shows_splits(index) {
+      for fragment in self.split_fragments() {
+        text.add(fragment);
+      }
+    }
+    // Wrapped before the board, however big the text.
+    text.set_bounds(na::Point2::new(self.layout.boards[index].score.w, f32::INFINITY), graphics::Align::Left);
+    self.players[index].text = text;
+  }
+
+  // Only the first board keeps splits, played from this machine at the
+  // normal speed, outside of missions, puzzles and openers.
+  pub(super) fn tracks_splits(&self) -> bool {
+    let player = &self.players[0];
+    return !self.in_demo() && player.bot.is_none() && player.controls != Controls::Remote && player.game.speed() == 1.0
+      && self.mission().is_none() && self.puzzle().is_none() && self.opener().is_none() && self.watching.is_none() && !self.board_edited;
+  }
+
+  // Beside the board and on the results, for a sprint.
+  pub(super) fn shows_splits(&self, index: usize) -> bool {
+    return index == 0 && self.tracks_splits() && self.players[0].game.mode() == Mode::Sprint;
+  }
+
+  // Every SPLIT_LINES lines the clear of the first board crosses is a split
+  // at the time of the clear.
+  pub(super) fn record_splits(&mut self) {
+    let game = &self.players[0].game;
+    while (self.splits.len() as u32 + 1) * SPLIT_LINES <= game.current_score().line_removed {
+      self.splits.push(game.time_played());
+    }
+  }
+
+  // A line per split of a sprint, with its gap to the best one, green ahead
+  // and red behind. Those to come show the best ones in gray.
+  pub(super) fn split_fragments(&self) -> Vec<graphics::TextFragment> {
+    let (font, size) = (self.font(), self.font_size());
+    let fragment = |text: String, color: Option<graphics::Color>| {
+      let fragment = graphics::TextFragment::new((text, font, size));
+      match color {
+        Some(color) => fragment.color(color),
+        None => fragment,
+      }
+    };
+    let mut fragments = vec![fragment(format!("\n\n{}", self.strings.get("score.splits")), None)];
+    for index in 0..(SPRINT_LINES / SPLIT_LINES) as usize {
+      let lines = (index as u32 + 1) * SPLIT_LINES;
+      let best = self.best_splits.get(index).copied();
+      match self.splits.get(index) {
+        Some(split) => {
+          let split = split.as_secs_f64();
+          fragments.push(fragment(format!("\n{}  {}", lines, format_split(split)), None));
+          if let Some(best) = best {
+            fragments.push(fragment(format!("  {:+.2}", split - best), Some(if split <= best { SPLIT_AHEAD_COLOR } else { SPLIT_BEHIND_COLOR })));
+          }
+        },
+        None => fragments.push(fragment(format!("\n{}  {}", lines, best.map_or(String::from("-"), format_split)), Some(SPLIT_TO_COME_COLOR))),
+      }
+    }
+    return fragments;
+  }
+
+  pub(super) fn show_notice(&mut self, text: &str) {
+    self.notice = Some(graphics::Text::new((text, self.font(), self.font_size())));
+    self.notice_timer = NOTICE_DURATION;
+  }
+
+  // The notice of `key` in resources/lang.
+  pub(super) fn show_text(&mut self, key: &str) {
+    let text = self.strings.get(key).to_string();
+    self.show_notice(&text);
+  }
+
+  pub(super) fn update_notice(&mut self, delta: Duration) {
+    self.notice_timer = self.notice_timer.checked_sub(delta).unwrap_or_default();
+    if self.notice_timer == Duration::from_secs(0) {
+      self.notice = None;
+    }
+  }
+
+  pub(super) fn draw_notice(&self, ctx: &mut Context) -> GameResult {
+    if let Some(notice) = &self.notice {
+      let (width, _) = notice.dimensions(ctx);
+      let x = self.layout.frame.x + (self.layout.frame.w - width as f32) / 2.0;
+      graphics::draw(ctx, notice, (na::Point2::new(x, self.layout.frame.y + self.font_size()),))?;
+    }
+
+    Ok(())
+  }
+
+  // The settings and achievements are too long for the banner size.
+  // Saved at once, a crash later in the game does not lose it.
+  pub(super) fn unlock(&mut self, achievement: Achievement) {
+    if !self.lifetime.achievements.insert(achievement.id().to_string()) {
+      return;
+    }
+    debug!("Achievement: {:?}", achievement);
+    self.toasts.push_back(achievement);
+    if let Some(directory) = &self.stats_directory {
+      if let Err(e) = self.lifetime.save(directory) {
+        warn!("Cannot write the lifetime statistics ({})", e);
+      }
+    }
+  }
+
+  pub(super) fn update_toast(&mut self, delta: Duration) {
+    self.toast_timer = self.toast_timer.checked_sub(delta).unwrap_or_default();
+    if self.toast_timer > Duration::from_secs(0) {
+      return;
+    }
+    self.toast = self.toasts.pop_front().map(|achievement| {
+      let text = self.strings.fill("achievements.unlocked", &[("name", self.strings.get(&format!("achievement.{}.name", achievement.id())).to_string())]);
+      graphics::Text::new((text, self.font(), self.font_size()))
+    });
+    if self.toast.is_some() {
+      self.toast_timer = TOAST_DURATION;
+    }
+  }
+
+  pub(super) fn draw_toast(&self, ctx: &mut Context) -> GameResult {
+    let toast = match &self.toast {
+      Some(toast) => toast,
+      None => return Ok(()),
+    };
+    let (width, height) = toast.dimensions(ctx);
+    let (width, height) = (width as f32 + 2.0 * TOAST_PADDING, height as f32 + 2.0 * TOAST_PADDING);
+    // Out of the window on the right, in for most of its time, out again.
+    let shown = TOAST_DURATION - self.toast_timer;
+    let slide = shown.min(self.toast_timer).as_secs_f32() / TOAST_SLIDE.as_secs_f32();
+    let visible = if self.settings.reduced_motion { 1.0 } else { slide.min(1.0) };
+    let x = self.layout.frame.x + self.layout.frame.w - (width + TOAST_PADDING) * visible;
+    let y = self.layout.frame.y + self.layout.frame.h - height - TOAST_PADDING;
+    let panel = graphics::Rect::new(x, y, width, height);
+    let mesh_back = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), panel, TOAST_COLOR)?;
+    graphics::draw(ctx, &mesh_back, (na::Point2::new(0.0, 0.0),))?;
+    let mesh_border = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(GRID_STROKE_SIZE), panel, graphics::WHITE)?;
+    graphics::draw(ctx, &mesh_border, (na::Point2::new(0.0, 0.0),))?;
+    graphics::draw(ctx, toast, (na::Point2::new(x + TOAST_PADDING, y + TOAST_PADDING),))?;
+
+    Ok(())
+  }
+
+  pub(super) fn create_banner_text(&self, text: &str) -> graphics::Text {
+    let list = match self.phase {
+      GamePhase::Settings | GamePhase::Controls | GamePhase::Achievements | GamePhase::HighScores => true,
+      GamePhase::GameOver => self.results_table().is_some(),
+      GamePhase::Menu => self.title_menu() && !self.in_demo(),
+      _ => false,
+    };
+    let size = if list { self.font_size() } else { self.config.font_size * BANNER_FONT_SCALE };
+    return graphics::Text::new((text, self.font(), size));
+  }
+
+  // Below 1 for a text that does not fit the window, with a big font.
+  pub(super) fn fit_scale(&self, width: f32, height: f32) -> f32 {
+    let banner = self.layout.banner;
+    return (banner.w / width).min(banner.h / height).min(1.0);
+  }
+
+  // Centered in the window, over the board in solo play.
+  pub(super) fn draw_banner(&mut self, ctx: &mut Context) -> GameResult {
+    if let Some(banner) = &self.banner {
+      let (width, height) = banner.dimensions(ctx);
+      let scale = self.fit_scale(width as f32, height as f32);
+      let (width, height) = (width as f32 * scale, height as f32 * scale);
+      let x = self.layout.banner.x + (self.layout.banner.w - width) / 2.0;
+      let y = self.layout.banner.y + (self.layout.banner.h - height) / 2.0;
+      // Never over the colors of the board in high contrast.
+      if self.settings.high_contrast {
+        let padding = self.config.font_size;
+        let mesh_back = graphics::Mesh::new_rectangle(
+          ctx,
+          graphics::DrawMode::fill(),
+          graphics::Rect::new(x - padding, y - padding, width + 2.0 * padding, height + 2.0 * padding),
+          graphics::BLACK,
+        )?;
+        graphics::draw(ctx, &mesh_back, (na::Point2::new(0.0, 0.0),))?;
+      }
+      graphics::draw(ctx, banner, graphics::DrawParam::new().dest(na::Point2::new(x, y)).scale(na::Vector2::new(scale, scale)))?;
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn update_garbage_meters(&mut self, delta: Duration) {
+    let height = self.config.grid_height as f32;
+    let reduced_motion = self.settings.reduced_motion;
+    for player in self.players.iter_mut() {
+      let queued = player.game.pending_garbage();
+      if queued > player.garbage_queued && !reduced_motion {
+        player.garbage_pulse = GARBAGE_PULSE;
+      }
+      player.garbage_queued = queued;
+      player.garbage_pulse = player.garbage_pulse.checked_sub(delta).unwrap_or_default();
+      let target = (queued as f32).min(height);
+      let step = GARBAGE_METER_SPEED * delta.as_secs_f32();
+      player.garbage_shown = match player.garbage_shown {
+        _ if reduced_motion => target,
+        shown if shown < target => (shown + step).min(target),
+        shown => (shown - step).max(target),
+      };
+    }
+  }
+
+  // One red segment per row of garbage queued, left of the board from its
+  // bottom.
+  pub(super) fn draw_garbage_meter(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    if player.garbage_shown <= 0.0 {
+      return Ok(());
+    }
+
+    let pulse = player.garbage_pulse.as_secs_f32() / GARBAGE_PULSE.as_secs_f32();
+    let color = graphics::Color {
+      r: GARBAGE_METER_COLOR.r + (1.0 - GARBAGE_METER_COLOR.r) * pulse,
+      g: GARBAGE_METER_COLOR.g + (1.0 - GARBAGE_METER_COLOR.g) * pulse,
+      b: GARBAGE_METER_COLOR.b + (1.0 - GARBAGE_METER_COLOR.b) * pulse,
+      a: 1.0,
+    };
+    let row_height = self.layout.cases_per_case as f32 * self.layout.case_pitch();
+    let builder = &mut graphics::MeshBuilder::new();
+    let mut bottom = board.grid_frame.h;
+    let mut left = player.garbage_shown;
+    let mut segments = 0;
+    while left > 0.0 {
+      let height = left.min(1.0) * row_height - GARBAGE_METER_GAP;
+      if height > 0.0 {
+        segments += 1;
+        builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(-GARBAGE_METER_WIDTH - CASE_BORDER, bottom - height, GARBAGE_METER_WIDTH, height), color);
+      }
+      bottom -= row_height;
+      left -= 1.0;
+    }
+    // A meter still growing from nothing.
+    if segments == 0 {
+      return Ok(());
+    }
+    let mesh_meter = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh_meter, (na::Point2::new(board.grid_frame.x, board.grid_frame.y),))?;
+
+    Ok(())
+  }
+
+  // Under the board watched, alone, or at the bottom of its column when the
+  // board fills it.
+  pub(super) fn draw_replay_progress(&self, ctx: &mut Context, board: &BoardLayout) -> GameResult {
+    let position = match self.watching.as_ref() {
+      Some(position) if !self.in_demo() => position,
+      _ => return Ok(()),
+    };
+    let frame = board.grid_frame;
+    let y = (frame.bottom() + REPLAY_BAR_GAP).min(board.region.bottom() - REPLAY_BAR_HEIGHT);
+    let builder = &mut graphics::MeshBuilder::new();
+    builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(frame.x, y, frame.w, REPLAY_BAR_HEIGHT), REPLAY_BAR_TRACK_COLOR);
+    let width = frame.w * position.progress();
+    if width > 0.0 {
+      builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(frame.x, y, width, REPLAY_BAR_HEIGHT), REPLAY_BAR_COLOR);
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+
+    Ok(())
+  }
+
+  // Over the board, while R is held during a game.
+  pub(super) fn draw_reset_progress(&self, ctx: &mut Context, board: &BoardLayout) -> GameResult {
+    let held = match self.reset_held {
+      Some(held) => held,
+      None => return Ok(()),
+    };
+    let frame = board.grid_frame;
+    let y = (frame.y - REPLAY_BAR_GAP - REPLAY_BAR_HEIGHT).max(board.region.y);
+    let builder = &mut graphics::MeshBuilder::new();
+    builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(frame.x, y, frame.w, REPLAY_BAR_HEIGHT), REPLAY_BAR_TRACK_COLOR);
+    let width = frame.w * (held.as_secs_f32() / RESET_HOLD.as_secs_f32()).min(1.0);
+    if width > 0.0 {
+      builder.rectangle(graphics::DrawMode::fill(), graphics::Rect::new(frame.x, y, width, REPLAY_BAR_HEIGHT), REPLAY_BAR_COLOR);
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+
+    Ok(())
+  }
+
+  pub(super) fn draw_score(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    graphics::draw(ctx, &player.text, (na::Point2::new(board.score.x, board.score.y),))?;
+
+    Ok(())
+  }
+
+  pub(super) fn show_callout(&mut self, index: usize, key: &str) {
+    let text = graphics::Text::new((self.strings.get(key), self.font(), self.config.font_size * CALLOUT_FONT_SCALE));
+    let player = &mut self.players[index];
+    player.callout = Some(text);
+    player.callout_timer = CALLOUT_DURATION;
+  }
+
+  // Centered on the board, as wide as it at most. Without motion, it shows
+  // at its size until it goes.
+  pub(super) fn draw_callout(&self, ctx: &mut Context, player: &Player, board: &BoardLayout) -> GameResult {
+    let callout = match &player.callout {
+      Some(callout) => callout,
+      None => return Ok(()),
+    };
+    let elapsed = (CALLOUT_DURATION - player.callout_timer).as_secs_f32();
+    let (grow, fade) = if self.settings.reduced_motion {
+      (1.0, 1.0)
+    } else {
+      ((elapsed / CALLOUT_GROW.as_secs_f32()).min(1.0), (player.callout_timer.as_secs_f32() / (CALLOUT_DURATION - CALLOUT_GROW).as_secs_f32()).min(1.0))
+    };
+    let (width, height) = callout.dimensions(ctx);
+    let board = board.board();
+    let scale = (board.w * CALLOUT_FIT / width as f32).min(1.0) * (0.5 + 0.5 * grow);
+    let dest = na::Point2::new(board.x + (board.w - width as f32 * scale) / 2.0, board.y + (board.h - height as f32 * scale) / 2.0);
+    let color = graphics::Color { a: fade, ..graphics::WHITE };
+    graphics::draw(ctx, callout, graphics::DrawParam::new().dest(dest).scale(na::Vector2::new(scale, scale)).color(color))?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,548 @@
+use std::time::{Duration, Instant};
+
+use ggez::event;
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use log::debug;
+
+use crate::game::{Input, Mode};
+use crate::keymap::{action_id, key_name, Bindings, Keymap, ACTIONS};
+use crate::layout::CASE_BORDER;
+use crate::phase::GamePhase;
+use crate::piece::Case;
+use crate::settings::{SettingItem, SPEED_STEP};
+
+use super::{Controls, MainState, BANNER_FONT_SCALE};
+use super::hud::GARBAGE_METER_WIDTH;
+use super::menus::TITLE_ITEMS;
+
+// Buttons for the pieces without a keyboard, see Settings::touch_controls:
+// the moves in the lower left corner, the rotation, hold and hard drop in
+// the lower right, as big as the room the boards leave there allows. A move
+// held repeats after TOUCH_REPEAT_DELAY, every TOUCH_REPEAT_INTERVAL.
+const TOUCH_LEFT_BUTTONS: [Input; 3] = [Input::MoveLeft, Input::SoftDrop, Input::MoveRight];
+const TOUCH_RIGHT_BUTTONS: [Input; 3] = [Input::Hold, Input::RotateCW, Input::HardDrop];
+// Of the size of a button, between two of them and around them.
+const TOUCH_SPACING: f32 = 0.15;
+// Of the height of the layout.
+const TOUCH_MAX_SIZE: f32 = 0.12;
+const TOUCH_LABEL_FIT: f32 = 0.8;
+pub(super) const TOUCH_REPEAT_DELAY: Duration = Duration::from_millis(170);
+const TOUCH_REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+const TOUCH_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 1.0, b: 1.0, a: 0.15 };
+const TOUCH_HELD_COLOR: graphics::Color = graphics::Color { r: 1.0, g: 1.0, b: 1.0, a: 0.35 };
+
+// During a game, R restarts once held RESET_HOLD, or pressed again within
+// RESET_DOUBLE_PRESS; the hold fills a bar over the boards.
+pub(super) const RESET_HOLD: Duration = Duration::from_millis(500);
+const RESET_DOUBLE_PRESS: Duration = Duration::from_secs(1);
+
+// 0 to 9 jump to a tenth of the replay.
+const REPLAY_KEYS: [event::KeyCode; 10] = [
+  event::KeyCode::Key0, event::KeyCode::Key1, event::KeyCode::Key2, event::KeyCode::Key3, event::KeyCode::Key4,
+  event::KeyCode::Key5, event::KeyCode::Key6, event::KeyCode::Key7, event::KeyCode::Key8, event::KeyCode::Key9,
+];
+
+fn key_input(controls: Controls, bindings: &Bindings, key: event::KeyCode) -> Option<Input> {
+  use event::KeyCode;
+  let input = match (controls, key) {
+    (Controls::Solo, _) => return bindings.input(key),
+    (Controls::Left, _) => return Keymap::Wasd.input(key),
+    (Controls::Right, KeyCode::Left) => Input::MoveLeft,
+    (Controls::Right, KeyCode::Right) => Input::MoveRight,
+    (Controls::Right, KeyCode::Down) => Input::SoftDrop,
+    (Controls::Right, KeyCode::Up) => Input::RotateCW,
+    (Controls::Right, KeyCode::RShift) => Input::RotateCCW,
+    (Controls::Right, KeyCode::RControl) => Input::Hold,
+    (Controls::Right, KeyCode::Return) => Input::HardDrop,
+    (Controls::Right, KeyCode::RAlt) => Input::UseItem,
+    _ => return None,
+  };
+  return Some(input);
+}
+
+// Keys the controls screen does not bind: they do something whatever the phase.
+const RESERVED_KEYS: [event::KeyCode; 21] = [
+  event::KeyCode::Escape, event::KeyCode::H, event::KeyCode::M, event::KeyCode::G, event::KeyCode::O, event::KeyCode::R, event::KeyCode::P,
+  event::KeyCode::F2, event::KeyCode::F3, event::KeyCode::F4, event::KeyCode::F6, event::KeyCode::F7, event::KeyCode::F8, event::KeyCode::F9, event::KeyCode::F10, event::KeyCode::F11, event::KeyCode::F12,
+  event::KeyCode::Equals, event::KeyCode::Add, event::KeyCode::Minus, event::KeyCode::Subtract,
+];
+
+// The controls screen waiting for the key of the action picked, or for
+// Enter to take a key from another action.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub(super) enum KeyCapture {
+  Waiting,
+  Swap(event::KeyCode),
+}
+
+impl MainState {
+  // The one board the touch buttons play: the player alone at the keyboard,
+  // against the network or the computer included. None in versus.
+  fn touch_player(&self) -> Option<usize> {
+    if !self.settings.touch_controls || self.in_demo() {
+      return None;
+    }
+    return self.players.iter().position(|player| player.controls == Controls::Solo && player.bot.is_none());
+  }
+
+  // Each button and its place in the layout, between the edges of the layout
+  // and the boards with their garbage meter. None when off.
+  fn touch_buttons(&self) -> Vec<(Input, graphics::Rect)> {
+    if self.touch_player().is_none() {
+      return Vec::new();
+    }
+    let (first, last) = (&self.layout.boards[0], &self.layout.boards[self.layout.boards.len() - 1]);
+    let left_room = (first.grid_frame.x - GARBAGE_METER_WIDTH - 2.0 * CASE_BORDER) * first.scale - self.layout.frame.x;
+    let right_room = self.layout.frame.right() - (last.grid_frame.right() + CASE_BORDER) * last.scale;
+    let size = |room: f32| (room / (3.0 + 4.0 * TOUCH_SPACING)).min(self.layout.frame.h * TOUCH_MAX_SIZE);
+    let mut buttons = Vec::new();
+    for (inputs, size, left) in [(TOUCH_LEFT_BUTTONS, size(left_room), true), (TOUCH_RIGHT_BUTTONS, size(right_room), false)] {
+      if size <= 0.0 {
+        continue;
+      }
+      let spacing = size * TOUCH_SPACING;
+      let x = if left { self.layout.frame.x + spacing } else { self.layout.frame.right() - 3.0 * (size + spacing) };
+      let y = self.layout.frame.bottom() - size - spacing;
+      for (index, &input) in inputs.iter().enumerate() {
+        buttons.push((input, graphics::Rect::new(x + index as f32 * (size + spacing), y, size, size)));
+      }
+    }
+    return buttons;
+  }
+
+  // Like a key of the player.
+  fn press_touch(&mut self, input: Input) {
+    if let Some(index) = self.touch_player() {
+      let player = &mut self.players[index];
+      player.pending_inputs.push(if player.game.mirror() { input.mirrored() } else { input });
+    }
+  }
+
+  fn touch_down(&mut self, ctx: &Context, x: f32, y: f32) {
+    if self.quit_question.is_some() || self.help.is_some() || !(self.phase == GamePhase::Playing || self.phase == GamePhase::LineClear) {
+      return;
+    }
+    let point = self.layout_point(ctx, x, y);
+    if let Some(&(input, _)) = self.touch_buttons().iter().find(|(_, button)| button.contains(point)) {
+      self.touch_held = Some(input);
+      self.touch_timer = Duration::from_secs(0);
+      self.press_touch(input);
+    }
+  }
+
+  // Sliding off the button lets it go.
+  fn touch_moved(&mut self, ctx: &Context, x: f32, y: f32) {
+    let point = self.layout_point(ctx, x, y);
+    if let Some(input) = self.touch_held {
+      if !self.touch_buttons().iter().any(|&(other, button)| other == input && button.contains(point)) {
+        self.touch_held = None;
+      }
+    }
+  }
+
+  pub(super) fn update_touch(&mut self, delta: Duration) {
+    let input = match self.touch_held {
+      Some(input @ (Input::MoveLeft | Input::MoveRight | Input::SoftDrop)) => input,
+      _ => return,
+    };
+    self.touch_timer += delta;
+    while self.touch_timer >= TOUCH_REPEAT_DELAY {
+      self.touch_timer -= TOUCH_REPEAT_INTERVAL;
+      self.press_touch(input);
+    }
+  }
+
+  pub(super) fn draw_touch_buttons(&self, ctx: &mut Context) -> GameResult {
+    let buttons = self.touch_buttons();
+    if buttons.is_empty() || !matches!(self.phase, GamePhase::Countdown | GamePhase::Playing | GamePhase::LineClear) {
+      return Ok(());
+    }
+
+    let builder = &mut graphics::MeshBuilder::new();
+    for &(input, button) in &buttons {
+      builder.rectangle(graphics::DrawMode::fill(), button, if self.touch_held == Some(input) { TOUCH_HELD_COLOR } else { TOUCH_COLOR });
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+    for &(input, button) in &buttons {
+      let label = graphics::Text::new((self.strings.get(&format!("touch.{}", action_id(input))), self.font(), self.font_size()));
+      let (width, height) = label.dimensions(ctx);
+      let scale = (button.w * TOUCH_LABEL_FIT / width as f32).min(button.h * TOUCH_LABEL_FIT / height as f32).min(BANNER_FONT_SCALE);
+      let dest = na::Point2::new(button.x + (button.w - width as f32 * scale) / 2.0, button.y + (button.h - height as f32 * scale) / 2.0);
+      graphics::draw(ctx, &label, graphics::DrawParam::new().dest(dest).scale(na::Vector2::new(scale, scale)))?;
+    }
+
+    Ok(())
+  }
+
+  // Before a quit or a restart in the middle of a game.
+  // Out of a game R restarts at once, see RESET_HOLD otherwise.
+  fn key_reset(&mut self, repeat: bool) -> GameResult {
+    if !self.game_in_progress() {
+      return self.restart();
+    }
+    // Held, the system repeats it: not a second press.
+    if repeat {
+      return Ok(());
+    }
+    if self.reset_pressed.is_some() {
+      return self.restart();
+    }
+    self.reset_held = Some(Duration::from_secs(0));
+    self.reset_pressed = Some(Duration::from_secs(0));
+    self.show_text("notice.reset");
+
+    Ok(())
+  }
+
+  // Every frame, frozen or paused too.
+  pub(super) fn update_reset_key(&mut self, delta: Duration) -> GameResult {
+    self.reset_pressed = self.reset_pressed.map(|time| time + delta).filter(|&time| time < RESET_DOUBLE_PRESS);
+    self.reset_held = self.reset_held.map(|time| time + delta);
+    if self.reset_held.is_some_and(|time| time >= RESET_HOLD) {
+      self.restart()?;
+    }
+
+    Ok(())
+  }
+
+  fn key_menu(&mut self, ctx: &mut Context, key: event::KeyCode) -> GameResult {
+    // Any key keeps the demo away.
+    self.phase_timer = Duration::from_secs(0);
+    let count = self.missions.len();
+    let items = TITLE_ITEMS.len();
+    match key {
+      event::KeyCode::Up if self.title_menu() => {
+        self.title_index = (self.title_index + items - 1) % items;
+        self.set_phase(GamePhase::Menu)?;
+      },
+      event::KeyCode::Down if self.title_menu() => {
+        self.title_index = (self.title_index + 1) % items;
+        self.set_phase(GamePhase::Menu)?;
+      },
+      event::KeyCode::Return if self.title_menu() => self.select_title_item(ctx)?,
+      event::KeyCode::Return if self.network.is_none() => {
+        self.reset()?;
+        self.set_phase(GamePhase::Countdown)?;
+      },
+      event::KeyCode::Up if count > 0 => {
+        self.mission_index = (self.mission_index + count - 1) % count;
+        self.set_phase(GamePhase::Menu)?;
+      },
+      event::KeyCode::Down if count > 0 => {
+        self.mission_index = (self.mission_index + 1) % count;
+        self.set_phase(GamePhase::Menu)?;
+      },
+      event::KeyCode::S => self.set_phase(GamePhase::Statistics)?,
+      event::KeyCode::A => self.set_phase(GamePhase::Achievements)?,
+      event::KeyCode::B => {
+        self.high_score_mode = self.players[0].game.mode();
+        self.set_phase(GamePhase::HighScores)?;
+      },
+      _ => (),
+    }
+
+    Ok(())
+  }
+
+  // Left and Right go through the modes.
+  fn key_high_scores(&mut self, key: event::KeyCode) -> GameResult {
+    let count = Mode::ALL.len();
+    let index = Mode::ALL.iter().position(|&mode| mode == self.high_score_mode).unwrap_or(0);
+    match key {
+      event::KeyCode::Escape | event::KeyCode::B | event::KeyCode::Return => self.set_phase(GamePhase::Menu)?,
+      event::KeyCode::Left => {
+        self.high_score_mode = Mode::ALL[(index + count - 1) % count];
+        self.set_phase(GamePhase::HighScores)?;
+      },
+      event::KeyCode::Right => {
+        self.high_score_mode = Mode::ALL[(index + 1) % count];
+        self.set_phase(GamePhase::HighScores)?;
+      },
+      _ => (),
+    }
+
+    Ok(())
+  }
+
+  // Saved at once, and used by the next key of the game.
+  fn bind_key(&mut self, ctx: &mut Context, input: Input, key: event::KeyCode) {
+    self.bindings.bind(input, key);
+    self.settings.bindings = self.bindings.to_names();
+    self.save_settings(ctx);
+  }
+
+  fn key_controls(&mut self, ctx: &mut Context, key: event::KeyCode) -> GameResult {
+    let count = ACTIONS.len() + 1;
+    match (self.key_capture, key) {
+      (Some(_), event::KeyCode::Escape) => self.key_capture = None,
+      (Some(KeyCapture::Waiting), _) if RESERVED_KEYS.contains(&key) || key_name(key).is_none() => self.show_notice(&self.strings.fill("controls.cannot_bind", &[("key", format!("{:?}", key))])),
+      (Some(KeyCapture::Waiting), _) => {
+        let input = ACTIONS[self.controls_index];
+        match self.bindings.input(key) {
+          Some(other) if other != input => self.key_capture = Some(KeyCapture::Swap(key)),
+          _ => {
+            self.bind_key(ctx, input, key);
+            self.key_capture = None;
+          },
+        }
+      },
+      (Some(KeyCapture::Swap(key)), event::KeyCode::Return) => {
+        self.bind_key(ctx, ACTIONS[self.controls_index], key);
+        self.key_capture = None;
+      },
+      (Some(KeyCapture::Swap(_)), _) => (),
+      (None, event::KeyCode::Up) => self.controls_index = (self.controls_index + count - 1) % count,
+      (None, event::KeyCode::Down) => self.controls_index = (self.controls_index + 1) % count,
+      (None, event::KeyCode::Return) if self.controls_index < ACTIONS.len() => self.key_capture = Some(KeyCapture::Waiting),
+      (None, event::KeyCode::Return) => {
+        self.bindings = Bindings::of(self.keymap);
+        self.settings.bindings.clear();
+        self.save_settings(ctx);
+        self.show_notice(&self.strings.fill("notice.keys", &[("keymap", self.keymap.name().to_string())]));
+      },
+      (None, event::KeyCode::Escape) => {
+        let timer = self.phase_timer;
+        self.set_phase(GamePhase::Settings)?;
+        self.phase_timer = timer;
+        return Ok(());
+      },
+      _ => return Ok(()),
+    }
+    self.banner = Some(self.create_banner_text(&self.controls_text()));
+
+    Ok(())
+  }
+
+  fn key_settings(&mut self, ctx: &mut Context, key: event::KeyCode) -> GameResult {
+    let count = SettingItem::ALL.len();
+    let item = SettingItem::ALL[self.settings_index];
+    match key {
+      event::KeyCode::Right | event::KeyCode::Return if item == SettingItem::Controls => return self.open_controls(),
+      event::KeyCode::Up => self.settings_index = (self.settings_index + count - 1) % count,
+      event::KeyCode::Down => self.settings_index = (self.settings_index + 1) % count,
+      event::KeyCode::Left => self.change_setting(ctx, item, -1),
+      event::KeyCode::Right | event::KeyCode::Return => self.change_setting(ctx, item, 1),
+      event::KeyCode::Escape | event::KeyCode::O => return self.close_settings(),
+      _ => return Ok(()),
+    }
+    self.banner = Some(self.create_banner_text(&self.settings_text()));
+
+    Ok(())
+  }
+
+  // Space pauses the replay, Left and Right go a piece back or forth, 0 to 9
+  // jump to a tenth of it.
+  fn key_replay(&mut self, key: event::KeyCode) -> GameResult {
+    let position = match self.watching.as_mut() {
+      Some(position) => position,
+      None => return Ok(()),
+    };
+    let tick = match key {
+      event::KeyCode::Space => {
+        position.paused = !position.paused;
+        self.create_score_text(0);
+        return Ok(());
+      },
+      event::KeyCode::Left => position.previous_lock(),
+      event::KeyCode::Right => position.next_lock(),
+      _ => match REPLAY_KEYS.iter().position(|&digit| digit == key) {
+        Some(tenths) => position.fraction(tenths as u32),
+        None => return Ok(()),
+      },
+    };
+    return self.seek_replay(tick);
+  }
+
+  // The game watched played again up to `tick`, with none of its sounds.
+  fn seek_replay(&mut self, tick: u32) -> GameResult {
+    let position = match self.watching.as_mut() {
+      Some(position) => position,
+      None => return Ok(()),
+    };
+    let start = Instant::now();
+    let game = position.seek(tick);
+    debug!("Replay at tick {} in {:?}", tick, start.elapsed());
+    let player = &mut self.players[0];
+    player.game = game;
+    player.pending_inputs.clear();
+    player.shake_timer = Duration::from_secs(0);
+    player.callout = None;
+    player.tetris_flash = Duration::from_secs(0);
+    self.update_danger();
+    self.create_score_text(0);
+    if self.players[0].game.is_over() {
+      return self.end_match();
+    }
+    let phase = if self.players[0].game.is_clearing() { GamePhase::LineClear } else { GamePhase::Playing };
+    if self.phase != phase {
+      self.set_phase(phase)?;
+    }
+
+    Ok(())
+  }
+
+  // Pressed again by the repeat of the system, a key held does nothing: the
+  // logic ticks repeat it.
+  fn key_playing(&mut self, key: event::KeyCode) {
+    for player in self.players.iter_mut() {
+      if let Some(input) = key_input(player.controls, &self.bindings, key) {
+        if let Some(input) = player.held.press(if player.game.mirror() { input.mirrored() } else { input }) {
+          player.pending_inputs.push(input);
+        }
+      }
+    }
+  }
+
+  pub(super) fn key_down(&mut self, ctx: &mut Context, key: event::KeyCode, repeat: bool) {
+    // Whatever is on screen, the demo and the overlays included.
+    if key == event::KeyCode::F12 {
+      self.screenshot_requested = true;
+      return;
+    }
+    if key == event::KeyCode::F11 {
+      self.save_clip();
+      return;
+    }
+    if self.in_demo() {
+      self.stop_demo().unwrap();
+      return;
+    }
+    if self.quit_question.is_some() {
+      match key {
+        event::KeyCode::Return | event::KeyCode::NumpadEnter => self.confirm_quit(ctx).unwrap(),
+        event::KeyCode::Escape => self.cancel_quit().unwrap(),
+        _ => (),
+      }
+      return;
+    }
+    // Every key belongs to the menu while it is open.
+    if self.help.is_some() {
+      if key == event::KeyCode::H || key == event::KeyCode::Escape {
+        self.hide_help().unwrap();
+      }
+      return;
+    }
+    if self.editor.is_some() {
+      match key {
+        event::KeyCode::F6 | event::KeyCode::Escape => self.close_editor().unwrap(),
+        event::KeyCode::C => self.clear_board(),
+        event::KeyCode::X => self.export_board(ctx),
+        event::KeyCode::F7 => self.save_board(ctx),
+        _ => (),
+      }
+      return;
+    }
+    if self.phase == GamePhase::Controls {
+      self.key_controls(ctx, key).unwrap();
+      return;
+    }
+    if self.phase == GamePhase::Settings {
+      self.key_settings(ctx, key).unwrap();
+      return;
+    }
+    match key {
+      event::KeyCode::M => self.change_setting(ctx, SettingItem::ThemeOn, 1),
+      event::KeyCode::G => self.change_setting(ctx, SettingItem::Ghost, 1),
+      event::KeyCode::F2 => self.change_setting(ctx, SettingItem::HighContrast, 1),
+      event::KeyCode::F4 => {
+        self.change_setting(ctx, SettingItem::Keymap, 1);
+        self.show_notice(&self.strings.fill("notice.keys", &[("keymap", self.keymap.name().to_string())]));
+      },
+      event::KeyCode::Equals | event::KeyCode::Add => self.change_speed(ctx, SPEED_STEP),
+      event::KeyCode::Minus | event::KeyCode::Subtract => self.change_speed(ctx, -SPEED_STEP),
+      event::KeyCode::F3 => self.change_setting(ctx, SettingItem::ReducedMotion, 1),
+      event::KeyCode::F6 => self.open_editor().unwrap(),
+      event::KeyCode::F7 if self.game_phase() != GamePhase::Menu => self.save_board(ctx),
+      event::KeyCode::F8 => self.toggle_frozen(),
+      event::KeyCode::F9 if self.frozen => self.frame_steps += 1,
+      event::KeyCode::F10 => self.toggle_debug_overlay(),
+      event::KeyCode::H => self.show_help().unwrap(),
+      event::KeyCode::O if self.phase == GamePhase::Menu || self.phase == GamePhase::Paused => self.open_settings().unwrap(),
+      // The opponent of a network match would not see a restart or a pause.
+      event::KeyCode::R if self.network.is_none() => self.key_reset(repeat).unwrap(),
+      event::KeyCode::P if self.network.is_none() => {
+        if self.phase != GamePhase::Menu && self.phase != GamePhase::GameOver {
+          self.toggle_pause().unwrap();
+        }
+      },
+      _ => match self.phase {
+        GamePhase::Menu => self.key_menu(ctx, key).unwrap(),
+        GamePhase::GameOver if key == event::KeyCode::Escape && self.title_menu() => self.enter_menu().unwrap(),
+        GamePhase::Paused if key == event::KeyCode::Escape && self.title_menu() => {
+          self.abandon_games();
+          self.enter_menu().unwrap();
+        },
+        GamePhase::Statistics => match key {
+          event::KeyCode::Escape | event::KeyCode::S | event::KeyCode::Return => self.set_phase(GamePhase::Menu).unwrap(),
+          _ => (),
+        },
+        GamePhase::Achievements => match key {
+          event::KeyCode::Escape | event::KeyCode::A | event::KeyCode::Return => self.set_phase(GamePhase::Menu).unwrap(),
+          _ => (),
+        },
+        GamePhase::HighScores => self.key_high_scores(key).unwrap(),
+        GamePhase::Playing | GamePhase::LineClear | GamePhase::Countdown if key == event::KeyCode::Escape => self.ask_quit().unwrap(),
+        GamePhase::Playing | GamePhase::LineClear if self.watching.is_some() => self.key_replay(key).unwrap(),
+        GamePhase::Playing | GamePhase::LineClear => self.key_playing(key),
+        _ => (),
+      },
+    }
+  }
+
+  // Whatever the phase, so no key stays held after a pause or a menu.
+  pub(super) fn key_up(&mut self, key: event::KeyCode) {
+    if key == event::KeyCode::R {
+      self.reset_held = None;
+    }
+    for player in self.players.iter_mut() {
+      if let Some(input) = key_input(player.controls, &self.bindings, key) {
+        player.held.release(if player.game.mirror() { input.mirrored() } else { input });
+      }
+    }
+  }
+
+  // In the editor, a left click paints the case or, on a case of that color,
+  // empties it; dragging does the same to every case crossed. Otherwise it
+  // presses the touch buttons.
+  pub(super) fn mouse_down(&mut self, ctx: &mut Context, button: event::MouseButton, x: f32, y: f32) {
+    if self.editor.is_none() {
+      if button == event::MouseButton::Left {
+        self.touch_down(ctx, x, y);
+      }
+      return;
+    }
+    let paint = match self.editor {
+      Some(paint) if self.quit_question.is_none() && self.help.is_none() => paint,
+      _ => return,
+    };
+    match button {
+      event::MouseButton::Left => {
+        if let Some((x, y)) = self.case_at(ctx, x, y) {
+          let case = if self.players[0].game.grid().get(x, y) == Some(paint) { Case::Empty } else { paint };
+          self.editor_stroke = Some(case);
+          self.paint_case(x, y, case);
+        }
+      },
+      event::MouseButton::Right => self.next_paint(),
+      _ => (),
+    }
+  }
+
+  pub(super) fn mouse_up(&mut self, button: event::MouseButton) {
+    if button == event::MouseButton::Left {
+      self.editor_stroke = None;
+      self.touch_held = None;
+    }
+  }
+
+  pub(super) fn mouse_motion(&mut self, ctx: &mut Context, x: f32, y: f32) {
+    self.touch_moved(ctx, x, y);
+    if let (Some(case), Some((x, y))) = (self.editor_stroke, self.case_at(ctx, x, y)) {
+      self.paint_case(x, y, case);
+    }
+  }
+}
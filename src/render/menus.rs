@@ -0,0 +1,491 @@
+use std::time::Duration;
+
+use ggez::event;
+use ggez::graphics;
+use ggez::nalgebra as na;
+use ggez::{Context, GameResult};
+
+use crate::achievement::Achievement;
+use crate::daily::{Date, DAILY_LINES};
+use crate::game::{Input, Mode, MAX_SPEED, MIN_SPEED};
+use crate::highscores::table_name;
+use crate::keymap::{action_id, key_name, Bindings, Keymap, ACTIONS};
+use crate::locale::language_names;
+use crate::network::ConnectionState;
+use crate::phase::GamePhase;
+use crate::replay::Replay;
+use crate::rumble::Pulse;
+use crate::settings::{next_name, SettingItem};
+use crate::skin::skin_names;
+
+use super::{mode_key, Controls, MainState, COUNTDOWN_DURATION, MENU_THEME_VOLUME, THEME_FADE_SECONDS};
+use super::hud::{format_split, format_time};
+use super::input::KeyCapture;
+
+// Over the whole window behind the key help and the quit question.
+const HELP_DIM_COLOR: graphics::Color = graphics::Color { r: 0.0, g: 0.0, b: 0.0, a: 0.75 };
+
+// One line of the title screen, chosen with Up, Down and Enter.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub(super) enum TitleItem {
+  Mode(Mode),
+  // Two boards: the computer with --ai, a second player on the keyboard otherwise.
+  Versus,
+  Settings,
+  Quit,
+}
+
+pub(super) const TITLE_ITEMS: [TitleItem; 7] = [
+  TitleItem::Mode(Mode::Marathon),
+  TitleItem::Mode(Mode::Sprint),
+  TitleItem::Mode(Mode::Ultra),
+  TitleItem::Mode(Mode::Zen),
+  TitleItem::Versus,
+  TitleItem::Settings,
+  TitleItem::Quit,
+];
+
+impl TitleItem {
+  // Of its name in resources/lang.
+  fn key(&self) -> String {
+    return match self {
+      TitleItem::Mode(mode) => mode_key(*mode),
+      TitleItem::Versus => String::from("title.versus"),
+      TitleItem::Settings => String::from("title.settings"),
+      TitleItem::Quit => String::from("title.quit"),
+    };
+  }
+}
+
+impl MainState {
+  // Sessions with a goal of their own keep their menu: missions, puzzle,
+  // opener, daily challenge, loaded board, replay and network match.
+  pub(super) fn title_menu(&self) -> bool {
+    return self.network.is_none() && self.missions.is_empty() && self.puzzle.is_none() && self.opener.is_none() && !self.daily && self.start_board.is_none()
+      && self.watching.is_none();
+  }
+
+  fn title_text(&self) -> String {
+    let lines: Vec<String> = TITLE_ITEMS.iter().enumerate().map(|(index, item)| format!(
+      "{} {}", if index == self.title_index { ">" } else { " " }, self.strings.get(&item.key()))).collect();
+    return self.strings.fill("title.menu", &[("items", lines.join("\n"))]);
+  }
+
+  // Back to the title screen, the theme quieter than in a game.
+  pub(super) fn enter_menu(&mut self) -> GameResult {
+    self.set_phase(GamePhase::Menu)?;
+    self.audio.stop_theme();
+    self.audio.fade_theme_in(self.settings.theme_volume * MENU_THEME_VOLUME, THEME_FADE_SECONDS);
+    if self.settings.theme_on {
+      self.audio.play_theme(self.sound_theme_pitch(), Duration::from_secs(0));
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn select_title_item(&mut self, ctx: &mut Context) -> GameResult {
+    let mode = match TITLE_ITEMS[self.title_index] {
+      TitleItem::Mode(mode) => {
+        self.set_boards(ctx, false)?;
+        mode
+      },
+      TitleItem::Versus => {
+        self.set_boards(ctx, true)?;
+        self.players[0].game.mode()
+      },
+      TitleItem::Settings => return self.open_settings(),
+      TitleItem::Quit => {
+        self.before_quit(ctx);
+        event::quit(ctx);
+        return Ok(());
+      },
+    };
+    for player in self.players.iter_mut() {
+      player.game.set_mode(mode);
+    }
+    self.reset()?;
+    self.set_phase(GamePhase::Countdown)?;
+
+    Ok(())
+  }
+
+  fn daily_menu_text(&self, date: Date) -> String {
+    let today = match self.daily_records.official(date) {
+      Some(result) => self.strings.fill(if result.completed { "menu.daily_completed" } else { "menu.daily_played" },
+        &[("score", self.strings.number(result.score)), ("lines", result.lines.to_string())]),
+      None => self.strings.get("menu.daily_not_played").to_string(),
+    };
+    return self.strings.fill("menu.daily", &[("date", date.to_string()), ("lines", DAILY_LINES.to_string()), ("today", today)]);
+  }
+
+  // With the splits on the results of a sprint.
+  pub(super) fn create_phase_banner(&self) -> Option<graphics::Text> {
+    let mut banner = self.create_banner_text(&self.phase_banner()?);
+    if self.phase == GamePhase::GameOver && self.shows_splits(0) {
+      for fragment in self.split_fragments() {
+        banner.add(fragment);
+      }
+    }
+    return Some(banner);
+  }
+
+  fn phase_banner(&self) -> Option<String> {
+    let strings = &self.strings;
+    let text = |key: &str| Some(strings.get(key).to_string());
+    let won = self.players[0].game.is_won();
+    let banner = match self.phase {
+      GamePhase::Menu if self.network.is_some() => text(match self.network_state {
+        ConnectionState::Connected => "menu.waiting_opponent",
+        ConnectionState::Waiting => "menu.waiting_join",
+        ConnectionState::Disconnected => "menu.disconnected",
+      }),
+      GamePhase::Menu if self.mission().is_some() => self.mission().map(|mission| strings.fill(
+        if self.completed_missions.contains(&mission.name) { "menu.mission_passed" } else { "menu.mission" },
+        &[("index", (self.mission_index + 1).to_string()), ("count", self.missions.len().to_string()), ("name", mission.name.clone()), ("description", mission.description.clone())])),
+      GamePhase::Menu if self.daily().is_some() => self.daily().map(|date| self.daily_menu_text(date)),
+      GamePhase::Menu if self.title_menu() => Some(self.title_text()),
+      GamePhase::Menu => text("menu.press_enter"),
+      GamePhase::Playing | GamePhase::LineClear if self.in_demo() => text("banner.demo"),
+      GamePhase::Countdown => Some(format!("{}", COUNTDOWN_DURATION.as_secs())),
+      GamePhase::Paused if self.title_menu() => text("banner.paused_menu"),
+      GamePhase::Paused => text("banner.paused"),
+      GamePhase::Settings => Some(self.settings_text()),
+      GamePhase::Controls => Some(self.controls_text()),
+      GamePhase::Statistics => Some(self.statistics_text()),
+      GamePhase::Achievements => Some(self.achievements_text()),
+      GamePhase::HighScores => Some(self.high_scores_text()),
+      GamePhase::GameOver if self.against_opponent() => text(match self.winner {
+        Some(0) => "banner.you_win",
+        Some(_) => "banner.you_lose",
+        None => "banner.opponent_disconnected",
+      }),
+      GamePhase::GameOver if self.versus() => match self.winner {
+        Some(winner) => Some(strings.fill("banner.player_wins", &[("player", (winner + 1).to_string())])),
+        None => text("banner.draw"),
+      },
+      GamePhase::GameOver if self.mission().is_some() => self.mission().map(|mission| strings.fill(
+        if won { "banner.mission_complete" } else { "banner.mission_failed" }, &[("name", mission.name.clone())])),
+      GamePhase::GameOver if self.daily().is_some() => {
+        let banner = strings.fill(if won { "banner.daily_complete" } else { "banner.daily_over" },
+          &[("score", strings.number(self.players[0].game.current_score().score))]);
+        Some(if self.daily_practice { format!("{}\n{}", banner, strings.get("banner.daily_practice")) } else { banner })
+      },
+      GamePhase::GameOver if self.puzzle().is_some() => self.puzzle().map(|puzzle| strings.fill(
+        if won { "banner.puzzle_solved" } else { "banner.puzzle_failed" }, &[("name", puzzle.name.clone())])),
+      GamePhase::GameOver if self.opener().is_some() => self.opener().map(|opener| strings.fill("banner.opener_done", &[("name", opener.name.clone()),
+        ("right", self.opener_run.right().to_string()), ("count", opener.pieces.len().to_string())])),
+      GamePhase::GameOver if won => Some(strings.fill("banner.mode_complete", &[("mode", strings.get(&mode_key(self.players[0].game.mode())).to_string())])),
+      GamePhase::GameOver => text("banner.game_over"),
+      _ => None,
+    };
+    let banner = match (banner, self.results_table()) {
+      (Some(banner), Some(table)) => Some(format!("{}\n\n{}", banner, table)),
+      (banner, _) => banner,
+    };
+    return match (banner, &self.submission) {
+      (Some(banner), Some(submission)) if self.phase == GamePhase::GameOver => Some(format!("{}\n\n{}", banner, submission)),
+      (banner, _) => banner,
+    };
+  }
+
+  // Every key, those of the pieces as bound now.
+  fn help_text(&self) -> String {
+    let actions: Vec<String> = ACTIONS.iter().map(|&input| format!("{}: {}", self.action_name(input), key_name(self.bindings.key(input)).unwrap_or("?"))).collect();
+    return self.strings.fill("help.text", &[("actions", actions.join("\n"))]);
+  }
+
+  fn action_name(&self, input: Input) -> String {
+    return self.strings.get(&format!("action.{}", action_id(input))).to_string();
+  }
+
+  pub(super) fn show_help(&mut self) -> GameResult {
+    self.help = Some(graphics::Text::new((self.help_text(), self.font(), self.font_size())));
+    let phase = self.phase;
+    if self.network.is_none() && !self.in_demo() && (phase == GamePhase::Playing || phase == GamePhase::LineClear || phase == GamePhase::Countdown) {
+      self.toggle_pause()?;
+      self.help_paused = true;
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn hide_help(&mut self) -> GameResult {
+    self.help = None;
+    if self.help_paused && self.phase == GamePhase::Paused {
+      self.toggle_pause()?;
+    }
+    self.help_paused = false;
+
+    Ok(())
+  }
+
+  pub(super) fn ask_quit(&mut self) -> GameResult {
+    self.quit_question = Some(graphics::Text::new((self.strings.get("quit.question"), self.font(), self.font_size())));
+    let phase = self.phase;
+    if self.network.is_none() && (phase == GamePhase::Playing || phase == GamePhase::LineClear || phase == GamePhase::Countdown) {
+      self.toggle_pause()?;
+      self.quit_paused = true;
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn cancel_quit(&mut self) -> GameResult {
+    self.quit_question = None;
+    if self.quit_paused && self.phase == GamePhase::Paused {
+      self.toggle_pause()?;
+    }
+    self.quit_paused = false;
+
+    Ok(())
+  }
+
+  pub(super) fn confirm_quit(&mut self, ctx: &mut Context) -> GameResult {
+    // Back to the phase the game was in, so it counts as abandoned.
+    self.cancel_quit()?;
+    self.before_quit(ctx);
+    event::quit(ctx);
+
+    Ok(())
+  }
+
+  // The key help or the quit question, over the board.
+  pub(super) fn draw_overlay(&self, ctx: &mut Context, text: &graphics::Text) -> GameResult {
+    let mesh_dim = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), graphics::screen_coordinates(ctx), HELP_DIM_COLOR)?;
+    graphics::draw(ctx, &mesh_dim, (na::Point2::new(0.0, 0.0),))?;
+    let (width, height) = text.dimensions(ctx);
+    let scale = self.fit_scale(width as f32, height as f32);
+    let x = self.layout.banner.x + (self.layout.banner.w - width as f32 * scale) / 2.0;
+    let y = self.layout.banner.y + (self.layout.banner.h - height as f32 * scale) / 2.0;
+    graphics::draw(ctx, text, graphics::DrawParam::new().dest(na::Point2::new(x, y)).scale(na::Vector2::new(scale, scale)))?;
+
+    Ok(())
+  }
+
+  pub(super) fn controls_text(&self) -> String {
+    let mut lines: Vec<String> = ACTIONS.iter().map(|&input| format!(
+      "{}: {}", self.action_name(input), key_name(self.bindings.key(input)).unwrap_or("?"))).collect();
+    lines.push(self.strings.get("controls.reset").to_string());
+    let lines: Vec<String> = lines.into_iter().enumerate().map(|(index, line)| format!("{} {}", if index == self.controls_index { ">" } else { " " }, line)).collect();
+    let footer = match self.key_capture {
+      None => self.strings.get("controls.footer").to_string(),
+      Some(KeyCapture::Waiting) => self.strings.fill("controls.waiting", &[("action", self.action_name(ACTIONS[self.controls_index]))]),
+      Some(KeyCapture::Swap(key)) => self.strings.fill("controls.swap", &[("key", key_name(key).unwrap_or("?").to_string()),
+        ("action", self.bindings.input(key).map_or(String::from("?"), |input| self.action_name(input)))]),
+    };
+    return self.strings.fill("controls.title", &[("lines", lines.join("\n")), ("footer", footer)]);
+  }
+
+  pub(super) fn settings_text(&self) -> String {
+    let lines: Vec<String> = SettingItem::ALL.iter().enumerate().map(|(index, item)| format!(
+      "{} {}: {}", if index == self.settings_index { ">" } else { " " }, self.strings.get(item.key()), item.value(&self.settings, &self.strings))).collect();
+    return self.strings.fill("settings.title", &[("lines", lines.join("\n"))]);
+  }
+
+  fn statistics_text(&self) -> String {
+    if self.stats_directory.is_none() {
+      return self.strings.get("statistics.not_recorded").to_string();
+    }
+    let lifetime = &self.lifetime;
+    return self.strings.fill("statistics.title", &[
+      ("games", lifetime.games_played.to_string()),
+      ("lines", lifetime.lines.to_string()),
+      ("tetrises", lifetime.tetrises.to_string()),
+      ("level", lifetime.highest_level.to_string()),
+      ("session", format_time(Duration::from_secs_f64(lifetime.longest_session_seconds))),
+      ("time", format_time(Duration::from_secs_f64(lifetime.play_seconds))),
+    ]);
+  }
+
+  // The table of `mode` with the rules of the game, its new entry marked.
+  fn high_score_lines(&self, mode: Mode, new: Option<usize>) -> (String, String) {
+    let strings = &self.strings;
+    let game = &self.players[0].game;
+    let table = table_name(mode, game);
+    let name = table.replacen(mode.name(), strings.get(&mode_key(mode)), 1);
+    let entries = self.high_scores.table(&table);
+    if entries.is_empty() {
+      return (name, strings.get("high_scores.none").to_string());
+    }
+    let lines: Vec<String> = entries.iter().enumerate().map(|(index, entry)| {
+      let rank = (index + 1).to_string();
+      let line = match mode {
+        Mode::Sprint => strings.fill("high_scores.time_entry", &[("rank", rank), ("time", format_split(entry.seconds))]),
+        _ => strings.fill("high_scores.score_entry", &[("rank", rank), ("score", strings.number(entry.score)),
+          ("level", entry.level.to_string()), ("lines", entry.lines.to_string())]),
+      };
+      if new == Some(index) { strings.fill("high_scores.new_entry", &[("entry", line)]) } else { line }
+    }).collect();
+    return (name, lines.join("\n"));
+  }
+
+  fn high_scores_text(&self) -> String {
+    if self.stats_directory.is_none() {
+      return self.strings.get("high_scores.not_recorded").to_string();
+    }
+    let (table, lines) = self.high_score_lines(self.high_score_mode, None);
+    return self.strings.fill("high_scores.title", &[("table", table), ("lines", lines)]);
+  }
+
+  // Under Game Over, the table of the game just played.
+  pub(super) fn results_table(&self) -> Option<String> {
+    if self.phase != GamePhase::GameOver || self.stats_directory.is_none() || !self.ranks_high_scores() {
+      return None;
+    }
+    let (table, lines) = self.high_score_lines(self.players[0].game.mode(), self.high_score_rank);
+    return Some(self.strings.fill("high_scores.results", &[("table", table), ("lines", lines)]));
+  }
+
+  // Solo games of the keyboard at the normal speed, without the modes that
+  // change the goal and on the board they started with.
+  pub(super) fn ranks_high_scores(&self) -> bool {
+    let player = &self.players[0];
+    return !self.versus() && !self.in_demo() && self.watching.is_none() && player.bot.is_none() && !self.board_edited && player.game.speed() == 1.0
+      && self.mission().is_none() && self.puzzle().is_none() && self.opener().is_none() && self.daily().is_none();
+  }
+
+  fn achievements_text(&self) -> String {
+    let lines: Vec<String> = Achievement::ALL.iter().map(|achievement| format!(
+      "[{}] {}: {}", if self.lifetime.achievements.contains(achievement.id()) { "x" } else { " " },
+      self.strings.get(&format!("achievement.{}.name", achievement.id())), self.strings.get(&format!("achievement.{}.description", achievement.id())))).collect();
+    return self.strings.fill("achievements.title", &[("lines", lines.join("\n"))]);
+  }
+
+  // Local games only, the practice speed is not part of the network protocol
+  // nor of the rules of the daily challenge.
+  pub(super) fn change_speed(&mut self, ctx: &mut Context, step: f64) {
+    if self.network.is_some() || self.daily || self.in_demo() || self.watching.is_some() {
+      return;
+    }
+    // The game would not play the same again.
+    self.recording = None;
+    let speed = (self.settings.game_speed + step).clamp(MIN_SPEED, MAX_SPEED);
+    self.settings.game_speed = speed;
+    for index in 0..self.players.len() {
+      self.players[index].game.set_speed(speed);
+      self.create_score_text(index);
+    }
+    self.show_notice(&self.strings.fill("notice.speed", &[("speed", speed.to_string())]));
+    self.save_settings(ctx);
+  }
+
+  // Games in progress stay paused under the menu, but the music plays so its
+  // volume can be heard.
+  pub(super) fn open_settings(&mut self) -> GameResult {
+    self.phase_before_settings = self.phase;
+    let timer = self.phase_timer;
+    self.set_phase(GamePhase::Settings)?;
+    self.phase_timer = timer;
+    if self.phase_before_settings == GamePhase::Paused {
+      self.audio.resume_theme();
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn close_settings(&mut self) -> GameResult {
+    let phase = self.phase_before_settings;
+    let timer = self.phase_timer;
+    self.set_phase(phase)?;
+    self.phase_timer = timer;
+    if phase == GamePhase::Paused {
+      self.audio.pause_theme();
+    }
+
+    Ok(())
+  }
+
+  // Shared by the settings menu and the shortcut keys.
+  pub(super) fn change_setting(&mut self, ctx: &mut Context, item: SettingItem, direction: i32) {
+    item.adjust(&mut self.settings, direction);
+    match item {
+      SettingItem::ThemeOn => {
+        if self.settings.theme_on {
+          self.audio.play_theme(self.sound_theme_pitch(), Duration::from_secs(0));
+        } else {
+          self.audio.stop_theme();
+        }
+      },
+      SettingItem::ThemeVolume => self.audio.set_theme_volume(self.settings.theme_volume),
+      // A sample at the new volume.
+      SettingItem::EffectsVolume => self.audio.play_effect(ctx, "/line.wav", self.settings.effects_volume),
+      // A sample, as for the volume.
+      SettingItem::Rumble if self.settings.rumble => self.rumble.pulse(Pulse::Lines(1)),
+      SettingItem::HighContrast => {
+        for index in 0..self.players.len() {
+          self.create_score_text(index);
+        }
+      },
+      SettingItem::ReducedMotion => {
+        for player in self.players.iter_mut() {
+          player.shake_timer = Duration::from_secs(0);
+        }
+      },
+      SettingItem::Keymap => {
+        self.keymap = Keymap::from_name(&self.settings.keymap).unwrap_or(Keymap::Arrows);
+        self.bindings = Bindings::of(self.keymap);
+        // Keys of the old keymap pressed since the last tick are dropped.
+        for player in self.players.iter_mut() {
+          player.pending_inputs.clear();
+        }
+      },
+      // Saved either way, but a network match and the daily challenge keep their speed.
+      SettingItem::GameSpeed if self.network.is_none() && !self.daily => {
+        for index in 0..self.players.len() {
+          self.players[index].game.set_speed(self.settings.game_speed);
+          self.create_score_text(index);
+        }
+      },
+      // From the next game.
+      SettingItem::HandicapRows if !self.daily => self.players[0].game.set_handicap_rows(self.settings.handicap_rows),
+      // At once. The replay of a game begun would not play the same.
+      SettingItem::MisdropProtection => {
+        for player in self.players.iter_mut().filter(|player| player.controls != Controls::Remote && player.bot.is_none()) {
+          player.game.set_misdrop_protection(self.settings.misdrop_protection);
+        }
+        self.recording = match self.recording.take() {
+          Some(replay) if replay.ticks == 0 => Some(Replay::start(&self.players[0].game)),
+          _ => None,
+        };
+      },
+      SettingItem::SpawnInputs => {
+        for player in self.players.iter_mut().filter(|player| player.controls != Controls::Remote && player.bot.is_none()) {
+          player.game.set_buffer_spawn_inputs(self.settings.buffer_spawn_inputs);
+        }
+        self.recording = match self.recording.take() {
+          Some(replay) if replay.ticks == 0 => Some(Replay::start(&self.players[0].game)),
+          _ => None,
+        };
+      },
+      SettingItem::Zoom => {
+        self.update_layout();
+        for index in 0..self.players.len() {
+          self.create_score_text(index);
+        }
+      },
+      SettingItem::Skin => {
+        self.settings.skin = next_name(&skin_names(ctx), &self.settings.skin, direction);
+        self.apply_skin(ctx);
+      },
+      SettingItem::Language => {
+        self.settings.language = next_name(&language_names(ctx), &self.settings.language, direction);
+        self.apply_language(ctx);
+      },
+      SettingItem::DiscordPresence => self.apply_discord(),
+      // See key_settings.
+      SettingItem::TouchControls => self.touch_held = None,
+      SettingItem::Ghost | SettingItem::ClearPreview | SettingItem::Rumble | SettingItem::GameSpeed | SettingItem::HandicapRows | SettingItem::FocusPause | SettingItem::TitleStatus | SettingItem::Controls => (),
+    }
+    self.save_settings(ctx);
+  }
+
+  pub(super) fn open_controls(&mut self) -> GameResult {
+    self.controls_index = 0;
+    self.key_capture = None;
+    let timer = self.phase_timer;
+    self.set_phase(GamePhase::Controls)?;
+    self.phase_timer = timer;
+
+    Ok(())
+  }
+}
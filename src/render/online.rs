@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use ggez::GameResult;
+
+use log::{debug, warn};
+
+use crate::broadcast::{GameState, STATE_INTERVAL};
+#[cfg(feature = "discord")]
+use crate::discord::{Activity, DiscordPresence};
+use crate::network::{ConnectionState, Message, PROTOCOL_VERSION};
+use crate::phase::GamePhase;
+use crate::randomizer::create_randomizer;
+
+use super::{MainState, THEME_FADE_SECONDS};
+#[cfg(feature = "discord")]
+use super::mode_key;
+
+// How often the board goes to the network opponent.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(100);
+
+impl MainState {
+  // Starts or stops the presence with the setting. Discord not running is
+  // only a warning in the log, see discord.rs.
+  pub(super) fn apply_discord(&mut self) {
+    #[cfg(feature = "discord")]
+    {
+      if !self.settings.discord_presence {
+        self.discord = None;
+      } else if self.discord.is_none() {
+        match &self.config.discord_client_id {
+          Some(id) => self.discord = Some(DiscordPresence::start(id)),
+          None => {
+            warn!("No discord_client_id in config.toml, no Discord presence");
+            self.show_text("notice.discord_no_client");
+          },
+        }
+      }
+    }
+    #[cfg(not(feature = "discord"))]
+    if self.settings.discord_presence {
+      warn!("Built without the discord feature, no Discord presence");
+      self.show_text("notice.discord_not_built");
+    }
+  }
+
+  // "Marathon" and "Level 7, 64 lines" for the first board, sent when it
+  // changes; the client is only written to every few seconds.
+  #[cfg(feature = "discord")]
+  pub(super) fn update_discord(&mut self) {
+    if self.discord.is_none() {
+      return;
+    }
+    let game = &self.players[0].game;
+    let score = game.current_score();
+    let strings = &self.strings;
+    let activity = if self.game_phase() == GamePhase::Menu || self.in_demo() {
+      Activity { details: strings.get("discord.menu").to_string(), state: String::new() }
+    } else {
+      let mode = strings.get(&mode_key(game.mode())).to_string();
+      let details = if let Some(puzzle) = self.puzzle() {
+        strings.fill("discord.puzzle", &[("name", puzzle.name.clone())])
+      } else if let Some(opener) = self.opener() {
+        strings.fill("discord.opener", &[("name", opener.name.clone())])
+      } else if let Some(mission) = self.mission() {
+        strings.fill("discord.mission", &[("name", mission.name.clone())])
+      } else if self.daily().is_some() {
+        strings.get("discord.daily").to_string()
+      } else if self.versus() {
+        strings.fill("discord.versus", &[("mode", mode)])
+      } else {
+        mode
+      };
+      let state = if self.phase == GamePhase::GameOver {
+        strings.fill("discord.game_over", &[("score", strings.number(score.score))])
+      } else {
+        strings.fill("discord.playing", &[("level", score.level.to_string()), ("lines", score.line_removed.to_string())])
+      };
+      Activity { details: details, state: state }
+    };
+    if let Some(discord) = self.discord.as_mut() {
+      discord.update(activity);
+    }
+  }
+
+  pub(super) fn end_match(&mut self) -> GameResult {
+    // Cut short when the network opponent left.
+    self.record_games(self.network.is_some() && self.winner.is_none());
+    let passed = self.mission().filter(|_| self.players[0].game.is_won()).map(|mission| mission.name.clone());
+    if let Some(name) = passed {
+      self.completed_missions.insert(name);
+    }
+    self.submit_score();
+    self.set_phase(GamePhase::GameOver)?;
+    self.audio.fade_theme_out(THEME_FADE_SECONDS);
+
+    Ok(())
+  }
+
+  // A match is played between the two Hello and the GameOver of either side,
+  // or a disconnection.
+  fn in_match(&self) -> bool {
+    let phase = self.game_phase();
+    return phase != GamePhase::Menu && phase != GamePhase::GameOver;
+  }
+
+  pub(super) fn update_network(&mut self, delta: Duration) -> GameResult {
+    let network = match self.network.as_mut() {
+      Some(network) => network,
+      None => return Ok(()),
+    };
+    let messages = network.poll();
+    let state = network.state();
+    if state != self.network_state {
+      debug!("Network: {:?}", state);
+      self.network_state = state;
+      self.hello_sent = false;
+      self.peer_ready = false;
+      if self.in_match() {
+        self.winner = None;
+        self.end_match()?;
+      } else if self.phase == GamePhase::Menu {
+        self.set_phase(GamePhase::Menu)?;
+      }
+    }
+
+    for message in messages {
+      self.handle_message(message)?;
+    }
+
+    let network = self.network.as_mut().unwrap();
+    if self.phase == GamePhase::Menu && state == ConnectionState::Connected && !self.hello_sent {
+      if network.is_host() {
+        self.match_seed = self.seed.unwrap_or_else(rand::random);
+      }
+      network.send(&Message::Hello { version: PROTOCOL_VERSION, seed: self.match_seed });
+      self.hello_sent = true;
+    }
+    if self.phase == GamePhase::Menu && self.hello_sent && self.peer_ready {
+      self.start_network_match()?;
+    }
+
+    if self.phase == GamePhase::Playing || self.phase == GamePhase::LineClear {
+      self.snapshot_timer += delta;
+      if self.snapshot_timer >= SNAPSHOT_INTERVAL {
+        self.snapshot_timer = Duration::from_secs(0);
+        let snapshot = self.players[0].game.snapshot();
+        self.network.as_mut().unwrap().send(&Message::BoardSnapshot(Box::new(snapshot)));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn handle_message(&mut self, message: Message) -> GameResult {
+    match message {
+      Message::Hello { version, seed } => {
+        if version != PROTOCOL_VERSION {
+          warn!("Opponent uses protocol {}, expected {}", version, PROTOCOL_VERSION);
+          return Ok(());
+        }
+        if !self.network.as_ref().unwrap().is_host() {
+          self.match_seed = seed;
+        }
+        self.peer_ready = true;
+      },
+      Message::BoardSnapshot(snapshot) => {
+        if self.in_match() {
+          self.players[1].game.restore(&snapshot);
+          self.create_score_text(1);
+          self.update_danger();
+        }
+      },
+      Message::Garbage(rows) => {
+        if self.in_match() {
+          self.players[0].game.add_garbage(rows);
+        }
+      },
+      Message::GameOver { won } => {
+        if self.in_match() {
+          self.winner = Some(if won { 1 } else { 0 });
+          self.end_match()?;
+        }
+      },
+    }
+
+    Ok(())
+  }
+
+  // Both sides said Hello: same seed, empty boards, and the countdown.
+  fn start_network_match(&mut self) -> GameResult {
+    self.hello_sent = false;
+    self.peer_ready = false;
+    self.snapshot_timer = Duration::from_secs(0);
+    let randomizer = create_randomizer(self.config.randomizer, self.config.pieces, Some(self.match_seed));
+    self.players[0].game.set_randomizer(randomizer);
+    self.players[1].game.reset();
+    for index in 0..self.players.len() {
+      self.create_score_text(index);
+    }
+    self.set_phase(GamePhase::Countdown)?;
+    self.audio.stop_theme();
+    self.audio.fade_theme_in(self.settings.theme_volume, THEME_FADE_SECONDS);
+    if self.settings.theme_on {
+      self.audio.play_theme(self.sound_theme_pitch(), Duration::from_secs(0));
+    }
+
+    Ok(())
+  }
+
+  pub(super) fn update_state_server(&mut self, delta: Duration) {
+    let state_server = match self.state_server.as_mut() {
+      Some(state_server) => state_server,
+      None => return,
+    };
+    self.state_timer += delta;
+    if self.state_timer >= STATE_INTERVAL {
+      self.state_timer = Duration::from_secs(0);
+      state_server.publish(&GameState::from_game(&self.players[0].game));
+    }
+  }
+}
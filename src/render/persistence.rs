@@ -0,0 +1,225 @@
+use std::io::Read;
+
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::{Context, GameError, GameResult};
+
+use log::{info, warn};
+
+use crate::clip::{ClipColors, CLIP_COLORS, CLIP_LENGTH};
+use crate::config::GameConfig;
+use crate::game::Mode;
+use crate::highscores::{table_name, HighScore};
+use crate::leaderboard::ScoreSubmission;
+use crate::phase::GamePhase;
+use crate::piece::{Case, Tetromino};
+use crate::replay::REPLAYS_DIRECTORY;
+use crate::screenshot::{now_seconds, timestamped_name};
+use crate::skin::SKIN_TILES;
+use crate::stats::{append_summary, GameSummary};
+
+use super::{Controls, MainState, BACKGROUND_COLOR};
+
+const CONFIG_FILE: &str = "/config.toml";
+
+// config.toml of the resources: built-in defaults when the file is absent,
+// an error when it is invalid.
+pub(super) fn load_config(ctx: &mut Context) -> GameResult<GameConfig> {
+  if !filesystem::exists(ctx, CONFIG_FILE) {
+    return Ok(GameConfig::default());
+  }
+
+  let mut text = String::new();
+  let mut file = filesystem::open(ctx, CONFIG_FILE)?;
+  file.read_to_string(&mut text)?;
+  return GameConfig::from_toml(&text)
+    .map_err(|e| GameError::ConfigError(format!("{}: {}", CONFIG_FILE, e)));
+}
+
+impl MainState {
+  // Written before the process goes, whatever way it quits.
+  pub(super) fn before_quit(&mut self, ctx: &mut Context) {
+    self.abandon_games();
+    self.remember_window(ctx);
+    self.save_settings(ctx);
+    // Clears the presence before the process goes.
+    #[cfg(feature = "discord")]
+    if let Some(discord) = self.discord.as_mut() {
+      discord.stop();
+    }
+  }
+
+  pub(super) fn save_settings(&mut self, ctx: &mut Context) {
+    if let Err(e) = self.settings.save(filesystem::user_config_dir(ctx)) {
+      warn!("Cannot save settings ({})", e);
+    }
+  }
+
+  // Only the window of a single board is kept, versus ones are wider.
+  pub(super) fn remember_window(&mut self, ctx: &Context) {
+    if self.versus() {
+      return;
+    }
+    self.settings.fullscreen = self.fullscreen;
+    if self.fullscreen {
+      return;
+    }
+    let (width, height) = graphics::drawable_size(ctx);
+    if width > 0.0 && height > 0.0 {
+      self.settings.window_size = Some((width, height));
+    }
+    if let Some(position) = graphics::window(ctx).get_position() {
+      self.settings.window_position = Some((position.x, position.y));
+    }
+  }
+
+  // One summary per local board, and the lifetime statistics of the first one
+  // when played from the keyboard. The game goes on when a file cannot be written.
+  pub(super) fn record_games(&mut self, abandoned: bool) {
+    self.high_score_rank = None;
+    // Watched, the game was already recorded when played.
+    if self.watching.is_some() {
+      return;
+    }
+    // Given up or not, the first game of the day is its official attempt, for
+    // the session at least with --no-stats.
+    if let Some(date) = self.daily() {
+      let summary = GameSummary::from_game(&self.players[0].game, self.seed, abandoned);
+      self.daily_records.record(date, &summary);
+      if let Some(directory) = &self.stats_directory {
+        if let Err(e) = self.daily_records.save(directory) {
+          warn!("Cannot write the daily challenges ({})", e);
+        }
+      }
+    }
+    let directory = match &self.stats_directory {
+      Some(directory) if !self.in_demo() => directory,
+      _ => return,
+    };
+    let seed = if self.network.is_some() { Some(self.match_seed) } else { self.seed };
+    for player in self.players.iter().filter(|player| player.controls != Controls::Remote) {
+      let mut summary = GameSummary::from_game(&player.game, seed, abandoned);
+      summary.mission = self.mission().map(|mission| mission.name.clone());
+      summary.mission_passed = summary.mission.is_some() && player.game.is_won();
+      summary.daily = self.daily().map(|date| date.to_string());
+      summary.practice = summary.daily.is_some() && self.daily_practice;
+      if let Err(e) = append_summary(directory, &summary) {
+        warn!("Cannot write the game statistics ({})", e);
+      }
+    }
+    // Only whole games, on the board they started with.
+    let whole = !abandoned && !self.board_edited;
+    if let Some(replay) = self.recording.as_mut().filter(|_| whole) {
+      replay.finish(&self.players[0].game);
+      match replay.save(&directory.join(REPLAYS_DIRECTORY), &timestamped_name(now_seconds(), "json")) {
+        Ok(name) => info!("Replay saved to {}", name),
+        Err(e) => warn!("Cannot write the replay ({})", e),
+      }
+    }
+    self.recording = None;
+    let splits: Vec<f64> = if self.tracks_splits() { self.splits.iter().map(|split| split.as_secs_f64()).collect() } else { Vec::new() };
+    let player = &self.players[0];
+    if player.bot.is_none() {
+      self.session_time += player.game.time_played();
+      self.lifetime.record(&GameSummary::from_game(&player.game, seed, abandoned), self.session_time.as_secs_f64());
+      self.lifetime.record_splits(player.game.mode(), &splits);
+      if let Err(e) = self.lifetime.save(directory) {
+        warn!("Cannot write the lifetime statistics ({})", e);
+      }
+      // A zen game only ends when given up; a sprint counts once its lines
+      // are done.
+      let mode = player.game.mode();
+      let ended = match mode {
+        Mode::Zen => player.game.current_score().score > 0,
+        Mode::Sprint => !abandoned && player.game.is_won(),
+        _ => !abandoned,
+      };
+      if ended && self.ranks_high_scores() {
+        let entry = HighScore::from_summary(&GameSummary::from_game(&player.game, seed, abandoned));
+        self.high_score_rank = self.high_scores.record(&table_name(mode, &player.game), mode, entry);
+        if self.high_score_rank.is_some() {
+          if let Err(e) = self.high_scores.save(directory) {
+            warn!("Cannot write the high scores ({})", e);
+          }
+        }
+      }
+    }
+  }
+
+  // Only solo games played from the keyboard make it to the leaderboard,
+  // missions, puzzles, openers, edited boards and daily practice aside.
+  pub(super) fn submit_score(&mut self) {
+    let practice = self.daily().is_some() && self.daily_practice;
+    let leaderboard = match &self.leaderboard {
+      Some(leaderboard) if !self.versus() && !self.in_demo() && self.missions.is_empty() && self.puzzle.is_none() && self.opener.is_none() && self.watching.is_none() && !practice && self.players[0].bot.is_none() && !self.board_edited && self.players[0].game.speed() == 1.0 => leaderboard,
+      _ => return,
+    };
+    let mut submission = ScoreSubmission::from_game(&self.players[0].game, &self.config.player_name, self.seed);
+    submission.daily = self.daily().map(|date| date.to_string());
+    leaderboard.submit(&submission);
+    self.submission = Some(self.strings.get("banner.submitting").to_string());
+  }
+
+  pub(super) fn update_leaderboard(&mut self) {
+    let result = match self.leaderboard.as_ref().and_then(|leaderboard| leaderboard.poll()) {
+      Some(result) => result,
+      None => return,
+    };
+    self.submission = Some(self.strings.get(if result.is_ok() { "banner.submitted" } else { "banner.not_submitted" }).to_string());
+    if self.phase == GamePhase::GameOver {
+      self.banner = self.create_phase_banner();
+    }
+  }
+
+  // Only the copy of the frame waits for the GPU, the PNG is written by the
+  // thread of Screenshots.
+  pub(super) fn take_screenshot(&mut self, ctx: &mut Context) {
+    let frame = graphics::screenshot(ctx).and_then(|image| Ok((image.width(), image.height(), image.to_rgba8(ctx)?)));
+    match frame {
+      Ok((width, height, rgba)) => self.screenshots.save(width as u32, height as u32, rgba),
+      Err(e) => {
+        warn!("Cannot take a screenshot ({})", e);
+        self.show_notice(&self.strings.fill("notice.screenshot_failed", &[("error", e.to_string())]));
+      },
+    }
+  }
+
+  // The colors of the board as drawn, without the skin images.
+  fn clip_colors(&self) -> ClipColors {
+    let rgb = |color: graphics::Color| {
+      let (r, g, b) = color.to_rgb();
+      [r, g, b]
+    };
+    let mut colors: ClipColors = [[0; 3]; CLIP_COLORS];
+    colors[0] = rgb(self.skin.grid_color.filter(|_| !self.settings.high_contrast).unwrap_or(graphics::WHITE));
+    colors[1] = rgb(self.skin.background_color.unwrap_or(BACKGROUND_COLOR));
+    for (index, &tetromino) in Tetromino::ALL.iter().enumerate() {
+      colors[index + 2] = rgb(self.case_color(Case::Filled(tetromino)));
+    }
+    colors[SKIN_TILES + 1] = rgb(self.case_color(Case::Garbage));
+    return colors;
+  }
+
+  pub(super) fn save_clip(&mut self) {
+    if self.clip.is_empty() {
+      self.show_text("notice.clip_nothing");
+    } else if self.clip.save(self.clip_colors()) {
+      self.show_notice(&self.strings.fill("notice.clip_saving", &[("seconds", CLIP_LENGTH.as_secs().to_string())]));
+    } else {
+      self.show_text("notice.clip_busy");
+    }
+  }
+
+  pub(super) fn update_screenshots(&mut self) {
+    match self.screenshots.poll() {
+      Some(Ok(name)) => self.show_notice(&self.strings.fill("notice.saved", &[("name", name)])),
+      Some(Err(e)) => self.show_notice(&self.strings.fill("notice.screenshot_not_saved", &[("error", e)])),
+      None => (),
+    }
+    match self.clip.poll() {
+      Some(Ok(name)) => self.show_notice(&self.strings.fill("notice.saved", &[("name", name)])),
+      Some(Err(e)) => self.show_notice(&self.strings.fill("notice.clip_not_saved", &[("error", e)])),
+      None => (),
+    }
+  }
+}
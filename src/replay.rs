@@ -0,0 +1,225 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::{Game, Input, Mode};
+use crate::grid::Grid;
+use crate::piece::Tetromino;
+use crate::randomizer::SequenceRandomizer;
+use crate::screenshot::create_unique;
+use crate::timestep::LOGIC_TICK;
+
+// Under the user data directory.
+pub const REPLAYS_DIRECTORY: &str = "replays";
+
+// An input applied at the start of the logic tick `tick`, before its step.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub struct ReplayInput {
+  pub tick: u32,
+  pub input: Input,
+}
+
+// A game played alone: the rules, the board it started on, the pieces it
+// was dealt and the inputs of each logic tick. A game started the same way
+// and given the same inputs at the same ticks plays the same, so a replay is
+// watched by playing it again.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Replay {
+  pub config: GameConfig,
+  pub mode: Mode,
+  pub level: u32,
+  pub speed: f64,
+  pub mirror: bool,
+  // Absent from the replays of before the protection.
+  #[serde(default)]
+  pub misdrop_protection: bool,
+  #[serde(default)]
+  pub buffer_spawn_inputs: bool,
+  pub grid: Grid,
+  pub pieces: Vec<Tetromino>,
+  pub inputs: Vec<ReplayInput>,
+  // Logic ticks of the whole game.
+  pub ticks: u32,
+}
+
+impl Replay {
+  // Right after the reset of `game`, before its first step. The leaderboard
+  // and Discord settings are left out, replays being shared.
+  pub fn start(game: &Game) -> Replay {
+    let config = GameConfig { leaderboard_url: None, leaderboard_token: None, discord_client_id: None, ..game.config().clone() };
+    return Replay {
+      config: config,
+      mode: game.mode(),
+      level: game.start_level(),
+      speed: game.speed(),
+      mirror: game.mirror(),
+      misdrop_protection: game.misdrop_protection(),
+      buffer_spawn_inputs: game.buffer_spawn_inputs(),
+      grid: game.grid().clone(),
+      pieces: Vec::new(),
+      inputs: Vec::new(),
+      ticks: 0,
+    };
+  }
+
+  pub fn record(&mut self, input: Input) {
+    self.inputs.push(ReplayInput { tick: self.ticks, input: input });
+  }
+
+  // After each step of the game.
+  pub fn step(&mut self) {
+    self.ticks += 1;
+  }
+
+  // Once the game is over, with the pieces it got.
+  pub fn finish(&mut self, game: &Game) {
+    self.pieces = game.dealt().to_vec();
+  }
+
+  // The rules of the replay, the looks and accounts of `config`, as
+  // daily_config does.
+  pub fn watching_config(&self, config: &GameConfig) -> GameConfig {
+    return GameConfig {
+      case_size: config.case_size,
+      leaderboard_url: config.leaderboard_url.clone(),
+      leaderboard_token: config.leaderboard_token.clone(),
+      player_name: config.player_name.clone(),
+      discord_client_id: config.discord_client_id.clone(),
+      font: config.font.clone(),
+      font_size: config.font_size,
+      colors: config.colors.clone(),
+      ..self.config.clone()
+    };
+  }
+
+  // The game at its first tick.
+  pub fn new_game(&self) -> Game {
+    let mut game = Game::with_config(self.config.clone(), Some(0));
+    game.set_mode(self.mode);
+    game.set_start_level(self.level);
+    game.set_speed(self.speed);
+    game.set_mirror(self.mirror);
+    game.set_misdrop_protection(self.misdrop_protection);
+    game.set_buffer_spawn_inputs(self.buffer_spawn_inputs);
+    if !self.pieces.is_empty() {
+      game.set_randomizer(Box::new(SequenceRandomizer::new(&self.pieces)));
+    }
+    game.set_grid(self.grid.clone());
+    return game;
+  }
+
+  pub fn from_json(text: &str) -> Result<Replay, String> {
+    let replay: Replay = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if replay.pieces.is_empty() {
+      return Err(String::from("no piece"));
+    }
+    if replay.inputs.windows(2).any(|pair| pair[0].tick > pair[1].tick) || replay.inputs.last().is_some_and(|last| last.tick >= replay.ticks) {
+      return Err(String::from("inputs out of order"));
+    }
+    replay.config.validate()?;
+    return Ok(replay);
+  }
+
+  // Written to `directory` under a new name, returned.
+  pub fn save(&self, directory: &Path, name: &str) -> Result<String, String> {
+    let text = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+    let (name, mut file) = create_unique(directory, name)?;
+    file.write_all(&text).map_err(|e| e.to_string())?;
+    return Ok(name);
+  }
+}
+
+pub fn load_replay(path: &Path) -> io::Result<Replay> {
+  let text = fs::read_to_string(path)?;
+  return Replay::from_json(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+}
+
+// Where a replay being watched is. Going back plays the game again from its
+// start, fast enough without snapshots: the logic alone runs a whole game in
+// a few milliseconds, see benches/logic.rs.
+#[derive(Clone,Debug,PartialEq)]
+pub struct ReplayPosition {
+  pub replay: Replay,
+  pub paused: bool,
+  // Ticks played, and the first input not applied yet.
+  tick: u32,
+  next_input: usize,
+  // The tick after each lock.
+  locks: Vec<u32>,
+}
+
+impl ReplayPosition {
+  pub fn new(replay: Replay) -> ReplayPosition {
+    let mut position = ReplayPosition { replay: replay, paused: false, tick: 0, next_input: 0, locks: Vec::new() };
+    let mut game = position.replay.new_game();
+    while position.advance(&mut game) {
+      game.drain_events();
+      if game.pieces_locked() as usize > position.locks.len() {
+        position.locks.push(position.tick);
+      }
+    }
+    position.tick = 0;
+    position.next_input = 0;
+    return position;
+  }
+
+  pub fn tick(&self) -> u32 {
+    return self.tick;
+  }
+
+  // Between 0 and 1.
+  pub fn progress(&self) -> f32 {
+    return if self.replay.ticks == 0 { 1.0 } else { self.tick as f32 / self.replay.ticks as f32 };
+  }
+
+  pub fn is_done(&self) -> bool {
+    return self.tick >= self.replay.ticks;
+  }
+
+  // Plays one tick of `game`, false at the end of the replay.
+  pub fn advance(&mut self, game: &mut Game) -> bool {
+    if self.is_done() {
+      return false;
+    }
+    let inputs = &self.replay.inputs[self.next_input..];
+    let count = inputs.iter().take_while(|input| input.tick == self.tick).count();
+    for input in &inputs[..count] {
+      game.apply(input.input);
+    }
+    self.next_input += count;
+    game.step(LOGIC_TICK);
+    self.tick += 1;
+    return true;
+  }
+
+  // The game at `tick`, played again from the start.
+  pub fn seek(&mut self, tick: u32) -> Game {
+    let mut game = self.replay.new_game();
+    self.tick = 0;
+    self.next_input = 0;
+    while self.tick < tick.min(self.replay.ticks) {
+      self.advance(&mut game);
+    }
+    game.drain_events();
+    return game;
+  }
+
+  // Right after the next lock, or the end.
+  pub fn next_lock(&self) -> u32 {
+    return self.locks.iter().copied().find(|&lock| lock > self.tick).unwrap_or(self.replay.ticks);
+  }
+
+  // Right after the lock before this point, so the piece locked last is in
+  // play again, or the start.
+  pub fn previous_lock(&self) -> u32 {
+    return self.locks.iter().copied().rev().find(|&lock| lock < self.tick).unwrap_or(0);
+  }
+
+  // `tenths` of the replay, 0 for its start.
+  pub fn fraction(&self, tenths: u32) -> u32 {
+    return (self.replay.ticks as u64 * tenths.min(10) as u64 / 10) as u32;
+  }
+}
@@ -0,0 +1,94 @@
+use ggez::input::gamepad::gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use ggez::input::gamepad::gilrs::{self, GamepadId, Gilrs};
+
+use log::{info, warn};
+
+// How hard the controllers shake, for an event of the game.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Pulse {
+  // A piece locked.
+  Light,
+  // 1 to 3 lines cleared.
+  Lines(u32),
+  // A tetris or a game over.
+  Strong,
+}
+
+impl Pulse {
+  // Magnitude out of u16::MAX, and milliseconds.
+  pub fn strength(&self) -> (u16, u32) {
+    return match self {
+      Pulse::Light => (12_000, 60),
+      Pulse::Lines(lines) => (20_000 + 10_000 * (*lines).min(3) as u16, 100 + 30 * (*lines).min(3)),
+      Pulse::Strong => (u16::MAX, 300),
+    };
+  }
+}
+
+// Force feedback on every connected controller that has it. ggez keeps its
+// own gilrs context to itself, so this is a second one. Without gamepad
+// support on the platform it does nothing, and the first effect that fails
+// is logged once before it turns itself off for the session.
+pub struct Rumble {
+  gilrs: Option<Gilrs>,
+  // An effect stops once dropped, this one lasts until the next pulse.
+  effect: Option<Effect>,
+}
+
+impl Rumble {
+  pub fn new() -> Rumble {
+    let gilrs = match Gilrs::new() {
+      Ok(gilrs) => Some(gilrs),
+      Err(gilrs::Error::NotImplemented(_)) => None,
+      Err(e) => {
+        info!("No gamepad rumble ({})", e);
+        None
+      },
+    };
+    return Rumble { gilrs: gilrs, effect: None };
+  }
+
+  // Keeps the list of connected controllers up to date, every frame.
+  pub fn update(&mut self) {
+    if let Some(gilrs) = self.gilrs.as_mut() {
+      while gilrs.next_event().is_some() {}
+    }
+  }
+
+  pub fn pulse(&mut self, pulse: Pulse) {
+    let gilrs = match self.gilrs.as_mut() {
+      Some(gilrs) => gilrs,
+      None => return,
+    };
+    let gamepads: Vec<GamepadId> = gilrs.gamepads().filter(|(_, gamepad)| gamepad.is_ff_supported()).map(|(id, _)| id).collect();
+    if gamepads.is_empty() {
+      return;
+    }
+    let (magnitude, milliseconds) = pulse.strength();
+    let duration = Ticks::from_ms(milliseconds);
+    let effect = EffectBuilder::new()
+      .add_effect(BaseEffect {
+        kind: BaseEffectType::Strong { magnitude: magnitude },
+        scheduling: Replay { play_for: duration, ..Default::default() },
+        envelope: Default::default(),
+      })
+      .repeat(Repeat::For(duration))
+      .gamepads(&gamepads)
+      .finish(gilrs)
+      .and_then(|effect| effect.play().map(|_| effect));
+    match effect {
+      Ok(effect) => self.effect = Some(effect),
+      Err(e) => {
+        warn!("Gamepad rumble disabled ({})", e);
+        self.effect = None;
+        self.gilrs = None;
+      },
+    }
+  }
+}
+
+impl Default for Rumble {
+  fn default() -> Rumble {
+    return Rumble::new();
+  }
+}
@@ -0,0 +1,40 @@
+// The rules of the score and the levels, apart from the game so each can be
+// checked on its own.
+
+// Points of a clear of 0 to 4 lines at level 1, none past the end.
+const CLEAR_POINTS: [i64; 5] = [0, 40, 100, 300, 1200];
+// Of a T-spin of 0 to 3 lines, in the ratio to a tetris of the guideline.
+const T_SPIN_POINTS: [i64; 4] = [600, 1200, 1800, 2400];
+
+// What a clear was, besides its lines.
+#[derive(Clone,Copy,Debug,Default,PartialEq)]
+pub struct ClearFlags {
+  // Step of the cascade the clear is, from 1; 0 counts as 1.
+  pub chain: u32,
+  pub t_spin: bool,
+  // A tetris or a T-spin clear right after another one.
+  pub back_to_back: bool,
+}
+
+// Points of a clear of `lines` at `level`. Each step of a cascade is worth
+// one more time its clear, a back-to-back half as much more.
+pub fn score_for_clear(lines: u32, level: u32, flags: ClearFlags) -> i64 {
+  let table: &[i64] = if flags.t_spin { &T_SPIN_POINTS } else { &CLEAR_POINTS };
+  let points = table.get(lines as usize).copied().unwrap_or(0) * level as i64 * flags.chain.max(1) as i64;
+  if flags.back_to_back {
+    return points * 3 / 2;
+  }
+  return points;
+}
+
+// Fixed goal leveling: a level for every `lines_per_level` lines, counted
+// from the starting level, so one big clear can gain several at once.
+pub fn level_for_lines(start_level: u32, lines: u32, lines_per_level: u32) -> u32 {
+  return start_level.max(1) + lines / lines_per_level.max(1);
+}
+
+// Lines still to clear before the level after `level`.
+pub fn lines_to_next_level(start_level: u32, level: u32, lines: u32, lines_per_level: u32) -> u32 {
+  let goal = (level + 1).saturating_sub(start_level.max(1)) * lines_per_level.max(1);
+  return goal.saturating_sub(lines);
+}
@@ -0,0 +1,104 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+
+use crate::daily::Date;
+
+// Under the user data directory.
+pub const SCREENSHOTS_DIRECTORY: &str = "screenshots";
+
+// tetrisrs_2026-10-14_21-30-05.png for `seconds` since the epoch, in UTC.
+pub fn timestamped_name(seconds: u64, extension: &str) -> String {
+  let date = Date::from_days((seconds / 86_400) as i64);
+  let time = seconds % 86_400;
+  return format!("tetrisrs_{}_{:02}-{:02}-{:02}.{}", date, time / 3600, time / 60 % 60, time % 60, extension);
+}
+
+pub fn now_seconds() -> u64 {
+  return SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+}
+
+// Creates `name` in `directory` and its parents. Several files in the same
+// second get a number after the time rather than overwriting each other, the
+// name returned is the one created.
+pub fn create_unique(directory: &Path, name: &str) -> Result<(String, File), String> {
+  fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+  let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+  let mut attempt = 1;
+  loop {
+    let name = if attempt == 1 { name.to_string() } else { format!("{}_{}.{}", stem, attempt, extension) };
+    match OpenOptions::new().write(true).create_new(true).open(directory.join(&name)) {
+      Ok(file) => return Ok((name, file)),
+      Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+      Err(e) => return Err(e.to_string()),
+    }
+  }
+}
+
+// Rows of the frame top first, as ggez reads them bottom first.
+pub fn flip_rows(rgba: &[u8], width: u32) -> Vec<u8> {
+  let row = width as usize * 4;
+  if row == 0 {
+    return Vec::new();
+  }
+  return rgba.chunks(row).rev().flatten().copied().collect();
+}
+
+// Encodes the frames from a thread each, so a big window never holds the
+// game; the file written or the error is read back with `poll`.
+pub struct Screenshots {
+  directory: PathBuf,
+  sender: Sender<Result<String, String>>,
+  results: Receiver<Result<String, String>>,
+}
+
+impl Screenshots {
+  pub fn new(user_data: &Path) -> Screenshots {
+    let (sender, results) = mpsc::channel();
+    return Screenshots { directory: user_data.join(SCREENSHOTS_DIRECTORY), sender: sender, results: results };
+  }
+
+  // `rgba` as given by Image::to_rgba8, bottom row first.
+  pub fn save(&self, width: u32, height: u32, rgba: Vec<u8>) {
+    let directory = self.directory.clone();
+    let sender = self.sender.clone();
+    thread::spawn(move || {
+      let result = write_png(&directory, &timestamped_name(now_seconds(), "png"), width, height, &flip_rows(&rgba, width));
+      match &result {
+        Ok(name) => info!("Screenshot saved to {}", directory.join(name).display()),
+        Err(e) => warn!("Cannot save the screenshot in {} ({})", directory.display(), e),
+      }
+      // Nobody to tell once the game has quit.
+      let _ = sender.send(result);
+    });
+  }
+
+  // The outcome of a screenshot, when one has arrived since the last call.
+  pub fn poll(&self) -> Option<Result<String, String>> {
+    return self.results.try_recv().ok();
+  }
+}
+
+// Returns the name of the file written.
+fn write_png(directory: &Path, name: &str, width: u32, height: u32, rgba: &[u8]) -> Result<String, String> {
+  let (name, file) = create_unique(directory, name)?;
+  let written = encode(file, width, height, rgba);
+  if written.is_err() {
+    // No half written file left behind.
+    let _ = fs::remove_file(directory.join(&name));
+  }
+  return written.map(|_| name);
+}
+
+fn encode(file: File, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+  let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+  encoder.set_color(png::ColorType::RGBA);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+  return writer.write_image_data(rgba).map_err(|e| e.to_string());
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::Path;
+
+use ggez::filesystem;
+use ggez::Context;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{HANDICAP_FREE_ROWS, MAX_SPEED, MIN_SPEED};
+use crate::grid::DEFAULT_GRID_HEIGHT;
+use crate::keymap::Keymap;
+use crate::locale::{Strings, DEFAULT_LANGUAGE};
+use crate::skin::DEFAULT_SKIN;
+use crate::stats::write_atomically;
+
+// In the user config folder, where ggez writes.
+const SETTINGS_FILE: &str = "settings.toml";
+const SETTINGS_VERSION: u32 = 1;
+
+pub const SPEED_STEP: f64 = 0.25;
+const VOLUME_STEP: f32 = 0.1;
+pub const MIN_ZOOM: f32 = 1.0;
+pub const MAX_ZOOM: f32 = 2.5;
+const ZOOM_STEP: f32 = 0.25;
+// Smaller saved windows are dropped as corrupt.
+const MIN_WINDOW_WIDTH: f32 = 320.0;
+const MIN_WINDOW_HEIGHT: f32 = 240.0;
+
+// Every field falls back to its default when missing, and unknown fields are
+// ignored, so files written by older or newer versions still load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  pub version: u32,
+  pub theme: String,
+  pub theme_on: bool,
+  pub theme_volume: f32,
+  pub effects_volume: f32,
+  pub ghost_on: bool,
+  // Tints the rows the piece would complete where the ghost is.
+  pub clear_preview: bool,
+  pub high_contrast: bool,
+  // No board shake or line clear flash; anything that moves on its own
+  // checks it where it starts.
+  pub reduced_motion: bool,
+  // "arrows", "wasd" or "left-handed", see `Keymap`.
+  pub keymap: String,
+  // Buttons in the lower corners of the window for the pieces, played with
+  // the mouse or a touch screen.
+  pub touch_controls: bool,
+  // Controllers with force feedback shake on locks, clears and game overs.
+  pub rumble: bool,
+  // Practice speed multiplier of local games, changed with + and -.
+  pub game_speed: f64,
+  // Rows of garbage the first local board starts with, see --handicap.
+  pub handicap_rows: usize,
+  // A hard drop right after a spawn is ignored, see GameConfig::misdrop_grace.
+  pub misdrop_protection: bool,
+  // Rotations, holds and moves pressed before a piece spawns apply to it,
+  // instead of being lost.
+  pub buffer_spawn_inputs: bool,
+  // A local game pauses when the window loses the focus, and stays paused
+  // when it comes back.
+  pub focus_pause: bool,
+  // Level and score of the game in progress, after the name of the window.
+  pub title_status: bool,
+  // The mode and level shown on Discord, see the discord feature.
+  pub discord_presence: bool,
+  // The key help of H opens by itself until it was seen once.
+  pub help_seen: bool,
+  // Size of the boards and their panels, as much of it as fits the layout.
+  pub zoom: f32,
+  // Instead of the zoom, the boards take most of the height of the window,
+  // smaller than at zoom 1 when they do not fit. Below 1x in the menu.
+  pub fit_board: bool,
+  // Folder of resources/skins, see `load_skin`.
+  pub skin: String,
+  // File of resources/lang without .toml, see `load_strings`.
+  pub language: String,
+  // Window of the last game alone, see Options::restore_window. No size nor
+  // position until one was saved; the position is not known everywhere.
+  pub fullscreen: bool,
+  pub window_size: Option<(f32, f32)>,
+  pub window_position: Option<(f64, f64)>,
+  // Keys changed on the controls screen, by action, over those of `keymap`;
+  // empty for the keymap as it is. A table, so it stays last for the TOML writer.
+  pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for Settings {
+  fn default() -> Settings {
+    return Settings {
+      version: SETTINGS_VERSION,
+      theme: String::from("/theme.ogg"),
+      theme_on: true,
+      theme_volume: 0.3,
+      effects_volume: 1.0,
+      ghost_on: true,
+      clear_preview: false,
+      high_contrast: false,
+      reduced_motion: false,
+      keymap: String::from("arrows"),
+      touch_controls: false,
+      rumble: true,
+      game_speed: 1.0,
+      handicap_rows: 0,
+      misdrop_protection: true,
+      buffer_spawn_inputs: true,
+      focus_pause: true,
+      title_status: true,
+      discord_presence: false,
+      help_seen: false,
+      zoom: 1.0,
+      fit_board: true,
+      skin: String::from(DEFAULT_SKIN),
+      language: String::from(DEFAULT_LANGUAGE),
+      fullscreen: false,
+      window_size: None,
+      window_position: None,
+      bindings: BTreeMap::new(),
+    };
+  }
+}
+
+impl Settings {
+  pub fn from_toml(text: &str) -> Result<Settings, toml::de::Error> {
+    let settings: Settings = toml::from_str(text)?;
+    return Ok(settings.migrate());
+  }
+
+  pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+    return toml::to_string(self);
+  }
+
+  // Upgrade a file written by an older version; fields added since then
+  // already hold their defaults thanks to `#[serde(default)]`.
+  fn migrate(mut self) -> Settings {
+    self.version = SETTINGS_VERSION;
+    self.theme_volume = self.theme_volume.clamp(0.0, 1.0);
+    self.effects_volume = self.effects_volume.clamp(0.0, 1.0);
+    if !self.game_speed.is_finite() {
+      self.game_speed = 1.0;
+    }
+    self.game_speed = self.game_speed.clamp(MIN_SPEED, MAX_SPEED);
+    if !self.zoom.is_finite() {
+      self.zoom = 1.0;
+    }
+    self.zoom = self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    self.window_size = self.window_size.filter(|&(width, height)| width.is_finite() && height.is_finite() && width >= MIN_WINDOW_WIDTH && height >= MIN_WINDOW_HEIGHT);
+    self.window_position = self.window_position.filter(|&(x, y)| x.is_finite() && y.is_finite());
+    return self;
+  }
+
+  pub fn load(ctx: &mut Context) -> Settings {
+    let path = Path::new("/").join(SETTINGS_FILE);
+    if !filesystem::exists(ctx, &path) {
+      info!("No settings file found, using defaults");
+      return Settings::default();
+    }
+
+    let mut text = String::new();
+    let read = filesystem::open(ctx, &path).and_then(|mut file| {
+      file.read_to_string(&mut text)?;
+      Ok(())
+    });
+    if let Err(e) = read {
+      warn!("Cannot read settings file ({}), using defaults", e);
+      return Settings::default();
+    }
+    return match Settings::from_toml(&text) {
+      Ok(settings) => settings,
+      Err(e) => {
+        warn!("Corrupt settings file ({}), using defaults", e);
+        Settings::default()
+      },
+    };
+  }
+
+  // Nothing is written when the settings do not serialize.
+  pub fn save(&self, directory: &Path) -> io::Result<()> {
+    let text = self.to_toml().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    return write_atomically(&directory.join(SETTINGS_FILE), text.as_bytes());
+  }
+}
+
+// One line of the settings menu, changed with Left and Right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingItem {
+  ThemeOn,
+  ThemeVolume,
+  EffectsVolume,
+  Ghost,
+  ClearPreview,
+  HighContrast,
+  ReducedMotion,
+  Keymap,
+  TouchControls,
+  Rumble,
+  GameSpeed,
+  HandicapRows,
+  MisdropProtection,
+  SpawnInputs,
+  Zoom,
+  Skin,
+  FocusPause,
+  TitleStatus,
+  DiscordPresence,
+  Language,
+  Controls,
+}
+
+impl SettingItem {
+  pub const ALL: [SettingItem; 21] = [
+    SettingItem::ThemeOn,
+    SettingItem::ThemeVolume,
+    SettingItem::EffectsVolume,
+    SettingItem::Ghost,
+    SettingItem::ClearPreview,
+    SettingItem::HighContrast,
+    SettingItem::ReducedMotion,
+    SettingItem::Keymap,
+    SettingItem::TouchControls,
+    SettingItem::Rumble,
+    SettingItem::GameSpeed,
+    SettingItem::HandicapRows,
+    SettingItem::MisdropProtection,
+    SettingItem::SpawnInputs,
+    SettingItem::Zoom,
+    SettingItem::Skin,
+    SettingItem::FocusPause,
+    SettingItem::TitleStatus,
+    SettingItem::DiscordPresence,
+    SettingItem::Language,
+    SettingItem::Controls,
+  ];
+
+  // Of its name in resources/lang.
+  pub fn key(&self) -> &'static str {
+    return match self {
+      SettingItem::ThemeOn => "setting.theme_on",
+      SettingItem::ThemeVolume => "setting.theme_volume",
+      SettingItem::EffectsVolume => "setting.effects_volume",
+      SettingItem::Ghost => "setting.ghost",
+      SettingItem::ClearPreview => "setting.clear_preview",
+      SettingItem::HighContrast => "setting.high_contrast",
+      SettingItem::ReducedMotion => "setting.reduced_motion",
+      SettingItem::Keymap => "setting.keymap",
+      SettingItem::TouchControls => "setting.touch_controls",
+      SettingItem::Rumble => "setting.rumble",
+      SettingItem::GameSpeed => "setting.game_speed",
+      SettingItem::HandicapRows => "setting.handicap_rows",
+      SettingItem::MisdropProtection => "setting.misdrop_protection",
+      SettingItem::SpawnInputs => "setting.spawn_inputs",
+      SettingItem::Zoom => "setting.zoom",
+      SettingItem::Skin => "setting.skin",
+      SettingItem::FocusPause => "setting.focus_pause",
+      SettingItem::TitleStatus => "setting.title_status",
+      SettingItem::DiscordPresence => "setting.discord_presence",
+      SettingItem::Language => "setting.language",
+      SettingItem::Controls => "setting.controls",
+    };
+  }
+
+  pub fn value(&self, settings: &Settings, strings: &Strings) -> String {
+    let on_off = |on: bool| strings.get(if on { "settings.on" } else { "settings.off" }).to_string();
+    return match self {
+      SettingItem::ThemeOn => on_off(settings.theme_on),
+      SettingItem::ThemeVolume => format!("{}%", (settings.theme_volume * 100.0).round()),
+      SettingItem::EffectsVolume => format!("{}%", (settings.effects_volume * 100.0).round()),
+      SettingItem::Ghost => on_off(settings.ghost_on),
+      SettingItem::ClearPreview => on_off(settings.clear_preview),
+      SettingItem::HighContrast => on_off(settings.high_contrast),
+      SettingItem::ReducedMotion => on_off(settings.reduced_motion),
+      SettingItem::Keymap => settings.keymap.clone(),
+      SettingItem::TouchControls => on_off(settings.touch_controls),
+      SettingItem::Rumble => on_off(settings.rumble),
+      SettingItem::GameSpeed => format!("{}x", settings.game_speed),
+      SettingItem::HandicapRows => format!("{}", settings.handicap_rows),
+      SettingItem::MisdropProtection => on_off(settings.misdrop_protection),
+      SettingItem::SpawnInputs => strings.get(if settings.buffer_spawn_inputs { "settings.buffer" } else { "settings.ignore" }).to_string(),
+      SettingItem::Zoom if settings.fit_board => strings.get("settings.fit_window").to_string(),
+      SettingItem::Zoom => format!("{}x", settings.zoom),
+      SettingItem::Skin => settings.skin.clone(),
+      SettingItem::Language => strings.get("language.name").to_string(),
+      SettingItem::FocusPause => on_off(settings.focus_pause),
+      SettingItem::TitleStatus => on_off(settings.title_status),
+      SettingItem::DiscordPresence => on_off(settings.discord_presence),
+      SettingItem::Controls => strings.get(if settings.bindings.is_empty() { "settings.keymap" } else { "settings.changed" }).to_string(),
+    };
+  }
+
+  // One step up for a positive `direction`, down otherwise; switches flip
+  // either way and keymaps wrap around, dropping the keys changed over the
+  // last one. Skins and languages depend on the files installed, see
+  // `next_name`, and controls have a screen of their own.
+  pub fn adjust(&self, settings: &mut Settings, direction: i32) {
+    let volume = |volume: f32| ((volume + VOLUME_STEP * direction.signum() as f32) / VOLUME_STEP).round() * VOLUME_STEP;
+    match self {
+      SettingItem::ThemeOn => settings.theme_on = !settings.theme_on,
+      SettingItem::ThemeVolume => settings.theme_volume = volume(settings.theme_volume).clamp(0.0, 1.0),
+      SettingItem::EffectsVolume => settings.effects_volume = volume(settings.effects_volume).clamp(0.0, 1.0),
+      SettingItem::Ghost => settings.ghost_on = !settings.ghost_on,
+      SettingItem::ClearPreview => settings.clear_preview = !settings.clear_preview,
+      SettingItem::HighContrast => settings.high_contrast = !settings.high_contrast,
+      SettingItem::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+      SettingItem::TouchControls => settings.touch_controls = !settings.touch_controls,
+      SettingItem::Rumble => settings.rumble = !settings.rumble,
+      SettingItem::MisdropProtection => settings.misdrop_protection = !settings.misdrop_protection,
+      SettingItem::SpawnInputs => settings.buffer_spawn_inputs = !settings.buffer_spawn_inputs,
+      SettingItem::FocusPause => settings.focus_pause = !settings.focus_pause,
+      SettingItem::TitleStatus => settings.title_status = !settings.title_status,
+      SettingItem::DiscordPresence => settings.discord_presence = !settings.discord_presence,
+      SettingItem::Keymap => {
+        let count = Keymap::ALL.len() as i32;
+        let index = Keymap::ALL.iter().position(|keymap| keymap.name() == settings.keymap).unwrap_or(0) as i32;
+        settings.keymap = Keymap::ALL[(index + direction.signum()).rem_euclid(count) as usize].name().to_string();
+        settings.bindings.clear();
+      },
+      SettingItem::GameSpeed => settings.game_speed = (settings.game_speed + SPEED_STEP * direction.signum() as f64).clamp(MIN_SPEED, MAX_SPEED),
+      SettingItem::HandicapRows => {
+        let rows = settings.handicap_rows as i32 + direction.signum();
+        settings.handicap_rows = rows.clamp(0, (DEFAULT_GRID_HEIGHT - HANDICAP_FREE_ROWS) as i32) as usize;
+      },
+      SettingItem::Zoom if settings.fit_board => {
+        if direction > 0 {
+          settings.fit_board = false;
+          settings.zoom = MIN_ZOOM;
+        }
+      },
+      SettingItem::Zoom if direction < 0 && settings.zoom <= MIN_ZOOM => settings.fit_board = true,
+      SettingItem::Zoom => settings.zoom = (settings.zoom + ZOOM_STEP * direction.signum() as f32).clamp(MIN_ZOOM, MAX_ZOOM),
+      SettingItem::Skin | SettingItem::Language | SettingItem::Controls => (),
+    }
+  }
+}
+
+// The skin or language after `current` among `names` in `direction`,
+// wrapping around; `current` when there is none.
+pub fn next_name(names: &[String], current: &str, direction: i32) -> String {
+  let count = names.len() as i32;
+  if count == 0 {
+    return current.to_string();
+  }
+  let index = names.iter().position(|name| name == current).unwrap_or(0) as i32;
+  return names[(index + direction.signum()).rem_euclid(count) as usize].clone();
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::ai::{best_placement, placement_inputs};
+use crate::config::GameConfig;
+use crate::game::{Game, Input, Mode};
+use crate::timestep::LOGIC_TICK;
+
+// A marathon played well never ends, nor does zen.
+pub const DEFAULT_MAX_PIECES: u32 = 10_000;
+
+// How the simulated player places each piece.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+  // The placement of ai::best_placement, as the computer opponent.
+  Greedy,
+  // Any rotation and column, a stress test more than a player.
+  Random,
+}
+
+impl Policy {
+  pub fn from_name(name: &str) -> Option<Policy> {
+    return match name {
+      "greedy" => Some(Policy::Greedy),
+      "random" => Some(Policy::Random),
+      _ => None,
+    };
+  }
+}
+
+// The rules every simulated game starts from.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Simulation {
+  pub config: GameConfig,
+  pub mode: Mode,
+  pub level: u32,
+  pub policy: Policy,
+  pub max_pieces: u32,
+}
+
+// How one game ended.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize)]
+pub struct GameOutcome {
+  pub seed: u64,
+  pub score: i64,
+  pub lines: u32,
+  pub pieces: u32,
+  // Time of the game, not the time it took to simulate.
+  pub seconds: f64,
+  // Stopped at max_pieces rather than over.
+  pub cut: bool,
+}
+
+impl Simulation {
+  // Plays a whole game with no delay: the inputs of each piece are applied
+  // as soon as it spawns, then time runs a logic tick at a time.
+  pub fn play(&self, seed: u64) -> GameOutcome {
+    let mut game = Game::with_config(self.config.clone(), Some(seed));
+    game.set_mode(self.mode);
+    game.set_start_level(self.level);
+    game.reset();
+    let mut rng = StdRng::seed_from_u64(seed);
+    while !game.is_over() && game.pieces_locked() < self.max_pieces {
+      if let Some(piece) = game.current_piece().filter(|_| !game.is_clearing()) {
+        let inputs = match self.policy {
+          Policy::Greedy => best_placement(game.grid(), piece).map(|placement| placement_inputs(piece, placement.rotation, placement.x)),
+          Policy::Random => Some(placement_inputs(piece, rng.gen_range(0, 4), rng.gen_range(0, game.grid().width() as i32))),
+        };
+        // Like the bot, a piece with nowhere to go is dropped where it is.
+        for input in inputs.unwrap_or_else(|| VecDeque::from(vec![Input::HardDrop])) {
+          game.apply(input);
+        }
+      }
+      game.step(LOGIC_TICK);
+    }
+    let score = game.current_score();
+    return GameOutcome {
+      seed: seed,
+      score: score.score,
+      lines: score.line_removed,
+      pieces: game.pieces_locked(),
+      seconds: game.time_played().as_secs_f64(),
+      cut: !game.is_over(),
+    };
+  }
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Serialize)]
+pub struct Spread {
+  pub mean: f64,
+  pub median: f64,
+  pub min: f64,
+  pub max: f64,
+}
+
+impl Spread {
+  pub fn of(values: &[f64]) -> Spread {
+    if values.is_empty() {
+      return Spread::default();
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] };
+    return Spread {
+      mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+      median: median,
+      min: sorted[0],
+      max: sorted[sorted.len() - 1],
+    };
+  }
+}
+
+// What --simulate prints, as text or JSON.
+#[derive(Clone,Debug,PartialEq,Serialize)]
+pub struct Summary {
+  pub games: usize,
+  pub seed: u64,
+  pub policy: Policy,
+  pub mode: Mode,
+  pub score: Spread,
+  pub lines: Spread,
+  pub pieces: Spread,
+  pub seconds: Spread,
+  // Games stopped at max_pieces.
+  pub cut: usize,
+  pub wall_seconds: f64,
+  // Pieces placed per second of simulation, every game together.
+  pub pieces_per_second: f64,
+}
+
+impl Summary {
+  pub fn of(results: &[GameOutcome], seed: u64, simulation: &Simulation, wall: Duration) -> Summary {
+    let spread = |value: fn(&GameOutcome) -> f64| Spread::of(&results.iter().map(value).collect::<Vec<f64>>());
+    let pieces: u64 = results.iter().map(|result| result.pieces as u64).sum();
+    return Summary {
+      games: results.len(),
+      seed: seed,
+      policy: simulation.policy,
+      mode: simulation.mode,
+      score: spread(|result| result.score as f64),
+      lines: spread(|result| result.lines as f64),
+      pieces: spread(|result| result.pieces as f64),
+      seconds: spread(|result| result.seconds),
+      cut: results.iter().filter(|result| result.cut).count(),
+      wall_seconds: wall.as_secs_f64(),
+      pieces_per_second: if wall.as_secs_f64() > 0.0 { pieces as f64 / wall.as_secs_f64() } else { 0.0 },
+    };
+  }
+
+  pub fn text(&self) -> String {
+    let line = |name: &str, spread: &Spread| format!("{:<8} mean {:>10.1}  median {:>10.1}  min {:>10.1}  max {:>10.1}", name, spread.mean, spread.median, spread.min, spread.max);
+    return format!(
+"{} {} games, {:?} policy, seeds from {}
+{}
+{}
+{}
+{}
+{} stopped at the piece limit
+{:.2}s, {:.0} pieces per second",
+      self.games, self.mode.name(), self.policy, self.seed,
+      line("score", &self.score), line("lines", &self.lines), line("pieces", &self.pieces), line("seconds", &self.seconds),
+      self.cut, self.wall_seconds, self.pieces_per_second);
+  }
+}
+
+// Game `index` gets the seed `seed + index`, so any of them can be played again.
+pub fn simulate(simulation: &Simulation, games: u32, seed: u64) -> Summary {
+  let start = Instant::now();
+  let results: Vec<GameOutcome> = (0..games).map(|index| simulation.play(seed.wrapping_add(index as u64))).collect();
+  return Summary::of(&results, seed, simulation, start.elapsed());
+}
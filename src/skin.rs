@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::{Context, GameError, GameResult};
+
+use log::info;
+use serde::Deserialize;
+
+use crate::config;
+use crate::piece::{Case, Tetromino};
+
+// Built in, drawn without any file.
+pub const DEFAULT_SKIN: &str = "default";
+const SKINS_DIRECTORY: &str = "/skins";
+// Tiles of blocks.png, left to right: the pieces in the order of
+// Tetromino::ALL, then garbage.
+pub const SKIN_TILES: usize = 8;
+
+// skin.toml, every field optional:
+//
+//   grid_color = "#ffffff"
+//   background_color = "#1a334d"
+//   [colors]
+//   I = "#00ffff"
+//   garbage = "#808080"
+#[derive(Clone,Debug,Default,PartialEq,Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SkinFile {
+  grid_color: Option<String>,
+  background_color: Option<String>,
+  colors: BTreeMap<String, String>,
+}
+
+// What a skin changes, the built-in look for everything it leaves out.
+// High contrast ignores its colors and blocks.
+#[derive(Clone,Debug,Default)]
+pub struct Skin {
+  pub name: String,
+  pub blocks: Option<graphics::Image>,
+  pub background: Option<graphics::Image>,
+  pub font: Option<graphics::Font>,
+  pub grid_color: Option<graphics::Color>,
+  pub background_color: Option<graphics::Color>,
+  // By tile, see SKIN_TILES.
+  pub colors: [Option<graphics::Color>; SKIN_TILES],
+}
+
+// Index of `case` in blocks.png and Skin::colors, none for an empty case.
+pub fn tile_index(case: Case) -> Option<usize> {
+  return match case {
+    Case::Filled(tetromino) => Tetromino::ALL.iter().position(|&other| other == tetromino),
+    Case::Garbage => Some(SKIN_TILES - 1),
+    Case::Empty => None,
+  };
+}
+
+// "#rrggbb" or "#rrggbbaa".
+pub fn parse_color(text: &str) -> Result<graphics::Color, String> {
+  return config::parse_color(text).map(graphics::Color::from);
+}
+
+impl Skin {
+  // The colors of skin.toml, on a skin without any image.
+  pub fn from_toml(name: &str, text: &str) -> Result<Skin, String> {
+    let file: SkinFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    let mut skin = Skin { name: name.to_string(), ..Skin::default() };
+    skin.grid_color = file.grid_color.as_deref().map(parse_color).transpose()?;
+    skin.background_color = file.background_color.as_deref().map(parse_color).transpose()?;
+    for (key, color) in &file.colors {
+      let index = match key.as_str() {
+        "garbage" => SKIN_TILES - 1,
+        _ => key.chars().next().filter(|_| key.chars().count() == 1).and_then(Tetromino::from_letter).and_then(|tetromino| tile_index(Case::Filled(tetromino)))
+          .ok_or(format!("unknown color \"{}\", expected a piece letter or garbage", key))?,
+      };
+      skin.colors[index] = Some(parse_color(color).map_err(|e| format!("colors.{}: {}", key, e))?);
+    }
+    return Ok(skin);
+  }
+}
+
+// The default first, then the folders of resources/skins by name.
+pub fn skin_names(ctx: &mut Context) -> Vec<String> {
+  let mut names: Vec<String> = match filesystem::read_dir(ctx, SKINS_DIRECTORY) {
+    Ok(paths) => paths.filter_map(|path| path.file_name().and_then(|name| name.to_str()).map(String::from)).collect(),
+    Err(_) => Vec::new(),
+  };
+  names.retain(|name| name != DEFAULT_SKIN && filesystem::is_dir(ctx, format!("{}/{}", SKINS_DIRECTORY, name)));
+  names.sort();
+  names.insert(0, String::from(DEFAULT_SKIN));
+  return names;
+}
+
+// resources/skins/NAME/ holds any of skin.toml, blocks.png (SKIN_TILES
+// square tiles in a row), background.png and font.ttf. A missing file keeps
+// the built-in asset, a file that does not load fails the whole skin.
+pub fn load_skin(ctx: &mut Context, name: &str) -> GameResult<Skin> {
+  if name == DEFAULT_SKIN {
+    return Ok(Skin { name: name.to_string(), ..Skin::default() });
+  }
+  // Like puzzles, it cannot leave its directory.
+  if name.is_empty() || !name.chars().all(|letter| letter.is_ascii_alphanumeric() || letter == '-' || letter == '_') {
+    return Err(GameError::ConfigError(format!("invalid skin name: {}", name)));
+  }
+  let directory = format!("{}/{}", SKINS_DIRECTORY, name);
+  if !filesystem::is_dir(ctx, &directory) {
+    return Err(GameError::ConfigError(format!("{}: no such skin", directory)));
+  }
+  let with_path = |path: &str, e: GameError| GameError::ResourceLoadError(format!("{}: {}", path, e));
+
+  let path = format!("{}/skin.toml", directory);
+  let mut skin = if filesystem::exists(ctx, &path) {
+    let mut text = String::new();
+    filesystem::open(ctx, &path)?.read_to_string(&mut text).map_err(|e| with_path(&path, e.into()))?;
+    Skin::from_toml(name, &text).map_err(|e| GameError::ConfigError(format!("{}: {}", path, e)))?
+  } else {
+    info!("{}: not found, the skin keeps the default colors", path);
+    Skin { name: name.to_string(), ..Skin::default() }
+  };
+
+  let path = format!("{}/blocks.png", directory);
+  if filesystem::exists(ctx, &path) {
+    let blocks = graphics::Image::new(ctx, &path).map_err(|e| with_path(&path, e))?;
+    if blocks.width() as usize != SKIN_TILES * blocks.height() as usize {
+      return Err(GameError::ConfigError(format!("{}: {}x{} pixels, expected {} square tiles in a row", path, blocks.width(), blocks.height(), SKIN_TILES)));
+    }
+    skin.blocks = Some(blocks);
+  }
+  let path = format!("{}/background.png", directory);
+  if filesystem::exists(ctx, &path) {
+    skin.background = Some(graphics::Image::new(ctx, &path).map_err(|e| with_path(&path, e))?);
+  }
+  let path = format!("{}/font.ttf", directory);
+  if filesystem::exists(ctx, &path) {
+    skin.font = Some(graphics::Font::new(ctx, &path).map_err(|e| with_path(&path, e))?);
+  }
+  return Ok(skin);
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, Mode};
+
+pub const STATS_FILE: &str = "games.jsonl";
+pub const LIFETIME_FILE: &str = "lifetime.json";
+const LIFETIME_VERSION: u32 = 1;
+// Lines between two splits of the timer, see LifetimeStats::best_splits.
+pub const SPLIT_LINES: u32 = 10;
+
+// Line clears of a game by kind. A T-spin clear is counted as a T-spin only.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct ClearCounts {
+  pub singles: u32,
+  pub doubles: u32,
+  pub triples: u32,
+  pub tetrises: u32,
+  pub t_spins: u32,
+}
+
+impl ClearCounts {
+  pub fn record(&mut self, lines: u32, t_spin: bool) {
+    let count = match (lines, t_spin) {
+      (0, _) => return,
+      (_, true) => &mut self.t_spins,
+      (1, _) => &mut self.singles,
+      (2, _) => &mut self.doubles,
+      (3, _) => &mut self.triples,
+      _ => &mut self.tetrises,
+    };
+    *count += 1;
+  }
+}
+
+// One line of games.jsonl, written when a game ends or is given up.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct GameSummary {
+  // Seconds since the Unix epoch.
+  pub ended_at: u64,
+  pub mode: Mode,
+  // Seed of the session, None when the pieces were random.
+  pub seed: Option<u64>,
+  // Quit or restarted before the end of the game.
+  pub abandoned: bool,
+  pub score: i64,
+  pub level: u32,
+  pub lines: u32,
+  pub clears: ClearCounts,
+  // Pieces locked, by tetromino letter.
+  pub pieces: BTreeMap<char, u32>,
+  pub seconds: f64,
+  pub pps: f64,
+  // Practice speed multiplier, 1 for a normal game.
+  pub speed: f64,
+  // Rows of garbage the board started with, 0 in older lines.
+  #[serde(default)]
+  pub handicap_rows: usize,
+  // Name of the mission played with --missions, absent from older lines.
+  #[serde(default)]
+  pub mission: Option<String>,
+  #[serde(default)]
+  pub mission_passed: bool,
+  // Date of the daily challenge played with --daily, and whether the game
+  // was only a practice attempt after the official one of the day.
+  #[serde(default)]
+  pub daily: Option<String>,
+  #[serde(default)]
+  pub practice: bool,
+}
+
+impl GameSummary {
+  pub fn from_game(game: &Game, seed: Option<u64>, abandoned: bool) -> GameSummary {
+    let score = game.current_score();
+    return GameSummary {
+      ended_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+      mode: game.mode(),
+      seed: seed,
+      abandoned: abandoned,
+      score: score.score,
+      level: score.level,
+      lines: score.line_removed,
+      clears: *game.clear_counts(),
+      pieces: game.piece_counts().iter().map(|(tetromino, &count)| (tetromino.letter(), count)).collect(),
+      seconds: game.time_played().as_secs_f64(),
+      pps: game.pieces_per_second(),
+      speed: game.speed(),
+      handicap_rows: game.handicap_rows(),
+      mission: None,
+      mission_passed: false,
+      daily: None,
+      practice: false,
+    };
+  }
+}
+
+// Written aside then renamed over the old file, so a crash in the middle
+// leaves the previous content. The directory is created when needed.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+  if let Some(directory) = path.parent() {
+    fs::create_dir_all(directory)?;
+  }
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".tmp");
+  let temporary = path.with_file_name(name);
+  let mut file = fs::File::create(&temporary)?;
+  file.write_all(content)?;
+  file.sync_all()?;
+  return fs::rename(&temporary, path);
+}
+
+// Appends the summary as one JSON line, creating the directory and the file
+// when needed.
+pub fn append_summary(directory: &Path, summary: &GameSummary) -> io::Result<()> {
+  fs::create_dir_all(directory)?;
+  let mut line = serde_json::to_vec(summary)?;
+  line.push(b'\n');
+  let mut file = OpenOptions::new().create(true).append(true).open(directory.join(STATS_FILE))?;
+  return file.write_all(&line);
+}
+
+// Names of the missions passed in the games of games.jsonl, none when the
+// file does not exist yet. Lines that cannot be read are skipped.
+pub fn completed_missions(directory: &Path) -> io::Result<HashSet<String>> {
+  let file = match fs::File::open(directory.join(STATS_FILE)) {
+    Ok(file) => file,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+    Err(e) => return Err(e),
+  };
+  let mut missions = HashSet::new();
+  for line in BufReader::new(file).lines() {
+    if let Ok(GameSummary { mission: Some(mission), mission_passed: true, .. }) = serde_json::from_str(&line?) {
+      missions.insert(mission);
+    }
+  }
+  return Ok(missions);
+}
+
+// Totals of every game played from the keyboard, kept in lifetime.json next
+// to games.jsonl. Fields missing from older files start at zero.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+  pub version: u32,
+  pub games_played: u32,
+  pub lines: u64,
+  pub tetrises: u64,
+  pub highest_level: u32,
+  // Time played in games during one launch of the game.
+  pub longest_session_seconds: f64,
+  pub play_seconds: f64,
+  // Ids of the achievements unlocked, see Achievement::id.
+  pub achievements: BTreeSet<String>,
+  // Seconds into the game at 10 lines, 20 lines... by Mode::name. Each
+  // split is the fastest ever, not always from the same game.
+  pub best_splits: BTreeMap<String, Vec<f64>>,
+}
+
+impl Default for LifetimeStats {
+  fn default() -> LifetimeStats {
+    return LifetimeStats {
+      version: LIFETIME_VERSION,
+      games_played: 0,
+      lines: 0,
+      tetrises: 0,
+      highest_level: 0,
+      longest_session_seconds: 0.0,
+      play_seconds: 0.0,
+      achievements: BTreeSet::new(),
+      best_splits: BTreeMap::new(),
+    };
+  }
+}
+
+impl LifetimeStats {
+  // `session_seconds` is the time played since the launch, this game included.
+  pub fn record(&mut self, summary: &GameSummary, session_seconds: f64) {
+    self.games_played += 1;
+    self.lines += summary.lines as u64;
+    self.tetrises += summary.clears.tetrises as u64;
+    self.highest_level = self.highest_level.max(summary.level);
+    self.play_seconds += summary.seconds;
+    self.longest_session_seconds = self.longest_session_seconds.max(session_seconds);
+  }
+
+  pub fn best_splits(&self, mode: Mode) -> &[f64] {
+    return self.best_splits.get(mode.name()).map_or(&[], |splits| splits.as_slice());
+  }
+
+  // Keeps the faster of each split of a game and the best one so far.
+  pub fn record_splits(&mut self, mode: Mode, splits: &[f64]) {
+    if splits.is_empty() {
+      return;
+    }
+    let best = self.best_splits.entry(mode.name().to_string()).or_default();
+    for (index, &split) in splits.iter().enumerate() {
+      match best.get_mut(index) {
+        Some(time) => *time = time.min(split),
+        None => best.push(split),
+      }
+    }
+  }
+
+  // Zero everywhere when the file does not exist yet.
+  pub fn load(directory: &Path) -> io::Result<LifetimeStats> {
+    let text = match fs::read_to_string(directory.join(LIFETIME_FILE)) {
+      Ok(text) => text,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(LifetimeStats::default()),
+      Err(e) => return Err(e),
+    };
+    let mut stats: LifetimeStats = serde_json::from_str(&text)?;
+    stats.version = LIFETIME_VERSION;
+    return Ok(stats);
+  }
+
+  pub fn save(&self, directory: &Path) -> io::Result<()> {
+    return write_atomically(&directory.join(LIFETIME_FILE), &serde_json::to_vec_pretty(self)?);
+  }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+// 120 logic updates per second, whatever the render rate.
+pub const LOGIC_TICK: Duration = Duration::from_nanos(1_000_000_000 / 120);
+// A longer frame (window drag, driver stall) is only partly caught up, so the
+// game slows down for a moment instead of fast-forwarding.
+pub const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+// Turns the variable frame time into a whole number of fixed logic ticks,
+// carrying the remainder over to the next frame.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct FixedTimestep {
+  tick: Duration,
+  max_frame_time: Duration,
+  accumulator: Duration,
+}
+
+impl Default for FixedTimestep {
+  fn default() -> FixedTimestep {
+    return FixedTimestep::new(LOGIC_TICK, MAX_FRAME_TIME);
+  }
+}
+
+impl FixedTimestep {
+  pub fn new(tick: Duration, max_frame_time: Duration) -> FixedTimestep {
+    return FixedTimestep { tick: tick, max_frame_time: max_frame_time, accumulator: Duration::from_secs(0) };
+  }
+
+  pub fn tick(&self) -> Duration {
+    return self.tick;
+  }
+
+  // Number of ticks to run for a frame that lasted `delta`.
+  pub fn advance(&mut self, delta: Duration) -> u32 {
+    self.accumulator += delta.min(self.max_frame_time);
+    let mut ticks = 0;
+    while self.accumulator >= self.tick {
+      self.accumulator -= self.tick;
+      ticks += 1;
+    }
+    return ticks;
+  }
+}
@@ -0,0 +1,38 @@
+use tetris_rs::grid::{Grid, ParseError};
+use tetris_rs::piece::{Case, Tetromino};
+
+const STACK: &str = include_str!("fixtures/stack.txt");
+const WELL_CRLF: &str = include_str!("fixtures/well_crlf.txt");
+
+#[test]
+fn a_board_reads_back_its_text() {
+  let mut grid = Grid::new(10, 20);
+  grid.set(0, 19, Case::Garbage);
+  grid.set(4, 18, Case::Filled(Tetromino::T));
+  grid.set(9, 0, Case::Filled(Tetromino::I));
+  assert_eq!(Grid::from_ascii(&grid.to_ascii()), Ok(grid));
+}
+
+#[test]
+fn fixtures_round_trip() {
+  let grid = Grid::from_ascii_sized(STACK, 10, 20).unwrap();
+  assert_eq!(grid.get(4, 16), Some(Case::Filled(Tetromino::T)));
+  assert_eq!(grid.get(8, 17), Some(Case::Garbage));
+  assert_eq!(grid.to_ascii(), STACK);
+
+  let grid = Grid::from_ascii_sized(WELL_CRLF, 10, 20).unwrap();
+  assert_eq!(grid.get(1, 18), Some(Case::Filled(Tetromino::L)));
+  assert_eq!(grid.get(9, 19), Some(Case::Empty));
+  assert_eq!(Grid::from_ascii(&grid.to_ascii()), Ok(grid));
+}
+
+#[test]
+fn errors_name_the_line_at_fault() {
+  assert_eq!(Grid::from_ascii(""), Err(ParseError::Empty));
+  assert_eq!(Grid::from_ascii(" \n\r\n"), Err(ParseError::Empty));
+  assert_eq!(Grid::from_ascii("...\n....\n"), Err(ParseError::WrongWidth { line: 2, width: 4, expected: 3 }));
+  assert_eq!(Grid::from_ascii("...\n.?.\n"), Err(ParseError::UnknownCharacter { line: 2, column: 2, character: '?' }));
+  assert_eq!(Grid::from_ascii_sized("...\n...\n", 4, 2), Err(ParseError::WrongWidth { line: 1, width: 3, expected: 4 }));
+  assert_eq!(Grid::from_ascii_sized("...\n...\n", 3, 3), Err(ParseError::WrongRowCount { rows: 2, expected: 3 }));
+  assert_eq!(ParseError::UnknownCharacter { line: 2, column: 2, character: '?' }.to_string(), "line 2, column 2: unknown case '?'");
+}
@@ -0,0 +1,13 @@
+use tetris_rs::config::{parse_color, GameConfig};
+use tetris_rs::piece::Tetromino;
+
+#[test]
+fn piece_colors_read_without_a_context() {
+  let config = GameConfig::from_toml("[colors]\nT = \"#ff8000\"\ni = \"#10203040\"\n").unwrap();
+  let colors = config.piece_colors().unwrap();
+  assert_eq!(colors[&Tetromino::T], (255, 128, 0, 255));
+  assert_eq!(colors[&Tetromino::I5], (16, 32, 48, 64));
+  assert_eq!(colors.len(), 2);
+  assert!(parse_color("ff8000").is_err() && parse_color("#ff80").is_err() && parse_color("#gg8000").is_err());
+  assert!(GameConfig::from_toml("[colors]\nQ = \"#ff8000\"\n").unwrap_err().starts_with("colors.Q"));
+}
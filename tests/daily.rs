@@ -0,0 +1,40 @@
+use tetris_rs::daily::{daily_seed, Date, DailyRecords};
+use tetris_rs::game::Game;
+use tetris_rs::stats::GameSummary;
+
+fn date(year: i64, month: u32, day: u32) -> Date {
+  return Date { year: year, month: month, day: day };
+}
+
+#[test]
+fn days_since_the_epoch_give_the_utc_date() {
+  assert_eq!(Date::from_days(0), date(1970, 1, 1));
+  assert_eq!(Date::from_days(31), date(1970, 2, 1));
+  assert_eq!(Date::from_days(11_016), date(2000, 2, 29));
+  assert_eq!(Date::from_days(20_010), date(2024, 10, 14));
+  assert_eq!(Date::from_days(-1), date(1969, 12, 31));
+  assert_eq!(date(2026, 3, 7).to_string(), "2026-03-07");
+}
+
+#[test]
+fn every_day_has_its_own_fixed_seed() {
+  // Shared by every player on the same day, whatever the version.
+  assert_eq!(daily_seed(date(2024, 10, 14)), 0x05e6_8a7a_e8ef_6f6d);
+  let seeds: Vec<u64> = (19_000..19_400).map(|days| daily_seed(Date::from_days(days))).collect();
+  for (index, seed) in seeds.iter().enumerate() {
+    assert!(!seeds[..index].contains(seed));
+  }
+}
+
+#[test]
+fn only_the_first_game_of_a_day_is_official() {
+  let summary = GameSummary::from_game(&Game::new(), Some(1), false);
+  let mut records = DailyRecords::default();
+  let today = date(2024, 10, 14);
+  assert!(records.record(today, &summary));
+  assert!(!records.record(today, &summary));
+  assert!(!records.record(today, &summary));
+  assert_eq!(records.official(today).map(|result| result.practice_attempts), Some(2));
+  assert!(records.official(date(2024, 10, 15)).is_none());
+  assert!(records.record(date(2024, 10, 15), &summary));
+}
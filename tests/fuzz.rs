@@ -0,0 +1,143 @@
+// Random byte streams played as inputs on the headless game, checking only
+// that nothing panics and the invariants below hold. FUZZ_CASES=10000 cargo
+// test --release --test fuzz hammers it for longer; any failure found is to
+// be kept as a test of its own at the end of this file.
+
+use proptest::prelude::*;
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::{Game, GameSnapshot, Input, Mode};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, PieceSet, Tetromino};
+use tetris_rs::timestep::LOGIC_TICK;
+
+const INPUTS: [Input; 8] = [
+  Input::MoveLeft, Input::MoveRight, Input::SoftDrop, Input::HardDrop,
+  Input::RotateCW, Input::RotateCCW, Input::Hold, Input::UseItem,
+];
+// Of a byte, the action is the rest of a division by ACTIONS: an input, a
+// batch of garbage or nothing. The quotient is the logic ticks played after.
+const ACTIONS: u8 = 10;
+
+#[derive(Clone,Debug)]
+struct Rules {
+  mode: Mode,
+  pieces: PieceSet,
+  width: usize,
+  height: usize,
+  cascade: bool,
+  items: bool,
+  mirror: bool,
+  handicap_rows: usize,
+  buffer_spawn_inputs: bool,
+}
+
+fn rules() -> impl Strategy<Value = Rules> {
+  let modes = prop::sample::select(vec![Mode::Marathon, Mode::Sprint, Mode::Ultra, Mode::Zen]);
+  let pieces = prop::sample::select(vec![PieceSet::Tetromino, PieceSet::Pentomino]);
+  let switches = (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>());
+  return (modes, pieces, 0..8usize, 0..16usize, switches, 0..6usize).prop_map(|(mode, pieces, width, height, (cascade, items, mirror, buffer), handicap_rows)| {
+    // From the smallest board the pieces fit in.
+    let size = pieces.max_size() as usize;
+    Rules { mode: mode, pieces: pieces, width: size + width, height: size + height, cascade: cascade, items: items, mirror: mirror, handicap_rows: handicap_rows, buffer_spawn_inputs: buffer }
+  });
+}
+
+fn new_game(rules: &Rules, seed: u64) -> Game {
+  let config = GameConfig { grid_width: rules.width, grid_height: rules.height, pieces: rules.pieces, cascade: rules.cascade, ..GameConfig::default() };
+  config.validate().unwrap();
+  let mut game = Game::with_config(config, Some(seed));
+  game.set_mode(rules.mode);
+  game.set_items(rules.items);
+  game.set_mirror(rules.mirror);
+  game.set_handicap_rows(rules.handicap_rows);
+  game.set_buffer_spawn_inputs(rules.buffer_spawn_inputs);
+  game.reset();
+  return game;
+}
+
+// The board keeps its size and its row counts, and the piece in play is
+// inside the board or above it, on free cases.
+fn check(game: &Game, rules: &Rules) -> Result<(), TestCaseError> {
+  let grid = game.grid();
+  prop_assert_eq!((grid.width(), grid.height()), (rules.width, rules.height));
+  for y in 0..grid.height() as i32 {
+    let filled = (0..grid.width() as i32).filter(|&x| grid.get(x, y) != Some(Case::Empty)).count();
+    prop_assert_eq!(grid.row_fill(y), filled, "row {}", y);
+  }
+  prop_assert_eq!(grid.get(-1, 0), None);
+  prop_assert_eq!(grid.get(grid.width() as i32, 0), None);
+  prop_assert_eq!(grid.get(0, grid.height() as i32), None);
+  // A top-out may leave the last piece over the stack.
+  if game.is_over() {
+    return Ok(());
+  }
+  if let Some(piece) = game.current_piece() {
+    for &(x, y) in piece.cells() {
+      let (x, y) = (piece.x + x, piece.y + y);
+      prop_assert!(x >= 0 && x < grid.width() as i32 && y < grid.height() as i32, "case ({}, {}) of {:?}", x, y, piece);
+      if y >= 0 {
+        prop_assert_eq!(grid.get(x, y), Some(Case::Empty), "case ({}, {}) of {:?}", x, y, piece);
+      }
+    }
+    prop_assert!(game.ghost_y() >= piece.y);
+  }
+  return Ok(());
+}
+
+// The byte stream on a game of `rules`, a new one after each game over.
+fn play(rules: &Rules, seed: u64, bytes: &[u8]) -> Result<(), TestCaseError> {
+  let mut game = new_game(rules, seed);
+  let mut score = 0;
+  for &byte in bytes {
+    match byte % ACTIONS {
+      8 => game.add_garbage(1 + (byte / ACTIONS) as u32 % 4),
+      9 => (),
+      action => game.apply(INPUTS[action as usize]),
+    }
+    check(&game, rules)?;
+    for _ in 0..byte / ACTIONS {
+      game.step(LOGIC_TICK);
+      check(&game, rules)?;
+    }
+    game.drain_events();
+    prop_assert!(game.current_score().score >= score, "score went from {} to {}", score, game.current_score().score);
+    score = game.current_score().score;
+    if game.is_over() {
+      game.reset();
+      score = 0;
+    }
+  }
+  return Ok(());
+}
+
+fn cases() -> u32 {
+  return std::env::var("FUZZ_CASES").ok().and_then(|cases| cases.parse().ok()).unwrap_or(48);
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig { cases: cases(), ..ProptestConfig::default() })]
+
+  // 200 to 600 bytes of about 12 ticks each, thousands of ticks per case.
+  #[test]
+  fn random_inputs_keep_the_game_sound(rules in rules(), seed in any::<u64>(), bytes in prop::collection::vec(any::<u8>(), 200..600)) {
+    play(&rules, seed, &bytes)?;
+  }
+}
+
+// Found by the fuzzer: out of the hold or the preview, the piece swapped in
+// could spawn over the stack.
+#[test]
+fn a_hold_into_a_blocked_spawn_is_refused() {
+  let grid = Grid::from_ascii(&("....##....\n".repeat(2) + &"..........\n".repeat(18))).unwrap();
+  let mut piece = create_piece(Tetromino::I, DEFAULT_GRID_WIDTH);
+  piece.y = 10;
+  for hold_piece in [None, Some(create_piece(Tetromino::O, DEFAULT_GRID_WIDTH))] {
+    let mut game = Game::new();
+    game.restore(&GameSnapshot { grid: grid.clone(), current_piece: Some(piece.clone()), hold_piece: hold_piece.clone(), ..GameSnapshot::default() });
+    game.apply(Input::Hold);
+    assert_eq!(game.current_piece(), Some(&piece));
+    assert_eq!(game.hold_piece(), hold_piece.as_ref());
+    assert!(game.drain_events().is_empty());
+  }
+}
@@ -0,0 +1,59 @@
+use tetris_rs::garbage::{attack, GarbageQueue};
+
+fn queue(batches: &[u32]) -> GarbageQueue {
+  let mut queue = GarbageQueue::new();
+  for &rows in batches {
+    queue.push(rows);
+  }
+  return queue;
+}
+
+fn batches(queue: &GarbageQueue) -> Vec<u32> {
+  return queue.batches().collect();
+}
+
+#[test]
+fn a_small_attack_cancels_part_of_the_oldest_batch() {
+  let mut queue = queue(&[3, 2]);
+  assert_eq!(queue.cancel(2), 0);
+  assert_eq!(batches(&queue), vec![1, 2]);
+  assert_eq!(queue.pending(), 3);
+}
+
+#[test]
+fn an_attack_cancels_across_batches() {
+  let mut queue = queue(&[1, 2, 4]);
+  assert_eq!(queue.cancel(5), 0);
+  assert_eq!(batches(&queue), vec![2]);
+}
+
+#[test]
+fn the_rest_of_an_attack_bigger_than_the_queue_is_sent() {
+  let mut queue = queue(&[1, 2]);
+  assert_eq!(queue.cancel(7), 4);
+  assert_eq!(queue.pending(), 0);
+  assert!(queue.take().is_empty());
+}
+
+#[test]
+fn an_empty_queue_cancels_nothing() {
+  let mut queue = GarbageQueue::new();
+  assert_eq!(queue.cancel(3), 3);
+  assert_eq!(queue.cancel(0), 0);
+  // Empty batches are not queued.
+  queue.push(0);
+  assert_eq!(queue, GarbageQueue::new());
+}
+
+#[test]
+fn attack_bonuses_past_the_end_of_their_tables() {
+  assert_eq!(attack(0, false, true, 20), 0);
+  assert_eq!(attack(2, false, false, 0), 1);
+  assert_eq!(attack(4, false, true, 0), 5);
+  // The last combo bonus applies from the end of the table on.
+  assert_eq!(attack(1, false, false, 11), 5);
+  assert_eq!(attack(1, false, false, 100), 5);
+  // Past the last line count of a table, its last attack.
+  assert_eq!(attack(6, false, false, 0), 4);
+  assert_eq!(attack(4, true, true, 100), 6 + 5 + 1);
+}
@@ -0,0 +1,49 @@
+use tetris_rs::game::{Game, GameSnapshot};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, Piece, Tetromino};
+
+// A game with `piece` in play over a board whose bottom `rows` are full but
+// for the last column.
+fn game_with(piece: &Piece, rows: usize) -> Game {
+  let mut grid = Grid::default();
+  for y in DEFAULT_GRID_HEIGHT - rows..DEFAULT_GRID_HEIGHT {
+    for x in 0..DEFAULT_GRID_WIDTH as i32 - 1 {
+      grid.set(x, y as i32, Case::Garbage);
+    }
+  }
+  let mut game = Game::new();
+  game.restore(&GameSnapshot { grid: grid, current_piece: Some(piece.clone()), ..GameSnapshot::default() });
+  return game;
+}
+
+// The row under the lowest case of the piece.
+fn bottom(piece: &Piece, y: i32) -> i32 {
+  return y + piece.cells().iter().map(|c| c.1).max().unwrap() + 1;
+}
+
+#[test]
+fn a_piece_in_the_air_has_its_ghost_on_the_stack() {
+  let mut piece = create_piece(Tetromino::O, DEFAULT_GRID_WIDTH);
+  piece.y = 2;
+  let game = game_with(&piece, 5);
+  assert_eq!(bottom(&piece, game.ghost_y()), DEFAULT_GRID_HEIGHT as i32 - 5);
+  let game = game_with(&piece, 0);
+  assert_eq!(bottom(&piece, game.ghost_y()), DEFAULT_GRID_HEIGHT as i32);
+}
+
+#[test]
+fn a_piece_resting_on_the_stack_is_its_own_ghost() {
+  let mut piece = create_piece(Tetromino::O, DEFAULT_GRID_WIDTH);
+  piece.y = DEFAULT_GRID_HEIGHT as i32 - 5 - bottom(&piece, 0);
+  let game = game_with(&piece, 5);
+  assert_eq!(game.ghost_y(), piece.y);
+}
+
+// Never above the piece, which has nowhere to go.
+#[test]
+fn a_piece_spawned_into_the_stack_keeps_the_ghost_on_itself() {
+  let piece = create_piece(Tetromino::T, DEFAULT_GRID_WIDTH);
+  let game = game_with(&piece, DEFAULT_GRID_HEIGHT);
+  assert!(game.grid().collides(&piece, 0, 0));
+  assert_eq!(game.ghost_y(), piece.y);
+}
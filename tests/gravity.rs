@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::drop_speed;
+use tetris_rs::gravity::GravityCurve;
+
+fn frames(curve: GravityCurve, level: u32) -> f64 {
+  return curve.row_time(&GameConfig::default(), level).as_secs_f64() * 60.0;
+}
+
+fn close(a: f64, b: f64) -> bool {
+  return (a - b).abs() < 1e-4;
+}
+
+#[test]
+fn classic_curve_follows_the_formula_of_the_config() {
+  let config = GameConfig::default();
+  // (drop_speed_base - 0) ^ 0: a second.
+  assert!(close(GravityCurve::Classic.row_time(&config, 1).as_secs_f64(), 1.0));
+  assert!(close(GravityCurve::Classic.row_time(&config, 2).as_secs_f64(), 0.793));
+  assert!(close(GravityCurve::Classic.row_time(&config, 10).as_secs_f64(), 0.737f64.powi(9)));
+  // The base reaches zero from level 116.
+  assert_eq!(GravityCurve::Classic.row_time(&config, 200), Duration::from_secs(0));
+}
+
+// Level 1 is level 0 of the NES version.
+#[test]
+fn nes_curve_counts_the_frames_of_the_nes_version() {
+  assert!(close(frames(GravityCurve::Nes, 1), 48.0));
+  assert!(close(frames(GravityCurve::Nes, 9), 8.0));
+  assert!(close(frames(GravityCurve::Nes, 10), 6.0));
+  assert!(close(frames(GravityCurve::Nes, 19), 3.0));
+  assert!(close(frames(GravityCurve::Nes, 20), 2.0));
+  assert!(close(frames(GravityCurve::Nes, 30), 1.0));
+  assert!(close(frames(GravityCurve::Nes, 999), 1.0));
+}
+
+#[test]
+fn tgm_curve_ramps_up_to_20g() {
+  // 4/256 of a row per frame.
+  assert!(close(frames(GravityCurve::Tgm, 1), 64.0));
+  // Internal level 100: 80/256.
+  assert!(close(frames(GravityCurve::Tgm, 3), 3.2));
+  // Internal level 200 drops back to 4/256.
+  assert!(close(frames(GravityCurve::Tgm, 5), 64.0));
+  // 224/256 at internal level 250, 2G at 300, 20G from 500.
+  assert!(close(frames(GravityCurve::Tgm, 6), 256.0 / 224.0));
+  assert!(close(frames(GravityCurve::Tgm, 7), 0.5));
+  assert!(close(frames(GravityCurve::Tgm, 11), 0.05));
+  assert!(close(frames(GravityCurve::Tgm, 50), 0.05));
+}
+
+// The curve of the config; the classic and NES ones never slow down.
+#[test]
+fn drop_speed_follows_the_curve_of_the_config() {
+  for &curve in &[GravityCurve::Classic, GravityCurve::Nes, GravityCurve::Tgm] {
+    let config = GameConfig { gravity: curve, ..GameConfig::default() };
+    for level in 1..40 {
+      assert_eq!(drop_speed(&config, level), curve.row_time(&config, level));
+      if curve != GravityCurve::Tgm {
+        assert!(drop_speed(&config, level + 1) <= drop_speed(&config, level), "{:?} level {}", curve, level);
+      }
+    }
+  }
+}
@@ -0,0 +1,107 @@
+use tetris_rs::grid::Grid;
+use tetris_rs::piece::{create_piece, Case, Piece, Tetromino};
+
+// A board of Grid::from_ascii, its rows indented.
+fn board(text: &str) -> Grid {
+  let rows: Vec<&str> = text.lines().map(str::trim).collect();
+  return Grid::from_ascii(&rows.join("\n")).unwrap();
+}
+
+// The O piece, its top left case at (x, y).
+fn o_piece(x: i32, y: i32) -> Piece {
+  let mut piece = create_piece(Tetromino::O, 4);
+  let (left, top) = (piece.cells().iter().map(|c| c.0).min().unwrap(), piece.cells().iter().map(|c| c.1).min().unwrap());
+  piece.x = x - left;
+  piece.y = y - top;
+  return piece;
+}
+
+#[test]
+fn pieces_collide_with_the_walls_the_floor_and_the_stack_only() {
+  let grid = board("
+    ....
+    ....
+    ....
+    ..#.
+  ");
+  assert!(!grid.collides(&o_piece(0, 0), 0, 0));
+  assert!(!grid.collides(&o_piece(0, 2), 0, 0));
+  assert!(grid.collides(&o_piece(1, 2), 0, 0));
+  // The walls and the floor, through the offsets too.
+  assert!(grid.collides(&o_piece(0, 0), -1, 0));
+  assert!(grid.collides(&o_piece(2, 0), 1, 0));
+  assert!(grid.collides(&o_piece(0, 2), 0, 1));
+  assert!(grid.collides(&o_piece(2, 1), 0, 1));
+  // Above the board is open.
+  assert!(!grid.collides(&o_piece(0, -2), 0, 0));
+  assert!(!grid.collides(&o_piece(2, -1), 0, 0));
+}
+
+#[test]
+fn clearing_full_rows_moves_the_rows_above_down() {
+  let mut grid = board("
+    .T..
+    ####
+    #.#.
+  ");
+  assert_eq!(grid.clear_full_rows(), 1);
+  assert_eq!(grid, board("
+    ....
+    .T..
+    #.#.
+  "));
+  assert_eq!(grid.clear_full_rows(), 0);
+  assert_eq!(grid.get(1, 1), Some(Case::Filled(Tetromino::T)));
+}
+
+#[test]
+fn a_row_with_a_gap_is_not_full() {
+  let mut grid = board("
+    ....
+    ###.
+    #.##
+  ");
+  assert!(!grid.is_row_full(1) && !grid.is_row_full(2));
+  assert!(!grid.has_full_row());
+  assert_eq!(grid.clear_full_rows(), 0);
+  assert_eq!(grid.row_fill(1), 3);
+}
+
+#[test]
+fn full_rows_apart_clear_together() {
+  let mut grid = board("
+    ..I.
+    ####
+    .O..
+    IIII
+    #..#
+  ");
+  assert!(grid.is_row_full(1) && grid.is_row_full(3));
+  assert_eq!(grid.clear_full_rows(), 2);
+  assert_eq!(grid, board("
+    ....
+    ....
+    ..I.
+    .O..
+    #..#
+  "));
+}
+
+#[test]
+fn the_bottom_row_clears() {
+  let mut grid = board("
+    ....
+    T...
+    ####
+  ");
+  assert!(grid.is_row_full(2));
+  // Out of the board, never full.
+  assert!(!grid.is_row_full(3) && !grid.is_row_full(-1));
+  assert_eq!(grid.clear_full_rows(), 1);
+  assert_eq!(grid, board("
+    ....
+    ....
+    T...
+  "));
+  assert_eq!((grid.row_fill(2), grid.row_fill(1)), (1, 0));
+}
@@ -0,0 +1,43 @@
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::{Game, Mode};
+use tetris_rs::highscores::{table_name, HighScore, HighScores, HIGH_SCORES};
+use tetris_rs::piece::PieceSet;
+
+fn entry(score: i64, seconds: f64) -> HighScore {
+  return HighScore { ended_at: 0, score: score, level: 1, lines: 40, seconds: seconds };
+}
+
+#[test]
+fn sprints_rank_by_time_and_the_others_by_score() {
+  let mut high_scores = HighScores::default();
+  assert_eq!(high_scores.record("Sprint", Mode::Sprint, entry(900, 80.0)), Some(0));
+  assert_eq!(high_scores.record("Sprint", Mode::Sprint, entry(100, 70.0)), Some(0));
+  assert_eq!(high_scores.record("Marathon", Mode::Marathon, entry(900, 80.0)), Some(0));
+  assert_eq!(high_scores.record("Marathon", Mode::Marathon, entry(100, 70.0)), Some(1));
+  assert_eq!(high_scores.table("Sprint")[0].seconds, 70.0);
+  assert_eq!(high_scores.table("Marathon")[0].score, 900);
+  assert!(high_scores.table("Ultra").is_empty());
+}
+
+#[test]
+fn a_table_keeps_the_best_games_only() {
+  let mut high_scores = HighScores::default();
+  for score in 1..=HIGH_SCORES as i64 {
+    high_scores.record("Ultra", Mode::Ultra, entry(score * 10, 120.0));
+  }
+  // A tie goes after the games already there, out of a full table.
+  assert_eq!(high_scores.record("Ultra", Mode::Ultra, entry(10, 120.0)), None);
+  assert_eq!(high_scores.record("Ultra", Mode::Ultra, entry(15, 120.0)), Some(HIGH_SCORES - 1));
+  let table = high_scores.table("Ultra");
+  assert_eq!(table.len(), HIGH_SCORES);
+  assert_eq!((table[0].score, table[HIGH_SCORES - 1].score), (HIGH_SCORES as i64 * 10, 15));
+}
+
+#[test]
+fn tables_follow_the_rules_that_change_the_scores() {
+  assert_eq!(table_name(Mode::Sprint, &Game::new()), "Sprint");
+  let config = GameConfig { grid_width: 12, grid_height: 24, pieces: PieceSet::Pentomino, ..GameConfig::default() };
+  let mut game = Game::with_config(config, None);
+  game.set_items(true);
+  assert_eq!(table_name(Mode::Marathon, &game), "Marathon 12x24 pentomino items");
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use tetris_rs::game::Input;
+use tetris_rs::input::{HeldInputs, DAS_DELAY, DAS_INTERVAL};
+use tetris_rs::timestep::LOGIC_TICK;
+
+// The inputs of the ticks of `duration`, as the game loop reads them.
+fn ticks(held: &mut HeldInputs, duration: Duration) -> Vec<Input> {
+  let mut inputs = Vec::new();
+  let mut time = Duration::from_secs(0);
+  while time + LOGIC_TICK <= duration {
+    inputs.extend(held.update(LOGIC_TICK));
+    time += LOGIC_TICK;
+  }
+  return inputs;
+}
+
+fn count(inputs: &[Input], input: Input) -> usize {
+  return inputs.iter().filter(|&&other| other == input).count();
+}
+
+#[test]
+fn a_held_direction_repeats_after_the_delay() {
+  let mut held = HeldInputs::new();
+  assert_eq!(held.press(Input::MoveLeft), Some(Input::MoveLeft));
+  // The repeats of the system are not presses.
+  assert_eq!(held.press(Input::MoveLeft), None);
+  assert!(ticks(&mut held, DAS_DELAY - LOGIC_TICK).is_empty());
+  let inputs = ticks(&mut held, LOGIC_TICK * 2 + DAS_INTERVAL * 4);
+  assert_eq!(inputs, vec![Input::MoveLeft; 5]);
+  held.release(Input::MoveLeft);
+  assert!(ticks(&mut held, DAS_DELAY * 2).is_empty());
+}
+
+#[test]
+fn the_last_direction_pressed_wins() {
+  let mut held = HeldInputs::new();
+  held.press(Input::MoveLeft);
+  ticks(&mut held, DAS_DELAY * 2);
+  assert_eq!(held.press(Input::MoveRight), Some(Input::MoveRight));
+  // Right charges from its press, Left is still held but stands still.
+  let inputs = ticks(&mut held, DAS_DELAY - LOGIC_TICK);
+  assert!(inputs.is_empty(), "{:?}", inputs);
+  let inputs = ticks(&mut held, DAS_INTERVAL * 3);
+  assert_eq!(count(&inputs, Input::MoveLeft), 0);
+  assert!(count(&inputs, Input::MoveRight) >= 3);
+}
+
+#[test]
+fn releasing_one_of_two_directions_goes_on_with_the_other() {
+  let mut held = HeldInputs::new();
+  held.press(Input::MoveLeft);
+  ticks(&mut held, DAS_DELAY * 2);
+  held.press(Input::MoveRight);
+  ticks(&mut held, DAS_DELAY / 2);
+  held.release(Input::MoveRight);
+  // Left was held long enough: it moves again on the next tick.
+  assert_eq!(held.update(LOGIC_TICK), vec![Input::MoveLeft]);
+  // Released the other way round, Right waits for its own delay.
+  let mut held = HeldInputs::new();
+  held.press(Input::MoveLeft);
+  held.press(Input::MoveRight);
+  held.release(Input::MoveLeft);
+  assert!(ticks(&mut held, DAS_DELAY - LOGIC_TICK).is_empty());
+  assert_eq!(count(&ticks(&mut held, DAS_INTERVAL), Input::MoveRight), 1);
+  assert!(!held.is_held(Input::MoveLeft));
+}
+
+#[test]
+fn chords_repeat_together() {
+  let mut held = HeldInputs::new();
+  held.press(Input::MoveRight);
+  assert_eq!(held.press(Input::SoftDrop), Some(Input::SoftDrop));
+  // A rotation while both are held leaves them held.
+  assert_eq!(held.press(Input::RotateCW), Some(Input::RotateCW));
+  held.release(Input::RotateCW);
+  let inputs = ticks(&mut held, DAS_DELAY + DAS_INTERVAL * 2);
+  assert!(count(&inputs, Input::MoveRight) >= 2, "{:?}", inputs);
+  // From the press, with no delay first.
+  assert!(count(&inputs, Input::SoftDrop) >= 4, "{:?}", inputs);
+  assert_eq!(inputs.iter().position(|&input| input == Input::SoftDrop), Some(0));
+  assert_eq!(count(&inputs, Input::RotateCW), 0);
+  // A rotation repeats only when pressed again.
+  assert_eq!(held.press(Input::RotateCW), Some(Input::RotateCW));
+  assert_eq!(held.press(Input::RotateCW), None);
+  held.clear();
+  assert!(ticks(&mut held, DAS_DELAY * 2).is_empty());
+}
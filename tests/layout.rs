@@ -0,0 +1,91 @@
+use ggez::graphics::Rect;
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::layout::{Layout, Zoom, FIT_HEIGHT};
+use tetris_rs::settings::{MAX_ZOOM, MIN_ZOOM};
+
+// The layouts of options::layout_size.
+const FRAMES: [(f32, f32); 3] = [(800.0, 600.0), (1200.0, 600.0), (1600.0, 600.0)];
+// A solo board, one against a network opponent at half size, two players.
+const SCALES: [&[f32]; 3] = [&[1.0], &[1.0, 0.5], &[1.0, 1.0]];
+
+fn zooms() -> Vec<Zoom> {
+  let mut zooms = vec![Zoom::Fit];
+  let mut zoom = MIN_ZOOM;
+  while zoom <= MAX_ZOOM {
+    zooms.push(Zoom::Fixed(zoom));
+    zoom += 0.25;
+  }
+  return zooms;
+}
+
+fn overlap(a: Rect, b: Rect) -> bool {
+  return a.x < b.right() && b.x < a.right() && a.y < b.bottom() && b.y < a.bottom();
+}
+
+fn inside(rect: Rect, region: Rect) -> bool {
+  const SLACK: f32 = 0.01;
+  return rect.x >= region.x - SLACK && rect.right() <= region.right() + SLACK && rect.y >= region.y - SLACK && rect.bottom() <= region.bottom() + SLACK;
+}
+
+#[test]
+fn panels_stay_in_their_region_clear_of_the_board_and_each_other() {
+  for big in [false, true] {
+    for config in [GameConfig::default(), GameConfig { grid_width: 16, grid_height: 30, ..GameConfig::default() }] {
+      for &(width, height) in FRAMES.iter() {
+        for scales in SCALES.iter() {
+          for zoom in zooms() {
+            let layout = Layout::new(Rect::new(0.0, 0.0, width, height), &config, big, scales, zoom);
+            let context = format!("{}x{} {:?} {:?} big {}", width, height, scales, zoom, big);
+            assert_eq!(layout.boards.len(), scales.len(), "{}", context);
+            for board in &layout.boards {
+              // At a fixed zoom, a board too big for its region is not made
+              // smaller, and leaves no room for the panels.
+              if !inside(board.grid_frame, board.region) {
+                assert!(zoom != Zoom::Fit, "{}: {:?}", context, board);
+                continue;
+              }
+              assert!(inside(board.board(), layout.frame), "{}: {:?}", context, board.board());
+              for panel in [board.hold, board.score, board.next] {
+                assert!(inside(panel, board.region), "{}: {:?} out of {:?}", context, panel, board.region);
+                assert!(!overlap(panel, board.grid_frame), "{}: {:?} over the board", context, panel);
+              }
+              assert!(!overlap(board.hold, board.score) && !overlap(board.score, board.next), "{}: {:?}", context, board);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+#[test]
+fn the_hold_panel_goes_down_rather_than_off_the_top() {
+  let config = GameConfig::default();
+  let layout = Layout::new(Rect::new(0.0, 0.0, 800.0, 600.0), &config, false, &[1.0], Zoom::Fixed(1.0));
+  let board = layout.boards[0];
+  // Above the score at the normal size, from a quarter of the height.
+  assert!(board.hold.y > board.region.y);
+  assert_eq!(board.score.y, board.region.y + board.region.h / 4.0);
+  assert_eq!(board.next.y, board.score.y);
+  let frame = Rect::new(0.0, 0.0, 2400.0, 400.0);
+  let layout = Layout::new(frame, &config, false, &[1.0], Zoom::Fixed(1.0));
+  let board = layout.boards[0];
+  assert_eq!(board.hold.y, board.region.y);
+  assert_eq!(board.score.y, board.hold.bottom());
+}
+
+#[test]
+fn the_zoom_shrinks_to_the_layout() {
+  let config = GameConfig::default();
+  let frame = Rect::new(0.0, 0.0, 800.0, 600.0);
+  let layout = Layout::new(frame, &config, false, &[1.0], Zoom::Fixed(MAX_ZOOM));
+  assert!(layout.zoom > MIN_ZOOM && layout.zoom < MAX_ZOOM, "{}", layout.zoom);
+  assert!(layout.boards[0].board().h <= frame.h);
+  // Smaller than 1 only to fit the window.
+  let big = GameConfig { grid_width: 30, grid_height: 60, ..GameConfig::default() };
+  assert_eq!(Layout::new(frame, &big, false, &[1.0], Zoom::Fixed(1.0)).zoom, MIN_ZOOM);
+  let layout = Layout::new(frame, &big, false, &[1.0], Zoom::Fit);
+  assert!(layout.zoom < 1.0);
+  assert!(layout.boards[0].board().h <= frame.h * FIT_HEIGHT + 0.01);
+}
@@ -0,0 +1,105 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::Game;
+use tetris_rs::leaderboard::{sends_token, validate_url, Leaderboard, ScoreSubmission};
+
+// Answers one request with `status`, and hands the request back.
+fn serve(listener: TcpListener, status: &'static str) -> JoinHandle<String> {
+  return thread::spawn(move || {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+      let read = stream.read(&mut buffer).unwrap();
+      request.extend_from_slice(&buffer[..read]);
+      let text = String::from_utf8_lossy(&request).to_string();
+      if let Some(end) = text.find("\r\n\r\n") {
+        let length = text.lines().find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(|length| length.parse::<usize>().unwrap())).unwrap_or(0);
+        if request.len() >= end + 4 + length {
+          break;
+        }
+      }
+      if read == 0 {
+        break;
+      }
+    }
+    write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+    return String::from_utf8(request).unwrap();
+  });
+}
+
+fn submit(url: &str, token: Option<&str>) -> Result<(), String> {
+  let leaderboard = Leaderboard::new(url, token);
+  leaderboard.submit(&ScoreSubmission::from_game(&Game::with_seed(1), "Ada", Some(1)));
+  let start = Instant::now();
+  while start.elapsed() < Duration::from_secs(10) {
+    if let Some(result) = leaderboard.poll() {
+      return result;
+    }
+    thread::sleep(Duration::from_millis(5));
+  }
+  panic!("no answer from {}", url);
+}
+
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+  return request.lines().find_map(|line| line.split_once(": ").filter(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value));
+}
+
+#[test]
+fn urls_with_ports_and_ipv6_hosts_are_valid() {
+  for url in ["http://scores.example", "https://scores.example/api/scores", "http://scores.example:8080/post", "http://[::1]:8080/scores", "https://[2001:db8::2]/"] {
+    assert_eq!(validate_url(url), Ok(()), "{}", url);
+  }
+  for url in ["ftp://scores.example", "scores.example", "http://", "http://scores.example:http/", "http://[::1/"] {
+    assert!(validate_url(url).is_err(), "{}", url);
+  }
+}
+
+#[test]
+fn the_token_goes_over_https_only() {
+  assert!(sends_token("https://scores.example:8443/"));
+  assert!(!sends_token("http://scores.example/"));
+  let config = |url: &str| GameConfig { leaderboard_url: Some(url.to_string()), leaderboard_token: Some(String::from("secret")), ..GameConfig::default() };
+  assert!(config("http://scores.example/").validate().unwrap_err().starts_with("leaderboard_token"));
+  assert_eq!(config("https://scores.example/").validate(), Ok(()));
+}
+
+#[test]
+fn a_submission_names_the_port_of_the_server() {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let port = listener.local_addr().unwrap().port();
+  let server = serve(listener, "201 Created");
+  assert_eq!(submit(&format!("http://127.0.0.1:{}/scores", port), Some("secret")), Ok(()));
+  let request = server.join().unwrap();
+  assert!(request.starts_with("POST /scores HTTP/1.1\r\n"), "{}", request);
+  assert_eq!(header(&request, "Host"), Some(format!("127.0.0.1:{}", port).as_str()));
+  assert_eq!(header(&request, "Authorization"), None);
+  assert!(request.ends_with("}") && request.contains("\"name\":\"Ada\""), "{}", request);
+}
+
+#[test]
+fn a_submission_reaches_an_ipv6_server() {
+  // Not every machine has an IPv6 loopback.
+  let listener = match TcpListener::bind("[::1]:0") {
+    Ok(listener) => listener,
+    Err(_) => return,
+  };
+  let address: SocketAddr = listener.local_addr().unwrap();
+  let server = serve(listener, "200 OK");
+  assert_eq!(submit(&format!("http://[::1]:{}/", address.port()), None), Ok(()));
+  assert_eq!(header(&server.join().unwrap(), "Host"), Some(format!("[::1]:{}", address.port()).as_str()));
+}
+
+#[test]
+fn a_refusal_of_the_server_is_reported() {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let port = listener.local_addr().unwrap().port();
+  let server = serve(listener, "403 Forbidden");
+  let result = submit(&format!("http://127.0.0.1:{}/", port), None);
+  server.join().unwrap();
+  assert_eq!(result, Err(String::from("server answered 403 Forbidden")));
+}
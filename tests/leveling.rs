@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::{Game, GameEvent, GameSnapshot, Input};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, Tetromino};
+use tetris_rs::scoring::{level_for_lines, lines_to_next_level};
+
+#[test]
+fn a_level_every_goal_from_the_starting_level() {
+  assert_eq!(level_for_lines(1, 0, 10), 1);
+  assert_eq!(level_for_lines(1, 9, 10), 1);
+  assert_eq!(level_for_lines(1, 10, 10), 2);
+  assert_eq!(level_for_lines(1, 99, 10), 10);
+  assert_eq!(level_for_lines(5, 0, 10), 5);
+  assert_eq!(level_for_lines(5, 10, 10), 6);
+  assert_eq!(level_for_lines(1, 4, 3), 2);
+  assert_eq!(level_for_lines(1, 4, 2), 3);
+
+  assert_eq!(lines_to_next_level(1, 1, 0, 10), 10);
+  assert_eq!(lines_to_next_level(1, 1, 7, 10), 3);
+  assert_eq!(lines_to_next_level(1, 2, 10, 10), 10);
+  assert_eq!(lines_to_next_level(5, 5, 0, 10), 10);
+  assert_eq!(lines_to_next_level(5, 6, 12, 10), 8);
+  // A tetris from 9 lines goes past the goal, the next one stays at 20.
+  assert_eq!(level_for_lines(1, 13, 10), 2);
+  assert_eq!(lines_to_next_level(1, 2, 13, 10), 7);
+}
+
+// Clears 4 rows with a standing I piece in the right column.
+fn clear_tetris(game: &mut Game) -> Vec<u32> {
+  let mut grid = Grid::default();
+  for y in DEFAULT_GRID_HEIGHT - 4..DEFAULT_GRID_HEIGHT {
+    for x in 0..DEFAULT_GRID_WIDTH - 1 {
+      grid.set(x as i32, y as i32, Case::Garbage);
+    }
+  }
+  let mut piece = create_piece(Tetromino::I, DEFAULT_GRID_WIDTH);
+  piece.index_rotation = 1;
+  piece.x = DEFAULT_GRID_WIDTH as i32 - 3;
+  game.restore(&GameSnapshot { grid: grid, current_piece: Some(piece), ..game.snapshot() });
+  game.apply(Input::HardDrop);
+  return game.drain_events().into_iter().filter_map(|event| match event {
+    GameEvent::LevelUp(level) => Some(level),
+    _ => None,
+  }).collect();
+}
+
+fn game(start_level: u32, lines_per_level: u32) -> Game {
+  let mut game = Game::with_config(GameConfig { lines_per_level: lines_per_level, ..GameConfig::default() }, Some(1));
+  game.set_start_level(start_level);
+  game.reset();
+  game.set_line_clear_delay(Duration::from_secs(0));
+  return game;
+}
+
+#[test]
+fn tetrises_level_up_on_the_goal() {
+  let mut game = game(1, 10);
+  assert_eq!(clear_tetris(&mut game), Vec::<u32>::new());
+  assert_eq!(game.lines_to_next_level(), 6);
+  assert_eq!(clear_tetris(&mut game), Vec::<u32>::new());
+  assert_eq!(clear_tetris(&mut game), vec![2]);
+  assert_eq!(game.current_score().level, 2);
+  assert_eq!(game.lines_to_next_level(), 8);
+}
+
+#[test]
+fn one_big_clear_gains_every_level_it_reaches() {
+  let mut game = game(1, 2);
+  assert_eq!(clear_tetris(&mut game), vec![3]);
+  assert_eq!(game.current_score().level, 3);
+  assert_eq!(game.lines_to_next_level(), 2);
+}
+
+#[test]
+fn the_goal_counts_from_the_starting_level() {
+  let mut game = game(8, 10);
+  assert_eq!(game.current_score().level, 8);
+  assert_eq!(clear_tetris(&mut game), Vec::<u32>::new());
+  assert_eq!(clear_tetris(&mut game), Vec::<u32>::new());
+  assert_eq!(clear_tetris(&mut game), vec![9]);
+}
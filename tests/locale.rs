@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+use tetris_rs::locale::Strings;
+
+const FRENCH: &str = include_str!("../resources/lang/fr.toml");
+
+fn keys(prefix: &str, value: &toml::Value, keys: &mut BTreeSet<String>) {
+  match value {
+    toml::Value::Table(table) => {
+      for (key, value) in table {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        self::keys(&key, value, keys);
+      }
+    },
+    _ => {
+      keys.insert(prefix.to_string());
+    },
+  }
+}
+
+#[test]
+fn a_missing_text_reads_as_in_english() {
+  let strings = Strings::from_toml("xx", "[banner]\ngame_over = \"Fin\"\n").unwrap();
+  assert_eq!(strings.get("banner.game_over"), "Fin");
+  assert_eq!(strings.get("banner.draw"), "Draw");
+  assert_eq!(strings.get("no.such.key"), "no.such.key");
+}
+
+#[test]
+fn fill_replaces_every_name() {
+  let strings = Strings::english();
+  assert_eq!(strings.fill("title.window", &[("level", String::from("7")), ("score", strings.number(45_300))]), "TetrisRS — Lv 7 · 45,300 pts");
+  assert_eq!(strings.number(-1_234_567), "-1,234,567");
+  assert_eq!(strings.number(999), "999");
+}
+
+#[test]
+fn a_file_that_is_not_text_fails() {
+  assert!(Strings::from_toml("xx", "[banner]\ngame_over = 3\n").is_err());
+  assert!(Strings::from_toml("xx", "[banner\n").is_err());
+}
+
+#[test]
+fn french_has_every_english_text() {
+  let (mut english, mut french) = (BTreeSet::new(), BTreeSet::new());
+  keys("", &toml::from_str(include_str!("../resources/lang/en.toml")).unwrap(), &mut english);
+  keys("", &toml::from_str(FRENCH).unwrap(), &mut french);
+  assert_eq!(english, french);
+  assert_eq!(Strings::from_toml("fr", FRENCH).unwrap().number(45_300), "45\u{a0}300");
+}
@@ -0,0 +1,90 @@
+use std::io::Write;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use tetris_rs::game::{Game, GameSnapshot, Input};
+use tetris_rs::network::{Connection, ConnectionState, Message};
+
+// Polls both ends until the receiver got `count` messages.
+fn receive(sender: &mut Connection, receiver: &mut Connection, count: usize) -> Vec<Message> {
+  let mut messages = Vec::new();
+  for _ in 0..500 {
+    sender.poll();
+    messages.extend(receiver.poll());
+    if messages.len() >= count {
+      break;
+    }
+    thread::sleep(Duration::from_millis(2));
+  }
+  return messages;
+}
+
+fn connected() -> (Connection, Connection) {
+  let mut host = Connection::host(0).unwrap();
+  let mut guest = Connection::join(&format!("127.0.0.1:{}", host.port().unwrap())).unwrap();
+  for _ in 0..500 {
+    host.poll();
+    if host.state() == ConnectionState::Connected {
+      break;
+    }
+    thread::sleep(Duration::from_millis(2));
+  }
+  assert_eq!((host.state(), guest.state()), (ConnectionState::Connected, ConnectionState::Connected));
+  guest.poll();
+  return (host, guest);
+}
+
+#[test]
+fn messages_arrive_whole_and_in_order() {
+  let (mut host, mut guest) = connected();
+  let mut game = Game::with_seed(5);
+  game.apply(Input::HardDrop);
+  let snapshot: GameSnapshot = game.snapshot();
+  let sent = vec![
+    Message::Hello { version: 2, seed: 5 },
+    Message::BoardSnapshot(Box::new(snapshot)),
+    Message::Garbage(3),
+    Message::GameOver { won: true },
+  ];
+  for message in &sent {
+    host.send(message);
+  }
+  assert_eq!(receive(&mut host, &mut guest, sent.len()), sent);
+  guest.send(&Message::Garbage(1));
+  assert_eq!(receive(&mut guest, &mut host, 1), vec![Message::Garbage(1)]);
+}
+
+#[test]
+fn the_host_waits_again_after_the_guest_leaves() {
+  let (mut host, guest) = connected();
+  drop(guest);
+  for _ in 0..500 {
+    host.poll();
+    if host.state() != ConnectionState::Connected {
+      break;
+    }
+    thread::sleep(Duration::from_millis(2));
+  }
+  assert_eq!(host.state(), ConnectionState::Waiting);
+}
+
+#[test]
+fn an_oversized_frame_closes_the_connection() {
+  let mut host = Connection::host(0).unwrap();
+  let mut peer = TcpStream::connect(("127.0.0.1", host.port().unwrap())).unwrap();
+  let frame = bincode::serialize(&Message::Garbage(2)).unwrap();
+  peer.write_all(&(frame.len() as u32).to_le_bytes()).unwrap();
+  peer.write_all(&frame).unwrap();
+  peer.write_all(&u32::MAX.to_le_bytes()).unwrap();
+  let mut messages = Vec::new();
+  for _ in 0..500 {
+    messages.extend(host.poll());
+    if !messages.is_empty() && host.state() != ConnectionState::Connected {
+      break;
+    }
+    thread::sleep(Duration::from_millis(2));
+  }
+  assert_eq!(messages, vec![Message::Garbage(2)]);
+  assert_eq!(host.state(), ConnectionState::Waiting);
+}
@@ -0,0 +1,55 @@
+use tetris_rs::opener::Opener;
+use tetris_rs::piece::{create_piece, Tetromino};
+
+const OPENERS: [(&str, &str); 3] = [
+  ("pco", include_str!("../resources/openers/pco.txt")),
+  ("tki", include_str!("../resources/openers/tki.txt")),
+  ("dt-cannon", include_str!("../resources/openers/dt-cannon.txt")),
+];
+
+#[test]
+fn the_shipped_openers_are_valid() {
+  for (name, text) in OPENERS.iter() {
+    let opener = Opener::parse(name, text, 10, 22).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    assert_eq!(opener.targets.len(), opener.pieces.len());
+    assert!(opener.targets.iter().all(|cases| cases.len() == 4));
+  }
+}
+
+#[test]
+fn a_piece_must_have_the_shape_of_its_letter() {
+  assert!(Opener::parse("t", "pieces: O\n11........\n.11.......\n", 10, 22).unwrap_err().contains("not a O piece"));
+  assert!(Opener::parse("t", "pieces: I\n111.......\n", 10, 22).is_err());
+  assert!(Opener::parse("t", "pieces: I\n1111..2...\n", 10, 22).unwrap_err().contains("after the 1"));
+}
+
+#[test]
+fn no_row_may_clear_before_the_last_piece() {
+  let text = "pieces: IIT\n.......3..\n......333.\n11112222##\n";
+  assert!(Opener::parse("t", text, 10, 22).unwrap_err().contains("full"));
+  // The last piece may.
+  assert!(Opener::parse("t", "pieces: IT\n.......2..\n1111##222#\n", 10, 22).is_ok());
+}
+
+#[test]
+fn a_piece_is_right_in_any_target_of_its_kind() {
+  let opener = Opener::parse("t", "pieces: OIO\n..........\n11.....33.\n112222.33.\n", 10, 22).unwrap();
+  let mut run = opener.start();
+  // The second O first, as after a hold.
+  let mut piece = create_piece(Tetromino::O, 10);
+  piece.x = 7;
+  piece.y = 20;
+  assert!(opener.place(&mut run, &piece));
+  piece.x = 0;
+  assert!(opener.place(&mut run, &piece));
+  assert!(!opener.is_done(&run));
+  let mut piece = create_piece(Tetromino::I, 10);
+  piece.x = 2;
+  piece.y = 19;
+  assert!(!opener.place(&mut run, &piece));
+  assert!(opener.is_done(&run));
+  // Nothing counts past the sequence.
+  piece.y = 20;
+  assert!(!opener.place(&mut run, &piece));
+  assert_eq!((run.right(), run.placements.len()), (2, 3));
+}
@@ -0,0 +1,328 @@
+use std::time::Duration;
+
+use proptest::prelude::*;
+
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::{Game, GameEvent, GameSnapshot, Input, Mode, PIECE_HISTORY};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, Piece, Tetromino};
+
+fn tetromino() -> impl Strategy<Value = Tetromino> {
+  return prop::sample::select(Tetromino::ALL.to_vec());
+}
+
+// A board where each case is filled with a 1 in `1 / density` chance.
+fn grid(density: u32) -> impl Strategy<Value = Grid> {
+  return prop::collection::vec(0..density, DEFAULT_GRID_WIDTH * DEFAULT_GRID_HEIGHT).prop_map(|cells| {
+    let mut grid = Grid::default();
+    for (i, &cell) in cells.iter().enumerate() {
+      if cell == 0 {
+        grid.set((i % DEFAULT_GRID_WIDTH) as i32, (i / DEFAULT_GRID_WIDTH) as i32, Case::Filled(Tetromino::O));
+      }
+    }
+    grid
+  });
+}
+
+// Bottom-heavy board with some full rows, as left after a lock.
+fn grid_with_full_rows() -> impl Strategy<Value = Grid> {
+  return (grid(3), prop::collection::vec(0..DEFAULT_GRID_HEIGHT as i32, 0..5)).prop_map(|(mut grid, rows)| {
+    for y in rows {
+      for x in 0..DEFAULT_GRID_WIDTH as i32 {
+        grid.set(x, y, Case::Filled(Tetromino::I));
+      }
+    }
+    grid
+  });
+}
+
+fn piece() -> impl Strategy<Value = Piece> {
+  return (tetromino(), 0..4usize, -2..DEFAULT_GRID_WIDTH as i32, -2..DEFAULT_GRID_HEIGHT as i32).prop_map(|(tetromino, rotation, x, y)| {
+    let mut piece = create_piece(tetromino, DEFAULT_GRID_WIDTH);
+    piece.index_rotation = rotation;
+    piece.x = x;
+    piece.y = y;
+    piece
+  });
+}
+
+fn filled_cells(grid: &Grid) -> usize {
+  let mut count = 0;
+  for x in 0..grid.width() as i32 {
+    for y in 0..grid.height() as i32 {
+      if grid.get(x, y) != Some(Case::Empty) {
+        count += 1;
+      }
+    }
+  }
+  return count;
+}
+
+fn absolute_cells(piece: &Piece) -> Vec<(i32, i32)> {
+  let mut cells: Vec<(i32, i32)> = piece.cells().iter().map(|&(x, y)| (piece.x + x, piece.y + y)).collect();
+  cells.sort();
+  return cells;
+}
+
+// A seeded game with its first piece in play.
+fn started_game(seed: u64) -> Game {
+  let mut game = Game::with_seed(seed);
+  while game.current_piece().is_none() {
+    game.step(Duration::from_millis(100));
+  }
+  return game;
+}
+
+// Every piece of both sets, mirrored or not, from every orientation, both
+// ways, in open space.
+#[test]
+fn four_rotations_in_open_space_are_the_identity() {
+  for &tetromino in Tetromino::ALL.iter().chain(Tetromino::PENTOMINOES.iter()) {
+    for (rotation, &mirrored) in (0..4).flat_map(|rotation| [false, true].iter().map(move |mirrored| (rotation, mirrored))) {
+      for &input in &[Input::RotateCW, Input::RotateCCW] {
+        let mut piece = create_piece(tetromino, DEFAULT_GRID_WIDTH);
+        piece.index_rotation = rotation;
+        piece.mirrored = mirrored;
+        piece.y = DEFAULT_GRID_HEIGHT as i32 / 2;
+        let mut game = Game::new();
+        game.restore(&GameSnapshot { current_piece: Some(piece.clone()), ..GameSnapshot::default() });
+        for _ in 0..4 {
+          game.apply(input);
+        }
+        assert_eq!(absolute_cells(game.current_piece().unwrap()), absolute_cells(&piece), "{:?} from rotation {} with {:?}, mirrored {}", tetromino, rotation, input, mirrored);
+      }
+    }
+  }
+}
+
+// Rows from the top, laid on the bottom of a default board.
+fn bottom_grid(rows: &[&str]) -> Grid {
+  let mut grid = Grid::default();
+  let top = DEFAULT_GRID_HEIGHT - rows.len();
+  for (y, row) in rows.iter().enumerate() {
+    for (x, letter) in row.chars().enumerate() {
+      if letter == '#' {
+        grid.set(x as i32, (top + y) as i32, Case::Garbage);
+      }
+    }
+  }
+  return grid;
+}
+
+// The spawned I piece fills the slot of the third row from the bottom. Once
+// it clears, the lone case on the right falls into the second row and clears
+// it, then the lone case on the left, stopped by that row, falls into the
+// bottom one.
+fn three_chain_clears(cascade: bool) -> (Vec<u32>, Game) {
+  let grid = bottom_grid(&[
+    "#........#",
+    "###....###",
+    "#########.",
+    ".#########",
+  ]);
+  let mut game = Game::with_config(GameConfig { cascade: cascade, ..GameConfig::default() }, Some(1));
+  game.set_line_clear_delay(Duration::from_secs(0));
+  let piece = create_piece(Tetromino::I, DEFAULT_GRID_WIDTH);
+  game.restore(&GameSnapshot { grid: grid, current_piece: Some(piece), ..GameSnapshot::default() });
+  game.apply(Input::HardDrop);
+  let clears = game.drain_events().into_iter().filter_map(|event| match event {
+    GameEvent::LinesCleared(lines) => Some(lines),
+    _ => None,
+  }).collect();
+  return (clears, game);
+}
+
+#[test]
+fn cascade_gravity_chains_the_crafted_board_three_times() {
+  let (clears, game) = three_chain_clears(true);
+  assert_eq!(clears, vec![1, 1, 1]);
+  assert!(game.grid().is_empty());
+  // 40, then twice and three times 40 for the chain.
+  assert_eq!(game.current_score().score, 40 + 80 + 120);
+
+  let (clears, game) = three_chain_clears(false);
+  assert_eq!(clears, vec![1]);
+  assert_eq!(filled_cells(game.grid()), 2 + 9 + 9);
+}
+
+#[test]
+fn ghost_full_rows_follow_the_moves() {
+  let grid = bottom_grid(&[
+    "#........#",
+    "###....###",
+    "#########.",
+    ".#########",
+  ]);
+  let mut game = Game::new();
+  game.restore(&GameSnapshot { grid: grid, current_piece: Some(create_piece(Tetromino::I, DEFAULT_GRID_WIDTH)), ..GameSnapshot::default() });
+  assert_eq!(game.ghost_full_rows(), &[DEFAULT_GRID_HEIGHT as i32 - 3]);
+  // Against the left side of the slot, it lands a row higher.
+  game.apply(Input::MoveLeft);
+  assert!(game.ghost_full_rows().is_empty());
+  game.apply(Input::MoveRight);
+  assert_eq!(game.ghost_full_rows(), &[DEFAULT_GRID_HEIGHT as i32 - 3]);
+}
+
+proptest! {
+  #[test]
+  fn four_rotations_restore_the_piece(piece in piece()) {
+    let rotated = piece.rotated().rotated().rotated().rotated();
+    prop_assert_eq!(absolute_cells(&rotated), absolute_cells(&piece));
+    let rotated = piece.rotated_counterclockwise().rotated_counterclockwise().rotated_counterclockwise().rotated_counterclockwise();
+    prop_assert_eq!(absolute_cells(&rotated), absolute_cells(&piece));
+  }
+
+  #[test]
+  fn four_rotations_in_play_restore_the_piece(seed in any::<u64>(), drop in 0..10usize, counterclockwise in any::<bool>()) {
+    let mut game = started_game(seed);
+    for _ in 0..drop {
+      game.apply(Input::SoftDrop);
+    }
+    let before = absolute_cells(game.current_piece().unwrap());
+    let input = if counterclockwise { Input::RotateCCW } else { Input::RotateCW };
+    for _ in 0..4 {
+      game.apply(input);
+    }
+    prop_assert_eq!(absolute_cells(game.current_piece().unwrap()), before);
+  }
+
+  // Zen, so the stack of hard drops never tops out.
+  #[test]
+  fn piece_history_is_the_last_pieces_spawned(seed in any::<u64>(), pieces in 1..20usize) {
+    let mut game = Game::with_seed(seed);
+    game.set_mode(Mode::Zen);
+    game.reset();
+    let mut spawned = Vec::new();
+    while spawned.len() < pieces {
+      match game.current_piece() {
+        Some(piece) => {
+          spawned.push(piece.tetromino);
+          game.apply(Input::HardDrop);
+        },
+        None => game.step(Duration::from_millis(100)),
+      }
+    }
+    let history: Vec<Tetromino> = game.piece_history().iter().map(|piece| piece.tetromino).collect();
+    prop_assert_eq!(&history[..], &spawned[spawned.len().saturating_sub(PIECE_HISTORY)..]);
+    game.reset();
+    prop_assert!(game.piece_history().is_empty());
+  }
+
+  #[test]
+  fn accepted_piece_stays_inside_and_does_not_overlap(grid in grid(4), piece in piece()) {
+    prop_assume!(!grid.collides(&piece, 0, 0));
+    let mut locked = grid.clone();
+    locked.put_piece(&piece);
+    // Only the rows above the board are open.
+    let on_board: Vec<(i32, i32)> = absolute_cells(&piece).into_iter().filter(|&(_, y)| y >= 0).collect();
+    for &(x, y) in &on_board {
+      prop_assert_eq!(grid.get(x, y), Some(Case::Empty));
+      prop_assert_eq!(locked.get(x, y), Some(Case::Filled(piece.tetromino)));
+    }
+    for (x, y) in absolute_cells(&piece) {
+      prop_assert!(x >= 0 && x < grid.width() as i32 && y < grid.height() as i32);
+    }
+    prop_assert_eq!(filled_cells(&locked), filled_cells(&grid) + on_board.len());
+  }
+
+  #[test]
+  fn rows_above_the_board_are_open(piece in piece(), rows in 1..4i32) {
+    let grid = Grid::default();
+    let mut above = piece.clone();
+    above.y = -rows;
+    let inside = absolute_cells(&above).iter().all(|&(x, y)| x >= 0 && x < grid.width() as i32 && y < grid.height() as i32);
+    prop_assert_eq!(grid.collides(&above, 0, 0), !inside);
+  }
+
+  #[test]
+  fn clearing_removes_exactly_the_full_rows(grid in grid_with_full_rows()) {
+    let mut cleared = grid.clone();
+    let count = cleared.clear_full_rows() as usize;
+    let full = (0..grid.height() as i32).filter(|&y| grid.is_row_full(y)).count();
+    prop_assert_eq!(count, full);
+    prop_assert!(filled_cells(&cleared) <= filled_cells(&grid));
+    prop_assert_eq!(filled_cells(&grid) - filled_cells(&cleared), count * grid.width());
+    prop_assert!(!cleared.has_full_row());
+  }
+
+  #[test]
+  fn settling_keeps_the_cases_and_leaves_nothing_to_fall(grid in grid(3)) {
+    let mut settled = grid.clone();
+    let falls = settled.settle();
+    prop_assert_eq!(filled_cells(&settled), filled_cells(&grid));
+    prop_assert!(falls.iter().all(|fall| fall.rows > 0));
+    prop_assert!(settled.clone().settle().is_empty());
+    prop_assert_eq!(falls.is_empty(), settled == grid);
+  }
+
+  #[test]
+  fn ghost_is_the_lowest_free_position(seed in any::<u64>(), grid in grid(5)) {
+    let mut game = started_game(seed);
+    let piece = game.current_piece().unwrap().clone();
+    prop_assume!(!grid.collides(&piece, 0, 0));
+    game.set_grid(grid.clone());
+    let drop = (0..).take_while(|&dy| !grid.collides(&piece, 0, dy)).last().unwrap();
+    prop_assert_eq!(game.ghost_y(), piece.y + drop);
+  }
+
+  #[test]
+  fn row_fills_follow_every_change(grid in grid_with_full_rows(), rows in 0..4usize, hole in 0..DEFAULT_GRID_WIDTH) {
+    let mut changed = grid.clone();
+    changed.clear_full_rows();
+    changed.settle();
+    changed.insert_garbage(rows, hole);
+    changed.empty_rows(&[false, true]);
+    for board in &[grid, changed] {
+      for y in 0..board.height() as i32 {
+        let filled = (0..board.width() as i32).filter(|&x| board.get(x, y) != Some(Case::Empty)).count();
+        prop_assert_eq!(board.row_fill(y), filled);
+      }
+    }
+  }
+
+  #[test]
+  fn ghost_full_rows_are_the_rows_the_drop_completes(seed in any::<u64>(), grid in grid_with_full_rows()) {
+    let mut game = started_game(seed);
+    let mut grid = grid;
+    grid.clear_full_rows();
+    game.set_grid(grid.clone());
+    let mut piece = game.current_piece().unwrap().clone();
+    prop_assume!(!grid.collides(&piece, 0, 0));
+    piece.y = game.ghost_y();
+    grid.put_piece(&piece);
+    let full: Vec<i32> = (0..grid.height() as i32).filter(|&y| grid.is_row_full(y)).collect();
+    prop_assert_eq!(game.ghost_full_rows(), full.as_slice());
+  }
+
+  #[test]
+  fn standing_i_piece_rotates_flat_against_the_walls(column in prop::sample::select(vec![0, 1, 8, 9]), rotation in prop::sample::select(vec![1usize, 3]), counterclockwise in any::<bool>()) {
+    let mut piece = create_piece(Tetromino::I, DEFAULT_GRID_WIDTH);
+    piece.index_rotation = rotation;
+    piece.x = column - piece.cells()[0].0;
+    piece.y = 5;
+    let mut game = Game::new();
+    game.restore(&GameSnapshot { current_piece: Some(piece), ..GameSnapshot::default() });
+    game.apply(if counterclockwise { Input::RotateCCW } else { Input::RotateCW });
+    let rotated = game.current_piece().unwrap();
+    prop_assert_eq!(rotated.index_rotation % 2, 0);
+    prop_assert!(!game.grid().collides(rotated, 0, 0));
+  }
+
+  // The flat piece with an end in `column`, by a wall, stands up over one of
+  // its own cases.
+  #[test]
+  fn flat_i_piece_stands_up_against_the_walls(column in prop::sample::select(vec![0, 1, 8, 9]), rotation in prop::sample::select(vec![0usize, 2]), counterclockwise in any::<bool>()) {
+    let mut piece = create_piece(Tetromino::I, DEFAULT_GRID_WIDTH);
+    piece.index_rotation = rotation;
+    piece.x = if column < 5 { column } else { column - 3 } - piece.cells()[0].0;
+    piece.y = 5;
+    let span = piece.x + piece.cells()[0].0..=piece.x + piece.cells()[3].0;
+    let mut game = Game::new();
+    game.restore(&GameSnapshot { current_piece: Some(piece), ..GameSnapshot::default() });
+    game.apply(if counterclockwise { Input::RotateCCW } else { Input::RotateCW });
+    let rotated = game.current_piece().unwrap();
+    prop_assert_eq!(rotated.index_rotation % 2, 1);
+    prop_assert!(!game.grid().collides(rotated, 0, 0));
+    prop_assert!(span.contains(&(rotated.x + rotated.cells()[0].0)));
+  }
+}
@@ -0,0 +1,94 @@
+mod support;
+
+use tetris_rs::piece::{PieceSet, Tetromino};
+use tetris_rs::randomizer::{create_randomizer, create_randomizer_of, RandomizerKind};
+
+use support::{chi_squared, chi_squared_limit};
+
+const SAMPLE: usize = 70_000;
+const KINDS: [RandomizerKind; 3] = [RandomizerKind::Uniform, RandomizerKind::Bag, RandomizerKind::Tgm];
+
+fn deal(kind: RandomizerKind, pieces: PieceSet, seed: u64, count: usize) -> Vec<Tetromino> {
+  let mut randomizer = create_randomizer(kind, pieces, Some(seed));
+  return (0..count).map(|_| randomizer.next()).collect();
+}
+
+// Times each piece of `pieces` was dealt, in its order.
+fn counts(dealt: &[Tetromino], pieces: &[Tetromino]) -> Vec<u64> {
+  return pieces.iter().map(|tetromino| dealt.iter().filter(|&other| other == tetromino).count() as u64).collect();
+}
+
+#[test]
+fn the_support_limit_matches_the_tables() {
+  // 0.999 quantiles of the chi-squared distribution, within 2%.
+  assert!((chi_squared_limit(6) / 22.458 - 1.0).abs() < 0.02);
+  assert!((chi_squared_limit(11) / 31.264 - 1.0).abs() < 0.02);
+}
+
+#[test]
+fn uniform_deals_every_piece_as_often() {
+  for &(set, seed) in &[(PieceSet::Tetromino, 1), (PieceSet::Tetromino, 2), (PieceSet::Pentomino, 3)] {
+    let pieces = set.pieces();
+    let counts = counts(&deal(RandomizerKind::Uniform, set, seed, SAMPLE), pieces);
+    let expected = vec![1.0 / pieces.len() as f64; pieces.len()];
+    let statistic = chi_squared(&counts, &expected);
+    assert!(statistic < chi_squared_limit(pieces.len() - 1), "{:?} seed {}: {} for {:?}", set, seed, statistic, counts);
+    for &count in &counts {
+      assert!((count as f64 / SAMPLE as f64 - expected[0]).abs() < 0.01, "{:?}", counts);
+    }
+  }
+}
+
+#[test]
+fn uniform_almost_never_deals_a_piece_three_times_in_a_row() {
+  for &seed in &[8, 9] {
+    let dealt = deal(RandomizerKind::Uniform, PieceSet::Tetromino, seed, SAMPLE);
+    let triples = dealt.windows(3).filter(|window| window[0] == window[1] && window[1] == window[2]).count();
+    // 1/49, 2%, without the rerolls; about 1/7 * (1/7)^3 after them.
+    let rate = triples as f64 / (dealt.len() - 2) as f64;
+    assert!(rate < 0.001, "seed {}: {}", seed, rate);
+    // Pairs are left alone.
+    let pairs = dealt.windows(2).filter(|window| window[0] == window[1]).count();
+    assert!(pairs as f64 / (dealt.len() - 1) as f64 > 0.1, "seed {}: {} pairs", seed, pairs);
+  }
+}
+
+#[test]
+fn bag_deals_every_piece_once_in_each_bag() {
+  for &set in &[PieceSet::Tetromino, PieceSet::Pentomino] {
+    let pieces = set.pieces();
+    let dealt = deal(RandomizerKind::Bag, set, 4, SAMPLE / pieces.len() * pieces.len());
+    for bag in dealt.chunks(pieces.len()) {
+      assert_eq!(counts(bag, pieces), vec![1; pieces.len()], "{:?}", bag);
+    }
+  }
+}
+
+#[test]
+fn tgm_rarely_deals_one_of_the_last_four() {
+  for &seed in &[5, 6] {
+    let dealt = deal(RandomizerKind::Tgm, PieceSet::Tetromino, seed, SAMPLE);
+    let repeats = (4..dealt.len()).filter(|&index| dealt[index - 4..index].contains(&dealt[index])).count();
+    // 1 - (6/7)^4, 46%, for the uniform randomizer; at most (4/7)^6, 3.5%,
+    // once every reroll is spent.
+    let rate = repeats as f64 / (dealt.len() - 4) as f64;
+    assert!(rate < 0.04, "seed {}: {}", seed, rate);
+    assert!(![Tetromino::S, Tetromino::Z, Tetromino::O].contains(&dealt[0]));
+  }
+}
+
+// A randomizer deals a Tetromino, so it cannot deal Case::Empty; what is
+// left to check is that it stays within the pieces it was given.
+#[test]
+fn only_the_pieces_given_are_dealt() {
+  let sets: [&[Tetromino]; 3] = [&Tetromino::ALL, &[Tetromino::S, Tetromino::Z], &[Tetromino::O]];
+  for &kind in &KINDS {
+    for &pieces in &sets {
+      let mut randomizer = create_randomizer_of(kind, pieces, Some(7));
+      for _ in 0..SAMPLE / 10 {
+        let tetromino = randomizer.next();
+        assert!(pieces.contains(&tetromino), "{:?} dealt {:?} out of {:?}", kind, tetromino, pieces);
+      }
+    }
+  }
+}
@@ -0,0 +1,74 @@
+use tetris_rs::ai::{Bot, Controller, Difficulty};
+use tetris_rs::game::{Game, Input};
+use tetris_rs::replay::{Replay, ReplayInput, ReplayPosition};
+use tetris_rs::timestep::LOGIC_TICK;
+
+// A game of the bot recorded as the game loop does, cut after `ticks`.
+fn recorded_game(seed: u64, ticks: u32) -> (Replay, Game) {
+  let mut game = Game::with_seed(seed);
+  let mut bot = Bot::new(Difficulty::Hard);
+  let mut replay = Replay::start(&game);
+  while replay.ticks < ticks && !game.is_over() {
+    if let Some(input) = bot.update(&game, LOGIC_TICK) {
+      replay.record(input);
+      game.apply(input);
+    }
+    game.step(LOGIC_TICK);
+    replay.step();
+    game.drain_events();
+  }
+  replay.finish(&game);
+  return (replay, game);
+}
+
+#[test]
+fn a_replay_plays_the_game_again() {
+  let (replay, game) = recorded_game(3, 120 * 60);
+  assert!(game.pieces_locked() > 20);
+  let mut position = ReplayPosition::new(replay.clone());
+  let mut watched = replay.new_game();
+  while position.advance(&mut watched) {
+    watched.drain_events();
+  }
+  assert!(position.is_done());
+  assert_eq!(watched.snapshot(), game.snapshot());
+  assert_eq!(position.seek(replay.ticks).snapshot(), game.snapshot());
+}
+
+#[test]
+fn seeking_goes_from_lock_to_lock() {
+  let (replay, game) = recorded_game(8, 120 * 30);
+  let mut position = ReplayPosition::new(replay.clone());
+  assert_eq!(position.previous_lock(), 0);
+  let first = position.next_lock();
+  assert_eq!(position.seek(first).pieces_locked(), 1);
+  let second = position.next_lock();
+  assert!(second > first);
+  assert_eq!(position.seek(second).pieces_locked(), 2);
+  assert_eq!(position.previous_lock(), first);
+  assert_eq!(position.tick(), second);
+  // The end, whatever it was.
+  assert_eq!(position.fraction(10), replay.ticks);
+  assert_eq!(position.seek(u32::MAX).pieces_locked(), game.pieces_locked());
+  assert_eq!(position.next_lock(), replay.ticks);
+}
+
+#[test]
+fn replays_survive_json() {
+  let (replay, _) = recorded_game(5, 120 * 10);
+  let text = serde_json::to_string(&replay).unwrap();
+  assert_eq!(Replay::from_json(&text).unwrap(), replay);
+}
+
+#[test]
+fn broken_replays_are_refused() {
+  let (replay, _) = recorded_game(5, 120 * 10);
+  let empty = Replay { pieces: Vec::new(), ..replay.clone() };
+  assert!(Replay::from_json(&serde_json::to_string(&empty).unwrap()).is_err());
+  let mut late = replay.clone();
+  late.inputs.push(ReplayInput { tick: late.ticks, input: Input::HardDrop });
+  assert!(Replay::from_json(&serde_json::to_string(&late).unwrap()).is_err());
+  let mut unordered = replay;
+  unordered.inputs.insert(0, ReplayInput { tick: 1000, input: Input::HardDrop });
+  assert!(Replay::from_json(&serde_json::to_string(&unordered).unwrap()).is_err());
+}
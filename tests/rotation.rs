@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+
+use tetris_rs::game::{Game, GameSnapshot, Input};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_HEIGHT, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Case, Piece, Tetromino};
+use tetris_rs::timestep::LOGIC_TICK;
+
+type Cells = Vec<(i32, i32)>;
+
+// The cases of the board the piece covers after each of 4 turns in the
+// middle of an empty board, where no kick moves it.
+fn turns(tetromino: Tetromino, input: Input, mirrored: bool) -> Vec<Cells> {
+  let mut piece = create_piece(tetromino, DEFAULT_GRID_WIDTH);
+  piece.y = 8;
+  piece.mirrored = mirrored;
+  let mut game = Game::new();
+  game.restore(&GameSnapshot { current_piece: Some(piece), ..GameSnapshot::default() });
+  return (0..4).map(|_| {
+    game.apply(input);
+    let piece = game.current_piece().unwrap();
+    let mut cells: Cells = piece.cells().iter().map(|&(x, y)| (piece.x + x, piece.y + y)).collect();
+    cells.sort();
+    cells
+  }).collect();
+}
+
+fn positions(turns: &[Cells]) -> usize {
+  return turns.iter().collect::<BTreeSet<_>>().len();
+}
+
+#[test]
+fn s_z_and_i_alternate_between_two_positions() {
+  assert_eq!(turns(Tetromino::I, Input::RotateCW, false), vec![
+    vec![(5, 8), (5, 9), (5, 10), (5, 11)],
+    vec![(3, 9), (4, 9), (5, 9), (6, 9)],
+    vec![(5, 8), (5, 9), (5, 10), (5, 11)],
+    vec![(3, 9), (4, 9), (5, 9), (6, 9)],
+  ]);
+  assert_eq!(turns(Tetromino::S, Input::RotateCW, false), vec![
+    vec![(4, 8), (4, 9), (5, 9), (5, 10)],
+    vec![(3, 9), (4, 8), (4, 9), (5, 8)],
+    vec![(4, 8), (4, 9), (5, 9), (5, 10)],
+    vec![(3, 9), (4, 8), (4, 9), (5, 8)],
+  ]);
+  assert_eq!(turns(Tetromino::Z, Input::RotateCW, false), vec![
+    vec![(4, 9), (4, 10), (5, 8), (5, 9)],
+    vec![(3, 8), (4, 8), (4, 9), (5, 9)],
+    vec![(4, 9), (4, 10), (5, 8), (5, 9)],
+    vec![(3, 8), (4, 8), (4, 9), (5, 9)],
+  ]);
+}
+
+#[test]
+fn every_piece_has_its_number_of_positions_either_way() {
+  let expected = [
+    (Tetromino::I, 2), (Tetromino::O, 1), (Tetromino::T, 4), (Tetromino::S, 2),
+    (Tetromino::Z, 2), (Tetromino::J, 4), (Tetromino::L, 4),
+  ];
+  for &(tetromino, count) in &expected {
+    for &mirrored in &[false, true] {
+      let clockwise = turns(tetromino, Input::RotateCW, mirrored);
+      let counterclockwise = turns(tetromino, Input::RotateCCW, mirrored);
+      assert_eq!(positions(&clockwise), count, "{:?} mirrored {}", tetromino, mirrored);
+      assert_eq!(positions(&counterclockwise), count, "{:?} mirrored {}", tetromino, mirrored);
+      // A full turn comes back where it started.
+      assert_eq!(clockwise[3], counterclockwise[3]);
+    }
+  }
+}
+
+// The game right after its first spawn, `inputs` applied before it.
+fn spawned(buffer: bool, inputs: &[Input]) -> Game {
+  let mut game = Game::with_seed(4);
+  game.set_buffer_spawn_inputs(buffer);
+  for &input in inputs {
+    game.apply(input);
+  }
+  while game.current_piece().is_none() {
+    game.step(LOGIC_TICK);
+  }
+  return game;
+}
+
+#[test]
+fn inputs_before_a_spawn_apply_to_the_piece_when_buffered() {
+  let mut live = spawned(false, &[]);
+  live.apply(Input::Hold);
+  live.apply(Input::RotateCCW);
+  live.apply(Input::MoveLeft);
+  // The latest rotation and direction only.
+  let buffered = spawned(true, &[Input::RotateCW, Input::MoveRight, Input::Hold, Input::RotateCCW, Input::MoveLeft]);
+  assert_eq!(buffered.current_piece(), live.current_piece());
+  assert_eq!(buffered.hold_piece(), live.hold_piece());
+  let ignored = spawned(false, &[Input::Hold, Input::RotateCCW, Input::MoveLeft]);
+  assert_eq!(ignored.current_piece(), spawned(true, &[]).current_piece());
+  assert!(ignored.hold_piece().is_none());
+  // A reset forgets them.
+  let mut game = Game::with_seed(4);
+  game.set_buffer_spawn_inputs(true);
+  game.apply(Input::Hold);
+  game.reset();
+  while game.current_piece().is_none() {
+    game.step(LOGIC_TICK);
+  }
+  assert!(game.hold_piece().is_none());
+}
+
+// Every case is filled but those of the piece: no turn nor kick fits.
+#[test]
+fn a_rotation_into_the_stack_is_refused() {
+  let mut piece = create_piece(Tetromino::T, DEFAULT_GRID_WIDTH);
+  piece.y = 8;
+  let mut grid = Grid::default();
+  for x in 0..DEFAULT_GRID_WIDTH as i32 {
+    for y in 0..DEFAULT_GRID_HEIGHT as i32 {
+      grid.set(x, y, Case::Garbage);
+    }
+  }
+  for &(x, y) in piece.cells() {
+    grid.set(piece.x + x, piece.y + y, Case::Empty);
+  }
+  for &input in &[Input::RotateCW, Input::RotateCCW] {
+    let mut game = Game::new();
+    game.restore(&GameSnapshot { grid: grid.clone(), current_piece: Some(piece.clone()), ..GameSnapshot::default() });
+    game.apply(input);
+    assert_eq!(game.current_piece(), Some(&piece), "{:?}", input);
+  }
+}
+
+// From every orientation against either wall, a turn is kicked back in.
+#[test]
+fn a_rotation_against_a_wall_is_kicked_inside() {
+  for rotation in 0..4 {
+    for &left in &[true, false] {
+      for &input in &[Input::RotateCW, Input::RotateCCW] {
+        let mut piece = create_piece(Tetromino::T, DEFAULT_GRID_WIDTH);
+        piece.index_rotation = rotation;
+        piece.y = 8;
+        let columns: Vec<i32> = piece.cells().iter().map(|c| c.0).collect();
+        piece.x = if left { -columns.iter().min().unwrap() } else { DEFAULT_GRID_WIDTH as i32 - 1 - columns.iter().max().unwrap() };
+        let mut game = Game::new();
+        game.restore(&GameSnapshot { current_piece: Some(piece.clone()), ..GameSnapshot::default() });
+        game.apply(input);
+        let turned = game.current_piece().unwrap();
+        assert_ne!(turned.index_rotation, rotation, "from {} {:?} left {}", rotation, input, left);
+        for &(x, y) in turned.cells() {
+          assert!(game.grid().get(turned.x + x, turned.y + y) == Some(Case::Empty), "from {} {:?} left {}: {:?}", rotation, input, left, turned);
+        }
+      }
+    }
+  }
+}
+
+// `tetromino` on the first visible row, its top above the board, over a
+// board full from `top` down but for the cases it covers.
+fn straddling_game(tetromino: Tetromino, top: i32) -> (Game, Piece) {
+  let mut piece = create_piece(tetromino, DEFAULT_GRID_WIDTH);
+  piece.y = -1;
+  assert!(piece.cells().iter().any(|&(_, y)| piece.y + y < 0) && piece.cells().iter().any(|&(_, y)| piece.y + y == 0));
+  let mut grid = Grid::default();
+  for x in 0..DEFAULT_GRID_WIDTH as i32 {
+    for y in top..DEFAULT_GRID_HEIGHT as i32 {
+      grid.set(x, y, Case::Garbage);
+    }
+  }
+  for &(x, y) in piece.cells() {
+    grid.set(piece.x + x, piece.y + y, Case::Empty);
+  }
+  let mut game = Game::new();
+  game.restore(&GameSnapshot { grid: grid, current_piece: Some(piece.clone()), ..GameSnapshot::default() });
+  return (game, piece);
+}
+
+#[test]
+fn rotations_on_the_first_visible_row_use_the_rows_above() {
+  // Free above the stack: the turn stays in place, partly above the board.
+  for &input in &[Input::RotateCW, Input::RotateCCW] {
+    let (mut game, piece) = straddling_game(Tetromino::T, 2);
+    game.apply(input);
+    let turned = game.current_piece().unwrap();
+    assert_ne!(turned.index_rotation, piece.index_rotation, "{:?}", input);
+    assert_eq!((turned.x, turned.y), (piece.x, piece.y), "{:?}", input);
+    assert!(turned.cells().iter().any(|&(_, y)| turned.y + y < 0), "{:?}", input);
+  }
+  // In the stack, the T gets out by the kick up, through its own cases.
+  let (mut game, piece) = straddling_game(Tetromino::T, 0);
+  game.apply(Input::RotateCW);
+  let turned = game.current_piece().unwrap();
+  assert_eq!((turned.x, turned.y), (piece.x, piece.y - 1));
+  // The S turned up still has a case in the stack, and no kick fits.
+  for &input in &[Input::RotateCW, Input::RotateCCW] {
+    let (mut game, piece) = straddling_game(Tetromino::S, 0);
+    game.apply(input);
+    assert_eq!(game.current_piece(), Some(&piece), "{:?}", input);
+  }
+}
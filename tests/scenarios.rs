@@ -0,0 +1,89 @@
+mod support;
+
+use tetris_rs::game::{Game, GameEvent, Input};
+use tetris_rs::piece::Tetromino;
+
+use support::script::Script;
+
+// The last `rows` rows of the board, from the top.
+fn bottom_rows(game: &Game, rows: usize) -> Vec<String> {
+  let ascii = game.grid().to_ascii();
+  let lines: Vec<&str> = ascii.lines().collect();
+  return lines[lines.len() - rows..].iter().map(|line| line.to_string()).collect();
+}
+
+fn moves(script: Script, ms: u64, input: Input, count: u64) -> Script {
+  return (0..count).fold(script, |script, index| script.at_ms(ms + index * 10, input));
+}
+
+#[test]
+fn a_flat_i_clears_a_single() {
+  let mut game = Script::new().pieces(&[Tetromino::I]).board("###....###")
+    .at_ms(200, Input::HardDrop)
+    .play(1000);
+  assert!(game.grid().is_empty());
+  // 40 for the single, the drop scores nothing.
+  assert_eq!(game.current_score().score, 40);
+  assert_eq!((game.current_score().level, game.current_score().line_removed), (1, 1));
+  assert!(game.drain_events().contains(&GameEvent::LinesCleared(1)));
+}
+
+#[test]
+fn an_upright_i_in_the_well_scores_a_tetris() {
+  let script = Script::new().pieces(&[Tetromino::I]).board("
+#########.
+#########.
+#########.
+#########.").at_ms(200, Input::RotateCW);
+  let mut game = moves(script, 210, Input::MoveRight, 4).at_ms(300, Input::HardDrop).play(1000);
+  assert!(game.grid().is_empty());
+  assert_eq!(game.current_score().score, 1200);
+  assert_eq!((game.current_score().level, game.current_score().line_removed), (1, 4));
+  let events = game.drain_events();
+  assert!(events.contains(&GameEvent::LinesCleared(4)));
+  assert!(events.contains(&GameEvent::Attack(4)));
+}
+
+#[test]
+fn a_tower_of_o_tops_out() {
+  let script = (0..12).fold(Script::new().pieces(&[Tetromino::O]), |script, index| script.at_ms(200 + index * 150, Input::HardDrop));
+  let mut game = script.play(2000);
+  assert!(game.is_over() && !game.is_won());
+  assert_eq!(game.pieces_locked(), 10);
+  assert!(game.grid().to_ascii().lines().all(|line| line == "....OO...."));
+  assert_eq!(game.drain_events().last(), Some(&GameEvent::GameOver));
+}
+
+// The piece spawned is the one tested against the stack: on a board full
+// but for a column, the first one tops out without being locked.
+#[test]
+fn a_piece_spawned_into_the_stack_tops_out() {
+  let height = Game::new().config().grid_height;
+  let mut game = Script::new().pieces(&[Tetromino::O]).board(&"#########.\n".repeat(height)).play(300);
+  assert!(game.is_over() && !game.is_won());
+  assert_eq!(game.pieces_locked(), 0);
+  assert_eq!(game.drain_events().last(), Some(&GameEvent::GameOver));
+}
+
+#[test]
+fn an_i_turned_against_the_wall_is_kicked_off_it() {
+  // Upright in the first column, flat it would stick out of the board.
+  let script = moves(Script::new().pieces(&[Tetromino::I]).at_ms(200, Input::RotateCW), 210, Input::MoveLeft, 6);
+  let game = script.at_ms(300, Input::RotateCW).at_ms(400, Input::HardDrop).play(1000);
+  assert_eq!(bottom_rows(&game, 2), vec!["..........", "IIII......"]);
+}
+
+#[test]
+fn holds_swap_the_piece_in_play() {
+  let mut game = Script::new().pieces(&[Tetromino::T, Tetromino::I, Tetromino::O])
+    // The T goes on hold for the I, then out of it for the O.
+    .at_ms(200, Input::Hold)
+    .at_ms(300, Input::HardDrop)
+    .at_ms(500, Input::Hold)
+    .at_ms(600, Input::HardDrop)
+    .play(1000);
+  assert_eq!(bottom_rows(&game, 3), vec!["....T.....", "...TTT....", "...IIII..."]);
+  assert_eq!(game.hold_piece().map(|piece| piece.tetromino), Some(Tetromino::O));
+  assert_eq!(game.current_score().score, 0);
+  assert_eq!(game.drain_events().iter().filter(|&&event| event == GameEvent::Hold).count(), 2);
+}
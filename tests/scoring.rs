@@ -0,0 +1,84 @@
+use tetris_rs::game::{Game, GameEvent, GameSnapshot, Input};
+use tetris_rs::grid::{Grid, DEFAULT_GRID_WIDTH};
+use tetris_rs::piece::{create_piece, Tetromino};
+use tetris_rs::scoring::{score_for_clear, ClearFlags};
+use tetris_rs::timestep::LOGIC_TICK;
+
+fn plain(lines: u32, level: u32) -> i64 {
+  return score_for_clear(lines, level, ClearFlags::default());
+}
+
+#[test]
+fn each_line_count_has_its_points() {
+  assert_eq!(plain(0, 1), 0);
+  assert_eq!(plain(1, 1), 40);
+  assert_eq!(plain(2, 1), 100);
+  assert_eq!(plain(3, 1), 300);
+  assert_eq!(plain(4, 1), 1200);
+}
+
+#[test]
+fn the_level_multiplies_the_points() {
+  for lines in 0..=4 {
+    for &level in &[2, 7, 15, 30] {
+      assert_eq!(plain(lines, level), plain(lines, 1) * level as i64, "{} lines at level {}", lines, level);
+    }
+  }
+  assert_eq!(plain(4, 10), 12_000);
+}
+
+#[test]
+fn each_step_of_a_cascade_counts_once_more() {
+  let chain = |chain: u32| score_for_clear(2, 3, ClearFlags { chain: chain, ..ClearFlags::default() });
+  assert_eq!(chain(0), 300);
+  assert_eq!(chain(1), 300);
+  assert_eq!(chain(2), 600);
+  assert_eq!(chain(3), 900);
+}
+
+#[test]
+fn t_spins_score_more_than_plain_clears() {
+  let t_spin = |lines: u32, level: u32| score_for_clear(lines, level, ClearFlags { t_spin: true, ..ClearFlags::default() });
+  assert_eq!(t_spin(0, 1), 600);
+  assert_eq!(t_spin(1, 1), 1200);
+  assert_eq!(t_spin(2, 1), 1800);
+  assert_eq!(t_spin(3, 1), 2400);
+  assert_eq!(t_spin(4, 1), 0);
+  for lines in 1..=3 {
+    assert!(t_spin(lines, 1) > plain(lines, 1), "{} lines", lines);
+    assert_eq!(t_spin(lines, 6), t_spin(lines, 1) * 6, "{} lines", lines);
+  }
+}
+
+#[test]
+fn a_back_to_back_is_worth_half_as_much_more() {
+  let back_to_back = |lines: u32, t_spin: bool, chain: u32| score_for_clear(lines, 2, ClearFlags { chain: chain, t_spin: t_spin, back_to_back: true });
+  assert_eq!(back_to_back(4, false, 1), 3600);
+  assert_eq!(back_to_back(2, true, 1), 5400);
+  assert_eq!(back_to_back(4, false, 0), back_to_back(4, false, 1));
+  // On top of the step of the cascade.
+  assert_eq!(back_to_back(4, false, 2), 7200);
+}
+
+// A T turned into the slot under the overhang, then left to lock: a T-spin
+// double, after a difficult clear or not.
+fn t_spin_double(back_to_back: bool) -> Game {
+  let rows = "..........\n".repeat(17) + "####......\n###...####\n####.#####\n";
+  let mut piece = create_piece(Tetromino::T, DEFAULT_GRID_WIDTH);
+  piece.index_rotation = 1;
+  piece.y = 17;
+  let mut game = Game::new();
+  let snapshot = GameSnapshot { grid: Grid::from_ascii(&rows).unwrap(), current_piece: Some(piece), back_to_back: back_to_back, ..GameSnapshot::default() };
+  game.restore(&snapshot);
+  game.apply(Input::RotateCW);
+  while !game.drain_events().contains(&GameEvent::LinesCleared(2)) {
+    game.step(LOGIC_TICK);
+  }
+  return game;
+}
+
+#[test]
+fn a_t_spin_in_a_game_scores_its_points() {
+  assert_eq!(t_spin_double(false).current_score().score, 1800);
+  assert_eq!(t_spin_double(true).current_score().score, 2700);
+}
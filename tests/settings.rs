@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use tetris_rs::settings::Settings;
+
+#[test]
+fn settings_written_load_back_the_same() {
+  let mut bindings = BTreeMap::new();
+  bindings.insert(String::from("hold"), String::from("C"));
+  let settings = Settings {
+    theme_volume: 0.5,
+    effects_volume: 0.0,
+    ghost_on: false,
+    keymap: String::from("wasd"),
+    game_speed: 1.5,
+    handicap_rows: 4,
+    zoom: 2.0,
+    fit_board: false,
+    window_size: Some((1024.0, 768.0)),
+    window_position: Some((10.0, 20.0)),
+    bindings: bindings,
+    ..Settings::default()
+  };
+  assert_eq!(Settings::from_toml(&settings.to_toml().unwrap()).unwrap(), settings);
+  assert_eq!(Settings::from_toml(&Settings::default().to_toml().unwrap()).unwrap(), Settings::default());
+}
+
+#[test]
+fn unknown_and_missing_keys_fall_back_to_the_defaults() {
+  let settings = Settings::from_toml("ghost_on = false\nfrom_a_newer_version = 3\n\n[some_table]\nkey = \"value\"\n").unwrap();
+  assert_eq!(settings, Settings { ghost_on: false, ..Settings::default() });
+  assert_eq!(Settings::from_toml("").unwrap(), Settings::default());
+}
+
+#[test]
+fn volumes_out_of_range_are_clamped() {
+  let settings = Settings::from_toml("version = 0\ntheme_volume = 4.0\neffects_volume = -1.0\n").unwrap();
+  assert_eq!((settings.theme_volume, settings.effects_volume), (1.0, 0.0));
+  assert_eq!(settings.version, Settings::default().version);
+}
+
+#[test]
+fn saving_replaces_the_old_file_whole() {
+  let directory = std::env::temp_dir().join(format!("tetris_rs_settings_{}", std::process::id()));
+  let _ = fs::remove_dir_all(&directory);
+  Settings::default().save(&directory).unwrap();
+  let settings = Settings { ghost_on: false, theme_volume: 0.3, ..Settings::default() };
+  settings.save(&directory).unwrap();
+  let text = fs::read_to_string(directory.join("settings.toml")).unwrap();
+  let files = fs::read_dir(&directory).unwrap().count();
+  fs::remove_dir_all(&directory).unwrap();
+  assert_eq!(Settings::from_toml(&text).unwrap(), settings);
+  assert_eq!(files, 1);
+}
@@ -0,0 +1,49 @@
+use tetris_rs::config::GameConfig;
+use tetris_rs::game::Mode;
+use tetris_rs::piece::PieceSet;
+use tetris_rs::simulate::{simulate, Policy, Simulation, Spread};
+
+fn simulation(policy: Policy, mode: Mode, max_pieces: u32) -> Simulation {
+  return Simulation { config: GameConfig::default(), mode: mode, level: 1, policy: policy, max_pieces: max_pieces };
+}
+
+#[test]
+fn the_same_seeds_play_the_same_games() {
+  let simulation = simulation(Policy::Greedy, Mode::Marathon, 300);
+  assert_eq!(simulation.play(7), simulation.play(7));
+  let summary = simulate(&simulation, 3, 7);
+  assert_eq!(summary.games, 3);
+  assert_eq!(summary.score, simulate(&simulation, 3, 7).score);
+}
+
+#[test]
+fn games_end_on_a_top_out_or_the_piece_limit() {
+  let outcome = simulation(Policy::Greedy, Mode::Marathon, 50).play(1);
+  assert!(outcome.cut);
+  assert_eq!(outcome.pieces, 50);
+  let outcome = simulation(Policy::Random, Mode::Marathon, 10_000).play(1);
+  assert!(!outcome.cut);
+  let outcome = simulation(Policy::Greedy, Mode::Sprint, 10_000).play(1);
+  assert!(!outcome.cut);
+  assert!(outcome.lines >= 40);
+}
+
+// Random placements, on every mode and with pentominoes, never panic.
+#[test]
+fn random_games_run_to_the_end() {
+  for &mode in &[Mode::Marathon, Mode::Sprint, Mode::Ultra, Mode::Zen] {
+    let summary = simulate(&simulation(Policy::Random, mode, 500), 50, 3);
+    assert_eq!(summary.games, 50);
+  }
+  let mut pentominoes = simulation(Policy::Random, Mode::Marathon, 500);
+  pentominoes.config = GameConfig { pieces: PieceSet::Pentomino, grid_width: 12, ..GameConfig::default() };
+  simulate(&pentominoes, 50, 3);
+}
+
+#[test]
+fn spread_of_values() {
+  let spread = Spread::of(&[4.0, 1.0, 3.0, 2.0]);
+  assert_eq!((spread.mean, spread.median, spread.min, spread.max), (2.5, 2.5, 1.0, 4.0));
+  assert_eq!(Spread::of(&[5.0, 1.0, 3.0]).median, 3.0);
+  assert_eq!(Spread::of(&[]), Spread::default());
+}
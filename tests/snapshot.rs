@@ -0,0 +1,45 @@
+use tetris_rs::game::{Game, GameSnapshot, Input};
+use tetris_rs::timestep::LOGIC_TICK;
+
+// A seeded game a few pieces in, with a stack, a hold piece and a piece in
+// play.
+fn played_game() -> Game {
+  let mut game = Game::with_seed(7);
+  for input in [Input::HardDrop, Input::MoveLeft, Input::HardDrop, Input::Hold, Input::RotateCW, Input::HardDrop, Input::MoveRight] {
+    while game.current_piece().is_none() {
+      game.step(LOGIC_TICK);
+    }
+    game.apply(input);
+  }
+  while game.current_piece().is_none() {
+    game.step(LOGIC_TICK);
+  }
+  return game;
+}
+
+#[test]
+fn a_snapshot_reads_back_the_same_board() {
+  let game = played_game();
+  let snapshot = game.snapshot();
+  assert!(!snapshot.grid.is_empty() && snapshot.current_piece.is_some() && snapshot.hold_piece.is_some());
+  let read: GameSnapshot = serde_json::from_str(&serde_json::to_string(&snapshot).unwrap()).unwrap();
+  assert_eq!(read.grid, snapshot.grid);
+  assert_eq!(read.current_piece, snapshot.current_piece);
+  assert_eq!(read.score, snapshot.score);
+  assert_eq!(read, snapshot);
+  // And restored, as a network opponent shows it.
+  let mut remote = Game::new();
+  remote.restore(&read);
+  assert_eq!(remote.grid(), game.grid());
+  assert_eq!(remote.current_piece(), game.current_piece());
+  assert_eq!(remote.current_score(), game.current_score());
+}
+
+#[test]
+fn fields_of_a_newer_version_are_ignored() {
+  let snapshot = played_game().snapshot();
+  let mut json = serde_json::to_value(&snapshot).unwrap();
+  json.as_object_mut().unwrap().insert(String::from("from_a_newer_version"), serde_json::json!({ "rows": [1, 2] }));
+  let read: GameSnapshot = serde_json::from_value(json).unwrap();
+  assert_eq!(read, snapshot);
+}
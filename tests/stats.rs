@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use tetris_rs::game::Mode;
+use tetris_rs::stats::{append_summary, ClearCounts, GameSummary, STATS_FILE};
+
+fn summary() -> GameSummary {
+  let mut pieces = BTreeMap::new();
+  pieces.insert('I', 12);
+  pieces.insert('T', 9);
+  return GameSummary {
+    ended_at: 1_600_000_000,
+    mode: Mode::Sprint,
+    seed: Some(42),
+    abandoned: false,
+    score: 12_340,
+    level: 5,
+    lines: 40,
+    clears: ClearCounts { singles: 4, doubles: 3, triples: 2, tetrises: 5, t_spins: 1 },
+    pieces: pieces,
+    seconds: 95.5,
+    pps: 2.25,
+    speed: 1.0,
+    handicap_rows: 2,
+    mission: Some(String::from("Clean up")),
+    mission_passed: true,
+    daily: None,
+    practice: false,
+  };
+}
+
+#[test]
+fn summaries_are_written_one_per_line_and_read_back() {
+  let directory = std::env::temp_dir().join(format!("tetris_rs_stats_{}", std::process::id()));
+  let _ = fs::remove_dir_all(&directory);
+  let second = GameSummary { abandoned: true, seed: None, ..summary() };
+  append_summary(&directory, &summary()).unwrap();
+  append_summary(&directory, &second).unwrap();
+  let text = fs::read_to_string(directory.join(STATS_FILE)).unwrap();
+  fs::remove_dir_all(&directory).unwrap();
+  let read: Vec<GameSummary> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+  assert_eq!(read, vec![summary(), second]);
+}
+
+// Lines of older versions lack the fields added since.
+#[test]
+fn an_older_line_still_reads() {
+  let line = r#"{"ended_at":1,"mode":"Marathon","seed":null,"abandoned":false,"score":100,"level":1,"lines":1,
+    "clears":{"singles":1,"doubles":0,"triples":0,"tetrises":0,"t_spins":0},"pieces":{"O":3},"seconds":10.0,"pps":0.3,"speed":1.0}"#;
+  let read: GameSummary = serde_json::from_str(line).unwrap();
+  assert_eq!((read.mode, read.score, read.pieces.get(&'O')), (Mode::Marathon, 100, Some(&3)));
+  assert_eq!((read.handicap_rows, read.mission, read.daily, read.practice), (0, None, None, false));
+}
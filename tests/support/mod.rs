@@ -0,0 +1,24 @@
+// Helpers shared by the test files that declare `mod support;`, each of
+// them using some only.
+#![allow(dead_code)]
+
+pub mod script;
+
+// Pearson's statistic of `counts` against the same number of draws spread
+// as `expected`, which holds the probability of each category.
+pub fn chi_squared(counts: &[u64], expected: &[f64]) -> f64 {
+  let total: u64 = counts.iter().sum();
+  return counts.iter().zip(expected).map(|(&count, &probability)| {
+    let expected = total as f64 * probability;
+    (count as f64 - expected).powi(2) / expected
+  }).sum();
+}
+
+// The statistic `chi_squared` stays below 999 times in 1000 for a fair
+// draw, with `degrees` = categories - 1 (Wilson-Hilferty approximation).
+pub fn chi_squared_limit(degrees: usize) -> f64 {
+  // The 0.999 quantile of the standard normal distribution.
+  const Z: f64 = 3.090;
+  let k = degrees as f64;
+  return k * (1.0 - 2.0 / (9.0 * k) + Z * (2.0 / (9.0 * k)).sqrt()).powi(3);
+}
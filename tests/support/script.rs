@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use tetris_rs::game::{Game, Input};
+use tetris_rs::grid::Grid;
+use tetris_rs::piece::Tetromino;
+use tetris_rs::randomizer::SequenceRandomizer;
+use tetris_rs::timestep::LOGIC_TICK;
+
+// A mini-game played headless the way the game loop does: the inputs due
+// by a tick are applied before its step, LOGIC_TICK at a time. The times
+// are from the reset, so the first piece spawns after the spawn delay, 200
+// ms by default, and inputs before it are lost.
+#[derive(Clone,Debug,Default)]
+pub struct Script {
+  seed: u64,
+  pieces: Vec<Tetromino>,
+  board: Option<String>,
+  // In the order added, applied by time.
+  inputs: Vec<(Duration, Input)>,
+}
+
+impl Script {
+  pub fn new() -> Script {
+    return Script::default();
+  }
+
+  pub fn seed(mut self, seed: u64) -> Script {
+    self.seed = seed;
+    return self;
+  }
+
+  // Dealt over and over instead of the pieces of the seed.
+  pub fn pieces(mut self, pieces: &[Tetromino]) -> Script {
+    self.pieces = pieces.to_vec();
+    return self;
+  }
+
+  // The bottom rows of the board at the start, see Grid::from_ascii.
+  pub fn board(mut self, text: &str) -> Script {
+    self.board = Some(text.to_string());
+    return self;
+  }
+
+  pub fn at_ms(mut self, ms: u64, input: Input) -> Script {
+    self.inputs.push((Duration::from_millis(ms), input));
+    return self;
+  }
+
+  // The game once every input was applied and at least `ms` went by. Its
+  // events are kept for `drain_events`.
+  pub fn play(&self, ms: u64) -> Game {
+    let mut game = Game::with_seed(self.seed);
+    if !self.pieces.is_empty() {
+      game.set_randomizer(Box::new(SequenceRandomizer::new(&self.pieces)));
+    }
+    if let Some(board) = &self.board {
+      let (width, height) = (game.config().grid_width, game.config().grid_height);
+      let rows = board.lines().filter(|line| !line.trim().is_empty()).count();
+      let empty = format!("{}\n", ".".repeat(width)).repeat(height.saturating_sub(rows));
+      game.set_grid(Grid::from_ascii_sized(&(empty + board), width, height).unwrap());
+    }
+    let mut inputs = self.inputs.clone();
+    inputs.sort_by_key(|&(time, _)| time);
+    let end = inputs.last().map_or(Duration::from_millis(ms), |&(time, _)| time.max(Duration::from_millis(ms)));
+    let mut inputs = inputs.into_iter().peekable();
+    let mut time = Duration::from_secs(0);
+    loop {
+      while let Some((_, input)) = inputs.next_if(|&(at, _)| at <= time) {
+        game.apply(input);
+      }
+      if time >= end {
+        break;
+      }
+      game.step(LOGIC_TICK);
+      time += LOGIC_TICK;
+    }
+    return game;
+  }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use tetris_rs::game::{Game, Input};
+use tetris_rs::piece::Tetromino;
+use tetris_rs::randomizer::SequenceRandomizer;
+use tetris_rs::timestep::{FixedTimestep, LOGIC_TICK, MAX_FRAME_TIME};
+
+// Two I pieces to the walls and an O between them, which clears the bottom
+// row. In ms from the reset, the first piece spawns at 200.
+const SCRIPT: [(u64, Input); 9] = [
+  (300, Input::MoveLeft), (350, Input::MoveLeft), (400, Input::MoveLeft), (500, Input::HardDrop),
+  (700, Input::MoveRight), (750, Input::MoveRight), (800, Input::MoveRight), (900, Input::HardDrop),
+  (1200, Input::HardDrop),
+];
+const END: Duration = Duration::from_millis(3000);
+
+// The script played the way MainState::update does at `fps`: the keys
+// pressed during a frame are queued, then applied at the start of the
+// first tick of the next one.
+fn play_at(fps: u32) -> Game {
+  let frame = Duration::from_secs(1) / fps;
+  let mut game = Game::with_seed(3);
+  game.set_randomizer(Box::new(SequenceRandomizer::new(&[Tetromino::I, Tetromino::I, Tetromino::O])));
+  let mut timestep = FixedTimestep::default();
+  let mut inputs = SCRIPT.iter().peekable();
+  let mut pending = Vec::new();
+  let mut time = Duration::from_secs(0);
+  while time < END {
+    for _ in 0..timestep.advance(frame) {
+      for input in pending.drain(..) {
+        game.apply(input);
+      }
+      game.step(timestep.tick());
+    }
+    time += frame;
+    while let Some(&(_, input)) = inputs.next_if(|&&(ms, _)| Duration::from_millis(ms) <= time) {
+      pending.push(input);
+    }
+  }
+  return game;
+}
+
+#[test]
+fn the_game_plays_the_same_at_any_frame_rate() {
+  let reference = play_at(60);
+  assert_eq!(reference.current_score().line_removed, 1);
+  assert!(reference.current_score().score > 0);
+  for fps in [30, 144] {
+    let game = play_at(fps);
+    assert_eq!(game.grid(), reference.grid(), "at {} fps", fps);
+    assert_eq!(game.current_score(), reference.current_score(), "at {} fps", fps);
+    assert_eq!(game.pieces_locked(), reference.pieces_locked(), "at {} fps", fps);
+  }
+}
+
+#[test]
+fn frames_run_whole_ticks_and_carry_the_rest() {
+  let mut timestep = FixedTimestep::default();
+  assert_eq!(timestep.tick(), LOGIC_TICK);
+  let half = LOGIC_TICK / 2;
+  assert_eq!(timestep.advance(half), 0);
+  assert_eq!(timestep.advance(LOGIC_TICK - half), 1);
+  assert_eq!(timestep.advance(LOGIC_TICK * 3 + half), 3);
+  assert_eq!(timestep.advance(LOGIC_TICK - half), 1);
+  // A long stall only catches up MAX_FRAME_TIME.
+  let mut timestep = FixedTimestep::default();
+  let ticks = timestep.advance(Duration::from_secs(5));
+  assert_eq!(ticks, (MAX_FRAME_TIME.as_nanos() / LOGIC_TICK.as_nanos()) as u32);
+}